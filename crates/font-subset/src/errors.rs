@@ -12,6 +12,11 @@ pub enum ParseErrorKind {
     UnexpectedFontVersion,
     /// Missing required font table (e.g., `head`).
     MissingTable,
+    /// Table directory is truncated, i.e., the font data cannot hold the declared number of table records.
+    TruncatedTableDirectory {
+        /// Declared number of tables.
+        table_count: u16,
+    },
     /// A font table is not aligned to a 4-byte boundary.
     UnalignedTable,
     /// No supported subtable in the `cmap` table.
@@ -36,6 +41,48 @@ pub enum ParseErrorKind {
     },
     /// Unexpected table format (e.g., for a `cmap` subtable).
     UnexpectedTableFormat(u16),
+    /// `unitsPerEm` value in the `head` table is outside the valid `16..=16384` range.
+    InvalidUnitsPerEm(u16),
+    /// Glyph with the specified index has a malformed or out-of-range bounding box.
+    InvalidGlyphBounds(u16),
+    /// Composite glyph references a non-existing glyph as its component.
+    InvalidComponentGlyph {
+        /// Index of the composite glyph.
+        referrer: u16,
+        /// Referenced glyph index.
+        referenced: u16,
+    },
+    /// Composite glyph with the specified index references itself, directly or via other composite glyphs.
+    CompositeGlyphCycle(u16),
+    /// Composite glyphs are nested deeper than the supported limit (16 levels); the payload is the index
+    /// of the glyph at which the limit was exceeded.
+    CompositeDepthExceeded(u16),
+    /// Composite glyph consists of more components than supported (65,535) when flattened, counting
+    /// the components of nested composite glyphs; the payload is the index of the glyph at which
+    /// the limit was exceeded.
+    CompositeComponentsExceeded(u16),
+    /// `CFF ` table contains a malformed DICT, a DICT misses a required entry (e.g., the offset
+    /// to charstrings), or the number of charstrings differs from the number of glyphs in `maxp`.
+    InvalidCffDict,
+    /// Charstring of the glyph with the specified index in the `CFF ` table is malformed (e.g., calls
+    /// a non-existing subroutine), or uses the unsupported `seac` operator for accented chars.
+    InvalidCharstring(u16),
+    /// Font outlines are stored in the specified table (`CFF ` or `CFF2`), which isn't supported
+    /// by the operation. For `CFF `, this is returned by glyph-level methods that require TrueType outlines;
+    /// for `CFF2`, this is returned when parsing the font.
+    ///
+    /// Such fonts can be converted to TrueType outlines beforehand, e.g., using the `otf2ttf` script
+    /// from [fontTools](https://github.com/fonttools/fonttools).
+    UnsupportedOutlineFormat(TableTag),
+    /// One of the [`ParseLimits`](crate::ParseLimits) is exceeded by the font data.
+    LimitExceeded {
+        /// Exceeded limit.
+        limit: LimitKind,
+        /// Value inferred from the font data.
+        value: usize,
+        /// Maximum allowed value.
+        max: usize,
+    },
     /// Checksum mismatch.
     Checksum {
         /// Expected checksum.
@@ -46,11 +93,18 @@ pub enum ParseErrorKind {
 }
 
 impl fmt::Display for ParseErrorKind {
+    #[allow(clippy::too_many_lines)] // a single `match` over all error kinds
     fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::UnexpectedEof => formatter.write_str("unexpected end of the font data"),
             Self::UnexpectedFontVersion => formatter.write_str("unexpected font version"),
             Self::MissingTable => formatter.write_str("missing required font table"),
+            Self::TruncatedTableDirectory { table_count } => {
+                write!(
+                    formatter,
+                    "table directory is truncated: font data cannot hold {table_count} table records"
+                )
+            }
             Self::UnalignedTable => {
                 formatter.write_str("font table is not aligned to a 4-byte boundary")
             }
@@ -81,6 +135,64 @@ impl fmt::Display for ParseErrorKind {
             Self::UnexpectedTableFormat(val) => {
                 write!(formatter, "unexpected table format ({val})")
             }
+            Self::InvalidUnitsPerEm(val) => {
+                write!(
+                    formatter,
+                    "`unitsPerEm` ({val}) is outside the valid 16..=16384 range"
+                )
+            }
+            Self::InvalidGlyphBounds(glyph_idx) => {
+                write!(
+                    formatter,
+                    "glyph #{glyph_idx} has a malformed or out-of-range bounding box"
+                )
+            }
+            Self::InvalidComponentGlyph {
+                referrer,
+                referenced,
+            } => {
+                write!(
+                    formatter,
+                    "composite glyph #{referrer} references non-existing glyph #{referenced}"
+                )
+            }
+            Self::CompositeGlyphCycle(glyph_idx) => {
+                write!(
+                    formatter,
+                    "composite glyph #{glyph_idx} references itself as a component"
+                )
+            }
+            Self::CompositeDepthExceeded(glyph_idx) => {
+                write!(
+                    formatter,
+                    "composite glyph #{glyph_idx} exceeds the maximum nesting depth"
+                )
+            }
+            Self::CompositeComponentsExceeded(glyph_idx) => {
+                write!(
+                    formatter,
+                    "composite glyph #{glyph_idx} exceeds the maximum number of flattened components"
+                )
+            }
+            Self::InvalidCffDict => {
+                formatter.write_str("`CFF ` table contains a malformed DICT or misses a DICT entry")
+            }
+            Self::InvalidCharstring(glyph_idx) => {
+                write!(
+                    formatter,
+                    "charstring of glyph #{glyph_idx} is malformed or unsupported"
+                )
+            }
+            Self::UnsupportedOutlineFormat(tag) => {
+                write!(
+                    formatter,
+                    "unsupported outline format (`{tag}` table); convert the font to TrueType (`glyf`) \
+                     outlines first, e.g. using `otf2ttf` from fontTools"
+                )
+            }
+            Self::LimitExceeded { limit, value, max } => {
+                write!(formatter, "{limit} ({value}) exceeds the limit ({max})")
+            }
             Self::Checksum { expected, actual } => {
                 write!(
                     formatter,
@@ -94,6 +206,31 @@ impl fmt::Display for ParseErrorKind {
 #[cfg(feature = "std")]
 impl std::error::Error for ParseErrorKind {}
 
+/// Kind of a [`ParseLimits`](crate::ParseLimits) limit exceeded by the font data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LimitKind {
+    /// Length of the font data in bytes.
+    FontLen,
+    /// Number of tables in the table directory.
+    TableCount,
+    /// Number of glyphs declared in the `maxp` table.
+    GlyphCount,
+    /// Number of segments or groups in a `cmap` subtable.
+    CmapSegmentCount,
+}
+
+impl fmt::Display for LimitKind {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(match self {
+            Self::FontLen => "font length",
+            Self::TableCount => "number of tables",
+            Self::GlyphCount => "number of glyphs",
+            Self::CmapSegmentCount => "number of `cmap` segments",
+        })
+    }
+}
+
 /// Errors that can occur when parsing an OpenType [`Font`](crate::Font).
 #[derive(Debug)]
 pub struct ParseError {
@@ -126,6 +263,38 @@ impl ParseError {
         }
     }
 
+    pub(crate) fn composite_glyph_cycle(glyph_idx: u16) -> Self {
+        Self {
+            kind: ParseErrorKind::CompositeGlyphCycle(glyph_idx),
+            offset: 0,
+            table: Some(TableTag::GLYF),
+        }
+    }
+
+    pub(crate) fn composite_depth_exceeded(glyph_idx: u16) -> Self {
+        Self {
+            kind: ParseErrorKind::CompositeDepthExceeded(glyph_idx),
+            offset: 0,
+            table: Some(TableTag::GLYF),
+        }
+    }
+
+    pub(crate) fn composite_components_exceeded(glyph_idx: u16) -> Self {
+        Self {
+            kind: ParseErrorKind::CompositeComponentsExceeded(glyph_idx),
+            offset: 0,
+            table: Some(TableTag::GLYF),
+        }
+    }
+
+    pub(crate) fn unsupported_outlines(tag: TableTag) -> Self {
+        Self {
+            kind: ParseErrorKind::UnsupportedOutlineFormat(tag),
+            offset: 0,
+            table: Some(tag),
+        }
+    }
+
     /// Gets the error kind.
     pub fn kind(&self) -> &ParseErrorKind {
         &self.kind
@@ -141,3 +310,80 @@ impl ParseError {
         self.offset
     }
 }
+
+/// Error converting a string to a [`TableTag`] via [`TryFrom`]. Returned if the string doesn't consist
+/// of exactly 4 printable ASCII chars.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidTableTag(pub(crate) ());
+
+impl fmt::Display for InvalidTableTag {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("table tag must consist of exactly 4 printable ASCII chars")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InvalidTableTag {}
+
+/// Errors that can occur when [subsetting](crate::FontSubset) a font.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum SubsetError {
+    /// Error parsing font data.
+    Parse(ParseError),
+    /// Retaining vertical metrics was requested, but the font contains only one of `vhea` and `vmtx` tables.
+    InconsistentVerticalMetrics,
+    /// [Fallback glyph](crate::SubsetOptions::fallback_glyph()) is out of range for the font.
+    InvalidFallbackGlyph(u16),
+    /// Font contains color tables (`COLR` / `CPAL`) that would be dropped by subsetting, and
+    /// [this was prohibited](crate::SubsetOptions::error_on_color()).
+    ColorTablesDropped,
+    /// Glyph [requested for subsetting](crate::Font::subset_glyphs()) is out of range for the font.
+    InvalidGlyph(u16),
+    /// Glyph with the specified ID in the subset doesn't match the corresponding glyph in the original font.
+    /// Returned by [`FontSubset::validate_against()`](crate::FontSubset::validate_against()).
+    GlyphMismatch(u16),
+}
+
+impl From<ParseError> for SubsetError {
+    fn from(err: ParseError) -> Self {
+        Self::Parse(err)
+    }
+}
+
+impl fmt::Display for SubsetError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(err) => fmt::Display::fmt(err, formatter),
+            Self::InconsistentVerticalMetrics => formatter.write_str(
+                "cannot retain vertical metrics: font contains only one of `vhea` and `vmtx` tables",
+            ),
+            Self::InvalidFallbackGlyph(glyph_idx) => {
+                write!(formatter, "fallback glyph #{glyph_idx} is out of range")
+            }
+            Self::ColorTablesDropped => {
+                formatter.write_str("font contains color tables (`COLR` / `CPAL`) that would be dropped")
+            }
+            Self::InvalidGlyph(glyph_idx) => {
+                write!(formatter, "requested glyph #{glyph_idx} is out of range")
+            }
+            Self::GlyphMismatch(glyph_idx) => {
+                write!(formatter, "glyph #{glyph_idx} in the subset doesn't match the original font")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SubsetError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Parse(err) => err.source(),
+            Self::InconsistentVerticalMetrics
+            | Self::InvalidFallbackGlyph(_)
+            | Self::ColorTablesDropped
+            | Self::InvalidGlyph(_)
+            | Self::GlyphMismatch(_) => None,
+        }
+    }
+}
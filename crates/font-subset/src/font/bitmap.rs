@@ -0,0 +1,279 @@
+//! Embedded bitmap (`EBLC` / `EBDT`) processing.
+
+use core::{cmp::Ordering, ops};
+
+use super::Cursor;
+use crate::{alloc::Vec, errors::ParseErrorKind, ParseError};
+
+/// Image size and big glyph metrics shared by all glyphs in an index subtable.
+pub(crate) type SharedMetrics = (u32, [u8; 8]);
+
+/// Range of the image data for a glyph together with the shared metrics, if any.
+type ImageRange = (ops::Range<usize>, Option<SharedMetrics>);
+
+/// Bitmap image of a single glyph from the `EBDT` table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct BitmapGlyph<'a> {
+    /// Format of the image data.
+    pub(crate) image_format: u16,
+    /// Image size and big glyph metrics shared by all glyphs in the index subtable (index formats 2 and 5).
+    /// `None` if the metrics are stored together with the image data.
+    pub(crate) shared_metrics: Option<SharedMetrics>,
+    /// Raw image data.
+    pub(crate) data: &'a [u8],
+}
+
+#[derive(Debug, Clone, Copy)]
+enum IndexSubtableData<'a> {
+    /// Formats 1 and 3: offsets for each glyph in the range.
+    Offsets { offsets: Cursor<'a>, is_wide: bool },
+    /// Format 2: all glyphs in the range have the same image size and metrics.
+    Constant {
+        image_size: u32,
+        big_metrics: [u8; 8],
+    },
+    /// Format 4: offsets for a sparse list of glyphs.
+    SparseOffsets {
+        /// `(glyphID, sbitOffset)` pairs, including the trailing pair used to compute the last image size.
+        pairs: Cursor<'a>,
+    },
+    /// Format 5: sparse list of glyphs with the same image size and metrics.
+    SparseConstant {
+        image_size: u32,
+        big_metrics: [u8; 8],
+        glyph_ids: Cursor<'a>,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct IndexSubtable<'a> {
+    glyphs: ops::RangeInclusive<u16>,
+    image_format: u16,
+    image_data_offset: u32,
+    data: IndexSubtableData<'a>,
+}
+
+impl<'a> IndexSubtable<'a> {
+    fn parse(glyphs: ops::RangeInclusive<u16>, mut cursor: Cursor<'a>) -> Result<Self, ParseError> {
+        let index_format = cursor.read_u16()?;
+        let image_format = cursor.read_u16()?;
+        let image_data_offset = cursor.read_u32()?;
+        let glyph_count = glyphs.clone().count();
+
+        let data = match index_format {
+            1 | 3 => {
+                let is_wide = index_format == 1;
+                let offset_len = if is_wide { 4 } else { 2 };
+                IndexSubtableData::Offsets {
+                    offsets: cursor.range(0..offset_len * (glyph_count + 1))?,
+                    is_wide,
+                }
+            }
+            2 => IndexSubtableData::Constant {
+                image_size: cursor.read_u32()?,
+                big_metrics: cursor.read_byte_array()?,
+            },
+            4 => {
+                let glyph_count = cursor.read_u32()? as usize;
+                let pairs_len = glyph_count.saturating_add(1).saturating_mul(4);
+                IndexSubtableData::SparseOffsets {
+                    pairs: cursor.range(0..pairs_len)?,
+                }
+            }
+            5 => {
+                let image_size = cursor.read_u32()?;
+                let big_metrics = cursor.read_byte_array()?;
+                let glyph_count = cursor.read_u32()? as usize;
+                IndexSubtableData::SparseConstant {
+                    image_size,
+                    big_metrics,
+                    glyph_ids: cursor.range(0..glyph_count.saturating_mul(2))?,
+                }
+            }
+            _ => return Err(cursor.err(ParseErrorKind::UnexpectedTableFormat(index_format))),
+        };
+        Ok(Self {
+            glyphs,
+            image_format,
+            image_data_offset,
+            data,
+        })
+    }
+
+    /// Returns the range of the image data relative to `image_data_offset`, and the shared metrics if any.
+    fn image_range(&self, glyph_idx: u16) -> Result<Option<ImageRange>, ParseError> {
+        if !self.glyphs.contains(&glyph_idx) {
+            return Ok(None);
+        }
+        let glyph_pos = usize::from(glyph_idx - self.glyphs.start());
+
+        Ok(match self.data {
+            IndexSubtableData::Offsets { offsets, is_wide } => {
+                let mut cursor = offsets;
+                let (start, end) = if is_wide {
+                    cursor.skip(glyph_pos * 4)?;
+                    (cursor.read_u32()? as usize, cursor.read_u32()? as usize)
+                } else {
+                    cursor.skip(glyph_pos * 2)?;
+                    (cursor.read_u16()?.into(), cursor.read_u16()?.into())
+                };
+                Some((start..end, None))
+            }
+            IndexSubtableData::Constant {
+                image_size,
+                big_metrics,
+            } => {
+                let range = Self::constant_range(glyph_pos, image_size);
+                Some((range, Some((image_size, big_metrics))))
+            }
+            IndexSubtableData::SparseOffsets { pairs } => {
+                let entries = &pairs.bytes[..pairs.bytes.len() - 4];
+                find_glyph(entries, 4, glyph_idx).map(|pos| {
+                    let read_offset = |pos: usize| {
+                        let bytes = &pairs.bytes[pos * 4 + 2..pos * 4 + 4];
+                        usize::from(u16::from_be_bytes([bytes[0], bytes[1]]))
+                    };
+                    (read_offset(pos)..read_offset(pos + 1), None)
+                })
+            }
+            IndexSubtableData::SparseConstant {
+                image_size,
+                big_metrics,
+                glyph_ids,
+            } => find_glyph(glyph_ids.bytes, 2, glyph_idx).map(|pos| {
+                let range = Self::constant_range(pos, image_size);
+                (range, Some((image_size, big_metrics)))
+            }),
+        })
+    }
+
+    fn constant_range(glyph_pos: usize, image_size: u32) -> ops::Range<usize> {
+        let image_size = image_size as usize;
+        let start = glyph_pos.saturating_mul(image_size);
+        start..start.saturating_add(image_size)
+    }
+
+    fn glyph(
+        &self,
+        ebdt: Cursor<'a>,
+        glyph_idx: u16,
+    ) -> Result<Option<BitmapGlyph<'a>>, ParseError> {
+        /// Image formats for composite bitmaps, which reference other glyphs.
+        const COMPOSITE_IMAGE_FORMATS: [u16; 2] = [8, 9];
+
+        let Some((range, shared_metrics)) = self.image_range(glyph_idx)? else {
+            return Ok(None);
+        };
+        if range.is_empty() || COMPOSITE_IMAGE_FORMATS.contains(&self.image_format) {
+            // An empty range signals a missing bitmap in index formats 1 and 3.
+            return Ok(None);
+        }
+        let image_data_offset = self.image_data_offset as usize;
+        let range = image_data_offset.saturating_add(range.start)
+            ..image_data_offset.saturating_add(range.end);
+        Ok(Some(BitmapGlyph {
+            image_format: self.image_format,
+            shared_metrics,
+            data: ebdt.range(range)?.bytes,
+        }))
+    }
+}
+
+/// Performs binary search for a glyph ID in a sorted array of `stride`-byte records starting with the ID.
+fn find_glyph(records: &[u8], stride: usize, glyph_idx: u16) -> Option<usize> {
+    let (mut start, mut end) = (0, records.len() / stride);
+    while start < end {
+        let mid = (start + end) / 2;
+        let id = u16::from_be_bytes([records[mid * stride], records[mid * stride + 1]]);
+        match id.cmp(&glyph_idx) {
+            Ordering::Less => start = mid + 1,
+            Ordering::Greater => end = mid,
+            Ordering::Equal => return Some(mid),
+        }
+    }
+    None
+}
+
+/// Length of a `BitmapSize` record in the `EBLC` table.
+pub(crate) const BITMAP_SIZE_LEN: usize = 48;
+
+/// Strike (i.e., a set of bitmaps for a specific size) from the `EBLC` table.
+#[derive(Debug, Clone)]
+pub(crate) struct BitmapStrike<'a> {
+    /// Raw `BitmapSize` record.
+    pub(crate) record: [u8; BITMAP_SIZE_LEN],
+    subtables: Vec<IndexSubtable<'a>>,
+}
+
+impl<'a> BitmapStrike<'a> {
+    fn parse(eblc: Cursor<'a>, header_cursor: &mut Cursor<'a>) -> Result<Self, ParseError> {
+        let record = header_cursor.read_byte_array::<BITMAP_SIZE_LEN>()?;
+        let mut cursor = Cursor::new(&record);
+        let array_offset = cursor.read_u32()? as usize;
+        cursor.skip(4)?; // indexTablesSize
+        let subtable_count = cursor.read_u32()?;
+
+        let mut array_cursor = eblc;
+        array_cursor.skip(array_offset)?;
+        let array_start = array_cursor;
+        let subtables = (0..subtable_count).map(|_| {
+            let first_glyph = array_cursor.read_u16()?;
+            let last_glyph = array_cursor.read_u16()?;
+            let mut subtable_cursor = array_start;
+            subtable_cursor.skip(array_cursor.read_u32()? as usize)?;
+            IndexSubtable::parse(first_glyph..=last_glyph, subtable_cursor)
+        });
+        Ok(Self {
+            record,
+            subtables: subtables.collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Returns the bitmap for the specified glyph, or `None` if the strike doesn't contain it.
+    /// Composite bitmaps (image formats 8 and 9) are not supported and are skipped as well.
+    pub(crate) fn glyph(
+        &self,
+        ebdt: Cursor<'a>,
+        glyph_idx: u16,
+    ) -> Result<Option<BitmapGlyph<'a>>, ParseError> {
+        for subtable in &self.subtables {
+            if let Some(glyph) = subtable.glyph(ebdt, glyph_idx)? {
+                return Ok(Some(glyph));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Embedded bitmaps from the `EBLC` and `EBDT` tables.
+#[derive(Debug, Clone)]
+pub(crate) struct EmbeddedBitmaps<'a> {
+    pub(crate) strikes: Vec<BitmapStrike<'a>>,
+    pub(crate) ebdt: Cursor<'a>,
+}
+
+impl<'a> EmbeddedBitmaps<'a> {
+    /// Version 2.0 shared by `EBLC` and `EBDT` tables.
+    pub(crate) const VERSION: u32 = 0x_0002_0000;
+
+    pub(super) fn parse(eblc: Cursor<'a>, ebdt: Cursor<'a>) -> Result<Self, ParseError> {
+        let check_version = |version| {
+            if version == Self::VERSION {
+                Ok(())
+            } else {
+                Err(ParseErrorKind::UnexpectedTableVersion(version))
+            }
+        };
+        let mut version_cursor = ebdt;
+        version_cursor.read_u32_checked(check_version)?;
+
+        let mut cursor = eblc;
+        cursor.read_u32_checked(check_version)?;
+        let strike_count = cursor.read_u32()?;
+        let strikes = (0..strike_count).map(|_| BitmapStrike::parse(eblc, &mut cursor));
+        Ok(Self {
+            strikes: strikes.collect::<Result<_, _>>()?,
+            ebdt,
+        })
+    }
+}
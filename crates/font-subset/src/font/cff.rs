@@ -0,0 +1,939 @@
+//! `CFF ` table processing (Compact Font Format, version 1).
+
+use super::Cursor;
+use crate::{
+    alloc::{BTreeSet, Vec},
+    errors::ParseErrorKind,
+    ParseError,
+};
+
+/// `INDEX` structure, i.e., an array of variable-length objects.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Index<'a> {
+    count: u16,
+    off_size: usize,
+    offsets: &'a [u8],
+    data: Cursor<'a>,
+    /// Raw bytes of the entire structure.
+    pub(crate) raw: &'a [u8],
+}
+
+impl<'a> Index<'a> {
+    /// Parses the structure, advancing `cursor` past its end.
+    fn parse(cursor: &mut Cursor<'a>) -> Result<Self, ParseError> {
+        let start = *cursor;
+        let count = cursor.read_u16()?;
+        if count == 0 {
+            return Ok(Self {
+                count,
+                off_size: 1,
+                offsets: &[],
+                data: cursor.range(0..0)?,
+                raw: &start.bytes[..2],
+            });
+        }
+
+        let off_size = cursor.read_u8_checked(|off_size| {
+            if (1..=4).contains(&off_size) {
+                Ok(usize::from(off_size))
+            } else {
+                Err(ParseErrorKind::UnexpectedTableFormat(off_size.into()))
+            }
+        })?;
+        let offsets = cursor.split_at((usize::from(count) + 1) * off_size)?.bytes;
+        let mut this = Self {
+            count,
+            off_size,
+            offsets,
+            data: *cursor,
+            raw: &[],
+        };
+        // Offsets are 1-based; the last offset points to the end of the object data.
+        let data_len = this
+            .offset(count.into())
+            .checked_sub(1)
+            .ok_or_else(|| cursor.err(ParseErrorKind::OffsetOutOfBounds(0)))?;
+        this.data = cursor.split_at(data_len)?;
+        this.raw = &start.bytes[..cursor.offset - start.offset];
+        Ok(this)
+    }
+
+    fn offset(&self, idx: usize) -> usize {
+        let bytes = &self.offsets[idx * self.off_size..(idx + 1) * self.off_size];
+        bytes
+            .iter()
+            .fold(0, |acc, &byte| (acc << 8) + usize::from(byte))
+    }
+
+    pub(crate) fn len(&self) -> u16 {
+        self.count
+    }
+
+    pub(crate) fn get(&self, idx: u16) -> Result<Cursor<'a>, ParseError> {
+        if idx >= self.count {
+            return Err(self.data.err(ParseErrorKind::OffsetOutOfBounds(idx.into())));
+        }
+        let idx = usize::from(idx);
+        let (start, end) = (self.offset(idx), self.offset(idx + 1));
+        let start = start
+            .checked_sub(1)
+            .ok_or_else(|| self.data.err(ParseErrorKind::OffsetOutOfBounds(start)))?;
+        self.data.range(start..end.saturating_sub(1))
+    }
+
+    /// Returns the bytes of the object with the specified index.
+    pub(crate) fn object(&self, idx: u16) -> Result<&'a [u8], ParseError> {
+        self.get(idx).map(|cursor| cursor.bytes)
+    }
+
+    /// Returns the bias added to subroutine indices in charstrings.
+    fn subr_bias(&self) -> i32 {
+        match self.count {
+            0..1_240 => 107,
+            1_240..33_900 => 1_131,
+            _ => 32_768,
+        }
+    }
+}
+
+/// Entry in a `DICT` structure.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DictEntry<'a> {
+    /// Operator. Two-byte operators are encoded as `0x0c00 | second_byte`.
+    pub(crate) operator: u16,
+    /// Raw operands preceding the operator.
+    pub(crate) operands: &'a [u8],
+}
+
+impl<'a> DictEntry<'a> {
+    pub(crate) const CHARSET: u16 = 15;
+    pub(crate) const ENCODING: u16 = 16;
+    pub(crate) const CHARSTRINGS: u16 = 17;
+    pub(crate) const PRIVATE: u16 = 18;
+    pub(crate) const SUBRS: u16 = 19;
+    const CHARSTRING_TYPE: u16 = 0x0c06;
+    const ROS: u16 = 0x0c1e;
+    pub(crate) const FD_ARRAY: u16 = 0x0c24;
+    pub(crate) const FD_SELECT: u16 = 0x0c25;
+
+    /// Parses all entries in a `DICT`.
+    fn parse_dict(cursor: Cursor<'a>) -> Result<Vec<Self>, ParseError> {
+        let bytes = cursor.bytes;
+        let mut entries = Vec::new();
+        let (mut operands_start, mut pos) = (0, 0);
+        while let Some(&b0) = bytes.get(pos) {
+            let operand_len = match b0 {
+                0..=21 => {
+                    let operands = &bytes[operands_start..pos];
+                    let operator = if b0 == 12 {
+                        let b1 = *bytes
+                            .get(pos + 1)
+                            .ok_or_else(|| cursor.err(ParseErrorKind::UnexpectedEof))?;
+                        pos += 2;
+                        0x0c00 | u16::from(b1)
+                    } else {
+                        pos += 1;
+                        b0.into()
+                    };
+                    entries.push(Self { operator, operands });
+                    operands_start = pos;
+                    continue;
+                }
+                28 => 3,
+                29 => 5,
+                30 => {
+                    // Real number encoded as nibbles terminated by the `0xf` nibble.
+                    let end = bytes[pos + 1..]
+                        .iter()
+                        .position(|&byte| byte & 0xf == 0xf || byte >> 4 == 0xf);
+                    let end = end.ok_or_else(|| cursor.err(ParseErrorKind::UnexpectedEof))?;
+                    end + 2
+                }
+                32..=246 => 1,
+                247..=254 => 2,
+                _ => return Err(cursor.err(ParseErrorKind::InvalidCffDict)),
+            };
+            pos += operand_len;
+        }
+
+        if pos > bytes.len() || operands_start < bytes.len() {
+            // Truncated operand, or operands not followed by an operator.
+            return Err(cursor.err(ParseErrorKind::UnexpectedEof));
+        }
+        Ok(entries)
+    }
+
+    fn find(entries: &[Self], operator: u16) -> Option<&Self> {
+        entries.iter().find(|entry| entry.operator == operator)
+    }
+
+    /// Decodes operands if they are all integers.
+    fn int_operands<const N: usize>(&self) -> Option<[i32; N]> {
+        let mut values = [0; N];
+        let mut bytes = self.operands;
+        for value in &mut values {
+            let (&b0, rest) = bytes.split_first()?;
+            (*value, bytes) = match (b0, rest) {
+                (28, [b1, b2, rest @ ..]) => (i16::from_be_bytes([*b1, *b2]).into(), rest),
+                (29, [b1, b2, b3, b4, rest @ ..]) => {
+                    (i32::from_be_bytes([*b1, *b2, *b3, *b4]), rest)
+                }
+                (32..=246, _) => (i32::from(b0) - 139, rest),
+                (247..=250, [b1, rest @ ..]) => {
+                    ((i32::from(b0) - 247) * 256 + i32::from(*b1) + 108, rest)
+                }
+                (251..=254, [b1, rest @ ..]) => {
+                    (-(i32::from(b0) - 251) * 256 - i32::from(*b1) - 108, rest)
+                }
+                _ => return None,
+            };
+        }
+        bytes.is_empty().then_some(values)
+    }
+
+    /// Reads non-negative integer operands (e.g., offsets) of the entry with the specified operator.
+    fn read_offsets<const N: usize>(
+        entries: &[Self],
+        operator: u16,
+        dict: Cursor<'_>,
+    ) -> Result<Option<[usize; N]>, ParseError> {
+        let Some(entry) = Self::find(entries, operator) else {
+            return Ok(None);
+        };
+        let values = entry
+            .int_operands::<N>()
+            .ok_or_else(|| dict.err(ParseErrorKind::InvalidCffDict))?;
+        let mut offsets = [0; N];
+        for (offset, value) in offsets.iter_mut().zip(values) {
+            *offset =
+                usize::try_from(value).map_err(|_| dict.err(ParseErrorKind::InvalidCffDict))?;
+        }
+        Ok(Some(offsets))
+    }
+}
+
+/// Returns the string ID of the glyph name corresponding to `code` in the standard encoding,
+/// or `None` if the code is not encoded.
+fn standard_encoding_sid(code: u8) -> Option<u16> {
+    /// String IDs for codes `161..=251`; 0 corresponds to `.notdef`.
+    #[rustfmt::skip]
+    const HIGH_SIDS: [u8; 91] = [
+        96, 97, 98, 99, 100, 101, 102, 103, 104, 105, 106, 107, 108, 109, 110, 0,
+        111, 112, 113, 114, 0, 115, 116, 117, 118, 119, 120, 121, 122, 0, 123, 0,
+        124, 125, 126, 127, 128, 129, 130, 131, 0, 132, 133, 0, 134, 135, 136, 137,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        138, 0, 139, 0, 0, 0, 0, 140, 141, 142, 143, 0, 0, 0, 0, 0,
+        144, 0, 0, 0, 145, 0, 0, 146, 147, 148, 149,
+    ];
+
+    let sid = match code {
+        32..=126 => code - 31,
+        161..=251 => HIGH_SIDS[usize::from(code - 161)],
+        _ => 0,
+    };
+    (sid != 0).then_some(sid.into())
+}
+
+/// Mapping of glyph IDs to string IDs (for name-keyed fonts) or CIDs (for CID-keyed fonts).
+#[derive(Debug, Clone, Copy)]
+enum Charset<'a> {
+    /// Predefined `ISOAdobe` charset, in which the glyph ID is equal to the string ID.
+    IsoAdobe,
+    /// Format 0: an ID for each glyph except `.notdef`.
+    Array(Cursor<'a>),
+    /// Formats 1 and 2: ranges of consecutive IDs.
+    Ranges { ranges: Cursor<'a>, is_wide: bool },
+}
+
+impl<'a> Charset<'a> {
+    fn parse(table: Cursor<'a>, offset: usize, glyph_count: u16) -> Result<Self, ParseError> {
+        match offset {
+            0 => return Ok(Self::IsoAdobe),
+            // Expert and ExpertSubset charsets are not used in OpenType fonts.
+            1 | 2 => return Err(table.err(ParseErrorKind::InvalidCffDict)),
+            _ => { /* custom charset */ }
+        }
+
+        let mut cursor = table;
+        cursor.skip(offset)?;
+        let remaining_glyphs = usize::from(glyph_count.saturating_sub(1));
+        let format = cursor.read_u8()?;
+        Ok(match format {
+            0 => Self::Array(cursor.range(0..2 * remaining_glyphs)?),
+            1 | 2 => {
+                let is_wide = format == 2;
+                let record_len = if is_wide { 4 } else { 3 };
+                let start = cursor;
+                let mut covered_glyphs = 0;
+                while covered_glyphs < remaining_glyphs {
+                    cursor.skip(2)?; // first
+                    let left_count = if is_wide {
+                        usize::from(cursor.read_u16()?)
+                    } else {
+                        usize::from(cursor.read_u8()?)
+                    };
+                    covered_glyphs += left_count + 1;
+                }
+                let ranges_len = cursor.offset - start.offset;
+                debug_assert_eq!(ranges_len % record_len, 0);
+                Self::Ranges {
+                    ranges: start.range(0..ranges_len)?,
+                    is_wide,
+                }
+            }
+            _ => return Err(cursor.err(ParseErrorKind::UnexpectedTableFormat(format.into()))),
+        })
+    }
+
+    fn id(&self, glyph_idx: u16) -> Result<u16, ParseError> {
+        if glyph_idx == 0 {
+            return Ok(0);
+        }
+        match self {
+            Self::IsoAdobe => Ok(glyph_idx),
+            Self::Array(ids) => {
+                let mut cursor = *ids;
+                cursor.skip(2 * usize::from(glyph_idx - 1))?;
+                cursor.read_u16()
+            }
+            Self::Ranges { ranges, is_wide } => {
+                let mut cursor = *ranges;
+                let mut first_glyph = 1_u16;
+                while !cursor.bytes.is_empty() {
+                    let first_id = cursor.read_u16()?;
+                    let left_count = if *is_wide {
+                        cursor.read_u16()?
+                    } else {
+                        cursor.read_u8()?.into()
+                    };
+                    let pos = glyph_idx - first_glyph;
+                    if pos <= left_count {
+                        return Ok(first_id.wrapping_add(pos));
+                    }
+                    first_glyph = first_glyph.saturating_add(left_count).saturating_add(1);
+                    if first_glyph > glyph_idx {
+                        break;
+                    }
+                }
+                Err(ranges.err(ParseErrorKind::OffsetOutOfBounds(glyph_idx.into())))
+            }
+        }
+    }
+
+    /// Finds the glyph with the specified string ID / CID. This is the inverse of [`Self::id()`].
+    fn find_glyph(&self, id: u16, glyph_count: u16) -> Option<u16> {
+        let glyph_idx = match self {
+            Self::IsoAdobe => id,
+            Self::Array(ids) => {
+                let pos = ids
+                    .bytes
+                    .chunks_exact(2)
+                    .position(|chunk| u16::from_be_bytes([chunk[0], chunk[1]]) == id)?;
+                u16::try_from(pos + 1).ok()?
+            }
+            Self::Ranges { ranges, is_wide } => {
+                let record_len = if *is_wide { 4 } else { 3 };
+                let mut first_glyph = 1_u16;
+                let mut found = None;
+                for record in ranges.bytes.chunks_exact(record_len) {
+                    let first_id = u16::from_be_bytes([record[0], record[1]]);
+                    let left_count = if *is_wide {
+                        u16::from_be_bytes([record[2], record[3]])
+                    } else {
+                        record[2].into()
+                    };
+                    if let Some(pos) = id.checked_sub(first_id).filter(|&pos| pos <= left_count) {
+                        found = first_glyph.checked_add(pos);
+                        break;
+                    }
+                    first_glyph = first_glyph.checked_add(left_count)?.checked_add(1)?;
+                }
+                found?
+            }
+        };
+        (glyph_idx < glyph_count).then_some(glyph_idx)
+    }
+}
+
+/// Mapping of glyph IDs to font dicts in CID-keyed fonts.
+#[derive(Debug, Clone, Copy)]
+enum FdSelect<'a> {
+    /// Format 0: font dict index for each glyph.
+    Array(Cursor<'a>),
+    /// Format 3: ranges of glyphs with the same font dict, as `(first_glyph, font_dict)` records
+    /// followed by the sentinel glyph ID.
+    Ranges(Cursor<'a>),
+}
+
+impl<'a> FdSelect<'a> {
+    fn parse(table: Cursor<'a>, offset: usize, glyph_count: u16) -> Result<Self, ParseError> {
+        let mut cursor = table;
+        cursor.skip(offset)?;
+        let format = cursor.read_u8()?;
+        Ok(match format {
+            0 => Self::Array(cursor.range(0..glyph_count.into())?),
+            3 => {
+                let range_count = cursor.read_u16()?;
+                Self::Ranges(cursor.range(0..3 * usize::from(range_count) + 2)?)
+            }
+            _ => return Err(cursor.err(ParseErrorKind::UnexpectedTableFormat(format.into()))),
+        })
+    }
+
+    fn font_dict(&self, glyph_idx: u16) -> Result<u8, ParseError> {
+        match self {
+            Self::Array(indices) => {
+                let mut cursor = *indices;
+                cursor.skip(glyph_idx.into())?;
+                cursor.read_u8()
+            }
+            Self::Ranges(ranges) => {
+                let mut cursor = *ranges;
+                let mut first_glyph = cursor.read_u16()?;
+                while cursor.bytes.len() >= 3 {
+                    let font_dict = cursor.read_u8()?;
+                    let next_glyph = cursor.read_u16()?;
+                    if (first_glyph..next_glyph).contains(&glyph_idx) {
+                        return Ok(font_dict);
+                    }
+                    first_glyph = next_glyph;
+                }
+                Err(ranges.err(ParseErrorKind::OffsetOutOfBounds(glyph_idx.into())))
+            }
+        }
+    }
+}
+
+/// Font dict together with the private dict and local subroutines it references.
+#[derive(Debug, Clone)]
+pub(crate) struct FontDict<'a> {
+    /// Entries of the font dict in the `FDArray`. Empty for name-keyed fonts, which have no font dicts.
+    pub(crate) entries: Vec<DictEntry<'a>>,
+    /// Entries of the private dict.
+    pub(crate) private_entries: Vec<DictEntry<'a>>,
+    /// Local subroutines, if any.
+    pub(crate) local_subrs: Option<Index<'a>>,
+}
+
+impl<'a> FontDict<'a> {
+    fn parse(
+        table: Cursor<'a>,
+        entries: Vec<DictEntry<'a>>,
+        dict: Cursor<'a>,
+    ) -> Result<Self, ParseError> {
+        let [private_len, private_offset] =
+            DictEntry::read_offsets(&entries, DictEntry::PRIVATE, dict)?
+                .ok_or_else(|| dict.err(ParseErrorKind::InvalidCffDict))?;
+        let private_dict =
+            table.range(private_offset..private_offset.saturating_add(private_len))?;
+        let private_entries = DictEntry::parse_dict(private_dict)?;
+        let local_subrs =
+            DictEntry::read_offsets(&private_entries, DictEntry::SUBRS, private_dict)?;
+        let local_subrs = local_subrs
+            .map(|[offset]| {
+                // The offset is relative to the start of the private dict.
+                let mut cursor = table;
+                cursor.skip(private_offset.saturating_add(offset))?;
+                Index::parse(&mut cursor)
+            })
+            .transpose()?;
+
+        Ok(Self {
+            entries,
+            private_entries,
+            local_subrs,
+        })
+    }
+}
+
+/// Glyph from the `CFF ` table.
+#[derive(Debug, Clone)]
+pub(crate) struct CffGlyph<'a> {
+    /// Type 2 charstring of the glyph.
+    pub(crate) charstring: &'a [u8],
+    /// String ID of the glyph name (for name-keyed fonts) or the CID (for CID-keyed fonts).
+    pub(crate) id: u16,
+    /// Index of the font dict used by the glyph. Always 0 for name-keyed fonts.
+    pub(crate) font_dict: u8,
+    /// Number of contours, i.e., `moveto` operators in the charstring.
+    pub(crate) contour_count: u16,
+    /// Indices of global subroutines called by the glyph, directly or indirectly.
+    pub(crate) global_subrs: BTreeSet<u16>,
+    /// Indices of local subroutines called by the glyph, directly or indirectly.
+    pub(crate) local_subrs: BTreeSet<u16>,
+    /// Base and accent glyphs referenced by the deprecated `seac`-like `endchar` operator, if the glyph
+    /// uses it and the referenced glyphs could be resolved.
+    pub(crate) seac_components: Option<[u16; 2]>,
+}
+
+impl CffGlyph<'_> {
+    /// Charstring consisting of a single `endchar` operator.
+    const EMPTY_CHARSTRING: &'static [u8] = &[CharstringWalker::ENDCHAR];
+
+    /// Replaces the glyph outline with an empty one. The glyph ID / CID and font dict are retained.
+    pub(crate) fn blank(&mut self) {
+        self.charstring = Self::EMPTY_CHARSTRING;
+        self.contour_count = 0;
+        self.global_subrs.clear();
+        self.local_subrs.clear();
+        self.seac_components = None;
+    }
+}
+
+/// Marker error for malformed charstrings.
+#[derive(Debug)]
+struct InvalidCharstring;
+
+/// Traverses charstrings, recording called subroutines.
+#[derive(Debug)]
+struct CharstringWalker<'r, 'a> {
+    global_subrs: &'r Index<'a>,
+    local_subrs: Option<&'r Index<'a>>,
+    /// Operand stack with 16.16 fixed-point operands. Operand values are only used
+    /// to determine subroutine indices and `seac` char codes; hence, lossy arithmetic is fine.
+    stack: Vec<i32>,
+    /// Transient array used by `put` / `get` operators. Its size is specified by the Type 2 charstring spec.
+    transient: [i32; 32],
+    stem_count: usize,
+    contour_count: u16,
+    used_global_subrs: BTreeSet<u16>,
+    used_local_subrs: BTreeSet<u16>,
+    /// Standard encoding codes of the base and accent chars for a `seac`-like `endchar`.
+    seac_codes: Option<[u8; 2]>,
+}
+
+impl<'r, 'a> CharstringWalker<'r, 'a> {
+    const ENDCHAR: u8 = 14;
+    /// Maximum number of operands on the stack as per the Type 2 charstring spec.
+    const MAX_STACK_LEN: usize = 48;
+    /// Maximum nesting of subroutine calls as per the Type 2 charstring spec.
+    const MAX_CALL_DEPTH: usize = 10;
+    /// 1.0 in the 16.16 fixed-point format.
+    const ONE: i32 = 1 << 16;
+
+    fn new(global_subrs: &'r Index<'a>, local_subrs: Option<&'r Index<'a>>) -> Self {
+        Self {
+            global_subrs,
+            local_subrs,
+            stack: Vec::with_capacity(Self::MAX_STACK_LEN),
+            transient: [0; 32],
+            stem_count: 0,
+            contour_count: 0,
+            used_global_subrs: BTreeSet::new(),
+            used_local_subrs: BTreeSet::new(),
+            seac_codes: None,
+        }
+    }
+
+    fn push(&mut self, value: i32) -> Result<(), InvalidCharstring> {
+        if self.stack.len() >= Self::MAX_STACK_LEN {
+            return Err(InvalidCharstring);
+        }
+        self.stack.push(value);
+        Ok(())
+    }
+
+    fn push_int(&mut self, value: i32) -> Result<(), InvalidCharstring> {
+        self.push(value << 16)
+    }
+
+    fn pop(&mut self) -> Result<i32, InvalidCharstring> {
+        self.stack.pop().ok_or(InvalidCharstring)
+    }
+
+    /// Pops the integer part of the top operand.
+    fn pop_int(&mut self) -> Result<i32, InvalidCharstring> {
+        self.pop().map(|value| value >> 16)
+    }
+
+    /// Pops the integer index of a transient array element.
+    fn pop_transient_idx(&mut self) -> Result<usize, InvalidCharstring> {
+        let idx = usize::try_from(self.pop_int()?).map_err(|_| InvalidCharstring)?;
+        if idx < self.transient.len() {
+            Ok(idx)
+        } else {
+            Err(InvalidCharstring)
+        }
+    }
+
+    fn count_stems(&mut self) {
+        // Stem hint operators may be preceded by the glyph width, which is dropped by the division.
+        self.stem_count += self.stack.len() / 2;
+        self.stack.clear();
+    }
+
+    fn call_subr(&mut self, is_global: bool, depth: usize) -> Result<bool, InvalidCharstring> {
+        if depth >= Self::MAX_CALL_DEPTH {
+            return Err(InvalidCharstring);
+        }
+        let subrs = if is_global {
+            self.global_subrs
+        } else {
+            self.local_subrs.ok_or(InvalidCharstring)?
+        };
+        let biased_idx = self.pop_int()?;
+        let idx = biased_idx
+            .checked_add(subrs.subr_bias())
+            .and_then(|idx| u16::try_from(idx).ok())
+            .ok_or(InvalidCharstring)?;
+        let subr = subrs.get(idx).map_err(|_| InvalidCharstring)?;
+        if is_global {
+            self.used_global_subrs.insert(idx);
+        } else {
+            self.used_local_subrs.insert(idx);
+        }
+        self.walk(subr.bytes, depth + 1)
+    }
+
+    /// Walks a charstring or a subroutine. Returns `true` if the glyph has ended (i.e., `endchar` was encountered).
+    fn walk(&mut self, bytes: &[u8], depth: usize) -> Result<bool, InvalidCharstring> {
+        let mut pos = 0;
+        while let Some(&b0) = bytes.get(pos) {
+            pos += 1;
+            let mut read_byte = || {
+                let byte = bytes.get(pos).copied().ok_or(InvalidCharstring);
+                pos += 1;
+                byte
+            };
+            match b0 {
+                28 => {
+                    let value = i16::from_be_bytes([read_byte()?, read_byte()?]);
+                    self.push_int(value.into())?;
+                }
+                32..=246 => self.push_int(i32::from(b0) - 139)?,
+                247..=250 => {
+                    let value = (i32::from(b0) - 247) * 256 + i32::from(read_byte()?) + 108;
+                    self.push_int(value)?;
+                }
+                251..=254 => {
+                    let value = -(i32::from(b0) - 251) * 256 - i32::from(read_byte()?) - 108;
+                    self.push_int(value)?;
+                }
+                255 => {
+                    // 16.16 fixed-point number
+                    let bytes = [read_byte()?, read_byte()?, read_byte()?, read_byte()?];
+                    self.push(i32::from_be_bytes(bytes))?;
+                }
+                // hstem, vstem, hstemhm, vstemhm
+                1 | 3 | 18 | 23 => self.count_stems(),
+                // hintmask, cntrmask
+                19 | 20 => {
+                    // Stem hints may be specified implicitly before the mask (as an omitted `vstemhm`).
+                    self.count_stems();
+                    pos += self.stem_count.div_ceil(8);
+                    if pos > bytes.len() {
+                        return Err(InvalidCharstring);
+                    }
+                }
+                // rmoveto, hmoveto, vmoveto
+                21 | 22 | 4 => {
+                    self.contour_count = self.contour_count.saturating_add(1);
+                    self.stack.clear();
+                }
+                // callsubr, callgsubr
+                10 | 29 => {
+                    if self.call_subr(b0 == 29, depth)? {
+                        return Ok(true);
+                    }
+                }
+                // return
+                11 => return Ok(false),
+                Self::ENDCHAR => {
+                    // `endchar` with 4 or 5 (incl. width) operands denotes a deprecated `seac` accented char,
+                    // which references the base and accent glyphs via the standard encoding.
+                    if self.stack.len() >= 4 {
+                        let achar = u8::try_from(self.pop_int()?).map_err(|_| InvalidCharstring)?;
+                        let bchar = u8::try_from(self.pop_int()?).map_err(|_| InvalidCharstring)?;
+                        self.seac_codes = Some([bchar, achar]);
+                    }
+                    return Ok(true);
+                }
+                12 => {
+                    let op = read_byte()?;
+                    self.escaped_operator(op)?;
+                }
+                _ => self.stack.clear(),
+            }
+        }
+        Ok(false)
+    }
+
+    /// Executes a two-byte operator. Arithmetic and storage operators are emulated since their results
+    /// may be used as operands of subsequent operators (e.g., as subroutine indices). Other operators
+    /// (e.g., flex) clear the stack.
+    fn escaped_operator(&mut self, op: u8) -> Result<(), InvalidCharstring> {
+        let from_bool = |flag: bool| if flag { Self::ONE } else { 0 };
+        let saturate = |value: i64| {
+            i32::try_from(value).unwrap_or(if value < 0 { i32::MIN } else { i32::MAX })
+        };
+
+        match op {
+            // and, or, eq
+            3 | 4 | 15 => {
+                let (b, a) = (self.pop()?, self.pop()?);
+                let result = match op {
+                    3 => a != 0 && b != 0,
+                    4 => a != 0 || b != 0,
+                    _ => a == b,
+                };
+                self.push(from_bool(result))?;
+            }
+            // not
+            5 => {
+                let a = self.pop()?;
+                self.push(from_bool(a == 0))?;
+            }
+            // abs
+            9 => {
+                let a = self.pop()?;
+                self.push(a.saturating_abs())?;
+            }
+            // neg
+            14 => {
+                let a = self.pop()?;
+                self.push(a.saturating_neg())?;
+            }
+            // add, sub, mul, div
+            10 | 11 | 24 | 12 => {
+                let (b, a) = (i64::from(self.pop()?), i64::from(self.pop()?));
+                let result = match op {
+                    10 => a + b,
+                    11 => a - b,
+                    24 => (a * b) >> 16,
+                    _ => (a << 16).checked_div(b).ok_or(InvalidCharstring)?,
+                };
+                self.push(saturate(result))?;
+            }
+            // sqrt
+            26 => {
+                let a = u64::try_from(self.pop()?).map_err(|_| InvalidCharstring)?;
+                self.push(saturate(i64::try_from(isqrt(a << 16)).unwrap()))?;
+            }
+            // random; the exact value (which must be in the (0, 1] range) doesn't matter
+            23 => self.push(Self::ONE)?,
+            // drop
+            18 => {
+                self.pop()?;
+            }
+            // dup
+            27 => {
+                let a = *self.stack.last().ok_or(InvalidCharstring)?;
+                self.push(a)?;
+            }
+            // exch
+            28 => {
+                let (b, a) = (self.pop()?, self.pop()?);
+                self.stack.extend([b, a]);
+            }
+            // index
+            29 => {
+                let idx = self.pop_int()?;
+                let value = match usize::try_from(idx) {
+                    Ok(idx) => self.stack.len().checked_sub(idx + 1),
+                    // Negative indices copy the top element.
+                    Err(_) => self.stack.len().checked_sub(1),
+                };
+                let value = value.and_then(|pos| self.stack.get(pos).copied());
+                self.push(value.ok_or(InvalidCharstring)?)?;
+            }
+            // roll
+            30 => {
+                let (shift, count) = (self.pop_int()?, self.pop_int()?);
+                let len = usize::try_from(count).map_err(|_| InvalidCharstring)?;
+                let start = self.stack.len().checked_sub(len).ok_or(InvalidCharstring)?;
+                if len > 0 {
+                    let shift = usize::try_from(shift.rem_euclid(count)).unwrap();
+                    self.stack[start..].rotate_right(shift);
+                }
+            }
+            // put
+            20 => {
+                let idx = self.pop_transient_idx()?;
+                self.transient[idx] = self.pop()?;
+            }
+            // get
+            21 => {
+                let idx = self.pop_transient_idx()?;
+                self.push(self.transient[idx])?;
+            }
+            // ifelse
+            22 => {
+                let (v2, v1, s2, s1) = (self.pop()?, self.pop()?, self.pop()?, self.pop()?);
+                self.push(if v1 <= v2 { s1 } else { s2 })?;
+            }
+            _ => self.stack.clear(),
+        }
+        Ok(())
+    }
+}
+
+/// Integer square root, rounded down.
+fn isqrt(value: u64) -> u64 {
+    // Newton's method converging from above.
+    let mut root = value;
+    let mut next = value.div_ceil(2);
+    while next < root {
+        root = next;
+        next = (root + value / root) / 2;
+    }
+    root
+}
+
+/// Parsed `CFF ` table.
+#[derive(Debug, Clone)]
+pub(crate) struct CffTable<'a> {
+    pub(crate) name_index: Index<'a>,
+    pub(crate) top_dict: Vec<DictEntry<'a>>,
+    pub(crate) string_index: Index<'a>,
+    pub(crate) global_subrs: Index<'a>,
+    charstrings: Index<'a>,
+    charset: Charset<'a>,
+    /// Font dicts. Name-keyed fonts have a single dict containing the private dict from the top dict.
+    pub(crate) font_dicts: Vec<FontDict<'a>>,
+    /// `FDSelect` structure. Only present for CID-keyed fonts.
+    fd_select: Option<FdSelect<'a>>,
+}
+
+impl<'a> CffTable<'a> {
+    pub(super) fn parse(table: Cursor<'a>, glyph_count: u16) -> Result<Self, ParseError> {
+        let mut cursor = table;
+        cursor.read_u8_checked(|major_version| {
+            if major_version == 1 {
+                Ok(())
+            } else {
+                Err(ParseErrorKind::UnexpectedTableVersion(major_version.into()))
+            }
+        })?;
+        cursor.skip(1)?; // minor version
+        let header_len = cursor.read_u8()?;
+        let mut cursor = table;
+        cursor.skip(header_len.into())?;
+
+        let name_index = Index::parse(&mut cursor)?;
+        let top_dicts = Index::parse(&mut cursor)?;
+        let string_index = Index::parse(&mut cursor)?;
+        let global_subrs = Index::parse(&mut cursor)?;
+        if top_dicts.len() != 1 {
+            // OpenType fonts must contain exactly one font.
+            return Err(top_dicts.data.err(ParseErrorKind::InvalidCffDict));
+        }
+        let top_dict_cursor = top_dicts.get(0)?;
+        let top_dict = DictEntry::parse_dict(top_dict_cursor)?;
+
+        if let Some(entry) = DictEntry::find(&top_dict, DictEntry::CHARSTRING_TYPE) {
+            let charstring_type = entry.int_operands::<1>();
+            if charstring_type != Some([2]) {
+                let format = charstring_type.map_or(0, |[val]| u16::try_from(val).unwrap_or(0));
+                return Err(top_dict_cursor.err(ParseErrorKind::UnexpectedTableFormat(format)));
+            }
+        }
+
+        let [charstrings_offset] =
+            DictEntry::read_offsets(&top_dict, DictEntry::CHARSTRINGS, top_dict_cursor)?
+                .ok_or_else(|| top_dict_cursor.err(ParseErrorKind::InvalidCffDict))?;
+        let mut charstrings_cursor = table;
+        charstrings_cursor.skip(charstrings_offset)?;
+        let charstrings_start = charstrings_cursor;
+        let charstrings = Index::parse(&mut charstrings_cursor)?;
+        if charstrings.len() != glyph_count {
+            return Err(charstrings_start.err(ParseErrorKind::InvalidCffDict));
+        }
+
+        let charset_offset =
+            DictEntry::read_offsets(&top_dict, DictEntry::CHARSET, top_dict_cursor)?;
+        let charset = Charset::parse(
+            table,
+            charset_offset.map_or(0, |[offset]| offset),
+            glyph_count,
+        )?;
+
+        let is_cid_keyed = DictEntry::find(&top_dict, DictEntry::ROS).is_some();
+        let (font_dicts, fd_select) = if is_cid_keyed {
+            let [fd_array_offset] =
+                DictEntry::read_offsets(&top_dict, DictEntry::FD_ARRAY, top_dict_cursor)?
+                    .ok_or_else(|| top_dict_cursor.err(ParseErrorKind::InvalidCffDict))?;
+            let [fd_select_offset] =
+                DictEntry::read_offsets(&top_dict, DictEntry::FD_SELECT, top_dict_cursor)?
+                    .ok_or_else(|| top_dict_cursor.err(ParseErrorKind::InvalidCffDict))?;
+
+            let mut fd_array_cursor = table;
+            fd_array_cursor.skip(fd_array_offset)?;
+            let fd_array = Index::parse(&mut fd_array_cursor)?;
+            let font_dicts = (0..fd_array.len()).map(|i| {
+                let dict = fd_array.get(i)?;
+                FontDict::parse(table, DictEntry::parse_dict(dict)?, dict)
+            });
+            let font_dicts = font_dicts.collect::<Result<_, _>>()?;
+            let fd_select = FdSelect::parse(table, fd_select_offset, glyph_count)?;
+            (font_dicts, Some(fd_select))
+        } else {
+            let font_dict = FontDict::parse(table, top_dict.clone(), top_dict_cursor)?;
+            let font_dict = FontDict {
+                entries: Vec::new(),
+                ..font_dict
+            };
+            (Vec::from([font_dict]), None)
+        };
+
+        Ok(Self {
+            name_index,
+            top_dict,
+            string_index,
+            global_subrs,
+            charstrings,
+            charset,
+            font_dicts,
+            fd_select,
+        })
+    }
+
+    /// Checks whether this is a CID-keyed font.
+    pub(crate) fn is_cid_keyed(&self) -> bool {
+        self.fd_select.is_some()
+    }
+
+    pub(crate) fn glyph(&self, glyph_idx: u16) -> Result<CffGlyph<'a>, ParseError> {
+        let charstring = self.charstrings.get(glyph_idx)?;
+        let id = self.charset.id(glyph_idx)?;
+        let font_dict = match &self.fd_select {
+            Some(fd_select) => fd_select.font_dict(glyph_idx)?,
+            None => 0,
+        };
+        let local_subrs = self
+            .font_dicts
+            .get(usize::from(font_dict))
+            .ok_or_else(|| charstring.err(ParseErrorKind::InvalidCharstring(glyph_idx)))?
+            .local_subrs
+            .as_ref();
+
+        let mut walker = CharstringWalker::new(&self.global_subrs, local_subrs);
+        walker
+            .walk(charstring.bytes, 0)
+            .map_err(|InvalidCharstring| {
+                charstring.err(ParseErrorKind::InvalidCharstring(glyph_idx))
+            })?;
+        Ok(CffGlyph {
+            charstring: charstring.bytes,
+            id,
+            font_dict,
+            contour_count: walker.contour_count,
+            global_subrs: walker.used_global_subrs,
+            local_subrs: walker.used_local_subrs,
+            seac_components: walker.seac_codes.and_then(|codes| self.resolve_seac(codes)),
+        })
+    }
+
+    /// Resolves the base and accent glyphs referenced by a `seac`-like `endchar` via standard encoding codes.
+    /// Returns `None` if the glyphs cannot be resolved (e.g., for CID-keyed fonts, which don't support `seac`);
+    /// in this case, the referencing glyph is retained as-is.
+    fn resolve_seac(&self, codes: [u8; 2]) -> Option<[u16; 2]> {
+        if self.is_cid_keyed() {
+            return None;
+        }
+        let [base, accent] = codes.map(|code| {
+            let sid = standard_encoding_sid(code)?;
+            self.charset.find_glyph(sid, self.charstrings.len())
+        });
+        Some([base?, accent?])
+    }
+}
@@ -1,7 +1,88 @@
 //! `cmap` table processing.
 
-use super::Cursor;
-use crate::{alloc::Vec, errors::ParseErrorKind, ParseError, TableTag};
+use super::{Cursor, ParseLimits};
+use crate::{
+    alloc::{BTreeSet, Cow, Vec},
+    errors::{LimitKind, ParseErrorKind},
+    ParseError, TableTag,
+};
+
+/// Set of chars covered by a [`Font`](crate::Font), i.e., mapped to a non-missing glyph by the `cmap` table.
+/// Can be obtained via [`Font::coverage_bitset()`](crate::Font::coverage_bitset()).
+///
+/// Internally, the set is represented as a sorted list of code point ranges, so membership checks
+/// and intersections are cheap even for fonts covering many chars.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CoverageBitset {
+    /// Sorted, non-overlapping and non-adjacent inclusive ranges of code points.
+    ranges: Vec<(u32, u32)>,
+}
+
+impl CoverageBitset {
+    fn from_ranges(mut ranges: Vec<(u32, u32)>) -> Self {
+        ranges.sort_unstable();
+        let mut merged_ranges = Vec::<(u32, u32)>::with_capacity(ranges.len());
+        for (start, end) in ranges {
+            match merged_ranges.last_mut() {
+                Some((_, prev_end)) if start <= prev_end.saturating_add(1) => {
+                    *prev_end = (*prev_end).max(end);
+                }
+                _ => merged_ranges.push((start, end)),
+            }
+        }
+        Self {
+            ranges: merged_ranges,
+        }
+    }
+
+    /// Returns sorted inclusive ranges of covered code points.
+    pub(crate) fn ranges(&self) -> &[(u32, u32)] {
+        &self.ranges
+    }
+
+    /// Returns covered chars in the increasing order.
+    pub(crate) fn into_chars(self) -> impl Iterator<Item = char> {
+        self.ranges
+            .into_iter()
+            .flat_map(|(start, end)| (start..=end).filter_map(char::from_u32))
+    }
+
+    /// Returns the number of covered chars.
+    pub(crate) fn char_count(&self) -> usize {
+        self.ranges
+            .iter()
+            .map(|&(start, end)| (end - start) as usize + 1)
+            .sum()
+    }
+
+    /// Checks whether the specified char is covered.
+    pub fn contains(&self, ch: char) -> bool {
+        let ch = u32::from(ch);
+        let range_idx = self.ranges.partition_point(|&(_, end)| end < ch);
+        self.ranges
+            .get(range_idx)
+            .is_some_and(|&(start, _)| start <= ch)
+    }
+
+    /// Returns covered chars from the provided set. This takes linear time w.r.t. the number of `chars`
+    /// and ranges in this set.
+    pub fn intersect(&self, chars: &BTreeSet<char>) -> BTreeSet<char> {
+        let mut ranges = self.ranges.iter().peekable();
+        let covered_chars = chars.iter().copied().filter(|&ch| {
+            let ch = u32::from(ch);
+            let mut range = ranges.peek();
+            while let Some(&&(_, end)) = range {
+                if end >= ch {
+                    break;
+                }
+                ranges.next();
+                range = ranges.peek();
+            }
+            range.is_some_and(|&&(start, _)| start <= ch)
+        });
+        covered_chars.collect()
+    }
+}
 
 #[derive(Debug)]
 enum CmapTableFormat {
@@ -9,6 +90,8 @@ enum CmapTableFormat {
     SegmentDeltas,
     /// Segmented coverage (format 12).
     SegmentedCoverage,
+    /// Byte encoding (format 0) for Mac Roman char codes.
+    MacRoman,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -23,11 +106,12 @@ pub(crate) struct SegmentWithDelta {
 #[derive(Debug, Clone)]
 pub(crate) struct SegmentDeltas<'a> {
     pub(crate) segments: Vec<SegmentWithDelta>,
-    pub(crate) glyph_id_array: &'a [u8],
+    /// Raw `glyphIdArray` (big-endian `u16` glyph IDs).
+    pub(crate) glyph_id_array: Cow<'a, [u8]>,
 }
 
 impl<'a> SegmentDeltas<'a> {
-    fn parse(mut cursor: Cursor<'a>) -> Result<Self, ParseError> {
+    fn parse(mut cursor: Cursor<'a>, max_segment_count: u32) -> Result<Self, ParseError> {
         cursor.read_u16_checked(|format| {
             if format != 4 {
                 return Err(ParseErrorKind::UnexpectedTableFormat(format));
@@ -43,7 +127,15 @@ impl<'a> SegmentDeltas<'a> {
         cursor = cursor.range(0..remaining_len)?;
 
         cursor.skip(2)?; // language
-        let segment_count = cursor.read_u16()? / 2;
+        let segment_count = cursor.read_u16_checked(|segment_count_x2| {
+            let segment_count = segment_count_x2 / 2;
+            ParseLimits::check(
+                max_segment_count as usize,
+                segment_count.into(),
+                LimitKind::CmapSegmentCount,
+            )?;
+            Ok(segment_count)
+        })?;
         cursor.skip(6)?; // searchRange, entrySelector, rangeShift
 
         let vec_len = 2 * usize::from(segment_count);
@@ -64,24 +156,32 @@ impl<'a> SegmentDeltas<'a> {
 
         Ok(Self {
             segments: segments.collect::<Result<_, ParseError>>()?,
-            glyph_id_array: cursor.bytes,
+            glyph_id_array: Cow::Borrowed(cursor.bytes),
         })
     }
 
-    fn map_char(&self, c: char) -> Result<u16, ParseError> {
+    /// Returns `None` if `c` is not covered by any segment.
+    fn lookup_char(&self, c: char) -> Result<Option<u16>, ParseError> {
         let Ok(c) = u16::try_from(c as u32) else {
-            return Ok(0); // missing glyph
+            return Ok(None);
         };
 
         let segment_idx = self
             .segments
             .binary_search_by_key(&c, |segment| segment.end_code)
             .unwrap_or_else(|pos| pos);
-        let segment = &self.segments[segment_idx];
+        let Some(segment) = self.segments.get(segment_idx) else {
+            return Ok(None); // `c` exceeds `end_code` for the last segment (which is malformed per spec)
+        };
         if segment.start_code > c {
-            return Ok(0); // missing glyph
+            return Ok(None);
         }
+        self.map_char_in_segment(segment_idx, c).map(Some)
+    }
 
+    /// Maps a char known to belong to the specified segment.
+    fn map_char_in_segment(&self, segment_idx: usize, c: u16) -> Result<u16, ParseError> {
+        let segment = &self.segments[segment_idx];
         if segment.id_range_offset == 0 {
             Ok(segment.id_delta.wrapping_add(c))
         } else {
@@ -111,6 +211,33 @@ impl<'a> SegmentDeltas<'a> {
             Ok(segment.id_delta.wrapping_add(glyph_id))
         }
     }
+
+    /// Pushes ranges of chars mapped to non-missing glyphs. Chars with unresolvable mappings are skipped.
+    fn push_coverage(&self, ranges: &mut Vec<(u32, u32)>) {
+        for (segment_idx, segment) in self.segments.iter().enumerate() {
+            let (start, end) = (segment.start_code, segment.end_code);
+            if segment.id_range_offset == 0 {
+                // At most one char in the segment is mapped to the missing glyph
+                let missing_char = 0_u16.wrapping_sub(segment.id_delta);
+                if (start..=end).contains(&missing_char) {
+                    if missing_char > start {
+                        ranges.push((start.into(), (missing_char - 1).into()));
+                    }
+                    if missing_char < end {
+                        ranges.push(((missing_char + 1).into(), end.into()));
+                    }
+                } else if start <= end {
+                    ranges.push((start.into(), end.into()));
+                }
+            } else {
+                for c in start..=end {
+                    if matches!(self.map_char_in_segment(segment_idx, c), Ok(id) if id != 0) {
+                        ranges.push((c.into(), c.into()));
+                    }
+                }
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -133,7 +260,7 @@ pub(crate) struct SegmentedCoverage {
 }
 
 impl SegmentedCoverage {
-    fn parse(mut cursor: Cursor<'_>) -> Result<Self, ParseError> {
+    fn parse(mut cursor: Cursor<'_>, max_group_count: u32) -> Result<Self, ParseError> {
         cursor.read_u16_checked(|format| {
             if format != 12 {
                 return Err(ParseErrorKind::UnexpectedTableFormat(format));
@@ -151,7 +278,14 @@ impl SegmentedCoverage {
         cursor = cursor.range(0..remaining_len)?;
 
         cursor.skip(4)?; // language
-        let num_groups = cursor.read_u32()?;
+        let num_groups = cursor.read_u32_checked(|num_groups| {
+            ParseLimits::check(
+                max_group_count as usize,
+                num_groups as usize,
+                LimitKind::CmapSegmentCount,
+            )?;
+            Ok(num_groups)
+        })?;
         let groups = (0..num_groups).map(|_| {
             Ok(SequentialMapGroup {
                 start_char_code: cursor.read_u32()?,
@@ -165,20 +299,345 @@ impl SegmentedCoverage {
         })
     }
 
-    fn map_char(&self, ch: char) -> u16 {
+    /// Returns `None` if `ch` is not covered by any group.
+    fn lookup_char(&self, ch: char) -> Result<Option<u16>, ParseError> {
         let ch = u32::from(ch);
         let group_idx = self
             .groups
             .binary_search_by_key(&ch, |group| group.end_char_code)
             .unwrap_or_else(|pos| pos);
         let Some(group) = self.groups.get(group_idx) else {
-            return 0; // `ch` exceeds `end_char_code` for the last segment
+            return Ok(None); // `ch` exceeds `end_char_code` for the last segment
         };
         if group.start_char_code > ch {
-            return 0; // missing glyph
+            return Ok(None);
+        }
+        let glyph_id = (ch - group.start_char_code).checked_add(group.start_glyph_id);
+        let glyph_id = glyph_id
+            .and_then(|id| u16::try_from(id).ok())
+            .ok_or_else(|| {
+                // The group maps chars to glyph IDs outside the 16-bit range.
+                let start_glyph_id = group.start_glyph_id as usize;
+                let group_len = (group.end_char_code - group.start_char_code) as usize + 1;
+                ParseError {
+                    kind: ParseErrorKind::RangeOutOfBounds {
+                        range: start_glyph_id..start_glyph_id.saturating_add(group_len),
+                        len: usize::from(u16::MAX) + 1,
+                    },
+                    offset: 0,
+                    table: Some(TableTag::CMAP),
+                }
+            })?;
+        Ok(Some(glyph_id))
+    }
+
+    /// Pushes ranges of chars mapped to non-missing glyphs.
+    fn push_coverage(&self, ranges: &mut Vec<(u32, u32)>) {
+        for group in &self.groups {
+            let mut start = group.start_char_code;
+            if group.start_glyph_id == 0 {
+                // The first char in the group is mapped to the missing glyph
+                start = start.saturating_add(1);
+            }
+            let end = group.end_char_code.min(char::MAX.into());
+            if start <= end {
+                ranges.push((start, end));
+            }
+        }
+    }
+}
+
+/// Trimmed table mapping (format 6) subtable of the `cmap` table, i.e., a dense array of glyph IDs
+/// for a contiguous range of BMP chars.
+#[derive(Debug, Clone)]
+pub(crate) struct TrimmedMapping<'a> {
+    first_code: u16,
+    glyph_id_array: &'a [u8],
+}
+
+impl<'a> TrimmedMapping<'a> {
+    fn parse(mut cursor: Cursor<'a>) -> Result<Self, ParseError> {
+        cursor.read_u16_checked(|format| {
+            if format != 6 {
+                return Err(ParseErrorKind::UnexpectedTableFormat(format));
+            }
+            Ok(())
+        })?;
+        cursor.skip(4)?; // length, language
+
+        let first_code = cursor.read_u16()?;
+        let entry_count = cursor.read_u16()?;
+        let glyph_id_array = cursor.range(0..2 * usize::from(entry_count))?;
+        Ok(Self {
+            first_code,
+            glyph_id_array: glyph_id_array.bytes,
+        })
+    }
+
+    /// Returns `None` if `ch` is outside the covered range.
+    fn lookup_char(&self, ch: char) -> Option<u16> {
+        let idx = u32::from(ch).checked_sub(self.first_code.into())?;
+        let offset = 2 * usize::try_from(idx).ok()?;
+        let bytes = self.glyph_id_array.get(offset..offset + 2)?;
+        Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    /// Pushes ranges of chars mapped to non-missing glyphs.
+    fn push_coverage(&self, ranges: &mut Vec<(u32, u32)>) {
+        let glyph_ids = self.glyph_id_array.chunks_exact(2);
+        for (ch, glyph_id) in (u32::from(self.first_code)..).zip(glyph_ids) {
+            if glyph_id != [0, 0] {
+                ranges.push((ch, ch));
+            }
+        }
+    }
+}
+
+/// Unicode code points for Mac Roman char codes `0x80..=0xff`. Lower char codes coincide with ASCII.
+#[rustfmt::skip]
+const MAC_ROMAN_HIGH_CODES: [u16; 128] = [
+    0x00c4, 0x00c5, 0x00c7, 0x00c9, 0x00d1, 0x00d6, 0x00dc, 0x00e1,
+    0x00e0, 0x00e2, 0x00e4, 0x00e3, 0x00e5, 0x00e7, 0x00e9, 0x00e8,
+    0x00ea, 0x00eb, 0x00ed, 0x00ec, 0x00ee, 0x00ef, 0x00f1, 0x00f3,
+    0x00f2, 0x00f4, 0x00f6, 0x00f5, 0x00fa, 0x00f9, 0x00fb, 0x00fc,
+    0x2020, 0x00b0, 0x00a2, 0x00a3, 0x00a7, 0x2022, 0x00b6, 0x00df,
+    0x00ae, 0x00a9, 0x2122, 0x00b4, 0x00a8, 0x2260, 0x00c6, 0x00d8,
+    0x221e, 0x00b1, 0x2264, 0x2265, 0x00a5, 0x00b5, 0x2202, 0x2211,
+    0x220f, 0x03c0, 0x222b, 0x00aa, 0x00ba, 0x03a9, 0x00e6, 0x00f8,
+    0x00bf, 0x00a1, 0x00ac, 0x221a, 0x0192, 0x2248, 0x2206, 0x00ab,
+    0x00bb, 0x2026, 0x00a0, 0x00c0, 0x00c3, 0x00d5, 0x0152, 0x0153,
+    0x2013, 0x2014, 0x201c, 0x201d, 0x2018, 0x2019, 0x00f7, 0x25ca,
+    0x00ff, 0x0178, 0x2044, 0x20ac, 0x2039, 0x203a, 0xfb01, 0xfb02,
+    0x2021, 0x00b7, 0x201a, 0x201e, 0x2030, 0x00c2, 0x00ca, 0x00c1,
+    0x00cb, 0x00c8, 0x00cd, 0x00ce, 0x00cf, 0x00cc, 0x00d3, 0x00d4,
+    0xf8ff, 0x00d2, 0x00da, 0x00db, 0x00d9, 0x0131, 0x02c6, 0x02dc,
+    0x00af, 0x02d8, 0x02d9, 0x02da, 0x00b8, 0x02dd, 0x02db, 0x02c7,
+];
+
+/// Byte encoding table (format 0) subtable of the `cmap` table, i.e., a dense array of single-byte glyph IDs
+/// for 256 char codes.
+#[derive(Debug, Clone)]
+pub(crate) struct ByteEncoding<'a> {
+    glyph_id_array: &'a [u8],
+    /// Are char codes in the Mac Roman encoding (as opposed to Unicode code points)?
+    is_mac_roman: bool,
+}
+
+impl<'a> ByteEncoding<'a> {
+    const CODE_COUNT: usize = 256;
+
+    fn parse(mut cursor: Cursor<'a>, is_mac_roman: bool) -> Result<Self, ParseError> {
+        cursor.read_u16_checked(|format| {
+            if format != 0 {
+                return Err(ParseErrorKind::UnexpectedTableFormat(format));
+            }
+            Ok(())
+        })?;
+        cursor.skip(4)?; // length, language
+
+        let glyph_id_array = cursor.range(0..Self::CODE_COUNT)?;
+        Ok(Self {
+            glyph_id_array: glyph_id_array.bytes,
+            is_mac_roman,
+        })
+    }
+
+    /// Converts `ch` to a char code, or returns `None` if `ch` cannot be encoded.
+    fn encode(&self, ch: char) -> Option<u8> {
+        let code = u8::try_from(u32::from(ch)).ok();
+        if !self.is_mac_roman || code.is_some_and(|code| code < 0x80) {
+            return code;
         }
-        let glyph_id = ch - group.start_char_code + group.start_glyph_id;
-        glyph_id.try_into().expect("glyph ID exceeds u16::MAX")
+        let ch = u16::try_from(u32::from(ch)).ok()?;
+        let pos = MAC_ROMAN_HIGH_CODES
+            .iter()
+            .position(|&high_ch| high_ch == ch)?;
+        // `unwrap()` is safe: there are 128 high codes
+        Some(0x80 + u8::try_from(pos).unwrap())
+    }
+
+    /// Converts a char code to a Unicode code point.
+    fn decode(&self, code: u8) -> u32 {
+        if self.is_mac_roman && code >= 0x80 {
+            MAC_ROMAN_HIGH_CODES[usize::from(code - 0x80)].into()
+        } else {
+            code.into()
+        }
+    }
+
+    /// Returns `None` if `ch` cannot be encoded.
+    fn lookup_char(&self, ch: char) -> Option<u16> {
+        let code = self.encode(ch)?;
+        Some(self.glyph_id_array[usize::from(code)].into())
+    }
+
+    /// Pushes ranges of chars mapped to non-missing glyphs.
+    fn push_coverage(&self, ranges: &mut Vec<(u32, u32)>) {
+        for (code, &glyph_id) in (0..=u8::MAX).zip(self.glyph_id_array) {
+            if glyph_id != 0 {
+                let ch = self.decode(code);
+                ranges.push((ch, ch));
+            }
+        }
+    }
+}
+
+/// Mapping of a Unicode variation sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum VariationMapping {
+    /// The sequence is mapped to the default glyph for the base char, i.e., one from the main `cmap` subtable.
+    Default,
+    /// The sequence is mapped to the specified glyph.
+    Glyph(u16),
+}
+
+/// `VariationSelector` record from a Unicode variation sequences subtable.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct VariationSelectorRecord<'a> {
+    pub(crate) selector: u32,
+    /// `(startUnicodeValue, additionalCount)` records from the default UVS table.
+    default_ranges: Cursor<'a>,
+    /// `(unicodeValue, glyphID)` records from the non-default UVS table.
+    glyph_mappings: Cursor<'a>,
+}
+
+impl<'a> VariationSelectorRecord<'a> {
+    const DEFAULT_RANGE_LEN: usize = 4;
+    const GLYPH_MAPPING_LEN: usize = 5;
+
+    fn parse(subtable: Cursor<'a>, cursor: &mut Cursor<'a>) -> Result<Self, ParseError> {
+        let selector = read_u24(cursor)?;
+        let default_offset = cursor.read_u32()? as usize;
+        let glyph_offset = cursor.read_u32()? as usize;
+        Ok(Self {
+            selector,
+            default_ranges: Self::parse_records(subtable, default_offset, Self::DEFAULT_RANGE_LEN)?,
+            glyph_mappings: Self::parse_records(subtable, glyph_offset, Self::GLYPH_MAPPING_LEN)?,
+        })
+    }
+
+    /// Parses a UVS table at the specified `offset` (0 means that the table is absent).
+    fn parse_records(
+        subtable: Cursor<'a>,
+        offset: usize,
+        record_len: usize,
+    ) -> Result<Cursor<'a>, ParseError> {
+        if offset == 0 {
+            return subtable.range(0..0);
+        }
+        let mut cursor = subtable;
+        cursor.skip(offset)?;
+        let record_count = cursor.read_u32()? as usize;
+        cursor.range(0..record_count.saturating_mul(record_len))
+    }
+
+    /// Iterates over glyphs from the non-default UVS table, in the order of base chars.
+    pub(crate) fn glyphs(self) -> impl Iterator<Item = u16> + 'a {
+        self.glyph_mappings
+            .bytes
+            .chunks_exact(Self::GLYPH_MAPPING_LEN)
+            .map(|mapping| u16::from_be_bytes([mapping[3], mapping[4]]))
+    }
+
+    /// Maps the `base` char combined with the selector of this record.
+    pub(crate) fn map_char(&self, base: char) -> Option<VariationMapping> {
+        let base = u32::from(base);
+        // Both tables are sorted by the base char as per spec.
+        let ranges = self.default_ranges.bytes;
+        let range_count = partition_by_u24(ranges, Self::DEFAULT_RANGE_LEN, base);
+        if let Some(range_idx) = range_count.checked_sub(1) {
+            let range = &ranges[range_idx * Self::DEFAULT_RANGE_LEN..];
+            if base <= u24_at(range) + u32::from(range[3]) {
+                return Some(VariationMapping::Default);
+            }
+        }
+
+        let mappings = self.glyph_mappings.bytes;
+        let mapping_count = partition_by_u24(mappings, Self::GLYPH_MAPPING_LEN, base);
+        let mapping_idx = mapping_count.checked_sub(1)?;
+        let mapping = &mappings[mapping_idx * Self::GLYPH_MAPPING_LEN..];
+        (u24_at(mapping) == base)
+            .then(|| VariationMapping::Glyph(u16::from_be_bytes([mapping[3], mapping[4]])))
+    }
+}
+
+fn u24_at(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]])
+}
+
+/// Returns the number of fixed-length `records` starting with a 24-bit value not exceeding `value`.
+/// Records must be sorted by this value.
+fn partition_by_u24(records: &[u8], record_len: usize, value: u32) -> usize {
+    let (mut low, mut high) = (0, records.len() / record_len);
+    while low < high {
+        let mid = (low + high) / 2;
+        if u24_at(&records[mid * record_len..]) <= value {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    low
+}
+
+fn read_u24(cursor: &mut Cursor<'_>) -> Result<u32, ParseError> {
+    cursor.read_byte_array::<3>().map(|bytes| u24_at(&bytes))
+}
+
+/// Unicode variation sequences (format 14) subtable of the `cmap` table.
+#[derive(Debug, Clone)]
+pub(crate) struct VariationSequences<'a> {
+    /// Records sorted by the variation selector.
+    pub(crate) records: Vec<VariationSelectorRecord<'a>>,
+}
+
+impl<'a> VariationSequences<'a> {
+    /// Encoding ID of the subtable for the Unicode platform.
+    pub(crate) const ENCODING_ID: u16 = 5;
+
+    /// Parses the subtable from the `cmap` table. Returns `Ok(None)` if the table doesn't contain the subtable.
+    pub(super) fn parse_cmap(cmap: Cursor<'a>) -> Result<Option<Self>, ParseError> {
+        let mut cursor = cmap;
+        cursor.skip(2)?; // version; checked when parsing the main subtable
+        let num_tables = cursor.read_u16()?;
+        for _ in 0..num_tables {
+            let platform_id = cursor.read_u16()?;
+            let encoding_id = cursor.read_u16()?;
+            let offset = cursor.read_u32()? as usize;
+            if (platform_id, encoding_id) == (CmapTable::UNICODE_PLATFORM, Self::ENCODING_ID) {
+                let mut subtable = cmap;
+                subtable.skip(offset)?;
+                return Self::parse(subtable).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    fn parse(subtable: Cursor<'a>) -> Result<Self, ParseError> {
+        let mut cursor = subtable;
+        cursor.read_u16_checked(|format| {
+            if format != 14 {
+                return Err(ParseErrorKind::UnexpectedTableFormat(format));
+            }
+            Ok(())
+        })?;
+        cursor.skip(4)?; // length
+        let record_count = cursor.read_u32()?;
+        let records =
+            (0..record_count).map(|_| VariationSelectorRecord::parse(subtable, &mut cursor));
+        Ok(Self {
+            records: records.collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Maps a variation sequence. Returns `None` if the sequence is not defined by the subtable.
+    pub(crate) fn map_char(&self, base: char, selector: char) -> Option<VariationMapping> {
+        let selector = u32::from(selector);
+        let record_idx = self
+            .records
+            .binary_search_by_key(&selector, |record| record.selector)
+            .ok()?;
+        self.records[record_idx].map_char(base)
     }
 }
 
@@ -186,13 +645,31 @@ impl SegmentedCoverage {
 pub(crate) enum CmapTable<'a> {
     Deltas(SegmentDeltas<'a>),
     Coverage(SegmentedCoverage),
+    /// Only produced when parsing; subsets always use one of other variants.
+    Trimmed(TrimmedMapping<'a>),
+    /// Only produced when parsing; subsets always use one of other variants.
+    Byte(ByteEncoding<'a>),
 }
 
 impl<'a> CmapTable<'a> {
     pub(crate) const UNICODE_PLATFORM: u16 = 0;
+    const MACINTOSH_PLATFORM: u16 = 1;
     const WINDOWS_PLATFORM: u16 = 3;
 
-    pub(super) fn parse(mut cursor: Cursor<'a>) -> Result<Self, ParseError> {
+    /// Returns the format of the parsed subtable.
+    pub(crate) fn format(&self) -> u16 {
+        match self {
+            Self::Deltas(_) => 4,
+            Self::Coverage(_) => 12,
+            Self::Trimmed(_) => 6,
+            Self::Byte(_) => 0,
+        }
+    }
+
+    pub(super) fn parse(
+        mut cursor: Cursor<'a>,
+        max_segment_count: u32,
+    ) -> Result<Self, ParseError> {
         let table_cursor = cursor;
         cursor.read_u16_checked(|version| {
             if version != 0 {
@@ -202,11 +679,16 @@ impl<'a> CmapTable<'a> {
         })?;
 
         let num_tables = cursor.read_u16()?;
+        // Subtables must be located after the header and the encoding records.
+        let directory_len = 4 + 8 * usize::from(num_tables);
         let mut this = None;
+        let mut mac_roman_offset = None;
         for _ in 0..num_tables {
             let platform_id = cursor.read_u16()?;
             let encoding_id = cursor.read_u16()?;
-            let offset = cursor.read_u32()?;
+            // The offset is only validated for the subtables that are parsed.
+            let offset_cursor = cursor;
+            cursor.skip(4)?;
             let expected_table_format = match (platform_id, encoding_id) {
                 (Self::UNICODE_PLATFORM, 3) | (Self::WINDOWS_PLATFORM, 1) => {
                     CmapTableFormat::SegmentDeltas
@@ -214,31 +696,96 @@ impl<'a> CmapTable<'a> {
                 (Self::UNICODE_PLATFORM, 4) | (Self::WINDOWS_PLATFORM, 10) => {
                     CmapTableFormat::SegmentedCoverage
                 }
+                (Self::MACINTOSH_PLATFORM, 0) => CmapTableFormat::MacRoman,
                 _ => continue, // unsupported table format
             };
 
+            // If the font contains both format 4 and format 12 subtables, the format 12 subtable is preferred
+            // since it's a superset of the format 4 one (i.e., additionally covers non-BMP chars).
+            // A legacy Mac Roman subtable is only parsed if there are no Unicode subtables.
             match expected_table_format {
+                CmapTableFormat::MacRoman => {
+                    mac_roman_offset = Some(offset_cursor);
+                }
                 CmapTableFormat::SegmentDeltas if this.is_none() => {
-                    let mut subtable = table_cursor;
-                    subtable.skip(offset as usize)?;
-                    this = Some(Self::Deltas(SegmentDeltas::parse(subtable)?));
+                    let subtable = Self::subtable(table_cursor, offset_cursor, directory_len)?;
+                    this = Some(Self::parse_bmp_subtable(subtable, max_segment_count)?);
                 }
-                CmapTableFormat::SegmentedCoverage if this.is_none() => {
-                    let mut subtable = table_cursor;
-                    subtable.skip(offset as usize)?;
-                    this = Some(Self::Coverage(SegmentedCoverage::parse(subtable)?));
+                CmapTableFormat::SegmentedCoverage if !matches!(this, Some(Self::Coverage(_))) => {
+                    let subtable = Self::subtable(table_cursor, offset_cursor, directory_len)?;
+                    this = Some(Self::Coverage(SegmentedCoverage::parse(
+                        subtable,
+                        max_segment_count,
+                    )?));
                 }
                 _ => { /* We've already got a necessary table; do nothing */ }
             }
         }
 
+        if let (None, Some(offset_cursor)) = (&this, mac_roman_offset) {
+            let subtable = Self::subtable(table_cursor, offset_cursor, directory_len)?;
+            // Mac Roman subtables in other formats (e.g., 6) are ignored.
+            if subtable.bytes.get(..2) == Some(&[0, 0]) {
+                this = Some(Self::Byte(ByteEncoding::parse(subtable, true)?));
+            }
+        }
         this.ok_or_else(|| cursor.err(ParseErrorKind::NoSupportedCmap))
     }
 
+    /// Reads the subtable offset from an encoding record and returns the cursor at the subtable start.
+    /// Subtables must be located after the header and the encoding records (of the total `directory_len`).
+    fn subtable(
+        table_cursor: Cursor<'a>,
+        mut offset_cursor: Cursor<'a>,
+        directory_len: usize,
+    ) -> Result<Cursor<'a>, ParseError> {
+        let offset = offset_cursor.read_u32_checked(|offset| {
+            let offset = offset as usize;
+            if offset < directory_len {
+                return Err(ParseErrorKind::OffsetOutOfBounds(offset));
+            }
+            Ok(offset)
+        })?;
+        let mut subtable = table_cursor;
+        subtable.skip(offset)?;
+        Ok(subtable)
+    }
+
+    /// Parses a subtable for BMP chars, which can be in format 4 (the common case), 6 or 0.
+    fn parse_bmp_subtable(
+        subtable: Cursor<'a>,
+        max_segment_count: u32,
+    ) -> Result<Self, ParseError> {
+        match subtable.bytes.get(..2) {
+            Some([0, 6]) => TrimmedMapping::parse(subtable).map(Self::Trimmed),
+            Some([0, 0]) => ByteEncoding::parse(subtable, false).map(Self::Byte),
+            _ => SegmentDeltas::parse(subtable, max_segment_count).map(Self::Deltas),
+        }
+    }
+
     pub(super) fn map_char(&self, ch: char) -> Result<u16, ParseError> {
+        Ok(self.lookup_char(ch)?.unwrap_or(0))
+    }
+
+    /// Maps `ch` to a glyph. Unlike [`Self::map_char()`], distinguishes between chars absent
+    /// from the subtable (`None`) and chars explicitly mapped to the missing glyph (`Some(0)`).
+    pub(super) fn lookup_char(&self, ch: char) -> Result<Option<u16>, ParseError> {
+        match self {
+            Self::Deltas(deltas) => deltas.lookup_char(ch),
+            Self::Coverage(coverage) => coverage.lookup_char(ch),
+            Self::Trimmed(trimmed) => Ok(trimmed.lookup_char(ch)),
+            Self::Byte(bytes) => Ok(bytes.lookup_char(ch)),
+        }
+    }
+
+    pub(super) fn coverage(&self) -> CoverageBitset {
+        let mut ranges = Vec::new();
         match self {
-            Self::Deltas(deltas) => deltas.map_char(ch),
-            Self::Coverage(coverage) => Ok(coverage.map_char(ch)),
+            Self::Deltas(deltas) => deltas.push_coverage(&mut ranges),
+            Self::Coverage(coverage) => coverage.push_coverage(&mut ranges),
+            Self::Trimmed(trimmed) => trimmed.push_coverage(&mut ranges),
+            Self::Byte(bytes) => bytes.push_coverage(&mut ranges),
         }
+        CoverageBitset::from_ranges(ranges)
     }
 }
@@ -0,0 +1,99 @@
+//! `GDEF` table processing.
+
+use super::Cursor;
+use crate::{errors::ParseErrorKind, ParseError};
+
+/// Class definition (`ClassDef`) table mapping glyph IDs to classes. Glyphs not covered by the table
+/// have class 0.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum ClassDef<'a> {
+    /// Format 1: classes for consecutive glyphs starting from `start_glyph`.
+    Array {
+        start_glyph: u16,
+        classes: Cursor<'a>,
+    },
+    /// Format 2: `(startGlyphID, endGlyphID, class)` records sorted by the start glyph ID.
+    Ranges(Cursor<'a>),
+}
+
+impl<'a> ClassDef<'a> {
+    const VERSION_1_0: u32 = 0x_0001_0000;
+    const VERSION_1_2: u32 = 0x_0001_0002;
+    const VERSION_1_3: u32 = 0x_0001_0003;
+    pub(crate) const RANGE_RECORD_LEN: usize = 6;
+
+    /// Parses the glyph class definition from the `GDEF` table. Returns `Ok(None)` if the table
+    /// doesn't contain one.
+    pub(super) fn parse_gdef(gdef: Cursor<'a>) -> Result<Option<Self>, ParseError> {
+        let mut cursor = gdef;
+        cursor.read_u32_checked(|version| match version {
+            Self::VERSION_1_0 | Self::VERSION_1_2 | Self::VERSION_1_3 => Ok(()),
+            _ => Err(ParseErrorKind::UnexpectedTableVersion(version)),
+        })?;
+        let offset = cursor.read_u16()?;
+        if offset == 0 {
+            return Ok(None);
+        }
+        let mut cursor = gdef;
+        cursor.skip(offset.into())?;
+        Self::parse(cursor).map(Some)
+    }
+
+    fn parse(mut cursor: Cursor<'a>) -> Result<Self, ParseError> {
+        let format = cursor.read_u16()?;
+        match format {
+            1 => {
+                let start_glyph = cursor.read_u16()?;
+                let glyph_count = cursor.read_u16()?;
+                Ok(Self::Array {
+                    start_glyph,
+                    classes: cursor.range(0..2 * usize::from(glyph_count))?,
+                })
+            }
+            2 => {
+                let range_count = cursor.read_u16()?;
+                let records_len = Self::RANGE_RECORD_LEN * usize::from(range_count);
+                Ok(Self::Ranges(cursor.range(0..records_len)?))
+            }
+            _ => Err(cursor.err(ParseErrorKind::UnexpectedTableFormat(format))),
+        }
+    }
+
+    /// Returns the class of the specified glyph.
+    pub(crate) fn class(&self, glyph_idx: u16) -> u16 {
+        let read_u16 = |bytes: &[u8], pos: usize| u16::from_be_bytes([bytes[pos], bytes[pos + 1]]);
+
+        match self {
+            Self::Array {
+                start_glyph,
+                classes,
+            } => {
+                let Some(idx) = glyph_idx.checked_sub(*start_glyph) else {
+                    return 0;
+                };
+                let pos = 2 * usize::from(idx);
+                if pos < classes.bytes.len() {
+                    read_u16(classes.bytes, pos)
+                } else {
+                    0
+                }
+            }
+            Self::Ranges(records) => {
+                let records = records.bytes;
+                let (mut start, mut end) = (0, records.len() / Self::RANGE_RECORD_LEN);
+                while start < end {
+                    let mid = (start + end) / 2;
+                    let pos = mid * Self::RANGE_RECORD_LEN;
+                    if glyph_idx < read_u16(records, pos) {
+                        end = mid;
+                    } else if glyph_idx > read_u16(records, pos + 2) {
+                        start = mid + 1;
+                    } else {
+                        return read_u16(records, pos + 4);
+                    }
+                }
+                0
+            }
+        }
+    }
+}
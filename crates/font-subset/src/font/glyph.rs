@@ -1,9 +1,86 @@
 //! `Glyph` and related types.
 
-use super::Cursor;
+use super::{CffGlyph, Cursor};
 use crate::{alloc::Vec, ParseError};
 
-#[derive(Debug)]
+/// Kind of a glyph as exposed by [`GlyphInfo`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum GlyphKind {
+    /// Glyph without an outline (e.g., a space).
+    Empty,
+    /// Simple glyph with the specified number of contours.
+    Simple {
+        /// Number of contours in the glyph.
+        contour_count: u16,
+    },
+    /// Composite glyph referencing other glyphs. For fonts with CFF outlines, this is a glyph
+    /// using the deprecated `seac` accent composition, with the base and accent glyphs as components.
+    Composite {
+        /// IDs of the component glyphs, in the order of their declaration.
+        components: Vec<u16>,
+    },
+}
+
+/// Information about a glyph in a [`Font`](crate::Font) or a [`FontSubset`](crate::FontSubset).
+/// Can be obtained via [`Font::glyph_info()`](crate::Font::glyph_info())
+/// or [`FontSubset::glyph()`](crate::FontSubset::glyph()).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlyphInfo {
+    kind: GlyphKind,
+    advance: u16,
+    lsb: i16,
+}
+
+impl GlyphInfo {
+    pub(crate) fn new(glyph: &GlyphWithMetrics<'_>) -> Self {
+        let kind = match &glyph.inner {
+            Glyph::Empty => GlyphKind::Empty,
+            Glyph::Simple(bytes) => GlyphKind::Simple {
+                contour_count: u16::from_be_bytes([bytes[0], bytes[1]]),
+            },
+            Glyph::Composite { components, .. } => GlyphKind::Composite {
+                components: components
+                    .iter()
+                    .map(|component| component.glyph_idx)
+                    .collect(),
+            },
+            Glyph::Cff(CffGlyph {
+                seac_components: Some(components),
+                ..
+            }) => GlyphKind::Composite {
+                components: components.to_vec(),
+            },
+            Glyph::Cff(glyph) if glyph.contour_count == 0 => GlyphKind::Empty,
+            Glyph::Cff(glyph) => GlyphKind::Simple {
+                contour_count: glyph.contour_count,
+            },
+        };
+        Self {
+            kind,
+            advance: glyph.advance,
+            lsb: i16::from_be_bytes(glyph.lsb.to_be_bytes()),
+        }
+    }
+
+    /// Returns the kind of this glyph. For composite glyphs, component IDs refer to glyphs in the font
+    /// or subset this glyph was obtained from.
+    pub fn kind(&self) -> &GlyphKind {
+        &self.kind
+    }
+
+    /// Returns the advance width of the glyph in font units.
+    pub fn advance(&self) -> u16 {
+        self.advance
+    }
+
+    /// Returns the left side bearing of the glyph in font units.
+    pub fn lsb(&self) -> i16 {
+        self.lsb
+    }
+}
+
+#[derive(Debug, Clone)]
 pub(crate) enum Glyph<'a> {
     Empty,
     Simple(&'a [u8]),
@@ -14,6 +91,8 @@ pub(crate) enum Glyph<'a> {
         /// Optional instructions after the last component descriptor
         instructions: &'a [u8],
     },
+    /// Glyph from the `CFF ` table.
+    Cff(CffGlyph<'a>),
 }
 
 impl<'a> Glyph<'a> {
@@ -40,13 +119,109 @@ impl<'a> Glyph<'a> {
                 instructions: cursor.bytes,
             })
         } else {
-            // Simple glyph
+            // Simple glyph; check that the bounding box is present
+            cursor.skip(8)?;
             Ok(Self::Simple(raw.bytes))
         }
     }
+
+    /// Returns the bounding box of the glyph in the `[x_min, y_min, x_max, y_max]` format.
+    pub(crate) fn bbox(&self) -> Option<[i16; 4]> {
+        let header = match self {
+            // CFF glyphs don't declare a bounding box.
+            Self::Empty | Self::Cff(_) => return None,
+            // The length of `bytes` is checked when parsing
+            Self::Simple(bytes) => &bytes[2..10],
+            Self::Composite { header, .. } => header,
+        };
+        let mut bbox = [0_i16; 4];
+        for (coord, bytes) in bbox.iter_mut().zip(header.chunks_exact(2)) {
+            *coord = i16::from_be_bytes([bytes[0], bytes[1]]);
+        }
+        Some(bbox)
+    }
+
+    /// Checks whether this glyph from a subset has the same outline as the `original` glyph. Component IDs
+    /// of composite glyphs are mapped to the original IDs using `new_to_old_glyph_idx`.
+    pub(crate) fn matches(&self, original: &Self, new_to_old_glyph_idx: &[u16]) -> bool {
+        match (self, original) {
+            (Self::Empty, Self::Empty) => true,
+            (Self::Simple(bytes), Self::Simple(original_bytes)) => bytes == original_bytes,
+            (
+                Self::Composite {
+                    header,
+                    components,
+                    instructions,
+                },
+                Self::Composite {
+                    header: original_header,
+                    components: original_components,
+                    instructions: original_instructions,
+                },
+            ) => {
+                header == original_header
+                    && instructions == original_instructions
+                    && components.len() == original_components.len()
+                    && components
+                        .iter()
+                        .zip(original_components)
+                        .all(|(component, original)| {
+                            let old_idx =
+                                new_to_old_glyph_idx.get(usize::from(component.glyph_idx));
+                            old_idx == Some(&original.glyph_idx)
+                                && component.flags == original.flags
+                                && component.args == original.args
+                                && component.transform == original.transform
+                        })
+            }
+            (Self::Cff(glyph), Self::Cff(original)) => {
+                glyph.charstring == original.charstring
+                    && glyph.id == original.id
+                    && glyph.font_dict == original.font_dict
+            }
+            _ => false,
+        }
+    }
+
+    /// Splits a simple glyph into the part preceding `instructionLength` (i.e., the header and `endPtsOfContours`),
+    /// instructions, and the part following them (flags and coordinates). Returns `None` if the glyph is truncated.
+    pub(crate) fn split_instructions(bytes: &[u8]) -> Option<(&[u8], &[u8], &[u8])> {
+        let contour_count = u16::from_be_bytes([bytes[0], bytes[1]]);
+        let offset = 10 + 2 * usize::from(contour_count);
+        let len_bytes = bytes.get(offset..offset + 2)?;
+        let instructions_end =
+            offset + 2 + usize::from(u16::from_be_bytes([len_bytes[0], len_bytes[1]]));
+        let instructions = bytes.get(offset + 2..instructions_end)?;
+        Some((&bytes[..offset], instructions, &bytes[instructions_end..]))
+    }
+
+    /// Checks whether the glyph contains non-empty TrueType instructions.
+    pub(crate) fn has_instructions(&self) -> bool {
+        let len_bytes = match self {
+            Self::Empty | Self::Cff(_) => return false,
+            Self::Simple(bytes) => {
+                // Instruction length follows the header and `endPtsOfContours` array.
+                let contour_count = u16::from_be_bytes([bytes[0], bytes[1]]);
+                let offset = 10 + 2 * usize::from(contour_count);
+                bytes.get(offset..offset + 2)
+            }
+            Self::Composite {
+                components,
+                instructions,
+                ..
+            } => {
+                let last_flags = components.last().map_or(0, |component| component.flags);
+                if last_flags & GlyphComponent::WE_HAVE_INSTRUCTIONS == 0 {
+                    return false;
+                }
+                instructions.get(..2)
+            }
+        };
+        len_bytes.is_some_and(|len| len != [0, 0])
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct GlyphComponent {
     pub(crate) flags: u16,
     pub(crate) glyph_idx: u16,
@@ -55,6 +230,14 @@ pub(crate) struct GlyphComponent {
 }
 
 impl GlyphComponent {
+    /// Flag signalling that the composite glyph has instructions after this (last) component.
+    pub(crate) const WE_HAVE_INSTRUCTIONS: u16 = 0x0100;
+    /// Maximum supported nesting depth of composite glyphs.
+    pub(crate) const MAX_DEPTH: usize = 16;
+    /// Maximum supported total number of components in a flattened composite glyph, including components
+    /// of nested composite glyphs.
+    pub(crate) const MAX_FLATTENED_COUNT: usize = 65_535;
+
     fn new(cursor: &mut Cursor<'_>) -> Result<(Self, bool), ParseError> {
         const ARG_1_AND_2_ARE_WORDS: u16 = 0x0001;
         const WE_HAVE_A_SCALE: u16 = 0x008;
@@ -95,13 +278,13 @@ impl GlyphComponent {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) enum GlyphComponentArgs {
     U16(u16),
     U32(u32),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) enum TransformData {
     None,
     Scale(u16),
@@ -110,9 +293,12 @@ pub(crate) enum TransformData {
 }
 
 /// [`Glyph`] together with metrics read from the `hmtx` table.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct GlyphWithMetrics<'a> {
     pub(crate) inner: Glyph<'a>,
     pub(crate) advance: u16,
     pub(crate) lsb: u16,
+    /// Advance height and top side bearing read from the `vmtx` table. Only populated if vertical metrics
+    /// are retained during subsetting.
+    pub(crate) vertical_metrics: Option<(u16, u16)>,
 }
@@ -0,0 +1,75 @@
+//! `kern` table processing.
+
+use super::Cursor;
+use crate::{
+    alloc::{vec, Vec},
+    ParseError,
+};
+
+/// Format 0 subtable of the `kern` table.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct KernSubtable<'a> {
+    /// Coverage field of the subtable header, including the format in the high byte.
+    pub(crate) coverage: u16,
+    /// `(left, right, value)` records sorted by `(left, right)`.
+    pub(crate) pairs: Cursor<'a>,
+}
+
+impl KernSubtable<'_> {
+    /// Iterates over `(left glyph, right glyph, value)` pairs in this subtable.
+    pub(crate) fn pairs(&self) -> impl Iterator<Item = (u16, u16, [u8; 2])> + '_ {
+        self.pairs
+            .bytes
+            .chunks_exact(KernTable::PAIR_LEN)
+            .map(|pair| {
+                let left = u16::from_be_bytes([pair[0], pair[1]]);
+                let right = u16::from_be_bytes([pair[2], pair[3]]);
+                (left, right, [pair[4], pair[5]])
+            })
+    }
+}
+
+/// `kern` table in the Microsoft format (i.e., version 0). Only subtables in format 0 (ordered kerning pairs)
+/// are retained.
+#[derive(Debug, Clone)]
+pub(crate) struct KernTable<'a> {
+    pub(crate) subtables: Vec<KernSubtable<'a>>,
+}
+
+impl<'a> KernTable<'a> {
+    pub(crate) const SUBTABLE_HEADER_LEN: usize = 14;
+    pub(crate) const PAIR_LEN: usize = 6;
+
+    /// Parses the table. Returns `Ok(None)` if the table has Apple's format or an unknown version,
+    /// which are not supported.
+    pub(super) fn parse(kern: Cursor<'a>) -> Result<Option<Self>, ParseError> {
+        let mut cursor = kern;
+        if cursor.read_u16()? != 0 {
+            // Apple's version starts with `1_u16`; other versions are not defined.
+            return Ok(None);
+        }
+
+        let subtable_count = cursor.read_u16()?;
+        let mut subtables = vec![];
+        for _ in 0..subtable_count {
+            let mut subtable = cursor;
+            subtable.skip(2)?; // version
+            let len = subtable.read_u16()?;
+            let coverage = subtable.read_u16()?;
+            if coverage >> 8 != 0 {
+                // Only format 0 is supported; other formats are skipped.
+                cursor.skip(len.into())?;
+                continue;
+            }
+
+            let pair_count = subtable.read_u16()?;
+            subtable.skip(6)?; // searchRange, entrySelector, rangeShift
+            let pairs_len = Self::PAIR_LEN * usize::from(pair_count);
+            let pairs = subtable.range(0..pairs_len)?;
+            subtables.push(KernSubtable { coverage, pairs });
+            // The subtable length is not used since it overflows for large subtables in real-world fonts.
+            cursor.skip(Self::SUBTABLE_HEADER_LEN + pairs_len)?;
+        }
+        Ok(Some(Self { subtables }))
+    }
+}
@@ -0,0 +1,274 @@
+//! `MATH` table processing.
+
+use super::Cursor;
+use crate::{
+    alloc::{vec, Vec},
+    errors::ParseErrorKind,
+    ParseError,
+};
+
+/// Parses a coverage table (format 1 or 2) into the list of glyph IDs ordered by the coverage index.
+/// Since glyphs in a coverage table are distinct, the table cannot cover more than `glyph_count` glyphs;
+/// this bounds the expansion of format 2 ranges.
+fn parse_coverage(
+    table: Cursor<'_>,
+    offset: u16,
+    glyph_count: u16,
+) -> Result<Vec<u16>, ParseError> {
+    let mut cursor = table;
+    cursor.skip(offset.into())?;
+    let format = cursor.read_u16()?;
+    match format {
+        1 => {
+            let glyph_count = cursor.read_u16()?;
+            (0..glyph_count).map(|_| cursor.read_u16()).collect()
+        }
+        2 => {
+            let range_count = cursor.read_u16()?;
+            let mut glyphs = vec![];
+            for _ in 0..range_count {
+                let start_glyph = cursor.read_u16()?;
+                let end_glyph = cursor.read_u16()?;
+                let range_len = if end_glyph < start_glyph {
+                    0
+                } else {
+                    usize::from(end_glyph - start_glyph) + 1
+                };
+                let coverage_indices = glyphs.len()..glyphs.len() + range_len;
+                if coverage_indices.end > usize::from(glyph_count) {
+                    return Err(cursor.err(ParseErrorKind::RangeOutOfBounds {
+                        range: coverage_indices,
+                        len: glyph_count.into(),
+                    }));
+                }
+                cursor.skip(2)?; // startCoverageIndex
+                glyphs.extend(start_glyph..=end_glyph);
+            }
+            Ok(glyphs)
+        }
+        _ => Err(cursor.err(ParseErrorKind::UnexpectedTableFormat(format))),
+    }
+}
+
+/// Glyph construction from the `MathVariants` table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct GlyphConstruction {
+    /// `(glyph, advance measurement)` tuples for the pre-designed size variants.
+    pub(crate) variants: Vec<(u16, u16)>,
+    pub(crate) assembly: Option<GlyphAssembly>,
+}
+
+impl GlyphConstruction {
+    pub(crate) const VARIANT_RECORD_LEN: usize = 4;
+
+    fn parse(mut cursor: Cursor<'_>) -> Result<Self, ParseError> {
+        let construction = cursor;
+        let assembly_offset = cursor.read_u16()?;
+        let variant_count = cursor.read_u16()?;
+        let variants = (0..variant_count)
+            .map(|_| Ok((cursor.read_u16()?, cursor.read_u16()?)))
+            .collect::<Result<_, ParseError>>()?;
+        let assembly = if assembly_offset == 0 {
+            None
+        } else {
+            let mut cursor = construction;
+            cursor.skip(assembly_offset.into())?;
+            Some(GlyphAssembly::parse(cursor)?)
+        };
+        Ok(Self { variants, assembly })
+    }
+}
+
+/// Glyph assembly from the `MathVariants` table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct GlyphAssembly {
+    /// Italics correction of the assembled glyph (without the device table).
+    pub(crate) italics_correction: i16,
+    /// Parts of the assembly as `(glyph, other fields)` tuples. Other fields are connector lengths,
+    /// the full advance and flags, which are copied verbatim.
+    pub(crate) parts: Vec<(u16, [u8; 8])>,
+}
+
+impl GlyphAssembly {
+    pub(crate) const PART_RECORD_LEN: usize = 10;
+
+    fn parse(mut cursor: Cursor<'_>) -> Result<Self, ParseError> {
+        let italics_correction = cursor.read_u16()?;
+        cursor.skip(2)?; // deviceOffset
+        let part_count = cursor.read_u16()?;
+        let parts = (0..part_count)
+            .map(|_| Ok((cursor.read_u16()?, cursor.read_byte_array()?)))
+            .collect::<Result<_, ParseError>>()?;
+        Ok(Self {
+            italics_correction: i16::from_be_bytes(italics_correction.to_be_bytes()),
+            parts,
+        })
+    }
+}
+
+/// Mathematical typesetting table (`MATH`).
+///
+/// Only the data referencing glyphs via glyph IDs is parsed: italics corrections, top accent attachments
+/// and extended shapes from `MathGlyphInfo`, and glyph constructions from `MathVariants`. Device tables
+/// and the `MathKernInfo` subtable are not parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MathTable<'a> {
+    /// Raw `MathConstants` subtable. It doesn't reference glyphs, so it's copied (almost) verbatim.
+    pub(crate) constants: &'a [u8],
+    /// `(glyph, value)` tuples ordered by the glyph ID.
+    pub(crate) italics_corrections: Vec<(u16, i16)>,
+    /// `(glyph, value)` tuples ordered by the glyph ID.
+    pub(crate) top_accent_attachments: Vec<(u16, i16)>,
+    /// Extended shape glyphs in the increasing order.
+    pub(crate) extended_shapes: Vec<u16>,
+    pub(crate) min_connector_overlap: u16,
+    /// `(glyph, construction)` tuples ordered by the glyph ID.
+    pub(crate) vertical_constructions: Vec<(u16, GlyphConstruction)>,
+    /// `(glyph, construction)` tuples ordered by the glyph ID.
+    pub(crate) horizontal_constructions: Vec<(u16, GlyphConstruction)>,
+}
+
+impl<'a> MathTable<'a> {
+    pub(crate) const HEADER_LEN: usize = 10;
+    /// Byte length of the `MathConstants` subtable: 4 16-bit values, 51 `MathValueRecord`s
+    /// and a trailing 16-bit value.
+    pub(crate) const CONSTANTS_LEN: usize = 214;
+    /// Byte length of the leading 16-bit values in the `MathConstants` subtable.
+    pub(crate) const CONSTANTS_PREFIX_LEN: usize = 8;
+    pub(crate) const VALUE_RECORD_LEN: usize = 4;
+
+    pub(super) fn parse(math: Cursor<'a>, glyph_count: u16) -> Result<Self, ParseError> {
+        let mut cursor = math;
+        cursor.read_u16_checked(|major_version| {
+            if major_version == 1 {
+                Ok(())
+            } else {
+                Err(ParseErrorKind::UnexpectedTableVersion(major_version.into()))
+            }
+        })?;
+        cursor.skip(2)?; // minorVersion
+        let constants_offset = cursor.read_u16()?;
+        let glyph_info_offset = cursor.read_u16()?;
+        let variants_offset = cursor.read_u16()?;
+
+        let mut constants = math;
+        constants.skip(constants_offset.into())?;
+        let mut this = Self {
+            constants: constants.range(0..Self::CONSTANTS_LEN)?.bytes,
+            italics_corrections: vec![],
+            top_accent_attachments: vec![],
+            extended_shapes: vec![],
+            min_connector_overlap: 0,
+            vertical_constructions: vec![],
+            horizontal_constructions: vec![],
+        };
+        if glyph_info_offset != 0 {
+            let mut glyph_info = math;
+            glyph_info.skip(glyph_info_offset.into())?;
+            this.parse_glyph_info(glyph_info, glyph_count)?;
+        }
+        if variants_offset != 0 {
+            let mut variants = math;
+            variants.skip(variants_offset.into())?;
+            this.parse_variants(variants, glyph_count)?;
+        }
+        Ok(this)
+    }
+
+    fn parse_glyph_info(
+        &mut self,
+        glyph_info: Cursor<'_>,
+        glyph_count: u16,
+    ) -> Result<(), ParseError> {
+        let mut cursor = glyph_info;
+        let italics_offset = cursor.read_u16()?;
+        let top_accents_offset = cursor.read_u16()?;
+        let extended_shapes_offset = cursor.read_u16()?;
+        // `mathKernInfoOffset` is ignored
+
+        if italics_offset != 0 {
+            self.italics_corrections =
+                Self::parse_glyph_values(glyph_info, italics_offset, glyph_count)?;
+        }
+        if top_accents_offset != 0 {
+            self.top_accent_attachments =
+                Self::parse_glyph_values(glyph_info, top_accents_offset, glyph_count)?;
+        }
+        if extended_shapes_offset != 0 {
+            self.extended_shapes = parse_coverage(glyph_info, extended_shapes_offset, glyph_count)?;
+            self.extended_shapes.sort_unstable();
+        }
+        Ok(())
+    }
+
+    /// Parses a `MathItalicsCorrectionInfo` or `MathTopAccentAttachment` subtable, which have the same layout.
+    fn parse_glyph_values(
+        parent: Cursor<'_>,
+        offset: u16,
+        glyph_count: u16,
+    ) -> Result<Vec<(u16, i16)>, ParseError> {
+        let mut subtable = parent;
+        subtable.skip(offset.into())?;
+        let mut cursor = subtable;
+        let coverage_offset = cursor.read_u16()?;
+        let glyphs = parse_coverage(subtable, coverage_offset, glyph_count)?;
+        let count = cursor.read_u16()?;
+        if usize::from(count) != glyphs.len() {
+            return Err(cursor.err(ParseErrorKind::UnexpectedTableLen {
+                expected: glyphs.len(),
+                actual: count.into(),
+            }));
+        }
+
+        let mut values = glyphs
+            .into_iter()
+            .map(|glyph| {
+                let value = cursor.read_u16()?;
+                cursor.skip(2)?; // deviceOffset
+                Ok((glyph, i16::from_be_bytes(value.to_be_bytes())))
+            })
+            .collect::<Result<Vec<_>, ParseError>>()?;
+        values.sort_unstable_by_key(|&(glyph, _)| glyph);
+        Ok(values)
+    }
+
+    fn parse_variants(&mut self, variants: Cursor<'_>, glyph_count: u16) -> Result<(), ParseError> {
+        let mut cursor = variants;
+        self.min_connector_overlap = cursor.read_u16()?;
+        let vertical_coverage_offset = cursor.read_u16()?;
+        let horizontal_coverage_offset = cursor.read_u16()?;
+        let vertical_count = cursor.read_u16()?;
+        let horizontal_count = cursor.read_u16()?;
+
+        let mut parse_constructions = |coverage_offset: u16, count: u16| {
+            let glyphs = if coverage_offset == 0 {
+                vec![]
+            } else {
+                parse_coverage(variants, coverage_offset, glyph_count)?
+            };
+            if usize::from(count) != glyphs.len() {
+                return Err(cursor.err(ParseErrorKind::UnexpectedTableLen {
+                    expected: glyphs.len(),
+                    actual: count.into(),
+                }));
+            }
+
+            let mut constructions = glyphs
+                .into_iter()
+                .map(|glyph| {
+                    let mut construction = variants;
+                    construction.skip(cursor.read_u16()?.into())?;
+                    Ok((glyph, GlyphConstruction::parse(construction)?))
+                })
+                .collect::<Result<Vec<_>, ParseError>>()?;
+            constructions.sort_unstable_by_key(|(glyph, _)| *glyph);
+            Ok(constructions)
+        };
+
+        self.vertical_constructions =
+            parse_constructions(vertical_coverage_offset, vertical_count)?;
+        self.horizontal_constructions =
+            parse_constructions(horizontal_coverage_offset, horizontal_count)?;
+        Ok(())
+    }
+}
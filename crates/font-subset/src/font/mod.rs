@@ -3,25 +3,49 @@
 use core::{fmt, ops};
 
 pub(crate) use self::{
-    cmap::{CmapTable, SegmentDeltas, SegmentWithDelta, SegmentedCoverage, SequentialMapGroup},
+    bitmap::{BitmapGlyph, EmbeddedBitmaps, BITMAP_SIZE_LEN},
+    cff::{CffGlyph, CffTable, DictEntry, Index},
+    cmap::{
+        CmapTable, SegmentDeltas, SegmentWithDelta, SegmentedCoverage, SequentialMapGroup,
+        VariationMapping, VariationSequences,
+    },
+    gdef::ClassDef,
     glyph::{Glyph, GlyphComponent, GlyphComponentArgs, GlyphWithMetrics, TransformData},
+    kern::KernTable,
+    math::{GlyphAssembly, GlyphConstruction, MathTable},
+    name::{NameRecord, NameTable},
+    post::{GlyphName, PostGlyphNames},
+    sbix::{SbixGlyph, SbixTable},
+};
+pub use self::{
+    cmap::CoverageBitset,
+    glyph::{GlyphInfo, GlyphKind},
 };
 use crate::{
-    alloc::BTreeSet,
-    errors::{ParseError, ParseErrorKind},
-    FontSubset,
+    alloc::{BTreeSet, Box, Vec},
+    errors::{InvalidTableTag, LimitKind, ParseError, ParseErrorKind, SubsetError},
+    FontSubset, SubsetOptions,
 };
 
+mod bitmap;
+mod cff;
 mod cmap;
+mod gdef;
 mod glyph;
+mod kern;
+mod math;
+mod name;
+mod outline;
+mod post;
+mod sbix;
 
 /// 4-byte tag of an OpenType font table.
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct TableTag(pub(crate) [u8; 4]);
 
 impl fmt::Debug for TableTag {
     fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if let Ok(s) = core::str::from_utf8(&self.0) {
+        if let Some(s) = self.as_str() {
             fmt::Debug::fmt(&s, formatter)
         } else {
             write!(formatter, "0x{:x}", u32::from_be_bytes(self.0))
@@ -31,7 +55,7 @@ impl fmt::Debug for TableTag {
 
 impl fmt::Display for TableTag {
     fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if let Ok(s) = core::str::from_utf8(&self.0) {
+        if let Some(s) = self.as_str() {
             fmt::Display::fmt(&s, formatter)
         } else {
             write!(formatter, "0x{:x}", u32::from_be_bytes(self.0))
@@ -45,6 +69,19 @@ impl From<u32> for TableTag {
     }
 }
 
+impl TryFrom<&str> for TableTag {
+    type Error = InvalidTableTag;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        let bytes: [u8; 4] = s.as_bytes().try_into().map_err(|_| InvalidTableTag(()))?;
+        if bytes.iter().all(|&byte| (0x20..=0x7e).contains(&byte)) {
+            Ok(Self(bytes))
+        } else {
+            Err(InvalidTableTag(()))
+        }
+    }
+}
+
 impl TableTag {
     pub(crate) const CMAP: Self = Self(*b"cmap");
     pub(crate) const HEAD: Self = Self(*b"head");
@@ -59,6 +96,176 @@ impl TableTag {
     pub(crate) const CVT: Self = Self(*b"cvt ");
     pub(crate) const FPGM: Self = Self(*b"fpgm");
     pub(crate) const PREP: Self = Self(*b"prep");
+    pub(crate) const VHEA: Self = Self(*b"vhea");
+    pub(crate) const VMTX: Self = Self(*b"vmtx");
+    pub(crate) const COLR: Self = Self(*b"COLR");
+    pub(crate) const CPAL: Self = Self(*b"CPAL");
+    pub(crate) const EBDT: Self = Self(*b"EBDT");
+    pub(crate) const EBLC: Self = Self(*b"EBLC");
+    pub(crate) const EBSC: Self = Self(*b"EBSC");
+    pub(crate) const FVAR: Self = Self(*b"fvar");
+    pub(crate) const CFF: Self = Self(*b"CFF ");
+    pub(crate) const CFF2: Self = Self(*b"CFF2");
+    pub(crate) const GDEF: Self = Self(*b"GDEF");
+    pub(crate) const KERN: Self = Self(*b"kern");
+    pub(crate) const SBIX: Self = Self(*b"sbix");
+    pub(crate) const GASP: Self = Self(*b"gasp");
+    pub(crate) const MATH: Self = Self(*b"MATH");
+
+    /// Creates a tag from the provided bytes. Unlike the [`TryFrom`] conversion from a string,
+    /// this doesn't validate the tag.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use font_subset::{ParseError, TableTag};
+    /// fn is_outline_error(err: &ParseError) -> bool {
+    ///     err.table() == Some(TableTag::new(b"glyf"))
+    /// }
+    ///
+    /// assert_eq!(TableTag::try_from("glyf"), Ok(TableTag::new(b"glyf")));
+    /// assert!(TableTag::try_from("cvt").is_err());
+    /// ```
+    pub const fn new(tag: &[u8; 4]) -> Self {
+        Self(*tag)
+    }
+
+    /// Returns the string presentation of this tag (e.g., `"cmap"` or `"OS/2"`), or `None` if the tag
+    /// is not valid UTF-8.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use font_subset::TableTag;
+    /// let tag = TableTag::from(u32::from_be_bytes(*b"glyf"));
+    /// assert_eq!(tag.as_str(), Some("glyf"));
+    /// assert_eq!(TableTag::from(0xffff_ffff).as_str(), None);
+    /// ```
+    pub fn as_str(&self) -> Option<&str> {
+        core::str::from_utf8(&self.0).ok()
+    }
+}
+
+/// Checksum information for a single font table, as returned by [`Font::checksum_report()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TableChecksum {
+    tag: TableTag,
+    expected: u32,
+    actual: u32,
+}
+
+impl TableChecksum {
+    /// Returns the table tag.
+    pub fn tag(&self) -> TableTag {
+        self.tag
+    }
+
+    /// Returns the checksum declared in the table directory.
+    pub fn expected(&self) -> u32 {
+        self.expected
+    }
+
+    /// Returns the checksum computed from the table data.
+    pub fn actual(&self) -> u32 {
+        self.actual
+    }
+
+    /// Checks whether the declared checksum matches the actual one.
+    pub fn matches(&self) -> bool {
+        self.expected == self.actual
+    }
+}
+
+/// Cheap statistics collected when parsing a font, as returned by [`Font::parse_with_stats()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseStats {
+    table_count: u16,
+    glyph_count: u16,
+    cmap_format: u16,
+    has_hinting: bool,
+}
+
+impl ParseStats {
+    /// Returns the number of tables in the table directory, including ones not used by this crate.
+    pub fn table_count(&self) -> u16 {
+        self.table_count
+    }
+
+    /// Returns the number of glyphs declared in the `maxp` table.
+    pub fn glyph_count(&self) -> u16 {
+        self.glyph_count
+    }
+
+    /// Returns the format of the `cmap` subtable used for char mapping (0, 4, 6 or 12).
+    pub fn cmap_format(&self) -> u16 {
+        self.cmap_format
+    }
+
+    /// Checks whether the font contains any TrueType hinting tables (`cvt `, `fpgm` or `prep`).
+    pub fn has_hinting(&self) -> bool {
+        self.has_hinting
+    }
+}
+
+/// Limits on the input font data checked by [`Font::parse_with_limits()`]. Exceeding any limit
+/// results in a [`LimitExceeded`](ParseErrorKind::LimitExceeded) error returned before the corresponding
+/// data is allocated or processed.
+///
+/// By default, all limits are disabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseLimits {
+    font_len: usize,
+    table_count: u16,
+    glyph_count: u16,
+    cmap_segment_count: u32,
+}
+
+impl Default for ParseLimits {
+    fn default() -> Self {
+        Self {
+            font_len: usize::MAX,
+            table_count: u16::MAX,
+            glyph_count: u16::MAX,
+            cmap_segment_count: u32::MAX,
+        }
+    }
+}
+
+impl ParseLimits {
+    /// Sets the maximum length of the font data in bytes.
+    #[must_use]
+    pub fn max_font_len(mut self, max: usize) -> Self {
+        self.font_len = max;
+        self
+    }
+
+    /// Sets the maximum number of tables in the table directory.
+    #[must_use]
+    pub fn max_table_count(mut self, max: u16) -> Self {
+        self.table_count = max;
+        self
+    }
+
+    /// Sets the maximum number of glyphs declared in the `maxp` table.
+    #[must_use]
+    pub fn max_glyph_count(mut self, max: u16) -> Self {
+        self.glyph_count = max;
+        self
+    }
+
+    /// Sets the maximum number of segments (format 4) or groups (format 12) in a `cmap` subtable.
+    #[must_use]
+    pub fn max_cmap_segment_count(mut self, max: u32) -> Self {
+        self.cmap_segment_count = max;
+        self
+    }
+
+    fn check(max: usize, value: usize, limit: LimitKind) -> Result<(), ParseErrorKind> {
+        if value > max {
+            return Err(ParseErrorKind::LimitExceeded { limit, value, max });
+        }
+        Ok(())
+    }
 }
 
 /// Font reading cursor.
@@ -84,6 +291,11 @@ impl<'a> Cursor<'a> {
         }
     }
 
+    /// Returns the remaining bytes with the original lifetime (unlike `AsRef`).
+    pub(crate) fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
     fn err(&self, kind: ParseErrorKind) -> ParseError {
         ParseError {
             kind,
@@ -102,6 +314,26 @@ impl<'a> Cursor<'a> {
         }
     }
 
+    fn read_u8(&mut self) -> Result<u8, ParseError> {
+        let [byte, rest @ ..] = self.bytes else {
+            return Err(self.err(ParseErrorKind::UnexpectedEof));
+        };
+        self.bytes = rest;
+        self.offset += 1;
+        Ok(*byte)
+    }
+
+    fn read_u8_checked<T>(
+        &mut self,
+        check: impl FnOnce(u8) -> Result<T, ParseErrorKind>,
+    ) -> Result<T, ParseError> {
+        check(self.read_u8()?).map_err(|kind| ParseError {
+            kind,
+            table: self.table,
+            offset: self.offset - 1, // use the starting offset for the value
+        })
+    }
+
     fn read_u16(&mut self) -> Result<u16, ParseError> {
         let [a, b, rest @ ..] = self.bytes else {
             return Err(self.err(ParseErrorKind::UnexpectedEof));
@@ -174,6 +406,96 @@ impl<'a> Cursor<'a> {
     }
 }
 
+/// Big-endian reader for OpenType data, which can be used to parse tables not supported by this crate.
+///
+/// Errors returned by the reader have the same format as errors during font parsing; offsets in errors
+/// are relative to the start of the data provided to [`Self::new()`].
+///
+/// # Examples
+///
+/// ```
+/// use font_subset::{ParseErrorKind, Reader};
+///
+/// let mut reader = Reader::new(&[0, 1, 0xff, 0xfe, 0, 0, 0, 42]);
+/// assert_eq!(reader.read_u16()?, 1);
+/// assert_eq!(reader.read_i16()?, -2);
+/// assert_eq!(reader.read_u32()?, 42);
+/// assert_eq!(reader.offset(), 8);
+///
+/// let err = reader.read_u16().unwrap_err();
+/// assert!(matches!(err.kind(), ParseErrorKind::UnexpectedEof));
+/// assert_eq!(err.offset(), 8);
+/// # Ok::<_, font_subset::ParseError>(())
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Reader<'a>(Cursor<'a>);
+
+impl<'a> Reader<'a> {
+    /// Creates a reader for the provided bytes.
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self(Cursor::new(bytes))
+    }
+
+    /// Returns the current offset relative to the start of the data.
+    pub fn offset(&self) -> usize {
+        self.0.offset
+    }
+
+    /// Returns the remaining unread bytes.
+    pub fn remaining(&self) -> &'a [u8] {
+        self.0.bytes
+    }
+
+    /// Skips `n` bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are less than `n` remaining bytes.
+    pub fn skip(&mut self, n: usize) -> Result<(), ParseError> {
+        self.0.skip(n)
+    }
+
+    /// Reads a big-endian `u16` value.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are less than 2 remaining bytes.
+    pub fn read_u16(&mut self) -> Result<u16, ParseError> {
+        self.0.read_u16()
+    }
+
+    /// Reads a big-endian `i16` value.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are less than 2 remaining bytes.
+    pub fn read_i16(&mut self) -> Result<i16, ParseError> {
+        self.0
+            .read_u16()
+            .map(|val| i16::from_be_bytes(val.to_be_bytes()))
+    }
+
+    /// Reads a big-endian `u32` value.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are less than 4 remaining bytes.
+    pub fn read_u32(&mut self) -> Result<u32, ParseError> {
+        self.0.read_u32()
+    }
+
+    /// Creates a reader for the specified range of the remaining bytes. The position of this reader
+    /// is not changed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the range is out of bounds of the remaining bytes.
+    pub fn range(&self, range: ops::Range<usize>) -> Result<Self, ParseError> {
+        self.0.range(range).map(Self)
+    }
+}
+
+/// `hhea` table. Also used for the `vhea` table, which has the same layout.
 #[derive(Debug, Clone, Copy)]
 pub(crate) struct HheaTable<'a> {
     pub(crate) raw: &'a [u8],
@@ -182,6 +504,9 @@ pub(crate) struct HheaTable<'a> {
 
 impl<'a> HheaTable<'a> {
     pub(crate) const EXPECTED_LEN: usize = 36; // 18 words as per spec
+    const ASCENDER_OFFSET: usize = 4;
+    const DESCENDER_OFFSET: usize = 6;
+    const LINE_GAP_OFFSET: usize = 8;
 
     fn parse(cursor: Cursor<'a>) -> Result<Self, ParseError> {
         let bytes = cursor.bytes;
@@ -198,16 +523,81 @@ impl<'a> HheaTable<'a> {
             number_of_h_metrics,
         })
     }
+
+    /// Reads an `i16` value at the specified offset. The offset must be in bounds.
+    fn read_i16(&self, offset: usize) -> i16 {
+        i16::from_be_bytes([self.raw[offset], self.raw[offset + 1]])
+    }
 }
 
+/// `OS/2` table. Fields past the end of the table are treated as missing since legacy tables may be short;
+/// e.g., version 0 as per Apple's TrueType spec ends before typographic metrics (i.e., is 68 bytes long).
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Os2Table<'a> {
+    pub(crate) raw: &'a [u8],
+}
+
+impl<'a> Os2Table<'a> {
+    /// Offset of the `ulUnicodeRange1..4` fields.
+    pub(crate) const UNICODE_RANGE_OFFSET: usize = 42;
+    const FS_SELECTION_OFFSET: usize = 62;
+    /// Offset of the `usFirstCharIndex` field, which is followed by `usLastCharIndex`.
+    pub(crate) const FIRST_CHAR_INDEX_OFFSET: usize = 64;
+    const TYPO_ASCENDER_OFFSET: usize = 68;
+    const TYPO_DESCENDER_OFFSET: usize = 70;
+    const TYPO_LINE_GAP_OFFSET: usize = 72;
+    /// `USE_TYPO_METRICS` bit in `fsSelection`.
+    const USE_TYPO_METRICS: u16 = 1 << 7;
+
+    fn new(cursor: Cursor<'a>) -> Self {
+        Self { raw: cursor.bytes }
+    }
+
+    /// Reads an `i16` value at the specified offset, or returns `None` if the offset is out of bounds.
+    fn read_i16(&self, offset: usize) -> Option<i16> {
+        let bytes = self.raw.get(offset..offset + 2)?;
+        Some(i16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    /// Returns 0 (i.e., no flags set) if the field is missing.
+    fn fs_selection(&self) -> u16 {
+        let offset = Self::FS_SELECTION_OFFSET;
+        self.raw
+            .get(offset..offset + 2)
+            .map_or(0, |bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+}
+
+/// `hmtx` table. Also used for the `vmtx` table, which has the same layout.
 #[derive(Debug, Clone, Copy)]
 pub(crate) struct HmtxTable<'a> {
     raw: Cursor<'a>,
     number_of_h_metrics: u16,
 }
 
-impl HmtxTable<'_> {
-    fn advance_and_lsb(&self, glyph_idx: u16) -> Result<(u16, u16), ParseError> {
+impl<'a> HmtxTable<'a> {
+    /// Checks that the table can hold metrics for all `glyph_count` glyphs, i.e., `number_of_h_metrics` long
+    /// metrics followed by left side bearings for the remaining glyphs. Trailing data is tolerated.
+    fn new(
+        raw: Cursor<'a>,
+        number_of_h_metrics: u16,
+        glyph_count: u16,
+    ) -> Result<Self, ParseError> {
+        let expected_len = usize::from(number_of_h_metrics) * 4
+            + usize::from(glyph_count.saturating_sub(number_of_h_metrics)) * 2;
+        if raw.bytes.len() < expected_len {
+            return Err(raw.err(ParseErrorKind::UnexpectedTableLen {
+                expected: expected_len,
+                actual: raw.bytes.len(),
+            }));
+        }
+        Ok(Self {
+            raw,
+            number_of_h_metrics,
+        })
+    }
+
+    pub(crate) fn advance_and_lsb(&self, glyph_idx: u16) -> Result<(u16, u16), ParseError> {
         let (advance, lsb);
         if glyph_idx < self.number_of_h_metrics {
             let offset = usize::from(glyph_idx) * 4;
@@ -216,7 +606,12 @@ impl HmtxTable<'_> {
             advance = cursor.read_u16()?;
             lsb = cursor.read_u16()?;
         } else {
-            let advance_offset = usize::from(self.number_of_h_metrics - 1) * 4;
+            // `numberOfHMetrics` must be positive as per spec; we don't check this on parsing
+            let last_long_idx = self
+                .number_of_h_metrics
+                .checked_sub(1)
+                .ok_or_else(|| self.raw.err(ParseErrorKind::UnexpectedEof))?;
+            let advance_offset = usize::from(last_long_idx) * 4;
             let mut read_cursor = self.raw;
             read_cursor.skip(advance_offset)?;
             advance = read_cursor.read_u16()?;
@@ -250,13 +645,24 @@ impl LocaFormat {
 pub(crate) struct LocaTable<'a> {
     format: LocaFormat,
     cursor: Cursor<'a>,
+    /// Byte length of the `glyf` table; used to check glyph ranges.
+    glyf_len: usize,
 }
 
 impl<'a> LocaTable<'a> {
-    fn new(format: LocaFormat, glyph_count: u16, cursor: Cursor<'a>) -> Result<Self, ParseError> {
+    fn new(
+        format: LocaFormat,
+        glyph_count: u16,
+        cursor: Cursor<'a>,
+        glyf_len: usize,
+    ) -> Result<Self, ParseError> {
         let expected_len = format.bytes_per_offset() * (glyph_count as usize + 1);
         if cursor.bytes.len() == expected_len {
-            Ok(Self { format, cursor })
+            Ok(Self {
+                format,
+                cursor,
+                glyf_len,
+            })
         } else {
             Err(cursor.err(ParseErrorKind::UnexpectedTableLen {
                 expected: expected_len,
@@ -265,129 +671,384 @@ impl<'a> LocaTable<'a> {
         }
     }
 
-    fn glyph_range(&self, glyph_idx: u16) -> Result<ops::Range<usize>, ParseError> {
+    pub(crate) fn format(&self) -> LocaFormat {
+        self.format
+    }
+
+    pub(crate) fn as_bytes(&self) -> &'a [u8] {
+        self.cursor.bytes
+    }
+
+    /// Returns the byte range of the specified glyph in the `glyf` table. The range is checked
+    /// to be well-formed and to fit into the `glyf` table.
+    pub(crate) fn glyph_range(&self, glyph_idx: u16) -> Result<ops::Range<usize>, ParseError> {
         let glyph_idx = usize::from(glyph_idx);
-        Ok(match self.format {
+        let mut cursor = self.cursor;
+        cursor.skip(glyph_idx * self.format.bytes_per_offset())?;
+        let entry = cursor;
+        let range = match self.format {
             LocaFormat::Short => {
-                let mut cursor = self.cursor;
-                cursor.skip(glyph_idx * 2)?;
                 let start_offset = usize::from(cursor.read_u16()?) * 2;
                 let end_offset = usize::from(cursor.read_u16()?) * 2;
                 start_offset..end_offset
             }
             LocaFormat::Long => {
-                let mut cursor = self.cursor;
-                cursor.skip(glyph_idx * 4)?;
-                let start_offset = cursor.read_u32()? as usize;
-                let end_offset = cursor.read_u32()? as usize;
+                // Offsets not fitting into `usize` (which is only possible on 16-bit targets)
+                // are saturated, so that they are caught by the bounds check below.
+                let start_offset = usize::try_from(cursor.read_u32()?).unwrap_or(usize::MAX);
+                let end_offset = usize::try_from(cursor.read_u32()?).unwrap_or(usize::MAX);
                 start_offset..end_offset
             }
-        })
+        };
+
+        if range.start > range.end || range.end > self.glyf_len {
+            return Err(entry.err(ParseErrorKind::RangeOutOfBounds {
+                range,
+                len: self.glyf_len,
+            }));
+        }
+        Ok(range)
     }
 }
 
+/// Glyph outlines in a [`Font`].
+#[derive(Debug, Clone)]
+pub(crate) enum GlyphData<'a> {
+    /// TrueType outlines from the `glyf` table, located via the `loca` table.
+    TrueType {
+        loca: LocaTable<'a>,
+        glyf: Cursor<'a>,
+    },
+    /// CFF outlines from the `CFF ` table.
+    Cff(Box<CffTable<'a>>),
+}
+
 /// Shallowly parsed OpenType font.
+///
+/// Both TrueType outlines (the `glyf` table) and CFF outlines (the `CFF ` table) are supported.
+/// Some glyph-level methods (e.g., [`Self::glyph_bbox()`]) are only supported for TrueType outlines;
+/// for fonts with CFF outlines, they return an [`UnsupportedOutlineFormat`](ParseErrorKind::UnsupportedOutlineFormat)
+/// error. Fonts with CFF2 outlines cannot be parsed and return the same error.
 #[derive(Debug, Clone)]
 pub struct Font<'a> {
     pub(crate) cmap: CmapTable<'a>,
+    /// Raw `cmap` table, which can be copied verbatim if glyph IDs don't change.
+    pub(crate) raw_cmap: Cursor<'a>,
     pub(crate) head: Cursor<'a>,
     pub(crate) hhea: HheaTable<'a>,
     pub(crate) hmtx: HmtxTable<'a>,
     pub(crate) maxp: Cursor<'a>,
-    pub(crate) name: Cursor<'a>,
-    pub(crate) os2: Cursor<'a>,
+    /// `name` table. While required by the OpenType spec, it may be absent from embedded fonts
+    /// (e.g., ones produced by [`SubsetOptions::drop_name()`](crate::SubsetOptions::drop_name())).
+    pub(crate) name: Option<Cursor<'a>>,
+    pub(crate) os2: Option<Os2Table<'a>>,
     pub(crate) post: Cursor<'a>,
-    pub(crate) loca: LocaTable<'a>,
-    pub(crate) glyf: Cursor<'a>,
+    pub(crate) glyph_data: GlyphData<'a>,
+    pub(crate) glyph_count: u16,
     pub(crate) cvt: Option<Cursor<'a>>,
     pub(crate) fpgm: Option<Cursor<'a>>,
     pub(crate) prep: Option<Cursor<'a>>,
+    pub(crate) vhea: Option<HheaTable<'a>>,
+    pub(crate) vmtx: Option<Cursor<'a>>,
+    pub(crate) eblc: Option<Cursor<'a>>,
+    pub(crate) ebdt: Option<Cursor<'a>>,
+    pub(crate) ebsc: Option<Cursor<'a>>,
+    pub(crate) gdef: Option<Cursor<'a>>,
+    pub(crate) kern: Option<Cursor<'a>>,
+    pub(crate) sbix: Option<Cursor<'a>>,
+    /// Grid-fitting and scan-conversion procedure table. It doesn't reference glyphs, so it's copied verbatim.
+    pub(crate) gasp: Option<Cursor<'a>>,
+    pub(crate) math: Option<Cursor<'a>>,
+    /// Tags of all tables in the table directory, including ones not used by this crate.
+    pub(crate) table_tags: Vec<TableTag>,
+    /// Does the font contain color tables (`COLR` / `CPAL`)?
+    pub(crate) is_color: bool,
+    /// Does the font contain the `fvar` table?
+    pub(crate) is_variable: bool,
 }
 
 impl<'a> Font<'a> {
     pub(crate) const SFNT_VERSION: u32 = 0x_0001_0000;
+    /// sfnt version (`OTTO`) for fonts with CFF outlines.
+    pub(crate) const CFF_SFNT_VERSION: u32 = 0x_4f54_544f;
     pub(crate) const SFNT_CHECKSUM: u32 = 0x_b1b0_afba;
 
+    /// Byte length of a table record in the table directory.
+    const TABLE_RECORD_LEN: usize = 16;
+    /// Offset of `fontRevision` in the `head` table.
+    const FONT_REVISION_OFFSET: usize = 4;
     /// Offset of the checksum in the `head` table.
     pub(crate) const HEAD_CHECKSUM_OFFSET: usize = 8;
+    /// Offset of `unitsPerEm` in the `head` table.
+    const UNITS_PER_EM_OFFSET: usize = 18;
+    /// Offset of the font bounding box (`xMin`, `yMin`, `xMax`, `yMax`) in the `head` table.
+    pub(crate) const HEAD_BBOX_OFFSET: usize = 36;
+    /// Offset of `macStyle` in the `head` table.
+    pub(crate) const HEAD_MAC_STYLE_OFFSET: usize = 44;
+    /// Valid range of `unitsPerEm` values as per the OpenType spec.
+    const UNITS_PER_EM_RANGE: ops::RangeInclusive<u16> = 16..=16_384;
+    /// Maximum ratio of glyph coordinates to `unitsPerEm` considered sane by [`Self::validate_glyph_bounds()`].
+    const MAX_GLYPH_COORD_TO_EM_RATIO: i32 = 8;
 
     /// Parses `bytes` of an OpenType font.
     ///
     /// # Errors
     ///
-    /// Returns parsing errors.
+    /// Returns parsing errors. In particular, returns an error if a checksum of any table is incorrect;
+    /// use [`Self::parse_lenient()`] to skip checksum verification.
     pub fn new(bytes: &'a [u8]) -> Result<Self, ParseError> {
+        Self::parse(bytes, true, &ParseLimits::default())
+    }
+
+    /// Parses `bytes` of an OpenType font without verifying table checksums. Other checks
+    /// (e.g., that all tables are in bounds and 4-byte aligned) are still performed.
+    ///
+    /// Many fonts in the wild (e.g., ones processed by other tools) have stale table checksums,
+    /// but are otherwise valid.
+    ///
+    /// # Errors
+    ///
+    /// Returns parsing errors.
+    pub fn parse_lenient(bytes: &'a [u8]) -> Result<Self, ParseError> {
+        Self::parse(bytes, false, &ParseLimits::default())
+    }
+
+    /// Parses `bytes` of an OpenType font in the same way as [`Self::new()`], additionally checking
+    /// the provided `limits`. This is useful to reject untrusted fonts (e.g., uploaded by users) that would
+    /// require excessive resources to process.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`LimitExceeded`](ParseErrorKind::LimitExceeded) error if any of `limits` is exceeded.
+    /// Otherwise, returns parsing errors, similarly to [`Self::new()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use font_subset::{Font, LimitKind, ParseErrorKind, ParseLimits};
+    /// let font_bytes = // font in the OpenType format
+    /// # include_bytes!("../../examples/FiraMono-Regular.ttf");
+    /// let limits = ParseLimits::default().max_font_len(1 << 20);
+    /// let font = Font::parse_with_limits(font_bytes, limits)?;
+    ///
+    /// let limits = limits.max_glyph_count(100);
+    /// let err = Font::parse_with_limits(font_bytes, limits).unwrap_err();
+    /// assert!(matches!(
+    ///     err.kind(),
+    ///     ParseErrorKind::LimitExceeded { limit: LimitKind::GlyphCount, .. }
+    /// ));
+    /// # Ok::<_, font_subset::ParseError>(())
+    /// ```
+    pub fn parse_with_limits(bytes: &'a [u8], limits: ParseLimits) -> Result<Self, ParseError> {
+        Self::parse(bytes, true, &limits)
+    }
+
+    /// Parses an OpenType font embedded into a larger container (e.g., a PDF font stream or a resource bundle)
+    /// starting at `offset` in `bytes`. Table offsets in the font directory are treated as relative
+    /// to the font start, so this is equivalent to [`Self::new()`] on `&bytes[offset..]`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OffsetOutOfBounds`](ParseErrorKind::OffsetOutOfBounds) error if `offset` exceeds
+    /// the length of `bytes`. Otherwise, returns parsing errors, similarly to [`Self::new()`].
+    pub fn parse_at(bytes: &'a [u8], offset: usize) -> Result<Self, ParseError> {
+        let font_bytes = bytes
+            .get(offset..)
+            .ok_or_else(|| Cursor::new(bytes).err(ParseErrorKind::OffsetOutOfBounds(offset)))?;
+        Self::new(font_bytes)
+    }
+
+    /// Reports expected and actual checksums for all tables in the font. Unlike [`Self::new()`],
+    /// this doesn't stop on the first checksum mismatch, which is useful to diagnose fonts produced
+    /// by other tools.
+    ///
+    /// # Errors
+    ///
+    /// Returns parsing errors for the font header and the table directory (e.g., if a table is out of bounds).
+    pub fn checksum_report(bytes: &'a [u8]) -> Result<Vec<TableChecksum>, ParseError> {
+        let (mut cursor, table_count) = Self::read_sfnt_header(bytes, &ParseLimits::default())?;
+        (0..table_count)
+            .map(|_| {
+                let (tag, expected, table_cursor) = Self::read_table_record(&mut cursor, bytes)?;
+                Ok(TableChecksum {
+                    tag,
+                    expected,
+                    actual: Self::table_checksum(tag, table_cursor.bytes),
+                })
+            })
+            .collect()
+    }
+
+    /// Parses `bytes` of an OpenType font in the same way as [`Self::new()`], additionally returning
+    /// statistics about the font. This is useful to get an overview of a font corpus.
+    ///
+    /// # Errors
+    ///
+    /// Returns parsing errors, similarly to [`Self::new()`].
+    pub fn parse_with_stats(bytes: &'a [u8]) -> Result<(Self, ParseStats), ParseError> {
+        let (_, table_count) = Self::read_sfnt_header(bytes, &ParseLimits::default())?;
+        let font = Self::parse(bytes, true, &ParseLimits::default())?;
+        let stats = ParseStats {
+            table_count,
+            glyph_count: font.glyph_count,
+            cmap_format: font.cmap.format(),
+            has_hinting: font.cvt.is_some() || font.fpgm.is_some() || font.prep.is_some(),
+        };
+        Ok((font, stats))
+    }
+
+    /// Reads the sfnt header and returns the cursor pointing to the start of table records
+    /// together with the number of tables.
+    fn read_sfnt_header(
+        bytes: &'a [u8],
+        limits: &ParseLimits,
+    ) -> Result<(Cursor<'a>, u16), ParseError> {
         let mut cursor = Cursor::new(bytes);
-        let font_bytes = bytes;
+        ParseLimits::check(limits.font_len, bytes.len(), LimitKind::FontLen)
+            .map_err(|kind| cursor.err(kind))?;
         let sfnt_version = cursor.read_u32()?;
-        if sfnt_version != Self::SFNT_VERSION {
+        if sfnt_version != Self::SFNT_VERSION && sfnt_version != Self::CFF_SFNT_VERSION {
             return Err(cursor.err(ParseErrorKind::UnexpectedFontVersion));
         }
-        let table_count = cursor.read_u16()?;
+        let table_count = cursor.read_u16_checked(|table_count| {
+            ParseLimits::check(
+                limits.table_count.into(),
+                table_count.into(),
+                LimitKind::TableCount,
+            )?;
+            Ok(table_count)
+        })?;
         cursor.skip(6)?; // searchRange, entrySelector, rangeShift
+        if cursor.bytes.len() < usize::from(table_count) * Self::TABLE_RECORD_LEN {
+            return Err(cursor.err(ParseErrorKind::TruncatedTableDirectory { table_count }));
+        }
+        Ok((cursor, table_count))
+    }
+
+    fn parse(
+        bytes: &'a [u8],
+        verify_checksums: bool,
+        limits: &ParseLimits,
+    ) -> Result<Self, ParseError> {
+        let font_bytes = bytes;
+        let (mut cursor, table_count) = Self::read_sfnt_header(bytes, limits)?;
 
-        let table_records =
-            (0..table_count).map(|_| Self::parse_table_record(&mut cursor, font_bytes));
+        let table_records = (0..table_count)
+            .map(|_| Self::parse_table_record(&mut cursor, font_bytes, verify_checksums));
 
         let (mut cmap, mut head, mut hhea, mut maxp, mut hmtx) = (None, None, None, None, None);
         let (mut name, mut os2, mut post, mut loca, mut glyf) = (None, None, None, None, None);
-        let (mut cvt, mut fpgm, mut prep) = (None, None, None);
+        let (mut cvt, mut fpgm, mut prep, mut cff, mut gdef) = (None, None, None, None, None);
+        let (mut vhea, mut vmtx, mut kern, mut sbix, mut gasp) = (None, None, None, None, None);
+        let (mut bitmap_locations, mut bitmap_data, mut bitmap_scaling) = (None, None, None);
+        let mut math = None;
+        let (mut is_color, mut is_variable, mut has_cff2) = (false, false, false);
+        let mut table_tags = Vec::with_capacity(table_count.into());
         for record in table_records {
             let (tag, table_cursor) = record?;
+            table_tags.push(tag);
             match tag {
                 TableTag::CMAP => {
-                    cmap = Some(CmapTable::parse(table_cursor)?);
+                    cmap = Some((
+                        CmapTable::parse(table_cursor, limits.cmap_segment_count)?,
+                        table_cursor,
+                    ));
                 }
                 TableTag::HEAD => head = Some(table_cursor),
                 TableTag::HHEA => hhea = Some(HheaTable::parse(table_cursor)?),
                 TableTag::HMTX => hmtx = Some(table_cursor),
                 TableTag::MAXP => maxp = Some(table_cursor),
                 TableTag::NAME => name = Some(table_cursor),
-                TableTag::OS2 => os2 = Some(table_cursor),
+                TableTag::OS2 => os2 = Some(Os2Table::new(table_cursor)),
                 TableTag::POST => post = Some(table_cursor),
                 TableTag::LOCA => loca = Some(table_cursor),
                 TableTag::GLYF => glyf = Some(table_cursor),
+                TableTag::CFF => cff = Some(table_cursor),
+                TableTag::CFF2 => has_cff2 = true,
                 TableTag::CVT => cvt = Some(table_cursor),
                 TableTag::FPGM => fpgm = Some(table_cursor),
                 TableTag::PREP => prep = Some(table_cursor),
+                TableTag::VHEA => vhea = Some(HheaTable::parse(table_cursor)?),
+                TableTag::VMTX => vmtx = Some(table_cursor),
+                TableTag::COLR | TableTag::CPAL => is_color = true,
+                TableTag::FVAR => is_variable = true,
+                TableTag::EBLC => bitmap_locations = Some(table_cursor),
+                TableTag::EBDT => bitmap_data = Some(table_cursor),
+                TableTag::EBSC => bitmap_scaling = Some(table_cursor),
+                TableTag::GDEF => gdef = Some(table_cursor),
+                TableTag::KERN => kern = Some(table_cursor),
+                TableTag::SBIX => sbix = Some(table_cursor),
+                TableTag::GASP => gasp = Some(table_cursor),
+                TableTag::MATH => math = Some(table_cursor),
                 _ => { /* skip table */ }
             }
         }
 
         let head = head.ok_or_else(|| ParseError::missing_table(TableTag::HEAD))?;
-        let loca_format = Self::parse_loca_format(head)?;
+        let loca_format = Self::parse_head(head)?;
         let maxp = maxp.ok_or_else(|| ParseError::missing_table(TableTag::MAXP))?;
-        let glyph_count = Self::parse_glyph_count(maxp)?;
-        let loca = loca.ok_or_else(|| ParseError::missing_table(TableTag::LOCA))?;
-        let loca = LocaTable::new(loca_format, glyph_count, loca)?;
-        let hhea = hhea.ok_or_else(|| ParseError::missing_table(TableTag::HHEA))?;
-        let hmtx = HmtxTable {
-            raw: hmtx.ok_or_else(|| ParseError::missing_table(TableTag::HMTX))?,
-            number_of_h_metrics: hhea.number_of_h_metrics,
+        let glyph_count = Self::parse_glyph_count(maxp, limits.glyph_count)?;
+        let glyph_data = if let (None, None, Some(cff)) = (loca, glyf, cff) {
+            GlyphData::Cff(Box::new(CffTable::parse(cff, glyph_count)?))
+        } else if let (None, None, None, true) = (loca, glyf, cff, has_cff2) {
+            return Err(ParseError::unsupported_outlines(TableTag::CFF2));
+        } else {
+            let loca = loca.ok_or_else(|| ParseError::missing_table(TableTag::LOCA))?;
+            let glyf = glyf.ok_or_else(|| ParseError::missing_table(TableTag::GLYF))?;
+            GlyphData::TrueType {
+                loca: LocaTable::new(loca_format, glyph_count, loca, glyf.bytes.len())?,
+                glyf,
+            }
         };
+        let hhea = hhea.ok_or_else(|| ParseError::missing_table(TableTag::HHEA))?;
+        let hmtx = hmtx.ok_or_else(|| ParseError::missing_table(TableTag::HMTX))?;
+        let hmtx = HmtxTable::new(hmtx, hhea.number_of_h_metrics, glyph_count)?;
+        let (cmap, raw_cmap) = cmap.ok_or_else(|| ParseError::missing_table(TableTag::CMAP))?;
 
         Ok(Self {
-            cmap: cmap.ok_or_else(|| ParseError::missing_table(TableTag::CMAP))?,
+            cmap,
+            raw_cmap,
             head,
             hhea,
             hmtx,
             maxp,
-            name: name.ok_or_else(|| ParseError::missing_table(TableTag::NAME))?,
-            os2: os2.ok_or_else(|| ParseError::missing_table(TableTag::OS2))?,
+            name,
+            os2,
             post: post.ok_or_else(|| ParseError::missing_table(TableTag::POST))?,
-            loca,
-            glyf: glyf.ok_or_else(|| ParseError::missing_table(TableTag::GLYF))?,
+            glyph_data,
+            glyph_count,
             cvt,
             fpgm,
             prep,
+            vhea,
+            vmtx,
+            eblc: bitmap_locations,
+            ebdt: bitmap_data,
+            ebsc: bitmap_scaling,
+            gdef,
+            kern,
+            sbix,
+            gasp,
+            math,
+            table_tags,
+            is_color,
+            is_variable,
         })
     }
 
-    fn aligned_checksum(cursor: &Cursor<'_>) -> Result<u32, ParseError> {
-        if cursor.offset % 4 != 0 {
-            return Err(cursor.err(ParseErrorKind::UnalignedTable));
+    /// Computes the checksum of a table, excluding the checksum adjustment for the `head` table.
+    fn table_checksum(tag: TableTag, table_bytes: &[u8]) -> u32 {
+        let mut checksum = Self::checksum(table_bytes);
+        if tag == TableTag::HEAD {
+            // Zero out the checksum adjustment field.
+            let adjustment =
+                table_bytes.get(Self::HEAD_CHECKSUM_OFFSET..Self::HEAD_CHECKSUM_OFFSET + 4);
+            if let Some(adjustment) = adjustment {
+                let adjustment = u32::from_be_bytes(adjustment.try_into().unwrap());
+                checksum = checksum.wrapping_sub(adjustment);
+            }
         }
-        Ok(Self::checksum(cursor.bytes))
+        checksum
     }
 
     pub(crate) fn checksum(bytes: &[u8]) -> u32 {
@@ -402,14 +1063,40 @@ impl<'a> Font<'a> {
     fn parse_table_record(
         header_cursor: &mut Cursor<'_>,
         font_bytes: &'a [u8],
+        verify_checksum: bool,
     ) -> Result<(TableTag, Cursor<'a>), ParseError> {
+        let (tag, checksum, cursor) = Self::read_table_record(header_cursor, font_bytes)?;
+        if cursor.offset % 4 != 0 {
+            return Err(cursor.err(ParseErrorKind::UnalignedTable));
+        }
+        if !verify_checksum {
+            return Ok((tag, cursor));
+        }
+
+        let actual_checksum = Self::table_checksum(tag, cursor.bytes);
+        if checksum != actual_checksum {
+            return Err(cursor.err(ParseErrorKind::Checksum {
+                expected: checksum,
+                actual: actual_checksum,
+            }));
+        }
+        Ok((tag, cursor))
+    }
+
+    /// Reads a table record, returning the table tag, the declared checksum and the table cursor.
+    fn read_table_record(
+        header_cursor: &mut Cursor<'_>,
+        font_bytes: &'a [u8],
+    ) -> Result<(TableTag, u32, Cursor<'a>), ParseError> {
         let tag = TableTag::from(header_cursor.read_u32()?);
         let checksum = header_cursor.read_u32()?;
         let offset = header_cursor.read_u32()? as usize;
         let len = header_cursor.read_u32()? as usize;
-        let table_bytes = font_bytes.get(offset..(offset + len)).ok_or_else(|| {
+        // `offset + len` may overflow on 32-bit platforms
+        let end = offset.saturating_add(len);
+        let table_bytes = font_bytes.get(offset..end).ok_or_else(|| {
             header_cursor.err(ParseErrorKind::RangeOutOfBounds {
-                range: offset..(offset + len),
+                range: offset..end,
                 len: font_bytes.len(),
             })
         })?;
@@ -418,26 +1105,11 @@ impl<'a> Font<'a> {
             offset,
             table: Some(tag),
         };
-        let mut actual_checksum = Self::aligned_checksum(&cursor)?;
-        if tag == TableTag::HEAD {
-            // Zero out the checksum adjustment field.
-            let adjustment =
-                &table_bytes[Self::HEAD_CHECKSUM_OFFSET..Self::HEAD_CHECKSUM_OFFSET + 4];
-            let adjustment = u32::from_be_bytes(adjustment.try_into().unwrap());
-            actual_checksum = actual_checksum.wrapping_sub(adjustment);
-        }
-
-        if checksum != actual_checksum {
-            return Err(cursor.err(ParseErrorKind::Checksum {
-                expected: checksum,
-                actual: actual_checksum,
-            }));
-        }
-
-        Ok((tag, cursor))
+        Ok((tag, checksum, cursor))
     }
 
-    fn parse_loca_format(mut head_cursor: Cursor<'_>) -> Result<LocaFormat, ParseError> {
+    /// Validates the `head` table and returns the `loca` format declared in it.
+    fn parse_head(mut head_cursor: Cursor<'_>) -> Result<LocaFormat, ParseError> {
         head_cursor.read_u32_checked(|version| {
             if version != 0x_0001_0000 {
                 return Err(ParseErrorKind::UnexpectedTableVersion(version));
@@ -445,9 +1117,16 @@ impl<'a> Font<'a> {
             Ok(())
         })?;
 
-        head_cursor.skip(46)?;
-        // ^ fontRevision, checksumAdjustment, magicNumber, flags, unitsPerEm, created, modified,
-        // bounding box, macStyle, lowestRecPPEM, fontDirectionHint
+        head_cursor.skip(Self::UNITS_PER_EM_OFFSET - 4)?;
+        // ^ fontRevision, checksumAdjustment, magicNumber, flags
+        head_cursor.read_u16_checked(|units_per_em| {
+            if !Self::UNITS_PER_EM_RANGE.contains(&units_per_em) {
+                return Err(ParseErrorKind::InvalidUnitsPerEm(units_per_em));
+            }
+            Ok(())
+        })?;
+        head_cursor.skip(30)?;
+        // ^ created, modified, bounding box, macStyle, lowestRecPPEM, fontDirectionHint
 
         head_cursor.read_u16_checked(|format| match format {
             0 => Ok(LocaFormat::Short),
@@ -456,38 +1135,539 @@ impl<'a> Font<'a> {
         })
     }
 
-    fn parse_glyph_count(mut maxp_cursor: Cursor<'_>) -> Result<u16, ParseError> {
+    fn parse_glyph_count(mut maxp_cursor: Cursor<'_>, max: u16) -> Result<u16, ParseError> {
         maxp_cursor.read_u32_checked(|version| {
             if version != 0x_0000_5000 && version != 0x_0001_0000 {
                 return Err(ParseErrorKind::UnexpectedTableVersion(version));
             }
             Ok(())
         })?;
-        maxp_cursor.read_u16()
+        maxp_cursor.read_u16_checked(|glyph_count| {
+            ParseLimits::check(max.into(), glyph_count.into(), LimitKind::GlyphCount)?;
+            Ok(glyph_count)
+        })
+    }
+
+    /// Returns tags of all tables in the font in the table directory order. This includes tables
+    /// not used by this crate (e.g., layout tables like `GSUB`).
+    pub fn table_tags(&self) -> &[TableTag] {
+        &self.table_tags
+    }
+
+    /// Returns the number of glyphs in the font as declared in the `maxp` table. Valid glyph IDs
+    /// are `0..self.glyph_count()`.
+    pub fn glyph_count(&self) -> u16 {
+        self.glyph_count
+    }
+
+    /// Returns information about the glyph with the specified ID, or `None` if the ID is out of range.
+    /// For composite glyphs, component IDs refer to glyphs in this font.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the glyph data or metrics are malformed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use font_subset::Font;
+    /// let font_bytes = // font in the OpenType format
+    /// # include_bytes!("../../examples/FiraMono-Regular.ttf");
+    /// let font = Font::new(font_bytes)?;
+    /// let mut total_advance = 0_u64;
+    /// for glyph_idx in 0..font.glyph_count() {
+    ///     let glyph = font.glyph_info(glyph_idx)?.unwrap();
+    ///     total_advance += u64::from(glyph.advance());
+    /// }
+    /// assert!(total_advance > 0);
+    /// # Ok::<_, font_subset::ParseError>(())
+    /// ```
+    pub fn glyph_info(&self, glyph_idx: u16) -> Result<Option<GlyphInfo>, ParseError> {
+        if glyph_idx >= self.glyph_count {
+            return Ok(None);
+        }
+        Ok(Some(GlyphInfo::new(&self.glyph(glyph_idx)?)))
+    }
+
+    /// Returns the number of font design units per em, which defines the design grid for glyph coordinates.
+    /// The returned value is guaranteed to be in the `16..=16384` range; fonts with other values are rejected
+    /// when parsing.
+    pub fn units_per_em(&self) -> u16 {
+        let offset = Self::UNITS_PER_EM_OFFSET;
+        // `head` length is checked when parsing the font
+        let bytes = &self.head.bytes[offset..offset + 2];
+        u16::from_be_bytes([bytes[0], bytes[1]])
+    }
+
+    /// Returns the font revision set by the font manufacturer (the `fontRevision` field in the `head` table).
+    /// The revision is preserved when subsetting.
+    pub fn font_revision(&self) -> f32 {
+        let offset = Self::FONT_REVISION_OFFSET;
+        // `head` length is checked when parsing the font
+        let bytes = &self.head.bytes[offset..offset + 4];
+        // `fontRevision` is a signed 16.16 fixed-point number.
+        let integer = i16::from_be_bytes([bytes[0], bytes[1]]);
+        let fraction = u16::from_be_bytes([bytes[2], bytes[3]]);
+        f32::from(integer) + f32::from(fraction) / 65_536.0
+    }
+
+    /// Checks whether this font contains color tables (`COLR` or `CPAL`). These tables are not retained
+    /// when subsetting, so a subset of a color font is rendered using plain outlines.
+    /// Use [`SubsetOptions::error_on_color()`] to prevent this.
+    pub fn is_color(&self) -> bool {
+        self.is_color
+    }
+
+    /// Checks whether this font is variable, i.e., contains the `fvar` table.
+    ///
+    /// Variation tables (`fvar`, `gvar`, `HVAR` etc.) are not retained when subsetting. The `glyf` outlines
+    /// and metrics of a variable font describe its default instance, so a subset of a variable font is a static
+    /// font corresponding to the default instance (e.g., the regular weight and normal width).
+    pub fn is_variable(&self) -> bool {
+        self.is_variable
+    }
+
+    /// Returns the typographic ascent of the font in font design units (the `ascender` field
+    /// of the `hhea` table).
+    pub fn ascender(&self) -> i16 {
+        self.hhea.read_i16(HheaTable::ASCENDER_OFFSET)
+    }
+
+    /// Returns the typographic descent of the font in font design units (the `descender` field
+    /// of the `hhea` table). This value is usually negative.
+    pub fn descender(&self) -> i16 {
+        self.hhea.read_i16(HheaTable::DESCENDER_OFFSET)
+    }
+
+    /// Returns the typographic line gap of the font in font design units (the `lineGap` field
+    /// of the `hhea` table).
+    pub fn line_gap(&self) -> i16 {
+        self.hhea.read_i16(HheaTable::LINE_GAP_OFFSET)
+    }
+
+    /// Returns the typographic ascent of the font in font design units (the `sTypoAscender` field
+    /// of the `OS/2` table). Should be preferred over [`Self::ascender()`] if
+    /// [`Self::uses_typo_metrics()`] returns `true`.
+    ///
+    /// If the font has no `OS/2` table, or if it is a legacy version 0 table not containing typographic metrics,
+    /// falls back to [`Self::ascender()`]. The same applies to [`Self::typo_descender()`]
+    /// and [`Self::typo_line_gap()`], which fall back to the corresponding `hhea` metrics.
+    pub fn typo_ascender(&self) -> i16 {
+        self.typo_metric(Os2Table::TYPO_ASCENDER_OFFSET)
+            .unwrap_or_else(|| self.ascender())
+    }
+
+    /// Returns the typographic descent of the font in font design units (the `sTypoDescender` field
+    /// of the `OS/2` table). This value is usually negative.
+    pub fn typo_descender(&self) -> i16 {
+        self.typo_metric(Os2Table::TYPO_DESCENDER_OFFSET)
+            .unwrap_or_else(|| self.descender())
+    }
+
+    /// Returns the typographic line gap of the font in font design units (the `sTypoLineGap` field
+    /// of the `OS/2` table).
+    pub fn typo_line_gap(&self) -> i16 {
+        self.typo_metric(Os2Table::TYPO_LINE_GAP_OFFSET)
+            .unwrap_or_else(|| self.line_gap())
+    }
+
+    fn typo_metric(&self, offset: usize) -> Option<i16> {
+        self.os2.as_ref()?.read_i16(offset)
+    }
+
+    /// Checks whether the `USE_TYPO_METRICS` flag is set in the `fsSelection` field of the `OS/2` table.
+    /// If set, the typographic metrics ([`Self::typo_ascender()`] etc.) should be used for line layout
+    /// instead of the `hhea` metrics. Always returns `false` if the typographic metrics are missing
+    /// (including the case when the font has no `OS/2` table).
+    pub fn uses_typo_metrics(&self) -> bool {
+        self.os2.as_ref().is_some_and(|os2| {
+            os2.fs_selection() & Os2Table::USE_TYPO_METRICS != 0
+                && os2.read_i16(Os2Table::TYPO_LINE_GAP_OFFSET).is_some()
+        })
+    }
+
+    /// Returns the bounding box of the specified glyph in the `[x_min, y_min, x_max, y_max]` format,
+    /// as declared in the glyph header. Returns `None` for empty glyphs (e.g., the space glyph).
+    ///
+    /// # Errors
+    ///
+    /// Returns parsing errors for the glyph data.
+    pub fn glyph_bbox(&self, glyph_idx: u16) -> Result<Option<[i16; 4]>, ParseError> {
+        self.truetype_outlines()?;
+        Ok(self.glyph(glyph_idx)?.inner.bbox())
+    }
+
+    /// Returns the number of contours of the specified glyph (the `numberOfContours` field
+    /// of the glyph header). The returned value is negative for composite glyphs and 0 for empty glyphs.
+    ///
+    /// This is cheaper than parsing the glyph since only the first 2 bytes of the glyph data are read.
+    ///
+    /// # Errors
+    ///
+    /// Returns parsing errors for the `loca` table or the glyph data.
+    pub fn glyph_contour_count(&self, glyph_idx: u16) -> Result<i16, ParseError> {
+        let (loca, glyf) = self.truetype_outlines()?;
+        let mut raw = glyf.range(loca.glyph_range(glyph_idx)?)?;
+        if raw.bytes.is_empty() {
+            return Ok(0);
+        }
+        Ok(i16::from_be_bytes(raw.read_byte_array::<2>()?))
+    }
+
+    /// Checks that bounding boxes of all glyphs in the font are sane, i.e., have correctly ordered
+    /// min / max coordinates and fit into a reasonable multiple of [units per em](Self::units_per_em()).
+    /// Wildly out-of-range glyph coordinates are a signal of a corrupted font.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`InvalidGlyphBounds`](ParseErrorKind::InvalidGlyphBounds) error for the first
+    /// offending glyph. Also returns parsing errors for the glyph data.
+    pub fn validate_glyph_bounds(&self) -> Result<(), ParseError> {
+        let max_coord = i32::from(self.units_per_em()) * Self::MAX_GLYPH_COORD_TO_EM_RATIO;
+        for glyph_idx in 0..self.glyph_count {
+            let Some(bbox) = self.glyph_bbox(glyph_idx)? else {
+                continue;
+            };
+            let [x_min, y_min, x_max, y_max] = bbox;
+            let is_ordered = x_min <= x_max && y_min <= y_max;
+            let is_in_range = bbox
+                .iter()
+                .all(|&coord| i32::from(coord).abs() <= max_coord);
+            if !is_ordered || !is_in_range {
+                let (loca, glyf) = self.truetype_outlines()?;
+                let range = loca.glyph_range(glyph_idx)?;
+                return Err(ParseError {
+                    kind: ParseErrorKind::InvalidGlyphBounds(glyph_idx),
+                    offset: glyf.offset + range.start,
+                    table: Some(TableTag::GLYF),
+                });
+            }
+        }
+        Ok(())
     }
 
     pub(crate) fn map_char(&self, ch: char) -> Result<u16, ParseError> {
         self.cmap.map_char(ch)
     }
 
+    /// Maps a Unicode variation sequence consisting of the `base` char and the variation `selector`
+    /// (e.g., `U+FE0F` for the emoji presentation) to a glyph using the format 14 `cmap` subtable.
+    /// Sequences mapped to the default glyph for `base` are resolved using the main `cmap` subtable.
+    ///
+    /// Returns `Ok(None)` if the font doesn't define the sequence, or if it's mapped to the default glyph
+    /// and `base` is not covered by the font.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `cmap` table is malformed.
+    pub fn map_variation(&self, base: char, selector: char) -> Result<Option<u16>, ParseError> {
+        let Some(sequences) = self.variation_sequences()? else {
+            return Ok(None);
+        };
+        Ok(match sequences.map_char(base, selector) {
+            None => None,
+            Some(VariationMapping::Default) => Some(self.map_char(base)?).filter(|&idx| idx != 0),
+            Some(VariationMapping::Glyph(glyph_idx)) => Some(glyph_idx),
+        })
+    }
+
+    /// Returns IDs of glyphs that Unicode variation sequences in the format 14 `cmap` subtable are mapped to,
+    /// in the increasing order and without duplicates. Sequences mapped to the default glyph for the base char
+    /// are not taken into account. If the font has no format 14 subtable, the returned iterator is empty.
+    ///
+    /// [Subsets](FontSubset) automatically retain these glyphs for the retained base chars.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `cmap` table is malformed.
+    pub fn variation_glyphs(&self) -> Result<impl Iterator<Item = u16>, ParseError> {
+        let sequences = self.variation_sequences()?;
+        let glyphs: BTreeSet<u16> = sequences
+            .into_iter()
+            .flat_map(|sequences| sequences.records)
+            .flat_map(cmap::VariationSelectorRecord::glyphs)
+            .collect();
+        Ok(glyphs.into_iter())
+    }
+
+    /// Returns Unicode variation sequences from the `cmap` table, if the table contains a format 14 subtable.
+    pub(crate) fn variation_sequences(&self) -> Result<Option<VariationSequences<'a>>, ParseError> {
+        VariationSequences::parse_cmap(self.raw_cmap)
+    }
+
+    /// Returns the set of chars covered by this font, i.e., mapped to non-missing glyphs.
+    /// Chars with mappings that cannot be resolved (e.g., because of out-of-bounds offsets in the `cmap` table)
+    /// are considered not covered.
+    pub fn coverage_bitset(&self) -> CoverageBitset {
+        self.cmap.coverage()
+    }
+
+    /// Returns the `loca` and `glyf` tables, or an error if the font has CFF outlines.
+    pub(crate) fn truetype_outlines(&self) -> Result<(&LocaTable<'a>, Cursor<'a>), ParseError> {
+        match &self.glyph_data {
+            GlyphData::TrueType { loca, glyf } => Ok((loca, *glyf)),
+            GlyphData::Cff(_) => Err(ParseError::unsupported_outlines(TableTag::CFF)),
+        }
+    }
+
     pub(crate) fn glyph(&self, glyph_idx: u16) -> Result<GlyphWithMetrics<'a>, ParseError> {
-        let range = self.loca.glyph_range(glyph_idx)?;
-        let raw = self.glyf.range(range.clone())?;
+        let (loca, glyf) = match &self.glyph_data {
+            GlyphData::TrueType { loca, glyf } => (loca, *glyf),
+            GlyphData::Cff(cff) => {
+                let inner = Glyph::Cff(cff.glyph(glyph_idx)?);
+                return self.with_metrics(glyph_idx, inner);
+            }
+        };
+        let range = loca.glyph_range(glyph_idx)?;
+        let raw = glyf.range(range.clone())?;
         let inner = Glyph::new(raw)?;
+        if let Glyph::Composite { components, .. } = &inner {
+            let invalid_component = components
+                .iter()
+                .find(|component| component.glyph_idx >= self.glyph_count);
+            if let Some(component) = invalid_component {
+                return Err(ParseError {
+                    kind: ParseErrorKind::InvalidComponentGlyph {
+                        referrer: glyph_idx,
+                        referenced: component.glyph_idx,
+                    },
+                    offset: glyf.offset + range.start,
+                    table: Some(TableTag::GLYF),
+                });
+            }
+        }
+        self.with_metrics(glyph_idx, inner)
+    }
+
+    fn with_metrics(
+        &self,
+        glyph_idx: u16,
+        inner: Glyph<'a>,
+    ) -> Result<GlyphWithMetrics<'a>, ParseError> {
         let (advance, lsb) = self.hmtx.advance_and_lsb(glyph_idx)?;
         Ok(GlyphWithMetrics {
             inner,
             advance,
             lsb,
+            vertical_metrics: None,
+        })
+    }
+
+    /// Returns glyph names from the `post` table, or `None` if the table doesn't contain glyph names.
+    pub(crate) fn glyph_names(&self) -> Result<Option<PostGlyphNames<'a>>, ParseError> {
+        PostGlyphNames::parse(self.post)
+    }
+
+    /// Returns embedded bitmaps if the font has both `EBLC` and `EBDT` tables.
+    pub(crate) fn embedded_bitmaps(&self) -> Result<Option<EmbeddedBitmaps<'a>>, ParseError> {
+        let (Some(eblc), Some(ebdt)) = (self.eblc, self.ebdt) else {
+            return Ok(None);
+        };
+        EmbeddedBitmaps::parse(eblc, ebdt).map(Some)
+    }
+
+    /// Returns the glyph class definition from the `GDEF` table, if the font has one.
+    pub(crate) fn glyph_class_def(&self) -> Result<Option<ClassDef<'a>>, ParseError> {
+        let Some(gdef) = self.gdef else {
+            return Ok(None);
+        };
+        ClassDef::parse_gdef(gdef)
+    }
+
+    /// Returns the parsed `name` table, if the font has one.
+    pub(crate) fn name_table(&self) -> Result<Option<NameTable<'a>>, ParseError> {
+        let Some(name) = self.name else {
+            return Ok(None);
+        };
+        NameTable::parse(name).map(Some)
+    }
+
+    /// Returns the `MATH` table, if the font has one.
+    pub(crate) fn math_table(&self) -> Result<Option<MathTable<'a>>, ParseError> {
+        let Some(math) = self.math else {
+            return Ok(None);
+        };
+        MathTable::parse(math, self.glyph_count).map(Some)
+    }
+
+    /// Returns the `kern` table, if the font has one in the supported format.
+    pub(crate) fn kern_table(&self) -> Result<Option<KernTable<'a>>, ParseError> {
+        let Some(kern) = self.kern else {
+            return Ok(None);
+        };
+        KernTable::parse(kern)
+    }
+
+    /// Returns the `sbix` table, if the font has one.
+    pub(crate) fn sbix_table(&self) -> Result<Option<SbixTable<'a>>, ParseError> {
+        let Some(sbix) = self.sbix else {
+            return Ok(None);
+        };
+        SbixTable::parse(sbix, self.glyph_count).map(Some)
+    }
+
+    /// Returns vertical metrics (`vmtx` table) if the font has both `vhea` and `vmtx` tables.
+    pub(crate) fn vertical_metrics(&self) -> Option<HmtxTable<'a>> {
+        Some(HmtxTable {
+            raw: self.vmtx?,
+            number_of_h_metrics: self.vhea?.number_of_h_metrics,
         })
     }
 
-    /// Subsets this font by retaining only specified `chars`.
+    /// Subsets this font by retaining only specified `chars`. The subset borrows the font, so a single font
+    /// can back multiple subsets.
     ///
     /// # Errors
     ///
     /// This operation will parse more font data, so it may return parsing errors.
-    pub fn subset(self, chars: &BTreeSet<char>) -> Result<FontSubset<'a>, ParseError> {
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::collections::BTreeSet;
+    /// # use font_subset::Font;
+    /// let font_bytes = // font in the OpenType format
+    /// # include_bytes!("../../examples/FiraMono-Regular.ttf");
+    /// let font = Font::new(font_bytes)?;
+    /// let digits = font.subset(&('0'..='9').collect())?;
+    /// let letters: BTreeSet<char> = ('A'..='Z').chain('a'..='z').collect();
+    /// let letters = font.subset(&letters)?;
+    /// assert!(digits.to_opentype().len() < letters.to_opentype().len());
+    /// # Ok::<_, font_subset::ParseError>(())
+    /// ```
+    pub fn subset(&self, chars: &BTreeSet<char>) -> Result<FontSubset<'_>, ParseError> {
         FontSubset::new(self, chars)
     }
+
+    /// Subsets this font in the same way as [`Self::subset()`], additionally returning the requested chars
+    /// that are absent from the `cmap` table. Such chars are mapped to the `.notdef` glyph in the subset.
+    ///
+    /// Chars explicitly mapped to glyph 0 by the `cmap` table are *not* considered absent.
+    ///
+    /// # Errors
+    ///
+    /// This operation will parse more font data, so it may return parsing errors.
+    pub fn subset_reporting(
+        &self,
+        chars: &BTreeSet<char>,
+    ) -> Result<(FontSubset<'_>, BTreeSet<char>), ParseError> {
+        let subset = FontSubset::new(self, chars)?;
+        let mut unmapped_chars = BTreeSet::new();
+        for &ch in chars {
+            if self.cmap.lookup_char(ch)?.is_none() {
+                unmapped_chars.insert(ch);
+            }
+        }
+        Ok((subset, unmapped_chars))
+    }
+
+    /// Subsets this font by retaining all chars from `text`. Chars are deduplicated and sorted internally.
+    ///
+    /// This only maps each char independently via the `cmap` table (e.g., combining marks are mapped
+    /// separately from their base chars); no text shaping is performed. Thus, glyphs only reachable
+    /// via substitutions (e.g., ligatures) are not retained.
+    ///
+    /// # Errors
+    ///
+    /// This operation will parse more font data, so it may return parsing errors.
+    pub fn subset_for_text(&self, text: &str) -> Result<FontSubset<'_>, ParseError> {
+        FontSubset::from_iter_chars(self, text.chars())
+    }
+
+    /// Subsets this font by retaining only specified `chars` with the specified options.
+    ///
+    /// # Errors
+    ///
+    /// This operation will parse more font data, so it may return parsing errors. It may also return
+    /// errors if `options` cannot be satisfied for this font.
+    pub fn subset_with_options(
+        &self,
+        chars: &BTreeSet<char>,
+        options: SubsetOptions,
+    ) -> Result<FontSubset<'_>, SubsetError> {
+        FontSubset::with_options(self, chars, options)
+    }
+
+    /// Creates a subset of this font for each of the specified `buckets` of chars, e.g., to split a large font
+    /// into several files loaded on demand via the `unicode-range` CSS descriptor. This is equivalent to calling
+    /// [`Self::subset()`] for each bucket, but is more efficient since glyphs shared among buckets
+    /// (e.g., the missing glyph or common components) are only parsed once.
+    ///
+    /// Subsets are independent from each other; e.g., each subset contains the missing glyph #0.
+    ///
+    /// # Errors
+    ///
+    /// This operation will parse more font data, so it may return parsing errors.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::collections::BTreeSet;
+    /// # use font_subset::Font;
+    /// let font_bytes = // font in the OpenType format
+    /// # include_bytes!("../../examples/FiraMono-Regular.ttf");
+    /// let font = Font::new(font_bytes)?;
+    /// let latin: BTreeSet<char> = (' '..='~').collect();
+    /// let cyrillic: BTreeSet<char> = ('\u{400}'..='\u{4ff}').collect();
+    /// let shards = font.subset_sharded(&[latin, cyrillic])?;
+    /// assert_eq!(shards.len(), 2);
+    /// let woff2_files: Vec<Vec<u8>> = shards.iter().map(|shard| shard.to_woff2()).collect();
+    /// # Ok::<_, font_subset::SubsetError>(())
+    /// ```
+    pub fn subset_sharded(
+        &self,
+        buckets: &[BTreeSet<char>],
+    ) -> Result<Vec<FontSubset<'_>>, SubsetError> {
+        FontSubset::sharded(self, buckets, &SubsetOptions::default())
+    }
+
+    /// Creates a subset of this font for each of the specified `buckets` of chars with the specified options.
+    /// This is equivalent to calling [`Self::subset_with_options()`] for each bucket; see [`Self::subset_sharded()`]
+    /// for details.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the options cannot be satisfied for this font, or if parsing font data fails.
+    pub fn subset_sharded_with_options(
+        &self,
+        buckets: &[BTreeSet<char>],
+        options: &SubsetOptions,
+    ) -> Result<Vec<FontSubset<'_>>, SubsetError> {
+        FontSubset::sharded(self, buckets, options)
+    }
+
+    /// Subsets this font by retaining glyphs with the specified IDs, plus glyphs they reference.
+    /// See [`FontSubset::from_glyphs()`] for details.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any of `glyph_ids` is out of range, or if parsing font data fails.
+    pub fn subset_glyphs(&self, glyph_ids: &BTreeSet<u16>) -> Result<FontSubset<'_>, SubsetError> {
+        FontSubset::from_glyphs(self, glyph_ids)
+    }
+
+    /// Creates a "subset" of this font retaining all its glyphs. Glyph IDs are not changed,
+    /// so the `cmap` table is copied verbatim (i.e., all its subtables are retained, including
+    /// ones not supported by this crate).
+    ///
+    /// This is useful to convert the font to WOFF2 or to drop tables not supported by this crate.
+    ///
+    /// # Errors
+    ///
+    /// This operation will parse all glyph data, so it may return parsing errors.
+    pub fn subset_all(&self) -> Result<FontSubset<'_>, ParseError> {
+        FontSubset::all(self, SubsetOptions::default())
+    }
+
+    /// Creates a minimal subset of this font containing only the missing glyph (`.notdef`).
+    /// The subset `cmap` table doesn't map any chars, i.e., all chars are mapped to the missing glyph.
+    /// This is equivalent to [subsetting](Self::subset()) with an empty char set, and can be used
+    /// to produce placeholder fonts.
+    ///
+    /// # Errors
+    ///
+    /// This operation will parse more font data, so it may return parsing errors.
+    pub fn empty_subset(&self) -> Result<FontSubset<'_>, ParseError> {
+        FontSubset::notdef_only(self)
+    }
 }
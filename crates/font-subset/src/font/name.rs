@@ -0,0 +1,104 @@
+//! `name` table processing.
+
+use super::Cursor;
+use crate::{
+    alloc::{vec, Vec},
+    errors::ParseErrorKind,
+    ParseError,
+};
+
+/// Record from the `name` table together with the referenced string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct NameRecord<'a> {
+    pub(crate) platform_id: u16,
+    pub(crate) encoding_id: u16,
+    pub(crate) language_id: u16,
+    pub(crate) name_id: u16,
+    /// String data in the platform-specific encoding.
+    pub(crate) string: &'a [u8],
+}
+
+impl NameRecord<'_> {
+    /// Returns the key by which records in the table must be sorted.
+    pub(crate) fn sort_key(&self) -> [u16; 4] {
+        [
+            self.platform_id,
+            self.encoding_id,
+            self.language_id,
+            self.name_id,
+        ]
+    }
+}
+
+/// Naming table (`name`) in format 0 or 1.
+#[derive(Debug, Clone)]
+pub(crate) struct NameTable<'a> {
+    pub(crate) version: u16,
+    pub(crate) records: Vec<NameRecord<'a>>,
+    /// Language tag strings (only present in format 1), indexed by `languageID - 0x8000`.
+    pub(crate) lang_tags: Vec<&'a [u8]>,
+    /// String storage; all strings in `records` and `lang_tags` are subslices of it.
+    pub(crate) storage: &'a [u8],
+}
+
+impl<'a> NameTable<'a> {
+    /// Byte length of a name record.
+    pub(crate) const RECORD_LEN: usize = 12;
+    /// Byte length of a language tag record.
+    pub(crate) const LANG_TAG_RECORD_LEN: usize = 4;
+
+    pub(super) fn parse(name: Cursor<'a>) -> Result<Self, ParseError> {
+        let mut cursor = name;
+        let version = cursor.read_u16_checked(|version| {
+            if version <= 1 {
+                Ok(version)
+            } else {
+                Err(ParseErrorKind::UnexpectedTableVersion(version.into()))
+            }
+        })?;
+        let count = cursor.read_u16()?;
+        let storage_offset = cursor.read_u16()?;
+        let mut storage = name;
+        storage.skip(storage_offset.into())?;
+        let read_string = |cursor: &mut Cursor<'a>| -> Result<&'a [u8], ParseError> {
+            let len = usize::from(cursor.read_u16()?);
+            let offset = usize::from(cursor.read_u16()?);
+            Ok(storage.range(offset..offset + len)?.bytes)
+        };
+
+        let mut records = Vec::with_capacity(count.into());
+        for _ in 0..count {
+            let platform_id = cursor.read_u16()?;
+            let encoding_id = cursor.read_u16()?;
+            let language_id = cursor.read_u16()?;
+            let name_id = cursor.read_u16()?;
+            records.push(NameRecord {
+                platform_id,
+                encoding_id,
+                language_id,
+                name_id,
+                string: read_string(&mut cursor)?,
+            });
+        }
+
+        let mut lang_tags = vec![];
+        if version == 1 {
+            let lang_tag_count = cursor.read_u16()?;
+            lang_tags = (0..lang_tag_count)
+                .map(|_| read_string(&mut cursor))
+                .collect::<Result<_, _>>()?;
+        }
+        Ok(Self {
+            version,
+            records,
+            lang_tags,
+            storage: storage.bytes,
+        })
+    }
+
+    /// Returns the offset of `string` in the string storage.
+    pub(crate) fn storage_offset(&self, string: &[u8]) -> usize {
+        // Strings are always borrowed from the storage, so the subtraction cannot overflow.
+        string.as_ptr() as usize - self.storage.as_ptr() as usize
+    }
+}
@@ -0,0 +1,350 @@
+//! Glyph outline decoding and conversion to SVG paths.
+
+use core::fmt::{self, Write as _};
+
+use super::{Cursor, Font, Glyph, GlyphComponent, GlyphComponentArgs, TableTag, TransformData};
+use crate::{
+    alloc::{String, Vec},
+    errors::ParseErrorKind,
+    ParseError,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct OutlinePoint {
+    x: f32,
+    y: f32,
+    on_curve: bool,
+}
+
+impl OutlinePoint {
+    fn midpoint(self, other: Self) -> Self {
+        Self {
+            x: (self.x + other.x) / 2.0,
+            y: (self.y + other.y) / 2.0,
+            on_curve: true,
+        }
+    }
+}
+
+/// 2x2 transform matrix together with an offset.
+#[derive(Debug, Clone, Copy)]
+struct Transform {
+    /// `[xx, xy, yx, yy]` such that `x' = xx * x + yx * y`, `y' = xy * x + yy * y`.
+    matrix: [f32; 4],
+    offset: (f32, f32),
+}
+
+impl Transform {
+    fn new(data: &TransformData) -> Self {
+        let matrix = match *data {
+            TransformData::None => [1.0, 0.0, 0.0, 1.0],
+            TransformData::Scale(scale) => {
+                let scale = f2dot14(scale);
+                [scale, 0.0, 0.0, scale]
+            }
+            TransformData::TwoScales([x_scale, y_scale]) => {
+                [f2dot14(x_scale), 0.0, 0.0, f2dot14(y_scale)]
+            }
+            TransformData::Affine(values) => values.map(f2dot14),
+        };
+        Self {
+            matrix,
+            offset: (0.0, 0.0),
+        }
+    }
+
+    fn apply_linear(&self, x: f32, y: f32) -> (f32, f32) {
+        let [xx, xy, yx, yy] = self.matrix;
+        (xx * x + yx * y, xy * x + yy * y)
+    }
+
+    fn apply(&self, point: OutlinePoint) -> OutlinePoint {
+        let (x, y) = self.apply_linear(point.x, point.y);
+        OutlinePoint {
+            x: x + self.offset.0,
+            y: y + self.offset.1,
+            on_curve: point.on_curve,
+        }
+    }
+}
+
+/// Converts a signed 2.14 fixed-point number to a float.
+fn f2dot14(raw: u16) -> f32 {
+    f32::from(i16::from_be_bytes(raw.to_be_bytes())) / 16_384.0
+}
+
+/// Glyph outline consisting of closed contours.
+#[derive(Debug, Default)]
+pub(crate) struct Outline {
+    contours: Vec<Vec<OutlinePoint>>,
+}
+
+impl Outline {
+    /// Decodes the outline of a simple glyph.
+    fn parse_simple(bytes: &[u8]) -> Result<Self, ParseError> {
+        const ON_CURVE_POINT: u8 = 0x01;
+        const X_SHORT_VECTOR: u8 = 0x02;
+        const Y_SHORT_VECTOR: u8 = 0x04;
+        const REPEAT_FLAG: u8 = 0x08;
+        const X_IS_SAME_OR_POSITIVE: u8 = 0x10;
+        const Y_IS_SAME_OR_POSITIVE: u8 = 0x20;
+
+        let mut cursor = Cursor {
+            bytes,
+            offset: 0,
+            table: Some(TableTag::GLYF),
+        };
+        let contour_count = cursor.read_u16()?;
+        cursor.skip(8)?; // bounding box
+        let end_points = (0..contour_count)
+            .map(|_| cursor.read_u16())
+            .collect::<Result<Vec<_>, _>>()?;
+        let instructions_len = cursor.read_u16()?;
+        cursor.skip(instructions_len.into())?;
+
+        let point_count = end_points.last().map_or(0, |&end| usize::from(end) + 1);
+        let mut flags = Vec::with_capacity(point_count);
+        while flags.len() < point_count {
+            let [flag] = cursor.read_byte_array()?;
+            flags.push(flag);
+            if flag & REPEAT_FLAG != 0 {
+                let [repeat_count] = cursor.read_byte_array()?;
+                flags.extend((0..repeat_count).map(|_| flag));
+            }
+        }
+        flags.truncate(point_count);
+
+        let mut read_coords = |short_flag: u8, same_or_positive_flag: u8| {
+            let mut value = 0_i32;
+            let coords = flags.iter().map(|&flag| {
+                let delta = if flag & short_flag != 0 {
+                    let [byte] = cursor.read_byte_array()?;
+                    let sign = if flag & same_or_positive_flag != 0 {
+                        1
+                    } else {
+                        -1
+                    };
+                    sign * i32::from(byte)
+                } else if flag & same_or_positive_flag != 0 {
+                    0
+                } else {
+                    i32::from(i16::from_be_bytes(cursor.read_byte_array()?))
+                };
+                value += delta;
+                Ok::<_, ParseError>(value)
+            });
+            coords.collect::<Result<Vec<_>, _>>()
+        };
+        let xs = read_coords(X_SHORT_VECTOR, X_IS_SAME_OR_POSITIVE)?;
+        let ys = read_coords(Y_SHORT_VECTOR, Y_IS_SAME_OR_POSITIVE)?;
+
+        // Coordinates are small enough to be represented exactly.
+        #[allow(clippy::cast_precision_loss)]
+        let mut points = flags
+            .iter()
+            .zip(xs.into_iter().zip(ys))
+            .map(|(&flag, (x, y))| OutlinePoint {
+                x: x as f32,
+                y: y as f32,
+                on_curve: flag & ON_CURVE_POINT != 0,
+            });
+
+        let mut contours = Vec::with_capacity(end_points.len());
+        let mut start = 0;
+        for &end in &end_points {
+            let end = usize::from(end) + 1;
+            if end < start {
+                // End points must be increasing.
+                return Err(cursor.err(ParseErrorKind::OffsetOutOfBounds(end)));
+            }
+            contours.push(points.by_ref().take(end - start).collect());
+            start = end;
+        }
+        Ok(Self { contours })
+    }
+
+    fn points(&self) -> impl Iterator<Item = OutlinePoint> + '_ {
+        self.contours.iter().flatten().copied()
+    }
+
+    fn extend(&mut self, other: Self, transform: &Transform) {
+        let transformed = other
+            .contours
+            .into_iter()
+            .map(|contour| contour.into_iter().map(|pt| transform.apply(pt)).collect());
+        self.contours.extend(transformed);
+    }
+}
+
+/// Formats the outline as the SVG path data.
+impl fmt::Display for Outline {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut is_first = true;
+        for contour in &self.contours {
+            let Some(&first) = contour.first() else {
+                continue;
+            };
+            // Choose an on-curve point to start the contour from, synthesizing it if necessary.
+            let last = contour[contour.len() - 1];
+            let (start, rest) = if first.on_curve {
+                (first, &contour[1..])
+            } else if last.on_curve {
+                (last, &contour[..contour.len() - 1])
+            } else {
+                (last.midpoint(first), contour.as_slice())
+            };
+
+            if !is_first {
+                formatter.write_str(" ")?;
+            }
+            is_first = false;
+            write!(formatter, "M{} {}", start.x, start.y)?;
+            let mut control = None;
+            for &point in rest {
+                match (control, point.on_curve) {
+                    (None, true) => write!(formatter, " L{} {}", point.x, point.y)?,
+                    (None, false) => control = Some(point),
+                    (Some(ctrl), true) => {
+                        write!(formatter, " Q{} {} {} {}", ctrl.x, ctrl.y, point.x, point.y)?;
+                        control = None;
+                    }
+                    (Some(ctrl), false) => {
+                        let mid = ctrl.midpoint(point);
+                        write!(formatter, " Q{} {} {} {}", ctrl.x, ctrl.y, mid.x, mid.y)?;
+                        control = Some(point);
+                    }
+                }
+            }
+            if let Some(ctrl) = control {
+                // Wrap around to the starting point.
+                write!(formatter, " Q{} {} {} {}", ctrl.x, ctrl.y, start.x, start.y)?;
+            }
+            formatter.write_str(" Z")?;
+        }
+        Ok(())
+    }
+}
+
+impl GlyphComponent {
+    /// Returns component arguments as signed values (offsets) or unsigned ones (point indices).
+    fn raw_args(&self) -> [i32; 2] {
+        let are_signed = self.are_args_offsets();
+        match self.args {
+            GlyphComponentArgs::U16(packed) => {
+                let [arg1, arg2] = packed.to_be_bytes();
+                if are_signed {
+                    [arg1, arg2].map(|arg| i32::from(i8::from_be_bytes([arg])))
+                } else {
+                    [arg1, arg2].map(i32::from)
+                }
+            }
+            GlyphComponentArgs::U32(packed) => {
+                let [a, b, c, d] = packed.to_be_bytes();
+                if are_signed {
+                    [[a, b], [c, d]].map(|arg| i32::from(i16::from_be_bytes(arg)))
+                } else {
+                    [[a, b], [c, d]].map(|arg| i32::from(u16::from_be_bytes(arg)))
+                }
+            }
+        }
+    }
+
+    fn are_args_offsets(&self) -> bool {
+        const ARGS_ARE_XY_VALUES: u16 = 0x0002;
+        self.flags & ARGS_ARE_XY_VALUES != 0
+    }
+
+    fn scales_offset(&self) -> bool {
+        const SCALED_COMPONENT_OFFSET: u16 = 0x0800;
+        self.flags & SCALED_COMPONENT_OFFSET != 0
+    }
+}
+
+impl Font<'_> {
+    pub(crate) fn glyph_outline(&self, glyph_idx: u16) -> Result<Outline, ParseError> {
+        self.glyph_outline_inner(glyph_idx, 0, &mut 0)
+    }
+
+    /// `component_count` is the total number of components flattened so far. It's limited since shared
+    /// components are flattened repeatedly, so the amount of work may grow exponentially with the depth.
+    fn glyph_outline_inner(
+        &self,
+        glyph_idx: u16,
+        depth: usize,
+        component_count: &mut usize,
+    ) -> Result<Outline, ParseError> {
+        let glyph = self.glyph(glyph_idx)?.inner;
+        let components = match glyph {
+            Glyph::Empty => return Ok(Outline::default()),
+            Glyph::Simple(bytes) => return Outline::parse_simple(bytes),
+            Glyph::Composite { components, .. } => components,
+            Glyph::Cff(_) => return Err(ParseError::missing_table(TableTag::GLYF)),
+        };
+
+        let mut outline = Outline::default();
+        for component in &components {
+            if depth >= GlyphComponent::MAX_DEPTH {
+                // Overly deep nesting may be caused by a reference cycle.
+                return Err(ParseError::composite_depth_exceeded(glyph_idx));
+            }
+            *component_count += 1;
+            if *component_count > GlyphComponent::MAX_FLATTENED_COUNT {
+                return Err(ParseError::composite_components_exceeded(glyph_idx));
+            }
+
+            let component_outline =
+                self.glyph_outline_inner(component.glyph_idx, depth + 1, component_count)?;
+            let mut transform = Transform::new(&component.transform);
+            let [arg1, arg2] = component.raw_args();
+            if component.are_args_offsets() {
+                #[allow(clippy::cast_precision_loss)] // args are 16-bit values
+                let (x, y) = (arg1 as f32, arg2 as f32);
+                transform.offset = if component.scales_offset() {
+                    transform.apply_linear(x, y)
+                } else {
+                    (x, y)
+                };
+            } else {
+                // Args are indices of the matched points in the parent and the component, respectively.
+                // `unwrap()`s are safe: unsigned args are non-negative.
+                let [parent_idx, child_idx] = [arg1, arg2].map(|arg| usize::try_from(arg).unwrap());
+                let parent_point = outline.points().nth(parent_idx);
+                let child_point = component_outline.points().nth(child_idx);
+                let (Some(parent_point), Some(child_point)) = (parent_point, child_point) else {
+                    return Err(ParseError {
+                        kind: ParseErrorKind::OffsetOutOfBounds(parent_idx.max(child_idx)),
+                        offset: 0,
+                        table: Some(TableTag::GLYF),
+                    });
+                };
+                let (x, y) = transform.apply_linear(child_point.x, child_point.y);
+                transform.offset = (parent_point.x - x, parent_point.y - y);
+            }
+            outline.extend(component_outline, &transform);
+        }
+        Ok(outline)
+    }
+
+    /// Converts the outline of the glyph with the specified index to the SVG path data (i.e., the value
+    /// of the `d` attribute of the `<path>` element). Composite glyphs are flattened, with component transforms
+    /// applied. Quadratic curves are converted to `Q` commands; each contour is closed with `Z`.
+    ///
+    /// Returns `None` if the glyph has no outline (e.g., it is a space).
+    ///
+    /// Coordinates are in font design units, with the y axis pointing up as in the font. To render the path
+    /// in the SVG coordinate system, flip it vertically, e.g., using `transform="scale(1 -1)"`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the glyph index is out of range, or the glyph data is malformed.
+    pub fn glyph_svg_path(&self, glyph_idx: u16) -> Result<Option<String>, ParseError> {
+        let outline = self.glyph_outline(glyph_idx)?;
+        if outline.contours.iter().all(Vec::is_empty) {
+            return Ok(None);
+        }
+        let mut path = String::new();
+        // Writing to a `String` is infallible.
+        write!(&mut path, "{outline}").ok();
+        Ok(Some(path))
+    }
+}
@@ -0,0 +1,117 @@
+//! `post` table processing.
+
+use super::Cursor;
+use crate::{
+    alloc::{vec, Vec},
+    errors::ParseErrorKind,
+    ParseError, TableTag,
+};
+
+/// Name of a glyph as recorded in the `post` table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum GlyphName<'a> {
+    /// Index of the name in the standard Macintosh ordering.
+    Standard(u16),
+    /// Custom name without the length prefix.
+    Custom(&'a [u8]),
+}
+
+/// Glyph names stored in the `post` table.
+#[derive(Debug, Clone)]
+pub(crate) enum PostGlyphNames<'a> {
+    /// Version 1.0: glyphs use names from the standard Macintosh ordering.
+    Standard,
+    /// Version 2.0: name index for each glyph + custom names.
+    Indexed {
+        name_indices: Cursor<'a>,
+        custom_names: Vec<&'a [u8]>,
+    },
+    /// Version 2.5 (deprecated): signed offsets from glyph indices to the standard Macintosh ordering.
+    Offsets(Cursor<'a>),
+}
+
+impl<'a> PostGlyphNames<'a> {
+    const VERSION_1: u32 = 0x_0001_0000;
+    pub(crate) const VERSION_2: u32 = 0x_0002_0000;
+    const VERSION_2_5: u32 = 0x_0002_5000;
+    pub(crate) const VERSION_3: u32 = 0x_0003_0000;
+    /// Length of the header shared by all `post` table versions.
+    pub(crate) const HEADER_LEN: usize = 32;
+    /// Offset of the memory usage fields (`minMemType42`, `maxMemType42`, `minMemType1`, `maxMemType1`)
+    /// that end the header.
+    pub(crate) const MEMORY_USAGE_OFFSET: usize = 16;
+    /// Number of names in the standard Macintosh ordering.
+    pub(crate) const STANDARD_NAME_COUNT: u16 = 258;
+
+    /// Returns `Ok(None)` if the table doesn't contain glyph names (i.e., has version 3.0).
+    pub(super) fn parse(mut cursor: Cursor<'a>) -> Result<Option<Self>, ParseError> {
+        let version = cursor.read_u32_checked(|version| match version {
+            Self::VERSION_1 | Self::VERSION_2 | Self::VERSION_2_5 | Self::VERSION_3 => Ok(version),
+            _ => Err(ParseErrorKind::UnexpectedTableVersion(version)),
+        })?;
+        cursor.skip(Self::HEADER_LEN - 4)?;
+
+        Ok(match version {
+            Self::VERSION_1 => Some(Self::Standard),
+            Self::VERSION_2 => {
+                let glyph_count = cursor.read_u16()?;
+                let name_indices = cursor.split_at(2 * usize::from(glyph_count))?;
+                let mut custom_names = vec![];
+                while let [len, ..] = cursor.bytes {
+                    cursor.skip(1)?;
+                    custom_names.push(cursor.split_at(usize::from(*len))?.bytes);
+                }
+                Some(Self::Indexed {
+                    name_indices,
+                    custom_names,
+                })
+            }
+            Self::VERSION_2_5 => {
+                let glyph_count = cursor.read_u16()?;
+                Some(Self::Offsets(cursor.split_at(glyph_count.into())?))
+            }
+            _ => None,
+        })
+    }
+
+    pub(crate) fn glyph_name(&self, glyph_idx: u16) -> Result<GlyphName<'a>, ParseError> {
+        let glyph_pos = usize::from(glyph_idx);
+        let name_idx = match self {
+            Self::Standard => Some(glyph_idx),
+            Self::Indexed {
+                name_indices,
+                custom_names,
+            } => {
+                let name_idx = name_indices
+                    .range(2 * glyph_pos..2 * glyph_pos + 2)?
+                    .read_u16()?;
+                if let Some(custom_idx) = name_idx.checked_sub(Self::STANDARD_NAME_COUNT) {
+                    let custom_idx = usize::from(custom_idx);
+                    return custom_names
+                        .get(custom_idx)
+                        .map(|name| GlyphName::Custom(name))
+                        .ok_or_else(|| Self::invalid_name(custom_idx));
+                }
+                Some(name_idx)
+            }
+            Self::Offsets(offsets) => {
+                // Convert to the version 2.0 representation: the name index is the glyph index + offset
+                let offset = offsets.range(glyph_pos..glyph_pos + 1)?.bytes[0];
+                glyph_idx.checked_add_signed(i8::from_be_bytes([offset]).into())
+            }
+        };
+
+        match name_idx {
+            Some(idx) if idx < Self::STANDARD_NAME_COUNT => Ok(GlyphName::Standard(idx)),
+            _ => Err(Self::invalid_name(glyph_pos)),
+        }
+    }
+
+    fn invalid_name(offset: usize) -> ParseError {
+        ParseError {
+            kind: ParseErrorKind::OffsetOutOfBounds(offset),
+            offset: 0,
+            table: Some(TableTag::POST),
+        }
+    }
+}
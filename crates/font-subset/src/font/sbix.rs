@@ -0,0 +1,107 @@
+//! Standard bitmap graphics (`sbix`) table processing.
+
+use super::Cursor;
+use crate::{alloc::Vec, errors::ParseErrorKind, ParseError};
+
+/// Glyph data record from an `sbix` strike.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SbixGlyph<'a> {
+    /// Raw record including the origin offsets and the graphic type.
+    pub(crate) record: &'a [u8],
+}
+
+impl SbixGlyph<'_> {
+    /// Byte length of the record header (`originOffsetX`, `originOffsetY` and `graphicType`).
+    const HEADER_LEN: usize = 8;
+    /// Graphic types with raster data that doesn't reference other glyphs.
+    const RASTER_TYPES: [&'static [u8; 4]; 3] = [b"png ", b"jpg ", b"tiff"];
+
+    /// Returns the graphic type tag of this glyph, e.g. `png `.
+    pub(crate) fn graphic_type(&self) -> [u8; 4] {
+        // The length of `record` is checked when parsing
+        self.record[4..Self::HEADER_LEN].try_into().unwrap()
+    }
+
+    /// Checks whether the glyph contains raster data, as opposed to referencing another glyph
+    /// (`dupe`, `flip`) or using a vector format.
+    pub(crate) fn is_raster(&self) -> bool {
+        Self::RASTER_TYPES.contains(&&self.graphic_type())
+    }
+}
+
+/// Strike (i.e., a set of glyph images for a specific ppem size) from the `sbix` table.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SbixStrike<'a> {
+    pub(crate) ppem: u16,
+    pub(crate) ppi: u16,
+    /// Strike data starting from the beginning of the strike header.
+    strike: Cursor<'a>,
+    /// `glyphDataOffsets` array, including the trailing offset used to compute the last record size.
+    offsets: Cursor<'a>,
+}
+
+impl<'a> SbixStrike<'a> {
+    fn parse(strike: Cursor<'a>, glyph_count: u16) -> Result<Self, ParseError> {
+        let mut cursor = strike;
+        let ppem = cursor.read_u16()?;
+        let ppi = cursor.read_u16()?;
+        let offsets = cursor.range(0..4 * (usize::from(glyph_count) + 1))?;
+        Ok(Self {
+            ppem,
+            ppi,
+            strike,
+            offsets,
+        })
+    }
+
+    /// Returns the glyph data record for the specified glyph, or `None` if the strike has no data for it.
+    pub(crate) fn glyph(&self, glyph_idx: u16) -> Result<Option<SbixGlyph<'a>>, ParseError> {
+        let mut cursor = self.offsets;
+        cursor.skip(4 * usize::from(glyph_idx))?;
+        let start = cursor.read_u32()? as usize;
+        let end = cursor.read_u32()? as usize;
+        if start == end {
+            return Ok(None);
+        }
+        if end < start.saturating_add(SbixGlyph::HEADER_LEN) {
+            return Err(cursor.err(ParseErrorKind::UnexpectedEof));
+        }
+        let record = self.strike.range(start..end)?;
+        Ok(Some(SbixGlyph {
+            record: record.bytes,
+        }))
+    }
+}
+
+/// Standard bitmap graphics table (`sbix`).
+#[derive(Debug, Clone)]
+pub(crate) struct SbixTable<'a> {
+    pub(crate) flags: u16,
+    pub(crate) strikes: Vec<SbixStrike<'a>>,
+}
+
+impl<'a> SbixTable<'a> {
+    pub(crate) const VERSION: u16 = 1;
+
+    pub(super) fn parse(sbix: Cursor<'a>, glyph_count: u16) -> Result<Self, ParseError> {
+        let mut cursor = sbix;
+        cursor.read_u16_checked(|version| {
+            if version == Self::VERSION {
+                Ok(())
+            } else {
+                Err(ParseErrorKind::UnexpectedTableVersion(version.into()))
+            }
+        })?;
+        let flags = cursor.read_u16()?;
+        let strike_count = cursor.read_u32()?;
+        let strikes = (0..strike_count).map(|_| {
+            let mut strike = sbix;
+            strike.skip(cursor.read_u32()? as usize)?;
+            SbixStrike::parse(strike, glyph_count)
+        });
+        Ok(Self {
+            flags,
+            strikes: strikes.collect::<Result<_, _>>()?,
+        })
+    }
+}
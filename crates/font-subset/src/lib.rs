@@ -40,17 +40,23 @@ mod alloc {
     extern crate alloc as std;
 
     pub(crate) use std::{
+        borrow::Cow,
         boxed::Box,
         collections::{BTreeMap, BTreeSet},
+        string::String,
         vec,
         vec::Vec,
     };
 }
 
 pub use crate::{
-    errors::{ParseError, ParseErrorKind},
-    font::{Font, TableTag},
-    subset::FontSubset,
+    errors::{InvalidTableTag, LimitKind, ParseError, ParseErrorKind, SubsetError},
+    font::{
+        CoverageBitset, Font, GlyphInfo, GlyphKind, ParseLimits, ParseStats, Reader, TableChecksum,
+        TableTag,
+    },
+    subset::{FontSubset, Script, SubsetOptions},
+    write::Woff2Options,
 };
 
 #[cfg(doctest)]
@@ -1,50 +1,1261 @@
+use core::ops;
+
 use crate::{
     alloc::{vec, BTreeMap, BTreeSet, Vec},
-    font::{Font, Glyph, GlyphWithMetrics},
-    ParseError,
+    font::{
+        BitmapGlyph, CffGlyph, Font, Glyph, GlyphAssembly, GlyphComponent, GlyphConstruction,
+        GlyphInfo, GlyphName, GlyphWithMetrics, MathTable, NameTable, SbixGlyph, VariationMapping,
+        BITMAP_SIZE_LEN,
+    },
+    ParseError, SubsetError,
 };
 
+/// Complex script for which all glyphs can be retained during subsetting,
+/// see [`SubsetOptions::retain_script_glyphs()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum Script {
+    /// Arabic script.
+    Arabic,
+    /// Hebrew script.
+    Hebrew,
+    /// Syriac script.
+    Syriac,
+    /// Thaana script.
+    Thaana,
+    /// Devanagari script.
+    Devanagari,
+    /// Bengali script.
+    Bengali,
+    /// Gurmukhi script.
+    Gurmukhi,
+    /// Gujarati script.
+    Gujarati,
+    /// Oriya script.
+    Oriya,
+    /// Tamil script.
+    Tamil,
+    /// Telugu script.
+    Telugu,
+    /// Kannada script.
+    Kannada,
+    /// Malayalam script.
+    Malayalam,
+    /// Sinhala script.
+    Sinhala,
+    /// Thai script.
+    Thai,
+    /// Lao script.
+    Lao,
+    /// Tibetan script.
+    Tibetan,
+    /// Myanmar script.
+    Myanmar,
+    /// Khmer script.
+    Khmer,
+}
+
+impl Script {
+    /// Returns Unicode blocks used by this script.
+    fn char_ranges(self) -> &'static [ops::RangeInclusive<char>] {
+        match self {
+            Self::Arabic => &[
+                '\u{600}'..='\u{6ff}',
+                '\u{750}'..='\u{77f}',
+                '\u{870}'..='\u{8ff}',
+                '\u{fb50}'..='\u{fdff}',
+                '\u{fe70}'..='\u{feff}',
+            ],
+            Self::Hebrew => &['\u{590}'..='\u{5ff}', '\u{fb1d}'..='\u{fb4f}'],
+            Self::Syriac => &['\u{700}'..='\u{74f}', '\u{860}'..='\u{86f}'],
+            Self::Thaana => &['\u{780}'..='\u{7bf}'],
+            Self::Devanagari => &['\u{900}'..='\u{97f}', '\u{a8e0}'..='\u{a8ff}'],
+            Self::Bengali => &['\u{980}'..='\u{9ff}'],
+            Self::Gurmukhi => &['\u{a00}'..='\u{a7f}'],
+            Self::Gujarati => &['\u{a80}'..='\u{aff}'],
+            Self::Oriya => &['\u{b00}'..='\u{b7f}'],
+            Self::Tamil => &['\u{b80}'..='\u{bff}'],
+            Self::Telugu => &['\u{c00}'..='\u{c7f}'],
+            Self::Kannada => &['\u{c80}'..='\u{cff}'],
+            Self::Malayalam => &['\u{d00}'..='\u{d7f}'],
+            Self::Sinhala => &['\u{d80}'..='\u{dff}'],
+            Self::Thai => &['\u{e00}'..='\u{e7f}'],
+            Self::Lao => &['\u{e80}'..='\u{eff}'],
+            Self::Tibetan => &['\u{f00}'..='\u{fff}'],
+            Self::Myanmar => &[
+                '\u{1000}'..='\u{109f}',
+                '\u{a9e0}'..='\u{a9ff}',
+                '\u{aa60}'..='\u{aa7f}',
+            ],
+            Self::Khmer => &['\u{1780}'..='\u{17ff}', '\u{19e0}'..='\u{19ff}'],
+        }
+    }
+
+    /// Checks whether the specified char belongs to this script.
+    pub fn contains(self, ch: char) -> bool {
+        self.char_ranges().iter().any(|range| range.contains(&ch))
+    }
+}
+
+/// Options for [`FontSubset`] creation.
+#[derive(Debug, Clone)]
+#[allow(clippy::struct_excessive_bools)] // flags are independent
+pub struct SubsetOptions {
+    pub(crate) vertical_metrics: bool,
+    pub(crate) compact_hmtx: bool,
+    pub(crate) compact_cmap: bool,
+    pub(crate) retained_scripts: BTreeSet<Script>,
+    pub(crate) fallback_glyph: Option<u16>,
+    pub(crate) drop_name: bool,
+    pub(crate) error_on_color: bool,
+    pub(crate) retain_bitmaps: bool,
+    pub(crate) retain_sbix: bool,
+    pub(crate) retain_glyph_classes: bool,
+    pub(crate) retain_math: bool,
+    pub(crate) blanked_glyphs: BTreeSet<u16>,
+    pub(crate) recompute_unicode_ranges: bool,
+    pub(crate) recompute_bounding_box: bool,
+}
+
+impl Default for SubsetOptions {
+    fn default() -> Self {
+        Self {
+            vertical_metrics: false,
+            compact_hmtx: true,
+            compact_cmap: false,
+            retained_scripts: BTreeSet::new(),
+            fallback_glyph: None,
+            drop_name: false,
+            error_on_color: false,
+            retain_bitmaps: false,
+            retain_sbix: false,
+            retain_glyph_classes: false,
+            retain_math: false,
+            blanked_glyphs: BTreeSet::new(),
+            recompute_unicode_ranges: false,
+            recompute_bounding_box: false,
+        }
+    }
+}
+
+impl SubsetOptions {
+    /// Specifies whether to retain vertical metrics (`vhea` and `vmtx` tables) for the retained glyphs.
+    /// By default, vertical metrics are dropped.
+    ///
+    /// If vertical metrics are retained, but the font has neither `vhea` nor `vmtx` tables, the subset will
+    /// not contain vertical metrics either. If the font contains only one of these tables, subsetting will fail
+    /// with [`SubsetError::InconsistentVerticalMetrics`].
+    #[must_use]
+    pub fn vertical_metrics(mut self, retain: bool) -> Self {
+        self.vertical_metrics = retain;
+        self
+    }
+
+    /// Specifies whether to compact trailing glyph metrics with equal advances in the `hmtx` table
+    /// (and `vmtx`, if [vertical metrics](Self::vertical_metrics()) are retained) into side bearing-only entries.
+    /// This is the default behavior, which saves some space. If disabled, a full metric is written for each glyph,
+    /// which produces output easier to compare with other tools.
+    #[must_use]
+    pub fn compact_hmtx(mut self, compact: bool) -> Self {
+        self.compact_hmtx = compact;
+        self
+    }
+
+    /// Specifies whether to encode contiguous char ranges mapped to non-sequential glyph IDs as single segments
+    /// of the format 4 `cmap` subtable, with glyph IDs listed in the `glyphIdArray`. Such ranges are merged
+    /// only if this reduces the subtable size, which is mostly the case for subsets with glyph IDs jumping
+    /// around. By default, each run of sequential glyph IDs is encoded as a separate segment.
+    ///
+    /// This option has no effect if the subset `cmap` table uses format 12 (i.e., if the subset contains
+    /// chars outside the Basic Multilingual Plane).
+    #[must_use]
+    pub fn compact_cmap(mut self, compact: bool) -> Self {
+        self.compact_cmap = compact;
+        self
+    }
+
+    /// Retains *all* glyphs in the font if any of the subsetted chars belongs to the specified complex `script`.
+    /// Can be called multiple times to specify several scripts.
+    ///
+    /// Shaping complex scripts (e.g., Arabic or Indic ones) substitutes positional forms and conjuncts
+    /// for the glyphs mapped in the `cmap` table, so a subset only retaining the mapped glyphs
+    /// would break text rendering. This option trades the subset size for correctness; it is a stopgap
+    /// until the glyph closure over `GSUB` substitutions is supported.
+    ///
+    /// If the option is triggered, the subset is equivalent to one created with [`Font::subset_all()`]
+    /// (but respects other options). Since the `cmap` table is copied verbatim in this case,
+    /// the [fallback glyph](Self::fallback_glyph()) is not applied; chars not covered by the font
+    /// remain mapped to the missing glyph.
+    #[must_use]
+    pub fn retain_script_glyphs(mut self, script: Script) -> Self {
+        self.retained_scripts.insert(script);
+        self
+    }
+
+    /// Maps subsetted chars not covered by the font to the specified glyph (e.g., a visible "missing" box)
+    /// instead of the missing glyph #0. `glyph_idx` is the glyph index in the original font; the glyph
+    /// is retained in the subset.
+    ///
+    /// This option has no effect if the subset retains all glyphs because of [`Self::retain_script_glyphs()`],
+    /// since the `cmap` table is copied verbatim in this case.
+    ///
+    /// If `glyph_idx` is out of range for the font, subsetting will fail with [`SubsetError::InvalidFallbackGlyph`].
+    #[must_use]
+    pub fn fallback_glyph(mut self, glyph_idx: u16) -> Self {
+        self.fallback_glyph = Some(glyph_idx);
+        self
+    }
+
+    /// Specifies whether to omit the `name` table from the subset. By default, the table is retained.
+    ///
+    /// The `name` table isn't required by rasterizers, so it can be dropped for fonts that are only embedded
+    /// (e.g., in web pages or PDF documents) and never installed. Note that the resulting font is not a valid
+    /// installable font, and some parsers will reject it. [`Font::new()`] accepts fonts without the `name` table,
+    /// so the subset can be parsed and subsetted further.
+    #[must_use]
+    pub fn drop_name(mut self, drop: bool) -> Self {
+        self.drop_name = drop;
+        self
+    }
+
+    /// Specifies whether to fail subsetting for [color fonts](Font::is_color()). Color tables (`COLR` / `CPAL`)
+    /// are not retained in subsets, so by default, color fonts are silently flattened to plain outlines.
+    /// If this option is set, subsetting such a font will return [`SubsetError::ColorTablesDropped`].
+    #[must_use]
+    pub fn error_on_color(mut self, error: bool) -> Self {
+        self.error_on_color = error;
+        self
+    }
+
+    /// Specifies whether to retain embedded bitmaps (`EBLC` / `EBDT` tables, plus `EBSC` if present)
+    /// for the retained glyphs. By default, embedded bitmaps are dropped.
+    ///
+    /// All index subtable formats (1 to 5) are supported when reading bitmaps; the output uses formats 1
+    /// and 2. Composite bitmaps (image formats 8 and 9) are not supported and are dropped; the corresponding
+    /// glyphs are rendered using outlines.
+    #[must_use]
+    pub fn retain_bitmaps(mut self, retain: bool) -> Self {
+        self.retain_bitmaps = retain;
+        self
+    }
+
+    /// Specifies whether to retain color bitmap strikes from the `sbix` table (used e.g. by Apple color emoji)
+    /// for the retained glyphs. By default, the `sbix` table is dropped.
+    ///
+    /// Only glyph records with raster data (`png `, `jpg ` and `tiff` graphic types) are retained.
+    /// Records referencing other glyphs (`dupe` and `flip`) and records in other formats are dropped;
+    /// the corresponding glyphs are rendered using outlines.
+    #[must_use]
+    pub fn retain_sbix(mut self, retain: bool) -> Self {
+        self.retain_sbix = retain;
+        self
+    }
+
+    /// Specifies whether to retain glyph classes (base, ligature, mark or component) from the `GDEF` table
+    /// for the retained glyphs. By default, `GDEF` is dropped together with other layout tables.
+    ///
+    /// Some consumers use glyph classes even without `GSUB` / `GPOS`, e.g., for fallback mark positioning.
+    /// If enabled, the subset contains a minimal `GDEF` table with only the glyph class definition remapped
+    /// to new glyph IDs; attachment points, ligature carets, mark attachment classes and mark glyph sets
+    /// are dropped. If the font has no glyph class definition, or none of the retained glyphs has a class,
+    /// `GDEF` is not written.
+    #[must_use]
+    pub fn retain_glyph_classes(mut self, retain: bool) -> Self {
+        self.retain_glyph_classes = retain;
+        self
+    }
+
+    /// Specifies whether to retain the mathematical typesetting table (`MATH`) for the retained glyphs.
+    /// By default, `MATH` is dropped together with other layout tables.
+    ///
+    /// If enabled, glyph IDs are remapped in the following `MATH` subtables: italics corrections, top accent
+    /// attachments and extended shape coverage (from `MathGlyphInfo`), and glyph constructions (from
+    /// `MathVariants`). Records for non-retained glyphs are dropped; in particular, size variants that are
+    /// not retained are removed from constructions, and glyph assemblies referencing a non-retained part
+    /// are dropped. Values in `MathConstants` are copied verbatim. Math kerning (`MathKernInfo`) and device tables
+    /// (including ones referenced from `MathConstants`) are dropped. If all glyphs are retained, the table
+    /// is copied verbatim.
+    #[must_use]
+    pub fn retain_math(mut self, retain: bool) -> Self {
+        self.retain_math = retain;
+        self
+    }
+
+    /// Replaces outlines of the specified glyphs with empty ones, e.g., to exclude glyphs that cannot be
+    /// redistributed for licensing reasons. `glyph_indices` are glyph indices in the original font.
+    /// Can be called multiple times; the sets of blanked glyphs are merged.
+    ///
+    /// Blanked glyphs retain their metrics (so that text layout is unaffected), and their embedded bitmaps
+    /// are dropped if [bitmaps are retained](Self::retain_bitmaps()). Composite glyphs referencing a blanked glyph
+    /// still reference it, i.e., the corresponding component is rendered empty.
+    #[must_use]
+    pub fn blank_glyphs(mut self, glyph_indices: &BTreeSet<u16>) -> Self {
+        self.blanked_glyphs.extend(glyph_indices);
+        self
+    }
+
+    /// Specifies whether to recompute Unicode ranges (`ulUnicodeRange1..4` fields) in the `OS/2` table
+    /// from the chars covered by the subset. By default, the ranges are copied verbatim, so they advertise
+    /// the coverage of the original font, which may mislead font fallback in OSes.
+    ///
+    /// Bits are assigned to Unicode blocks as per the OpenType spec. Code page ranges (`ulCodePageRange1..2`)
+    /// are not recomputed.
+    #[must_use]
+    pub fn recompute_unicode_ranges(mut self, recompute: bool) -> Self {
+        self.recompute_unicode_ranges = recompute;
+        self
+    }
+
+    /// Specifies whether to recompute the font bounding box in the `head` table as the union of bounding boxes
+    /// of the retained glyphs. By default, the bounding box is copied from the original font, so it may be
+    /// larger than necessary for the subset.
+    ///
+    /// Only glyphs with TrueType outlines declare bounding boxes, so this option has no effect for fonts
+    /// with CFF outlines.
+    #[must_use]
+    pub fn recompute_bounding_box(mut self, recompute: bool) -> Self {
+        self.recompute_bounding_box = recompute;
+        self
+    }
+
+    /// Checks whether the subset should retain all glyphs because of the requested complex-script chars.
+    fn retains_all_glyphs(&self, distinct_chars: &BTreeSet<char>) -> bool {
+        !self.retained_scripts.is_empty()
+            && distinct_chars.iter().any(|&ch| {
+                self.retained_scripts
+                    .iter()
+                    .any(|script| script.contains(ch))
+            })
+    }
+
+    /// Checks whether these options can be satisfied for the `font`.
+    fn check(&self, font: &Font<'_>) -> Result<(), SubsetError> {
+        if self.vertical_metrics && font.vhea.is_some() != font.vmtx.is_some() {
+            return Err(SubsetError::InconsistentVerticalMetrics);
+        }
+        if self.error_on_color && font.is_color {
+            return Err(SubsetError::ColorTablesDropped);
+        }
+        match self.fallback_glyph {
+            Some(glyph_idx) if glyph_idx >= font.glyph_count => {
+                Err(SubsetError::InvalidFallbackGlyph(glyph_idx))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
 /// Subset of a [`Font`] produced by removing some of its glyphs and related data.
+///
+/// The subset borrows the font it was created from, so a single parsed font can back multiple subsets
+/// (e.g., ones created concurrently in different threads).
+///
+/// Tables not related to glyph outlines and metrics (e.g., layout tables like `GSUB`, or color tables
+/// like `COLR`) are not retained in the subset. In particular, variation tables are dropped, so a subset
+/// of a [variable font](Font::is_variable()) is a static font corresponding to its default instance.
+/// Glyph classes from the `GDEF` table can be retained using [`SubsetOptions::retain_glyph_classes()`],
+/// `sbix` color bitmaps using [`SubsetOptions::retain_sbix()`], and the `MATH` table
+/// using [`SubsetOptions::retain_math()`].
+/// Kerning pairs from the legacy `kern` table (format 0 subtables in the Microsoft version of the table)
+/// are retained for the retained glyphs. The `gasp` table doesn't reference glyphs, so it's copied verbatim.
+/// In the `OS/2` table, `usFirstCharIndex` and `usLastCharIndex` are updated to reflect the chars covered
+/// by the subset; Unicode ranges are recomputed only if [requested](SubsetOptions::recompute_unicode_ranges()).
 #[derive(Debug)]
 pub struct FontSubset<'a> {
-    pub(crate) font: Font<'a>,
+    pub(crate) font: &'a Font<'a>,
+    pub(crate) options: SubsetOptions,
     pub(crate) char_map: Vec<(char, u16)>,
+    /// Unicode variation sequences for the retained chars, ordered by the variation selector.
+    /// If empty, the format 14 `cmap` subtable is not written.
+    pub(crate) variation_sequences: Vec<VariationSelectorSubset>,
     pub(crate) old_to_new_glyph_idx: BTreeMap<u16, u16>,
     pub(crate) glyphs: Vec<GlyphWithMetrics<'a>>,
+    /// Are all glyphs retained with unchanged IDs? In this case, `char_map` is empty, and the original `cmap`
+    /// is copied verbatim.
+    pub(crate) retains_all_glyphs: bool,
+    /// Parsed `name` table of the original font. `None` if the table is [dropped](SubsetOptions::drop_name())
+    /// or cannot be parsed; in the latter case, the table is copied to the subset verbatim.
+    pub(crate) name_table: Option<NameTable<'a>>,
+    /// IDs of records from the `name` table written to the subset.
+    pub(crate) retained_name_ids: BTreeSet<u16>,
+    /// Are TrueType hinting tables and glyph instructions dropped when writing the subset?
+    pub(crate) strips_hinting: bool,
+    /// Names of the retained glyphs from the `post` table. If `None`, names are not written.
+    pub(crate) glyph_names: Option<Vec<GlyphName<'a>>>,
+    /// Embedded bitmap strikes with glyphs remapped to new IDs. If `None`, bitmap tables are not written.
+    pub(crate) bitmap_strikes: Option<Vec<BitmapStrikeSubset<'a>>>,
+    /// `sbix` table flags and strikes with glyphs remapped to new IDs. If `None`, the `sbix` table is not written.
+    pub(crate) sbix_strikes: Option<(u16, Vec<SbixStrikeSubset<'a>>)>,
+    /// Non-zero glyph classes from the `GDEF` table as `(new glyph ID, class)` tuples ordered by the ID.
+    /// If `None`, the `GDEF` table is not written.
+    pub(crate) glyph_classes: Option<Vec<(u16, u16)>>,
+    /// Format 0 subtables from the `kern` table with pairs remapped to new glyph IDs. If `None`,
+    /// the `kern` table is not written (unless all glyphs are retained, in which case it's copied verbatim).
+    pub(crate) kern_subtables: Option<Vec<KernSubtableSubset>>,
+    /// `MATH` table with glyphs remapped to new IDs. If `None`, the `MATH` table is not written
+    /// (unless all glyphs are retained, in which case it's copied verbatim).
+    pub(crate) math_table: Option<MathTable<'a>>,
+    /// Bounding box in the `[x_min, y_min, x_max, y_max]` format written to the `head` table. Computed once
+    /// on construction so that it's shared among serializations.
+    pub(crate) bounding_box: [i16; 4],
+}
+
+/// Glyphs parsed from the original font keyed by the original glyph ID. Used to share parsing work
+/// among several subsets of the same font.
+type GlyphCache<'a> = BTreeMap<u16, GlyphWithMetrics<'a>>;
+
+/// Format 0 `kern` subtable retained in a [`FontSubset`].
+#[derive(Debug)]
+pub(crate) struct KernSubtableSubset {
+    /// Coverage field from the original subtable header.
+    pub(crate) coverage: u16,
+    /// `(left, right, value)` records with glyphs remapped to new IDs, sorted by `(left, right)`.
+    pub(crate) pairs: Vec<(u16, u16, [u8; 2])>,
+}
+
+/// Unicode variation sequences with a specific variation selector retained in a [`FontSubset`].
+#[derive(Debug)]
+pub(crate) struct VariationSelectorSubset {
+    pub(crate) selector: u32,
+    /// Base chars of the sequences mapped to the default glyph, in the increasing order.
+    pub(crate) default_bases: Vec<char>,
+    /// Base chars of the sequences mapped to other glyphs together with the new glyph IDs, ordered by the char.
+    pub(crate) glyph_mappings: Vec<(char, u16)>,
+}
+
+/// `sbix` strike retained in a [`FontSubset`].
+#[derive(Debug)]
+pub(crate) struct SbixStrikeSubset<'a> {
+    pub(crate) ppem: u16,
+    pub(crate) ppi: u16,
+    /// Glyph data records of the retained glyphs together with their new IDs, ordered by the ID.
+    pub(crate) glyphs: Vec<(u16, SbixGlyph<'a>)>,
+}
+
+/// Embedded bitmap strike retained in a [`FontSubset`].
+#[derive(Debug)]
+pub(crate) struct BitmapStrikeSubset<'a> {
+    /// Original `BitmapSize` record from the `EBLC` table.
+    pub(crate) record: [u8; BITMAP_SIZE_LEN],
+    /// Bitmaps of the retained glyphs together with their new IDs, ordered by the ID.
+    pub(crate) glyphs: Vec<(u16, BitmapGlyph<'a>)>,
 }
 
 impl<'a> FontSubset<'a> {
-    pub(crate) fn new(font: Font<'a>, distinct_chars: &BTreeSet<char>) -> Result<Self, ParseError> {
-        let mut this = Self::empty(font)?;
+    /// IDs of records from the `name` table retained by default: copyright notice, font family,
+    /// font subfamily, full font name, version, PostScript name, trademark, license description
+    /// and license info URL.
+    const ESSENTIAL_NAME_IDS: [u16; 9] = [0, 1, 2, 4, 5, 6, 7, 13, 14];
+
+    /// Creates a subset of the `font` retaining all `distinct_chars` with the default options.
+    /// This is equivalent to [`Font::subset()`].
+    ///
+    /// # Errors
+    ///
+    /// This operation will parse more font data, so it may return parsing errors.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use font_subset::{Font, FontSubset};
+    /// let font_bytes = // font in the OpenType format
+    /// # include_bytes!("../examples/FiraMono-Regular.ttf");
+    /// let font = Font::new(font_bytes)?;
+    /// let subset = FontSubset::new(&font, &('0'..='9').collect())?;
+    /// assert_eq!(subset.retained_char_count(), 10);
+    /// // Digits + the missing glyph (some digits may use additional component glyphs)
+    /// assert!(subset.retained_glyph_count() >= 11);
+    /// let ttf = subset.to_opentype();
+    /// # Ok::<_, font_subset::ParseError>(())
+    /// ```
+    pub fn new(font: &'a Font<'a>, distinct_chars: &BTreeSet<char>) -> Result<Self, ParseError> {
+        Self::with_checked_options(font, distinct_chars, SubsetOptions::default())
+    }
+
+    /// Creates a subset of the `font` retaining all `distinct_chars` with the specified options.
+    /// This is exposed publicly via [`Font::subset_with_options()`].
+    pub(crate) fn with_options(
+        font: &'a Font<'a>,
+        distinct_chars: &BTreeSet<char>,
+        options: SubsetOptions,
+    ) -> Result<Self, SubsetError> {
+        options.check(font)?;
+        Ok(Self::with_checked_options(font, distinct_chars, options)?)
+    }
+
+    fn with_checked_options(
+        font: &'a Font<'a>,
+        distinct_chars: &BTreeSet<char>,
+        options: SubsetOptions,
+    ) -> Result<Self, ParseError> {
+        if options.retains_all_glyphs(distinct_chars) {
+            return Self::all(font, options);
+        }
+
+        let mut this = Self::empty(font, options)?;
         for &ch in distinct_chars {
-            this.push_char(ch)?;
+            this.push_char(ch, None)?;
+        }
+        this.finish(None)?;
+        Ok(this)
+    }
+
+    /// Creates subsets of the `font` for each of the specified char `buckets` with the specified options.
+    /// Glyphs parsed for a bucket are cached and reused for the following buckets.
+    pub(crate) fn sharded(
+        font: &'a Font<'a>,
+        buckets: &[BTreeSet<char>],
+        options: &SubsetOptions,
+    ) -> Result<Vec<Self>, SubsetError> {
+        options.check(font)?;
+        let mut glyph_cache = GlyphCache::new();
+        buckets
+            .iter()
+            .map(|distinct_chars| {
+                if options.retains_all_glyphs(distinct_chars) {
+                    return Ok(Self::all(font, options.clone())?);
+                }
+
+                let mut this = Self::empty(font, options.clone())?;
+                for &ch in distinct_chars {
+                    this.push_char(ch, Some(&mut glyph_cache))?;
+                }
+                this.finish(Some(&mut glyph_cache))?;
+                Ok(this)
+            })
+            .collect()
+    }
+
+    /// Creates a subset of the `font` retaining glyphs with the specified IDs (plus glyphs referenced
+    /// by them as components, and the missing glyph #0) without consulting the `cmap` table. This is useful
+    /// if glyph IDs are already resolved, e.g., by a text shaping engine.
+    ///
+    /// Glyphs are renumbered in the subset, and the subset `cmap` table doesn't map any chars.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SubsetError::InvalidGlyph`] if any of `glyph_ids` is out of range for the `font`.
+    /// This operation will parse more font data, so it may also return parsing errors.
+    pub fn from_glyphs(font: &'a Font<'a>, glyph_ids: &BTreeSet<u16>) -> Result<Self, SubsetError> {
+        if let Some(&glyph_idx) = glyph_ids.range(font.glyph_count..).next() {
+            return Err(SubsetError::InvalidGlyph(glyph_idx));
+        }
+
+        let mut this = Self::empty(font, SubsetOptions::default())?;
+        for &glyph_idx in glyph_ids {
+            this.ensure_glyph(glyph_idx)?;
         }
+        this.finish(None)?;
+        Ok(this)
+    }
+
+    /// Creates a subset of the `font` retaining all provided `chars`. Chars are deduplicated
+    /// and sorted internally. To subset a font for a string, use [`Font::subset_for_text()`].
+    ///
+    /// # Errors
+    ///
+    /// This operation will parse more font data, so it may return parsing errors.
+    pub fn from_iter_chars(
+        font: &'a Font<'a>,
+        chars: impl IntoIterator<Item = char>,
+    ) -> Result<Self, ParseError> {
+        let distinct_chars: BTreeSet<char> = chars.into_iter().collect();
+        Self::new(font, &distinct_chars)
+    }
+
+    fn empty(font: &'a Font<'a>, options: SubsetOptions) -> Result<Self, ParseError> {
+        let name_table = Self::read_name_table(font, &options);
+        let mut this = Self {
+            font,
+            options,
+            char_map: vec![],
+            variation_sequences: vec![],
+            old_to_new_glyph_idx: BTreeMap::new(),
+            glyphs: vec![],
+            retains_all_glyphs: false,
+            name_table,
+            retained_name_ids: Self::ESSENTIAL_NAME_IDS.into_iter().collect(),
+            strips_hinting: false,
+            glyph_names: None,
+            bitmap_strikes: None,
+            sbix_strikes: None,
+            glyph_classes: None,
+            kern_subtables: None,
+            math_table: None,
+            bounding_box: [0; 4],
+        };
+        // The 0th glyph must always be mapped to itself
+        this.ensure_glyph(0)?;
+        Ok(this)
+    }
+
+    /// Creates a subset of the `font` retaining only the missing glyph #0 and not mapping any chars.
+    pub(crate) fn notdef_only(font: &'a Font<'a>) -> Result<Self, ParseError> {
+        let mut this = Self::empty(font, SubsetOptions::default())?;
+        this.finish(None)?;
         Ok(this)
     }
 
-    fn empty(font: Font<'a>) -> Result<Self, ParseError> {
-        let empty_glyph = font.glyph(0)?;
-        Ok(Self {
+    pub(crate) fn all(font: &'a Font<'a>, options: SubsetOptions) -> Result<Self, ParseError> {
+        let glyph_count = font.glyph_count;
+        let name_table = Self::read_name_table(font, &options);
+        let mut this = Self {
             font,
+            options,
             char_map: vec![],
-            // The 0th glyph must always be mapped to itself
-            old_to_new_glyph_idx: BTreeMap::from([(0, 0)]),
-            glyphs: vec![empty_glyph],
-        })
+            variation_sequences: vec![],
+            old_to_new_glyph_idx: BTreeMap::new(),
+            glyphs: Vec::with_capacity(glyph_count.into()),
+            retains_all_glyphs: true,
+            name_table,
+            retained_name_ids: Self::ESSENTIAL_NAME_IDS.into_iter().collect(),
+            strips_hinting: false,
+            glyph_names: None,
+            bitmap_strikes: None,
+            sbix_strikes: None,
+            glyph_classes: None,
+            kern_subtables: None,
+            math_table: None,
+            bounding_box: [0; 4],
+        };
+        for glyph_idx in 0..glyph_count {
+            // Since glyph IDs are unchanged, composite glyphs don't need to be transformed.
+            let glyph = this.read_glyph(glyph_idx)?;
+            this.glyphs.push(glyph);
+            this.old_to_new_glyph_idx.insert(glyph_idx, glyph_idx);
+        }
+        this.finish(None)?;
+        Ok(this)
     }
 
+    /// Extends this subset with the specified `chars`, e.g., to build subsets for progressive font loading.
+    /// Glyph IDs of the already retained glyphs are preserved, with glyphs for the new chars appended
+    /// after them, so that the glyph data of the original subset is a prefix of the glyph data of the extended one.
+    /// Chars already present in the subset are ignored.
+    ///
+    /// If this subset retains all glyphs from the font (e.g., because of [`SubsetOptions::retain_script_glyphs()`]),
+    /// this method is a no-op since all chars are already covered. Conversely, if any of `chars` belongs
+    /// to a script specified via [`SubsetOptions::retain_script_glyphs()`], all glyphs from the font are retained
+    /// after the existing ones. Unlike with [`Font::subset_with_options()`], glyph IDs are not preserved
+    /// from the original font in this case, and only the chars added to this subset are mapped.
+    ///
+    /// # Errors
+    ///
+    /// This operation will parse more font data, so it may return parsing errors. If an error is returned,
+    /// the subset may be left partially extended.
+    pub fn add_chars(&mut self, chars: &BTreeSet<char>) -> Result<(), ParseError> {
+        if self.retains_all_glyphs {
+            return Ok(());
+        }
+
+        let prev_len = self.char_map.len();
+        let prev_glyph_count = self.glyphs.len();
+        for &ch in chars {
+            if self.char_map[..prev_len]
+                .binary_search_by_key(&ch, |&(ch, _)| ch)
+                .is_err()
+            {
+                self.push_char(ch, None)?;
+            }
+        }
+        if self.options.retains_all_glyphs(chars) {
+            for old_idx in 0..self.font.glyph_count {
+                self.ensure_glyph(old_idx)?;
+            }
+        }
+        if self.char_map.len() == prev_len && self.glyphs.len() == prev_glyph_count {
+            return Ok(());
+        }
+        // New chars may precede the existing ones; glyph IDs are unaffected by sorting.
+        self.char_map.sort_unstable_by_key(|&(ch, _)| ch);
+
+        // Glyph-related data is recomputed from scratch for the extended set of glyphs.
+        self.finish(None)?;
+        if self.glyph_names.is_some() {
+            self.keep_glyph_names()?;
+        }
+        Ok(())
+    }
+
+    /// Retains glyph names from the `post` table for the glyphs in this subset. By default, glyph names
+    /// are dropped (i.e., the `post` table is written with version 3.0) to save space.
+    ///
+    /// Names are retained if the `post` table in the original font has version 1.0, 2.0 or 2.5;
+    /// the output table always has version 2.0. If the original table doesn't contain glyph names
+    /// (i.e., has version 3.0), this method is a no-op.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `post` table is malformed, or if it contains invalid names
+    /// for the retained glyphs.
+    pub fn keep_glyph_names(&mut self) -> Result<(), ParseError> {
+        let Some(names) = self.font.glyph_names()? else {
+            return Ok(());
+        };
+
+        let glyph_names = self
+            .new_to_old_glyphs()
+            .into_iter()
+            .map(|old_idx| names.glyph_name(old_idx));
+        self.glyph_names = Some(glyph_names.collect::<Result<_, _>>()?);
+        Ok(())
+    }
+
+    /// Sets IDs of the records from the `name` table to retain in this subset. By default, only the essential
+    /// names are retained: copyright notice (ID 0), font family (1), font subfamily (2), full font name (4),
+    /// version (5), PostScript name (6), trademark (7), license description (13) and license info URL (14).
+    /// Other names (e.g., descriptions, designer info or localized marketing strings) are dropped.
+    /// Legal notices can be trimmed by passing a set without the corresponding IDs.
+    ///
+    /// This method has no effect if the `name` table is [dropped](SubsetOptions::drop_name()) altogether.
+    pub fn with_retained_name_ids(&mut self, ids: &BTreeSet<u16>) {
+        self.retained_name_ids.clone_from(ids);
+    }
+
+    /// Strips TrueType hinting from this subset: the `cvt `, `fpgm` and `prep` tables are not written,
+    /// and instructions are removed from all glyphs. Hinting-related limits in the `maxp` table are reset
+    /// accordingly. This reduces the subset size at the cost of rendering quality at small sizes
+    /// on platforms that rely on hinting.
+    ///
+    /// This method has no effect on fonts with CFF outlines.
+    pub fn strip_hinting(&mut self) {
+        self.strips_hinting = true;
+    }
+
+    /// Returns glyph IDs in the original font for all glyphs retained in this subset, indexed by
+    /// the glyph ID in the subset. This can be used to remap external data indexed by glyph IDs
+    /// (e.g., a `CIDToGIDMap` in PDF documents).
+    pub fn new_to_old_glyphs(&self) -> Vec<u16> {
+        let mut new_to_old_glyph_idx = vec![0; self.glyphs.len()];
+        for (&old_idx, &new_idx) in &self.old_to_new_glyph_idx {
+            new_to_old_glyph_idx[usize::from(new_idx)] = old_idx;
+        }
+        new_to_old_glyph_idx
+    }
+
+    /// Returns the bounding box written to the `head` table of the serialized subset
+    /// in the `[x_min, y_min, x_max, y_max]` format.
+    ///
+    /// By default, this is the bounding box from the original font. If [`SubsetOptions::recompute_bounding_box()`]
+    /// is set, this is the bounding box of all glyphs retained in this subset. Only glyphs with TrueType outlines
+    /// declare bounding boxes; if the subset doesn't contain any such non-empty glyphs (e.g., the font has
+    /// CFF outlines), the bounding box from the original font is returned in this case as well.
+    pub fn bounding_box(&self) -> [i16; 4] {
+        self.bounding_box
+    }
+
+    /// Returns the number of glyphs retained in this subset, including the missing glyph #0
+    /// and glyphs referenced as components by other retained glyphs.
+    pub fn retained_glyph_count(&self) -> usize {
+        self.glyphs.len()
+    }
+
+    /// Returns the number of chars mapped to non-missing glyphs in this subset. Requested chars
+    /// not covered by the font (and not mapped to a [fallback glyph](SubsetOptions::fallback_glyph()))
+    /// are not counted.
+    pub fn retained_char_count(&self) -> usize {
+        if self.retains_all_glyphs {
+            self.font.coverage_bitset().char_count()
+        } else {
+            self.char_map
+                .iter()
+                .filter(|&&(_, glyph_idx)| glyph_idx != 0)
+                .count()
+        }
+    }
+
+    /// Iterates over the chars mapped to non-missing glyphs in this subset, in the increasing order.
+    /// Unlike the set of requested chars, this doesn't include chars not covered by the font
+    /// (unless they are mapped to a [fallback glyph](SubsetOptions::fallback_glyph())), so this is
+    /// the authoritative list of chars supported by the subset; e.g., it can be used to generate
+    /// a `unicode-range` CSS descriptor. The number of yielded chars equals [`Self::retained_char_count()`].
+    pub fn retained_chars(&self) -> impl Iterator<Item = char> + '_ {
+        let all_chars = self
+            .retains_all_glyphs
+            .then(|| self.font.coverage_bitset().into_chars());
+        let mapped_chars = (!self.retains_all_glyphs).then(|| {
+            self.char_map
+                .iter()
+                .filter(|&&(_, glyph_idx)| glyph_idx != 0)
+                .map(|&(ch, _)| ch)
+        });
+        all_chars
+            .into_iter()
+            .flatten()
+            .chain(mapped_chars.into_iter().flatten())
+    }
+
+    /// Returns chars mapped to the glyph with the specified ID in this subset, in the increasing order.
+    /// Multiple chars can share a glyph (e.g., Latin and Cyrillic lookalikes). This is useful to explain
+    /// why a glyph is retained in the subset.
+    ///
+    /// The returned list is empty for glyphs retained only as components of composite glyphs (or as glyphs
+    /// in variation sequences), and for the missing glyph #0, since chars not covered by the font
+    /// are not mapped in the subset.
+    pub fn chars_for_glyph(&self, new_idx: u16) -> Vec<char> {
+        if new_idx == 0 {
+            return vec![];
+        }
+        if self.retains_all_glyphs {
+            // Glyph IDs are unchanged, so the original `cmap` table can be queried.
+            self.font
+                .coverage_bitset()
+                .into_chars()
+                .filter(|&ch| self.font.map_char(ch).ok() == Some(new_idx))
+                .collect()
+        } else {
+            self.char_map
+                .iter()
+                .filter(|&&(_, glyph_idx)| glyph_idx == new_idx)
+                .map(|&(ch, _)| ch)
+                .collect()
+        }
+    }
+
+    /// Returns information about the glyph with the specified ID in this subset, or `None` if the subset
+    /// doesn't contain a glyph with this ID.
+    pub fn glyph(&self, new_idx: u16) -> Option<GlyphInfo> {
+        self.glyphs.get(usize::from(new_idx)).map(GlyphInfo::new)
+    }
+
+    /// Checks that this subset is consistent with the `original` font it was created from. Namely, checks that
+    /// every retained char is mapped to the glyph corresponding to the char in the `original` font,
+    /// and that every retained glyph has the same metrics and outline as in the `original` font (with component IDs
+    /// of composite glyphs remapped). [Blanked glyphs](SubsetOptions::blank_glyphs()) are only checked
+    /// for metrics.
+    ///
+    /// This is mostly useful for testing.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SubsetError::GlyphMismatch`] with the ID of the first inconsistent glyph in the subset.
+    /// This operation will parse more data from the `original` font, so it may also return parsing errors.
+    pub fn validate_against(&self, original: &Font<'_>) -> Result<(), SubsetError> {
+        let new_to_old_glyph_idx = self.new_to_old_glyphs();
+        for &(ch, new_idx) in &self.char_map {
+            let mut old_idx = original.map_char(ch)?;
+            if old_idx == 0 {
+                old_idx = self.options.fallback_glyph.unwrap_or(0);
+            }
+            if new_to_old_glyph_idx[usize::from(new_idx)] != old_idx {
+                return Err(SubsetError::GlyphMismatch(new_idx));
+            }
+        }
+
+        for (new_idx, (glyph, &old_idx)) in
+            (0_u16..).zip(self.glyphs.iter().zip(&new_to_old_glyph_idx))
+        {
+            let original_glyph = original.glyph(old_idx)?;
+            let metrics_match =
+                glyph.advance == original_glyph.advance && glyph.lsb == original_glyph.lsb;
+            let outline_matches = self.options.blanked_glyphs.contains(&old_idx)
+                || glyph
+                    .inner
+                    .matches(&original_glyph.inner, &new_to_old_glyph_idx);
+            if !metrics_match || !outline_matches {
+                return Err(SubsetError::GlyphMismatch(new_idx));
+            }
+        }
+        Ok(())
+    }
+
+    /// Collects glyph-related data for the retained glyphs. This must be called once all chars and glyphs
+    /// are retained; previously collected data is overwritten.
+    fn finish(&mut self, cache: Option<&mut GlyphCache<'a>>) -> Result<(), ParseError> {
+        self.variation_sequences.clear();
+        self.collect_variation_sequences(cache)?;
+        self.collect_bitmaps()?;
+        self.collect_sbix()?;
+        self.collect_glyph_classes()?;
+        self.collect_kerning()?;
+        self.collect_math()?;
+        self.collect_bounding_box();
+        Ok(())
+    }
+
+    /// Collects Unicode variation sequences for the retained chars. Glyphs that the sequences are mapped to
+    /// are added to the subset, so this must be called before collecting any glyph-related data.
+    fn collect_variation_sequences(
+        &mut self,
+        mut cache: Option<&mut GlyphCache<'a>>,
+    ) -> Result<(), ParseError> {
+        if self.char_map.is_empty() {
+            return Ok(()); // there are no base chars for sequences
+        }
+        // A malformed format 14 subtable shouldn't prevent subsetting; variation sequences are dropped instead.
+        let Ok(Some(sequences)) = self.font.variation_sequences() else {
+            return Ok(());
+        };
+
+        for record in &sequences.records {
+            let mut default_bases = vec![];
+            let mut glyph_mappings = vec![];
+            for i in 0..self.char_map.len() {
+                let (base, new_base_idx) = self.char_map[i];
+                let Some(mapping) = record.map_char(base) else {
+                    continue;
+                };
+                if self.font.map_char(base)? == 0 {
+                    continue; // sequences for uncovered chars are meaningless
+                }
+                match mapping {
+                    VariationMapping::Default => default_bases.push(base),
+                    VariationMapping::Glyph(old_idx) if old_idx >= self.font.glyph_count => {
+                        // Mappings to invalid glyphs are dropped.
+                    }
+                    VariationMapping::Glyph(old_idx) => {
+                        let new_idx = self.ensure_cached_glyph(old_idx, cache.as_deref_mut())?;
+                        if new_idx == new_base_idx {
+                            // Do not emit a redundant mapping to the default glyph.
+                            default_bases.push(base);
+                        } else {
+                            glyph_mappings.push((base, new_idx));
+                        }
+                    }
+                }
+            }
+
+            if !default_bases.is_empty() || !glyph_mappings.is_empty() {
+                self.variation_sequences.push(VariationSelectorSubset {
+                    selector: record.selector,
+                    default_bases,
+                    glyph_mappings,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Collects embedded bitmaps for the retained glyphs if required by the subset options.
+    fn collect_bitmaps(&mut self) -> Result<(), ParseError> {
+        if !self.options.retain_bitmaps {
+            return Ok(());
+        }
+        let Some(bitmaps) = self.font.embedded_bitmaps()? else {
+            return Ok(());
+        };
+
+        let new_to_old_glyph_idx = self.new_to_old_glyphs();
+        let strikes = bitmaps.strikes.iter().map(|strike| {
+            let mut glyphs = vec![];
+            for (new_idx, &old_idx) in (0_u16..).zip(&new_to_old_glyph_idx) {
+                if self.options.blanked_glyphs.contains(&old_idx) {
+                    continue;
+                }
+                if let Some(glyph) = strike.glyph(bitmaps.ebdt, old_idx)? {
+                    glyphs.push((new_idx, glyph));
+                }
+            }
+            Ok(BitmapStrikeSubset {
+                record: strike.record,
+                glyphs,
+            })
+        });
+        self.bitmap_strikes = Some(strikes.collect::<Result<_, ParseError>>()?);
+        Ok(())
+    }
+
+    /// Collects `sbix` strikes for the retained glyphs if required by the subset options.
+    fn collect_sbix(&mut self) -> Result<(), ParseError> {
+        if !self.options.retain_sbix {
+            return Ok(());
+        }
+        let Some(sbix) = self.font.sbix_table()? else {
+            return Ok(());
+        };
+
+        let new_to_old_glyph_idx = self.new_to_old_glyphs();
+        let strikes = sbix.strikes.iter().map(|strike| {
+            let mut glyphs = vec![];
+            for (new_idx, &old_idx) in (0_u16..).zip(&new_to_old_glyph_idx) {
+                if self.options.blanked_glyphs.contains(&old_idx) {
+                    continue;
+                }
+                if let Some(glyph) = strike.glyph(old_idx)? {
+                    if glyph.is_raster() {
+                        glyphs.push((new_idx, glyph));
+                    }
+                }
+            }
+            Ok(SbixStrikeSubset {
+                ppem: strike.ppem,
+                ppi: strike.ppi,
+                glyphs,
+            })
+        });
+        let strikes = strikes.collect::<Result<_, ParseError>>()?;
+        self.sbix_strikes = Some((sbix.flags, strikes));
+        Ok(())
+    }
+
+    /// Collects glyph classes for the retained glyphs if required by the subset options.
+    fn collect_glyph_classes(&mut self) -> Result<(), ParseError> {
+        if !self.options.retain_glyph_classes {
+            return Ok(());
+        }
+        let Some(class_def) = self.font.glyph_class_def()? else {
+            return Ok(());
+        };
+
+        let glyph_classes: Vec<_> = (0_u16..)
+            .zip(self.new_to_old_glyphs())
+            .filter_map(|(new_idx, old_idx)| {
+                let class = class_def.class(old_idx);
+                (class != 0).then_some((new_idx, class))
+            })
+            .collect();
+        self.glyph_classes = (!glyph_classes.is_empty()).then_some(glyph_classes);
+        Ok(())
+    }
+
+    fn read_name_table(font: &Font<'a>, options: &SubsetOptions) -> Option<NameTable<'a>> {
+        if options.drop_name {
+            None
+        } else {
+            // A malformed or unsupported `name` table shouldn't prevent subsetting.
+            font.name_table().ok().flatten()
+        }
+    }
+
+    /// Computes the union of bounding boxes of the retained glyphs if required by the subset options,
+    /// falling back to the bounding box from the original `head` table.
+    fn collect_bounding_box(&mut self) {
+        let union = self
+            .options
+            .recompute_bounding_box
+            .then(|| self.glyph_bboxes_union())
+            .flatten();
+        self.bounding_box = union.unwrap_or_else(|| {
+            let head = self.font.head.as_ref();
+            let mut bbox = [0_i16; 4];
+            let original = &head[Font::HEAD_BBOX_OFFSET..Font::HEAD_BBOX_OFFSET + 8];
+            for (coord, bytes) in bbox.iter_mut().zip(original.chunks_exact(2)) {
+                *coord = i16::from_be_bytes([bytes[0], bytes[1]]);
+            }
+            bbox
+        });
+    }
+
+    fn glyph_bboxes_union(&self) -> Option<[i16; 4]> {
+        self.glyphs
+            .iter()
+            .filter_map(|glyph| glyph.inner.bbox())
+            .reduce(|[x_min, y_min, x_max, y_max], bbox| {
+                [
+                    x_min.min(bbox[0]),
+                    y_min.min(bbox[1]),
+                    x_max.max(bbox[2]),
+                    y_max.max(bbox[3]),
+                ]
+            })
+    }
+
+    /// Collects kerning pairs from the `kern` table for the retained glyphs.
+    fn collect_kerning(&mut self) -> Result<(), ParseError> {
+        if self.retains_all_glyphs {
+            // The table is copied verbatim.
+            return Ok(());
+        }
+        let Some(kern) = self.font.kern_table()? else {
+            return Ok(());
+        };
+
+        let subtables = kern.subtables.iter().filter_map(|subtable| {
+            let mut pairs: Vec<_> = subtable
+                .pairs()
+                .filter_map(|(left, right, value)| {
+                    let left = *self.old_to_new_glyph_idx.get(&left)?;
+                    let right = *self.old_to_new_glyph_idx.get(&right)?;
+                    Some((left, right, value))
+                })
+                .collect();
+            // Pairs must be sorted for binary search; glyph renumbering can change the order.
+            pairs.sort_unstable_by_key(|&(left, right, _)| (left, right));
+            (!pairs.is_empty()).then_some(KernSubtableSubset {
+                coverage: subtable.coverage,
+                pairs,
+            })
+        });
+        let subtables: Vec<_> = subtables.collect();
+        self.kern_subtables = (!subtables.is_empty()).then_some(subtables);
+        Ok(())
+    }
+
+    /// Collects `MATH` table data for the retained glyphs if required by the subset options.
+    fn collect_math(&mut self) -> Result<(), ParseError> {
+        if !self.options.retain_math || self.retains_all_glyphs {
+            // If all glyphs are retained, the table is copied verbatim.
+            return Ok(());
+        }
+        let Some(math) = self.font.math_table()? else {
+            return Ok(());
+        };
+
+        let remap_values = |values: &[(u16, i16)]| {
+            let mut values: Vec<_> = values
+                .iter()
+                .filter_map(|&(glyph, value)| {
+                    Some((*self.old_to_new_glyph_idx.get(&glyph)?, value))
+                })
+                .collect();
+            values.sort_unstable_by_key(|&(glyph, _)| glyph);
+            values
+        };
+        let remap_constructions = |constructions: &[(u16, GlyphConstruction)]| {
+            let mut constructions: Vec<_> = constructions
+                .iter()
+                .filter_map(|(glyph, construction)| {
+                    let glyph = *self.old_to_new_glyph_idx.get(glyph)?;
+                    let construction = self.remap_glyph_construction(construction)?;
+                    Some((glyph, construction))
+                })
+                .collect();
+            constructions.sort_unstable_by_key(|(glyph, _)| *glyph);
+            constructions
+        };
+
+        let mut extended_shapes: Vec<_> = math
+            .extended_shapes
+            .iter()
+            .filter_map(|glyph| self.old_to_new_glyph_idx.get(glyph).copied())
+            .collect();
+        extended_shapes.sort_unstable();
+        let math = MathTable {
+            constants: math.constants,
+            italics_corrections: remap_values(&math.italics_corrections),
+            top_accent_attachments: remap_values(&math.top_accent_attachments),
+            extended_shapes,
+            min_connector_overlap: math.min_connector_overlap,
+            vertical_constructions: remap_constructions(&math.vertical_constructions),
+            horizontal_constructions: remap_constructions(&math.horizontal_constructions),
+        };
+        // Offsets in the table are 16-bit, so it's dropped if it becomes too large.
+        self.math_table = u16::try_from(math.len()).is_ok().then_some(math);
+        Ok(())
+    }
+
+    /// Remaps glyphs in a construction from the `MATH` table. Returns `None` if the construction
+    /// has neither retained variants, nor a fully retained assembly.
+    fn remap_glyph_construction(
+        &self,
+        construction: &GlyphConstruction,
+    ) -> Option<GlyphConstruction> {
+        let variants: Vec<_> = construction
+            .variants
+            .iter()
+            .filter_map(|&(glyph, advance)| {
+                Some((*self.old_to_new_glyph_idx.get(&glyph)?, advance))
+            })
+            .collect();
+        let assembly = construction.assembly.as_ref().and_then(|assembly| {
+            let parts = assembly
+                .parts
+                .iter()
+                .map(|&(glyph, fields)| Some((*self.old_to_new_glyph_idx.get(&glyph)?, fields)));
+            Some(GlyphAssembly {
+                italics_correction: assembly.italics_correction,
+                parts: parts.collect::<Option<_>>()?,
+            })
+        });
+        (!variants.is_empty() || assembly.is_some())
+            .then_some(GlyphConstruction { variants, assembly })
+    }
+
+    /// Reads a glyph from the font together with all metrics required by the subset options.
+    fn read_glyph(&self, old_idx: u16) -> Result<GlyphWithMetrics<'a>, ParseError> {
+        let mut glyph = self.font.glyph(old_idx)?;
+        if self.options.blanked_glyphs.contains(&old_idx) {
+            if let Glyph::Cff(cff_glyph) = &mut glyph.inner {
+                // The charset and font dict mapping must be retained for CFF glyphs.
+                cff_glyph.blank();
+            } else {
+                glyph.inner = Glyph::Empty;
+            }
+        }
+        if self.options.vertical_metrics {
+            if let Some(vmtx) = self.font.vertical_metrics() {
+                glyph.vertical_metrics = Some(vmtx.advance_and_lsb(old_idx)?);
+            }
+        }
+        Ok(glyph)
+    }
+
+    /// Ensures that the glyph with the specified original ID (and its components, if any) is retained
+    /// in the subset, and returns its new ID. Retained glyphs are memoized in `old_to_new_glyph_idx`,
+    /// so each original glyph (e.g., a component shared by many accented letters) is parsed
+    /// at most once per subset.
     fn ensure_glyph(&mut self, old_idx: u16) -> Result<u16, ParseError> {
+        self.ensure_cached_glyph(old_idx, None)
+    }
+
+    /// Same as [`Self::ensure_glyph()`], but reads the glyph from the `cache` (if provided) if possible.
+    fn ensure_cached_glyph(
+        &mut self,
+        old_idx: u16,
+        cache: Option<&mut GlyphCache<'a>>,
+    ) -> Result<u16, ParseError> {
+        self.ensure_nested_glyph(old_idx, cache, &mut vec![])
+    }
+
+    /// `stack` contains the composite glyphs (transitively) referencing `old_idx`; it is used to detect
+    /// reference cycles and limit the nesting depth.
+    fn ensure_nested_glyph(
+        &mut self,
+        old_idx: u16,
+        mut cache: Option<&mut GlyphCache<'a>>,
+        stack: &mut Vec<u16>,
+    ) -> Result<u16, ParseError> {
         if let Some(new_idx) = self.old_to_new_glyph_idx.get(&old_idx) {
             return Ok(*new_idx);
         }
+        if stack.contains(&old_idx) {
+            return Err(ParseError::composite_glyph_cycle(old_idx));
+        }
 
-        let mut glyph = self.font.glyph(old_idx)?;
+        let mut glyph = if let Some(cache) = cache.as_deref_mut() {
+            if let Some(glyph) = cache.get(&old_idx) {
+                glyph.clone()
+            } else {
+                let glyph = self.read_glyph(old_idx)?;
+                cache.insert(old_idx, glyph.clone());
+                glyph
+            }
+        } else {
+            self.read_glyph(old_idx)?
+        };
         match &mut glyph.inner {
-            Glyph::Empty | Glyph::Simple(_) => { /* do not transform the glyph */ }
+            // Subroutines called by a CFF glyph are collected when parsing it.
+            Glyph::Empty
+            | Glyph::Simple(_)
+            | Glyph::Cff(CffGlyph {
+                seac_components: None,
+                ..
+            }) => { /* do not transform the glyph */ }
+            Glyph::Cff(CffGlyph {
+                seac_components: Some(components),
+                ..
+            }) => {
+                // `seac` references glyphs by name, so the charstring doesn't need to be updated.
+                if stack.len() >= GlyphComponent::MAX_DEPTH {
+                    return Err(ParseError::composite_depth_exceeded(old_idx));
+                }
+                stack.push(old_idx);
+                for component_idx in components {
+                    *component_idx =
+                        self.ensure_nested_glyph(*component_idx, cache.as_deref_mut(), stack)?;
+                }
+                stack.pop();
+            }
             Glyph::Composite { components, .. } => {
+                if stack.len() >= GlyphComponent::MAX_DEPTH {
+                    return Err(ParseError::composite_depth_exceeded(old_idx));
+                }
+                stack.push(old_idx);
                 for component in components {
-                    component.glyph_idx = self.ensure_glyph(component.glyph_idx)?;
+                    component.glyph_idx =
+                        self.ensure_nested_glyph(component.glyph_idx, cache.as_deref_mut(), stack)?;
                 }
+                stack.pop();
             }
         }
 
@@ -54,10 +1265,17 @@ impl<'a> FontSubset<'a> {
         Ok(new_idx)
     }
 
-    /// Must be called with increasing `ch`.
-    fn push_char(&mut self, ch: char) -> Result<(), ParseError> {
-        let old_idx = self.font.map_char(ch)?;
-        let new_idx = self.ensure_glyph(old_idx)?;
+    /// Must be called with increasing `ch`; otherwise, `char_map` must be sorted afterwards.
+    fn push_char(
+        &mut self,
+        ch: char,
+        cache: Option<&mut GlyphCache<'a>>,
+    ) -> Result<(), ParseError> {
+        let mut old_idx = self.font.map_char(ch)?;
+        if old_idx == 0 {
+            old_idx = self.options.fallback_glyph.unwrap_or(0);
+        }
+        let new_idx = self.ensure_cached_glyph(old_idx, cache)?;
         self.char_map.push((ch, new_idx));
         Ok(())
     }
@@ -0,0 +1,624 @@
+//! Tests for fonts with CFF outlines. Since there are no CFF-flavored test fonts, the `CFF ` table
+//! is synthesized and put into a TrueType font instead of `glyf` / `loca` tables.
+
+use allsorts::cff::{CFFVariant, CFF};
+
+use super::*;
+
+const CFF_SFNT_VERSION: u32 = 0x4f54_544f;
+const GLOBAL_SUBR_COUNT: u16 = 40;
+/// Number of local subroutines. Larger than 1,240, so that the subroutine bias is 1,131.
+const LOCAL_SUBR_COUNT: u16 = 1_300;
+const LOCAL_SUBR_BIAS: i16 = 1_131;
+const GLOBAL_SUBR_BIAS: i16 = 107;
+/// String ID of the first custom string in the string INDEX.
+const FIRST_CUSTOM_SID: u16 = 391;
+
+fn write_index(objects: &[Vec<u8>]) -> Vec<u8> {
+    let mut buffer = u16::try_from(objects.len()).unwrap().to_be_bytes().to_vec();
+    if objects.is_empty() {
+        return buffer;
+    }
+    let data_len: usize = objects.iter().map(Vec::len).sum();
+    let max_offset = u32::try_from(data_len + 1).unwrap();
+    let off_size = usize::try_from(max_offset.ilog2() / 8 + 1).unwrap();
+    buffer.push(u8::try_from(off_size).unwrap());
+    let mut offset = 1_u32;
+    buffer.extend_from_slice(&offset.to_be_bytes()[4 - off_size..]);
+    for object in objects {
+        offset += u32::try_from(object.len()).unwrap();
+        buffer.extend_from_slice(&offset.to_be_bytes()[4 - off_size..]);
+    }
+    for object in objects {
+        buffer.extend_from_slice(object);
+    }
+    buffer
+}
+
+/// Encodes a DICT integer operand in the 5-byte form.
+fn dict_int(value: usize) -> [u8; 5] {
+    let [b0, b1, b2, b3] = i32::try_from(value).unwrap().to_be_bytes();
+    [29, b0, b1, b2, b3]
+}
+
+/// Encodes a charstring from `(operands, operator)` tuples. The operator may be followed by extra bytes
+/// (e.g., the `hintmask` mask).
+fn charstring(ops: &[(&[i16], &[u8])]) -> Vec<u8> {
+    let mut buffer = vec![];
+    for &(operands, operator) in ops {
+        for &operand in operands {
+            if (-107..=107).contains(&operand) {
+                buffer.push(u8::try_from(operand + 139).unwrap());
+            } else {
+                buffer.push(28);
+                buffer.extend_from_slice(&operand.to_be_bytes());
+            }
+        }
+        buffer.extend_from_slice(operator);
+    }
+    buffer
+}
+
+fn local_subr_idx(glyph_idx: u16) -> u16 {
+    (glyph_idx * 7) % LOCAL_SUBR_COUNT
+}
+
+fn font_dict_idx(glyph_idx: u16, is_cid_keyed: bool) -> usize {
+    if is_cid_keyed {
+        usize::from(glyph_idx % 2)
+    } else {
+        0
+    }
+}
+
+fn glyph_charstring(glyph_idx: u16) -> Vec<u8> {
+    let x = i16::try_from(glyph_idx % 100).unwrap();
+    let subr = i16::try_from(local_subr_idx(glyph_idx)).unwrap() - LOCAL_SUBR_BIAS;
+    // 5 stem hints + 4 implicit vertical stem hints = 9 hints, which need a 2-byte mask
+    let hstems: &[i16] = if glyph_idx % 3 == 0 {
+        &[500, 0, 20, 30, 20, 60, 20, 90, 20, 120, 20] // with the advance width
+    } else {
+        &[0, 20, 30, 20, 60, 20, 90, 20, 120, 20]
+    };
+    charstring(&[
+        (hstems, &[18]),                                       // hstemhm
+        (&[0, 20, 30, 20, 60, 20, 90, 20], &[19, 0xff, 0x80]), // hintmask
+        (&[x, 10], &[21]),                                     // rmoveto
+        (&[subr], &[10]),                                      // callsubr
+        (&[-200, -200], &[21]),                                // rmoveto
+        (&[10, 0, 0, 10], &[5]),                               // rlineto
+        (&[], &[14]),                                          // endchar
+    ])
+}
+
+fn local_subr(subr_idx: u16, font_dict_idx: usize) -> Vec<u8> {
+    let dx = i16::try_from(subr_idx).unwrap() + 1;
+    let dy = i16::try_from(font_dict_idx).unwrap() * 10;
+    let global_subr = i16::try_from(subr_idx % GLOBAL_SUBR_COUNT).unwrap() - GLOBAL_SUBR_BIAS;
+    charstring(&[
+        (&[dx, dy], &[5]),       // rlineto
+        (&[global_subr], &[29]), // callgsubr
+        (&[], &[11]),            // return
+    ])
+}
+
+fn global_subr(subr_idx: u16) -> Vec<u8> {
+    let d = i16::try_from(subr_idx).unwrap();
+    charstring(&[
+        (&[d, 5, 5, d, 0, 10], &[8]), // rrcurveto
+        (&[], &[11]),                 // return
+    ])
+}
+
+/// Customizations for synthesized `CFF ` tables.
+#[derive(Debug, Default)]
+struct CffOverrides {
+    /// Charstrings replacing the synthesized ones, keyed by the glyph index.
+    charstrings: BTreeMap<u16, Vec<u8>>,
+    /// String IDs replacing the synthesized ones in a name-keyed font, keyed by the glyph index.
+    sids: BTreeMap<u16, u16>,
+}
+
+fn synthesize_charset(glyph_count: u16, is_cid_keyed: bool, overrides: &CffOverrides) -> Vec<u8> {
+    let mut charset = vec![];
+    if is_cid_keyed {
+        charset.push(2); // format
+        charset.extend_from_slice(&1_001_u16.to_be_bytes()); // first CID
+        charset.extend_from_slice(&(glyph_count - 2).to_be_bytes()); // left glyphs in range
+    } else {
+        charset.push(0); // format
+        for glyph_idx in 1..glyph_count {
+            let sid = overrides
+                .sids
+                .get(&glyph_idx)
+                .copied()
+                .unwrap_or(FIRST_CUSTOM_SID + 1 + glyph_idx);
+            charset.extend_from_slice(&sid.to_be_bytes());
+        }
+    }
+    charset
+}
+
+/// Synthesizes `FDSelect` in format 3, with a separate range for each glyph.
+fn synthesize_fd_select(glyph_count: u16) -> Vec<u8> {
+    let mut fd_select = vec![3]; // format
+    fd_select.extend_from_slice(&glyph_count.to_be_bytes()); // number of ranges
+    for glyph_idx in 0..glyph_count {
+        fd_select.extend_from_slice(&glyph_idx.to_be_bytes());
+        fd_select.push(u8::try_from(font_dict_idx(glyph_idx, true)).unwrap());
+    }
+    fd_select.extend_from_slice(&glyph_count.to_be_bytes()); // sentinel
+    fd_select
+}
+
+/// Synthesizes a private dict together with local subroutines placed immediately after it.
+fn synthesize_private_dict(font_dict_idx: usize) -> (Vec<u8>, Vec<u8>) {
+    let subrs: Vec<_> = (0..LOCAL_SUBR_COUNT)
+        .map(|subr_idx| local_subr(subr_idx, font_dict_idx))
+        .collect();
+    let subrs = write_index(&subrs);
+    // BlueValues, defaultWidthX, nominalWidthX
+    let mut dict = vec![129, 149, 0xf7, 0x7e, 149, 6, 0xf8, 0x88, 20, 139, 21];
+    // Subrs; the offset is relative to the dict start
+    let dict_len = dict.len() + 6;
+    dict.extend_from_slice(&dict_int(dict_len));
+    dict.push(19);
+    (dict, subrs)
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+struct CffOffsets {
+    charset: usize,
+    fd_select: usize,
+    charstrings: usize,
+    fd_array: usize,
+    private_dicts: Vec<usize>,
+}
+
+/// Synthesizes a `CFF ` table with the specified number of glyphs. Glyphs call local subroutines,
+/// which in turn call global subroutines.
+///
+/// For a CID-keyed font, there are 2 font dicts (for even and odd glyphs respectively),
+/// and glyph CIDs are offset by 1,000 from glyph indices. For a name-keyed font, glyph names are `g{idx}`.
+fn synthesize_cff_table(glyph_count: u16, is_cid_keyed: bool, overrides: &CffOverrides) -> Vec<u8> {
+    let font_dict_count = if is_cid_keyed { 2 } else { 1 };
+    let name_index = write_index(&[b"Test".to_vec()]);
+    let mut strings = vec![b"Adobe".to_vec(), b"Identity".to_vec()];
+    if !is_cid_keyed {
+        strings.extend((1..glyph_count).map(|idx| format!("g{idx}").into_bytes()));
+    }
+    let string_index = write_index(&strings);
+    let global_subrs: Vec<_> = (0..GLOBAL_SUBR_COUNT).map(global_subr).collect();
+    let global_subrs = write_index(&global_subrs);
+
+    let charset = synthesize_charset(glyph_count, is_cid_keyed, overrides);
+    let fd_select = if is_cid_keyed {
+        synthesize_fd_select(glyph_count)
+    } else {
+        vec![]
+    };
+    let charstrings: Vec<_> = (0..glyph_count)
+        .map(|glyph_idx| {
+            overrides
+                .charstrings
+                .get(&glyph_idx)
+                .cloned()
+                .unwrap_or_else(|| glyph_charstring(glyph_idx))
+        })
+        .collect();
+    let charstrings = write_index(&charstrings);
+
+    let private_dicts: Vec<_> = (0..font_dict_count).map(synthesize_private_dict).collect();
+
+    let top_dict = |offsets: &CffOffsets| {
+        let mut dict = vec![];
+        if is_cid_keyed {
+            // ROS: registry SID, ordering SID, supplement
+            dict.extend_from_slice(&dict_int(FIRST_CUSTOM_SID.into()));
+            dict.extend_from_slice(&dict_int((FIRST_CUSTOM_SID + 1).into()));
+            dict.extend_from_slice(&[139, 12, 30]);
+        }
+        // FontMatrix: 0.001 0 0 0.001 0 0
+        dict.extend_from_slice(&[30, 0x0a, 0x00, 0x1f, 139, 139]);
+        dict.extend_from_slice(&[30, 0x0a, 0x00, 0x1f, 139, 139, 12, 7]);
+        dict.extend_from_slice(&dict_int(offsets.charset));
+        dict.push(15);
+        dict.extend_from_slice(&dict_int(offsets.charstrings));
+        dict.push(17);
+        if is_cid_keyed {
+            dict.extend_from_slice(&dict_int(glyph_count.into()));
+            dict.extend_from_slice(&[12, 34]); // CIDCount
+            dict.extend_from_slice(&dict_int(offsets.fd_array));
+            dict.extend_from_slice(&[12, 36]);
+            dict.extend_from_slice(&dict_int(offsets.fd_select));
+            dict.extend_from_slice(&[12, 37]);
+        } else {
+            dict.extend_from_slice(&dict_int(private_dicts[0].0.len()));
+            dict.extend_from_slice(&dict_int(offsets.private_dicts[0]));
+            dict.push(18);
+        }
+        dict
+    };
+    let fd_array = |offsets: &CffOffsets| {
+        let font_dicts: Vec<_> = private_dicts
+            .iter()
+            .zip(&offsets.private_dicts)
+            .map(|((dict, _), &offset)| {
+                let mut font_dict = dict_int(dict.len()).to_vec();
+                font_dict.extend_from_slice(&dict_int(offset));
+                font_dict.push(18);
+                font_dict
+            })
+            .collect();
+        write_index(&font_dicts)
+    };
+
+    // Since all offsets are encoded with the fixed length, they don't influence the table layout.
+    let assemble = |offsets: &CffOffsets| {
+        let mut table = vec![1, 0, 4, 4];
+        table.extend_from_slice(&name_index);
+        table.extend_from_slice(&write_index(&[top_dict(offsets)]));
+        table.extend_from_slice(&string_index);
+        table.extend_from_slice(&global_subrs);
+        let mut actual_offsets = CffOffsets {
+            charset: table.len(),
+            ..CffOffsets::default()
+        };
+        table.extend_from_slice(&charset);
+        actual_offsets.fd_select = table.len();
+        table.extend_from_slice(&fd_select);
+        actual_offsets.charstrings = table.len();
+        table.extend_from_slice(&charstrings);
+        if is_cid_keyed {
+            actual_offsets.fd_array = table.len();
+            table.extend_from_slice(&fd_array(offsets));
+        }
+        for (dict, subrs) in &private_dicts {
+            actual_offsets.private_dicts.push(table.len());
+            table.extend_from_slice(dict);
+            table.extend_from_slice(subrs);
+        }
+        (table, actual_offsets)
+    };
+
+    let placeholder_offsets = CffOffsets {
+        private_dicts: vec![0; font_dict_count],
+        ..CffOffsets::default()
+    };
+    let (_, offsets) = assemble(&placeholder_offsets);
+    let (table, actual_offsets) = assemble(&offsets);
+    assert_eq!(actual_offsets, offsets);
+    table
+}
+
+/// Replaces TrueType outlines in the test font with synthesized CFF ones.
+fn cff_font(is_cid_keyed: bool) -> Vec<u8> {
+    cff_font_with_overrides(is_cid_keyed, &CffOverrides::default())
+}
+
+fn cff_font_with_overrides(is_cid_keyed: bool, overrides: &CffOverrides) -> Vec<u8> {
+    let font = Font::new(MONO_FONT.bytes).unwrap();
+    let mut tables = read_tables(MONO_FONT.bytes);
+    for tag in [b"glyf", b"loca", b"cvt ", b"fpgm", b"prep"] {
+        tables.remove(tag);
+    }
+    let mut maxp = vec![0, 0, 0x50, 0]; // version 0.5
+    maxp.extend_from_slice(&font.glyph_count.to_be_bytes());
+    tables.insert(*b"maxp", maxp);
+    tables.insert(
+        *b"CFF ",
+        synthesize_cff_table(font.glyph_count, is_cid_keyed, overrides),
+    );
+    write_tables(CFF_SFNT_VERSION, &tables)
+}
+
+fn glyph_paths(cff: &mut CFF<'_>) -> Vec<String> {
+    let glyph_count = u16::try_from(cff.fonts[0].char_strings_index.len()).unwrap();
+    (0..glyph_count)
+        .map(|glyph_idx| {
+            let mut sink = SvgPathSink::default();
+            cff.visit(glyph_idx, &mut sink).unwrap();
+            sink.0
+        })
+        .collect()
+}
+
+/// Returns non-empty local subroutine indices for each font dict.
+fn non_empty_local_subrs(cff: &CFF<'_>) -> Vec<BTreeSet<usize>> {
+    let local_subrs = match &cff.fonts[0].data {
+        CFFVariant::CID(data) => data.local_subr_indices.iter().collect(),
+        CFFVariant::Type1(data) => vec![&data.local_subr_index],
+    };
+    local_subrs
+        .into_iter()
+        .map(|subrs| {
+            let subrs = subrs.as_ref().unwrap();
+            assert_eq!(subrs.len(), usize::from(LOCAL_SUBR_COUNT));
+            (0..subrs.len())
+                .filter(|&idx| !subrs.read_object(idx).unwrap().is_empty())
+                .collect()
+        })
+        .collect()
+}
+
+#[test_casing(2, [false, true])]
+fn subsetting_font_with_cff_outlines(is_cid_keyed: bool) {
+    let font_bytes = cff_font(is_cid_keyed);
+    let font = Font::new(&font_bytes).unwrap();
+    for glyph_idx in 0..font.glyph_count {
+        let Glyph::Cff(glyph) = font.glyph(glyph_idx).unwrap().inner else {
+            panic!("unexpected glyph: {glyph_idx}");
+        };
+        assert_eq!(glyph.contour_count, 2);
+    }
+    let err = font.glyph_bbox(0).unwrap_err();
+    assert!(
+        matches!(
+            err.kind,
+            ParseErrorKind::UnsupportedOutlineFormat(TableTag::CFF)
+        ),
+        "{err:?}"
+    );
+
+    let chars: BTreeSet<char> = "Hello, CFF!".chars().collect();
+    let subset = font.subset(&chars).unwrap();
+    let otf = subset.to_opentype();
+    assert_eq!(otf[..4], CFF_SFNT_VERSION.to_be_bytes());
+    assert_eq!(subset.estimated_opentype_size(), otf.len());
+    assert!(subset.glyf_table_bytes().is_none());
+    assert!(subset.loca_table_bytes().is_none());
+    // CFF glyphs don't declare bounding boxes, so the original one is retained.
+    assert_eq!(
+        read_tables(&otf)[b"head"][36..44],
+        font.head.as_ref()[36..44]
+    );
+    assert_valid_font(&otf, true, chars.iter().copied());
+    let woff2 = subset.to_woff2();
+    assert_valid_font(&woff2, false, chars.iter().copied());
+
+    let tables = read_tables(&font_bytes);
+    let mut original_cff = ReadScope::new(&tables[b"CFF "]).read::<CFF<'_>>().unwrap();
+    let original_paths = glyph_paths(&mut original_cff);
+    let subset_tables = read_tables(&otf);
+    assert!(!subset_tables.contains_key(b"glyf"));
+    let mut subset_cff = ReadScope::new(&subset_tables[b"CFF "])
+        .read::<CFF<'_>>()
+        .unwrap();
+    assert_eq!(subset_cff.fonts[0].is_cid_keyed(), is_cid_keyed);
+    let subset_paths = glyph_paths(&mut subset_cff);
+
+    let charset = &subset_cff.fonts[0].charset;
+    let mut expected_subrs = vec![BTreeSet::new(); if is_cid_keyed { 2 } else { 1 }];
+    for (new_idx, path) in (0..).zip(&subset_paths) {
+        let old_idx = match charset.id_for_glyph(new_idx).unwrap() {
+            0 => 0,
+            id if is_cid_keyed => id - 1_000,
+            sid => sid - FIRST_CUSTOM_SID - 1,
+        };
+        assert_eq!(
+            *path,
+            original_paths[usize::from(old_idx)],
+            "glyph #{new_idx}"
+        );
+        expected_subrs[font_dict_idx(old_idx, is_cid_keyed)]
+            .insert(usize::from(local_subr_idx(old_idx)));
+    }
+    assert_eq!(non_empty_local_subrs(&subset_cff), expected_subrs);
+    assert!(expected_subrs.iter().all(|subrs| !subrs.is_empty()));
+
+    let expected_global_subrs: BTreeSet<_> = expected_subrs
+        .iter()
+        .flatten()
+        .map(|&idx| idx % usize::from(GLOBAL_SUBR_COUNT))
+        .collect();
+    let global_subrs = &subset_cff.global_subr_index;
+    assert_eq!(global_subrs.len(), usize::from(GLOBAL_SUBR_COUNT));
+    let non_empty_global_subrs: BTreeSet<_> = (0..global_subrs.len())
+        .filter(|&idx| !global_subrs.read_object(idx).unwrap().is_empty())
+        .collect();
+    assert_eq!(non_empty_global_subrs, expected_global_subrs);
+}
+
+#[test]
+fn cff_charstring_count_mismatch_is_detected() {
+    let font_bytes = cff_font(false);
+    let patched = patch_font(&font_bytes, |tables| {
+        let maxp = tables.get_mut(b"maxp").unwrap();
+        maxp[4..6].copy_from_slice(&10_u16.to_be_bytes());
+    });
+    let err = Font::new(&patched).unwrap_err();
+    assert!(
+        matches!(err.kind, ParseErrorKind::InvalidCffDict),
+        "{err:?}"
+    );
+}
+
+#[test]
+fn cff2_outlines_are_rejected() {
+    let font_bytes = patch_font(&cff_font(false), |tables| {
+        let cff = tables.remove(b"CFF ").unwrap();
+        // The table contents are not parsed, so it's fine to keep CFF data here.
+        tables.insert(*b"CFF2", cff);
+    });
+    let err = Font::new(&font_bytes).unwrap_err();
+    assert_eq!(err.table(), Some(TableTag::CFF2));
+    assert!(
+        matches!(
+            err.kind,
+            ParseErrorKind::UnsupportedOutlineFormat(TableTag::CFF2)
+        ),
+        "{err:?}"
+    );
+    let err = err.to_string();
+    assert!(err.contains("`CFF2`"), "{err}");
+    assert!(err.contains("convert the font to TrueType"), "{err}");
+}
+
+#[test]
+fn invalid_subroutine_call_is_detected() {
+    let font_bytes = cff_font(true);
+    let font = Font::new(&font_bytes).unwrap();
+    let glyph_idx = font.map_char('A').unwrap();
+    let original_charstring = glyph_charstring(glyph_idx);
+    let patched = patch_font(&font_bytes, |tables| {
+        let cff = tables.get_mut(b"CFF ").unwrap();
+        let pos = cff
+            .windows(original_charstring.len())
+            .position(|window| window == original_charstring)
+            .unwrap();
+        // Replace the `callsubr` operand (encoded with 3 bytes) with an out-of-range index.
+        let subr_pos = pos + original_charstring.len() - 17;
+        assert_eq!(cff[subr_pos], 28);
+        cff[subr_pos + 1..subr_pos + 3].copy_from_slice(&i16::MAX.to_be_bytes());
+    });
+    let font = Font::new(&patched).unwrap();
+    let err = font.subset_for_text("A").unwrap_err();
+    assert!(
+        matches!(err.kind, ParseErrorKind::InvalidCharstring(idx) if idx == glyph_idx),
+        "{err:?}"
+    );
+}
+
+/// Standard string IDs of `a` and `grave` glyph names.
+const SEAC_SIDS: [u16; 2] = [66, 124];
+/// Standard encoding codes of `a` and `grave` glyph names.
+const SEAC_CODES: [i16; 2] = [97, 193];
+
+#[test]
+fn subsetting_cff_glyph_with_seac() {
+    let font = Font::new(MONO_FONT.bytes).unwrap();
+    let seac_glyph = font.map_char('à').unwrap();
+    let components = [font.map_char('a').unwrap(), font.map_char('`').unwrap()];
+    let [base, accent] = SEAC_CODES;
+    // Components are defined without hints, which are not supported by `allsorts` in `seac` components.
+    let component_charstring = |dx| {
+        charstring(&[
+            (&[dx, 10], &[21]),      // rmoveto
+            (&[10, 0, 0, 10], &[5]), // rlineto
+            (&[], &[14]),            // endchar
+        ])
+    };
+    let overrides = CffOverrides {
+        charstrings: BTreeMap::from([
+            (
+                seac_glyph,
+                charstring(&[(&[500, 100, 0, base, accent], &[14])]),
+            ),
+            (components[0], component_charstring(20)),
+            (components[1], component_charstring(50)),
+        ]),
+        sids: components.into_iter().zip(SEAC_SIDS).collect(),
+    };
+    let font_bytes = cff_font_with_overrides(false, &overrides);
+    let font = Font::new(&font_bytes).unwrap();
+    let info = font.glyph_info(seac_glyph).unwrap().unwrap();
+    assert_eq!(
+        *info.kind(),
+        GlyphKind::Composite {
+            components: components.to_vec()
+        }
+    );
+
+    let subset = font.subset_for_text("à").unwrap();
+    let new_to_old = subset.new_to_old_glyphs();
+    assert_eq!(new_to_old.len(), 4);
+    let new_idx = new_to_old
+        .iter()
+        .position(|&idx| idx == seac_glyph)
+        .unwrap();
+    let new_idx = u16::try_from(new_idx).unwrap();
+    let new_components = components.map(|old_idx| {
+        let new_idx = new_to_old.iter().position(|&idx| idx == old_idx).unwrap();
+        u16::try_from(new_idx).unwrap()
+    });
+    assert_eq!(
+        *subset.glyph(new_idx).unwrap().kind(),
+        GlyphKind::Composite {
+            components: new_components.to_vec()
+        }
+    );
+    let otf = subset.to_opentype();
+    assert_eq!(subset.estimated_opentype_size(), otf.len());
+
+    let tables = read_tables(&font_bytes);
+    let mut original_cff = ReadScope::new(&tables[b"CFF "]).read::<CFF<'_>>().unwrap();
+    let original_paths = glyph_paths(&mut original_cff);
+    let subset_tables = read_tables(&otf);
+    let mut subset_cff = ReadScope::new(&subset_tables[b"CFF "])
+        .read::<CFF<'_>>()
+        .unwrap();
+    let subset_paths = glyph_paths(&mut subset_cff);
+    let seac_path = &original_paths[usize::from(seac_glyph)];
+    assert!(seac_path.contains('M'), "{seac_path}");
+    assert_eq!(subset_paths[usize::from(new_idx)], *seac_path);
+}
+
+#[test]
+fn unresolved_seac_glyph_is_retained_as_is() {
+    let font = Font::new(MONO_FONT.bytes).unwrap();
+    let seac_glyph = font.map_char('à').unwrap();
+    // The `a` and `grave` glyph names are not present in the synthesized font.
+    let [base, accent] = SEAC_CODES;
+    let overrides = CffOverrides {
+        charstrings: BTreeMap::from([(
+            seac_glyph,
+            charstring(&[(&[500, 100, 0, base, accent], &[14])]),
+        )]),
+        ..CffOverrides::default()
+    };
+    let font_bytes = cff_font_with_overrides(false, &overrides);
+    let font = Font::new(&font_bytes).unwrap();
+    let info = font.glyph_info(seac_glyph).unwrap().unwrap();
+    assert_eq!(*info.kind(), GlyphKind::Empty);
+
+    let subset = font.subset_for_text("à").unwrap();
+    assert_eq!(subset.retained_glyph_count(), 2);
+    let otf = subset.to_opentype();
+    let subset_cff = &read_tables(&otf)[b"CFF "];
+    let seac_charstring = &overrides.charstrings[&seac_glyph];
+    assert!(subset_cff
+        .windows(seac_charstring.len())
+        .any(|window| window == seac_charstring));
+}
+
+#[test]
+fn two_byte_operators_are_emulated() {
+    let font_bytes = cff_font(false);
+    let font = Font::new(&font_bytes).unwrap();
+    let glyph_idx = font.map_char('A').unwrap();
+    let subr_idx = local_subr_idx(glyph_idx);
+    let biased_subr = i16::try_from(subr_idx).unwrap() - LOCAL_SUBR_BIAS;
+    let charstring = charstring(&[
+        (&[50, 10], &[21]),                     // rmoveto
+        (&[biased_subr - 10, 5, 2], &[12, 24]), // mul
+        (&[], &[12, 10]),                       // add
+        (&[], &[12, 27]),                       // dup
+        (&[0], &[12, 20]),                      // put
+        (&[], &[12, 18]),                       // drop
+        (&[1, 2], &[12, 28]),                   // exch
+        (&[2, 1], &[12, 30]),                   // roll
+        (&[], &[12, 11]),                       // sub
+        (&[0], &[12, 21]),                      // get
+        (&[1], &[12, 29]),                      // index
+        (&[], &[12, 9]),                        // abs
+        (&[], &[12, 18]),                       // drop
+        (&[], &[10]),                           // callsubr
+        (&[], &[14]),                           // endchar
+    ]);
+    let overrides = CffOverrides {
+        charstrings: BTreeMap::from([(glyph_idx, charstring)]),
+        ..CffOverrides::default()
+    };
+    let font_bytes = cff_font_with_overrides(false, &overrides);
+    let font = Font::new(&font_bytes).unwrap();
+    let Glyph::Cff(glyph) = font.glyph(glyph_idx).unwrap().inner else {
+        panic!("unexpected glyph");
+    };
+    assert_eq!(glyph.local_subrs, BTreeSet::from([subr_idx]));
+    assert_eq!(glyph.contour_count, 1);
+
+    let subset = font.subset_for_text("A").unwrap();
+    let subset_cff = &read_tables(&subset.to_opentype())[b"CFF "];
+    let subset_cff = ReadScope::new(subset_cff).read::<CFF<'_>>().unwrap();
+    assert!(non_empty_local_subrs(&subset_cff)[0].contains(&usize::from(subr_idx)));
+}
@@ -1,11 +1,47 @@
 use std::{
-    collections::BTreeSet, env, fmt, fs, io, io::Write, ops, process::Command, sync::OnceLock,
+    array,
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+    env,
+    fmt::{self, Write as _},
+    fs, io,
+    io::Write,
+    iter, ops,
+    process::Command,
+    sync::OnceLock,
+    thread,
 };
 
-use allsorts::{binary::read::ReadScope, font::MatchingPresentation, font_data::FontData};
+use allsorts::{
+    binary::read::ReadScope,
+    bitmap::{
+        cbdt::{self, CBDTTable, CBLCTable, GlyphBitmapData},
+        BitDepth,
+    },
+    font::MatchingPresentation,
+    font_data::FontData,
+    layout::GDEFTable,
+    outline::{OutlineBuilder, OutlineSink},
+    pathfinder_geometry::{line_segment::LineSegment2F, vector::Vector2F},
+    post::PostTable,
+    tables::{
+        glyf::GlyfTable,
+        loca::LocaTable,
+        os2::{FsSelection, Os2},
+        FontTableProvider, HeadTable, HheaTable,
+    },
+    tag,
+};
+use proptest::{prelude::*, sample::Index};
 use test_casing::{test_casing, Product};
 
-use crate::{Font, FontSubset};
+use crate::{
+    font::{CmapTable, Glyph, GlyphAssembly, GlyphConstruction, MathTable, NameRecord},
+    Font, FontSubset, GlyphKind, LimitKind, ParseErrorKind, ParseLimits, Reader, Script,
+    SubsetError, SubsetOptions, TableChecksum, TableTag,
+};
+
+mod cff;
 
 #[derive(Clone, Copy)]
 pub(crate) struct TestFont {
@@ -30,6 +66,83 @@ const SANS_FONT: TestFont = TestFont {
 
 pub(crate) const FONTS: [TestFont; 2] = [MONO_FONT, SANS_FONT];
 
+/// Raw font tables keyed by their tags.
+pub(crate) type RawTables = BTreeMap<[u8; 4], Vec<u8>>;
+
+/// Reads raw tables from an OpenType font without any validation.
+pub(crate) fn read_tables(font_bytes: &[u8]) -> RawTables {
+    let read_u32 = |pos: usize| u32::from_be_bytes(font_bytes[pos..pos + 4].try_into().unwrap());
+    let table_count = u16::from_be_bytes([font_bytes[4], font_bytes[5]]);
+    (0..usize::from(table_count))
+        .map(|i| {
+            let record_start = 12 + 16 * i;
+            let tag = font_bytes[record_start..record_start + 4]
+                .try_into()
+                .unwrap();
+            let offset = read_u32(record_start + 8) as usize;
+            let len = read_u32(record_start + 12) as usize;
+            (tag, font_bytes[offset..offset + len].to_vec())
+        })
+        .collect()
+}
+
+/// Writes an OpenType font with the specified tables, computing all checksums.
+pub(crate) fn write_tables(sfnt_version: u32, tables: &RawTables) -> Vec<u8> {
+    fn checksum(bytes: &[u8]) -> u32 {
+        bytes.chunks(4).fold(0_u32, |acc, chunk| {
+            let mut u32_bytes = [0_u8; 4];
+            u32_bytes[..chunk.len()].copy_from_slice(chunk);
+            acc.wrapping_add(u32::from_be_bytes(u32_bytes))
+        })
+    }
+
+    let table_count = u16::try_from(tables.len()).unwrap();
+    let mut buffer = vec![];
+    buffer.extend_from_slice(&sfnt_version.to_be_bytes());
+    buffer.extend_from_slice(&table_count.to_be_bytes());
+    let entry_selector = u16::try_from(table_count.ilog2()).unwrap();
+    let search_range = 16_u16 << entry_selector;
+    buffer.extend_from_slice(&search_range.to_be_bytes());
+    buffer.extend_from_slice(&entry_selector.to_be_bytes());
+    buffer.extend_from_slice(&(16 * table_count - search_range).to_be_bytes());
+
+    let data_offset = buffer.len() + 16 * tables.len();
+    let mut head_offset = None;
+    let mut data = vec![];
+    for (tag, table) in tables {
+        let offset = data_offset + data.len();
+        let mut table = table.clone();
+        if tag == b"head" && table.len() >= 12 {
+            table[8..12].copy_from_slice(&[0; 4]);
+            head_offset = Some(offset);
+        }
+        buffer.extend_from_slice(tag);
+        buffer.extend_from_slice(&checksum(&table).to_be_bytes());
+        buffer.extend_from_slice(&u32::try_from(offset).unwrap().to_be_bytes());
+        buffer.extend_from_slice(&u32::try_from(table.len()).unwrap().to_be_bytes());
+
+        data.extend_from_slice(&table);
+        while data.len() % 4 != 0 {
+            data.push(0);
+        }
+    }
+    buffer.extend_from_slice(&data);
+
+    if let Some(head_offset) = head_offset {
+        let adjustment = 0x_b1b0_afba_u32.wrapping_sub(checksum(&buffer));
+        buffer[head_offset + 8..head_offset + 12].copy_from_slice(&adjustment.to_be_bytes());
+    }
+    buffer
+}
+
+/// Patches tables of an OpenType font.
+pub(crate) fn patch_font(font_bytes: &[u8], patch: impl FnOnce(&mut RawTables)) -> Vec<u8> {
+    let mut tables = read_tables(font_bytes);
+    patch(&mut tables);
+    let sfnt_version = u32::from_be_bytes(font_bytes[..4].try_into().unwrap());
+    write_tables(sfnt_version, &tables)
+}
+
 #[derive(Debug, Clone)]
 pub(crate) enum TestCharSubset {
     Range(ops::RangeInclusive<char>),
@@ -146,10 +259,15 @@ fn subsetting_font(font: TestFont, chars: TestCharSubset) {
 
 fn test_subsetting_font(font: TestFont, chars: &BTreeSet<char>) -> (Vec<u8>, Vec<u8>) {
     let font = Font::new(font.bytes).unwrap();
-    let subset = FontSubset::new(font, chars).unwrap();
+    let subset = FontSubset::new(&font, chars).unwrap();
 
     let ttf = subset.to_opentype();
     assert_valid_font(&ttf, true, chars.iter().copied());
+    #[cfg(feature = "woff")]
+    {
+        let woff = subset.to_woff1();
+        assert_valid_font(&woff, false, chars.iter().copied());
+    }
     let woff2 = subset.to_woff2();
     assert_valid_font(&woff2, false, chars.iter().copied());
     (ttf, woff2)
@@ -178,7 +296,26 @@ fn subsetting_sans_font_with_ascii_chars() {
     assert_snapshot("examples/Roboto-ascii.woff", &woff2);
 }
 
-fn assert_valid_font(raw: &[u8], is_ttf: bool, expected_chars: impl Iterator<Item = char>) {
+/// Checks that the workflow from the crate docs and README produces the snapshotted outputs.
+#[test]
+fn documented_subsetting_matches_snapshots() {
+    let chars: BTreeSet<char> = (' '..='~').collect();
+    for (font, snapshot_prefix) in [
+        (MONO_FONT, "examples/FiraMono-ascii"),
+        (SANS_FONT, "examples/Roboto-ascii"),
+    ] {
+        let font = Font::new(font.bytes).unwrap();
+        let subset = font.subset(&chars).unwrap();
+        assert_snapshot(&format!("{snapshot_prefix}.ttf"), &subset.to_opentype());
+        assert_snapshot(&format!("{snapshot_prefix}.woff"), &subset.to_woff2());
+    }
+}
+
+pub(crate) fn assert_valid_font(
+    raw: &[u8],
+    is_ttf: bool,
+    expected_chars: impl Iterator<Item = char>,
+) {
     if is_ttf {
         Font::new(raw).unwrap();
     }
@@ -193,3 +330,3702 @@ fn assert_valid_font(raw: &[u8], is_ttf: bool, expected_chars: impl Iterator<Ite
 
     OpenTypeSanitizer::get().validate(raw);
 }
+
+#[test_casing(2, FONTS)]
+fn glyph_bounds_are_valid(font: TestFont) {
+    let font = Font::new(font.bytes).unwrap();
+    assert!([1_000, 2_048].contains(&font.units_per_em()));
+    font.validate_glyph_bounds().unwrap();
+
+    let space_glyph = font.map_char(' ').unwrap();
+    assert_eq!(font.glyph_bbox(space_glyph).unwrap(), None);
+    let glyph = font.map_char('A').unwrap();
+    let [x_min, y_min, x_max, y_max] = font.glyph_bbox(glyph).unwrap().unwrap();
+    assert!(x_min < x_max && y_min < y_max);
+    assert!(y_min >= 0 && y_max <= font.units_per_em().try_into().unwrap());
+}
+
+#[test]
+fn out_of_range_glyph_bounds_are_detected() {
+    let font = Font::new(MONO_FONT.bytes).unwrap();
+    let glyph_idx = font.map_char('A').unwrap();
+    let (loca, _) = font.truetype_outlines().unwrap();
+    let glyph_range = loca.glyph_range(glyph_idx).unwrap();
+    let patched = patch_font(MONO_FONT.bytes, |tables| {
+        let glyph = &mut tables.get_mut(b"glyf").unwrap()[glyph_range];
+        // Set `xMax` to a huge value
+        glyph[6..8].copy_from_slice(&30_000_i16.to_be_bytes());
+    });
+
+    let font = Font::new(&patched).unwrap();
+    let err = font.validate_glyph_bounds().unwrap_err();
+    assert!(
+        matches!(err.kind(), ParseErrorKind::InvalidGlyphBounds(idx) if *idx == glyph_idx),
+        "{err:?}"
+    );
+    assert_eq!(err.table(), Some(TableTag::GLYF));
+}
+
+#[test]
+fn creating_subset_from_text_and_iter() {
+    let font = Font::new(MONO_FONT.bytes).unwrap();
+    let subset = font.subset_for_text("Hello, world!").unwrap();
+    let expected_chars: BTreeSet<char> = "Hello, world!".chars().collect();
+    let mapped_chars: Vec<_> = subset.char_map.iter().map(|&(ch, _)| ch).collect();
+    assert_eq!(
+        mapped_chars,
+        expected_chars.iter().copied().collect::<Vec<_>>()
+    );
+    let ttf = subset.to_opentype();
+
+    let chars = vec![
+        'w', 'o', 'r', 'l', 'd', '!', 'H', 'e', 'l', 'l', 'o', ',', ' ',
+    ];
+    let subset = FontSubset::from_iter_chars(&font, chars).unwrap();
+    assert_eq!(subset.to_opentype(), ttf);
+}
+
+/// Adds synthetic vertical metrics to the font.
+pub(crate) fn add_vertical_metrics(tables: &mut RawTables) {
+    let maxp = &tables[b"maxp"];
+    let glyph_count = u16::from_be_bytes([maxp[4], maxp[5]]);
+    let mut vhea = tables[b"hhea"].clone();
+    vhea[..4].copy_from_slice(&0x_0001_1000_u32.to_be_bytes());
+    vhea[34..].copy_from_slice(&glyph_count.to_be_bytes());
+    let vmtx = (0..glyph_count).flat_map(|i| {
+        let advance_height = 1_000 + i % 7;
+        let top_side_bearing = i % 100;
+        [advance_height.to_be_bytes(), top_side_bearing.to_be_bytes()]
+    });
+    let vmtx = vmtx.flatten().collect();
+    tables.insert(*b"vhea", vhea);
+    tables.insert(*b"vmtx", vmtx);
+}
+
+#[test_casing(2, [*b"vhea", *b"vmtx"])]
+fn subsetting_with_inconsistent_vertical_metrics(present_table: [u8; 4]) {
+    let font_bytes = patch_font(MONO_FONT.bytes, |tables| {
+        add_vertical_metrics(tables);
+        tables.retain(|tag, _| (*tag != *b"vhea" && *tag != *b"vmtx") || *tag == present_table);
+    });
+    let chars: BTreeSet<char> = ('A'..='Z').collect();
+    let font = Font::new(&font_bytes).unwrap();
+    let options = SubsetOptions::default().vertical_metrics(true);
+    let err = font.subset_with_options(&chars, options).unwrap_err();
+    assert!(
+        matches!(err, SubsetError::InconsistentVerticalMetrics),
+        "{err:?}"
+    );
+
+    // Vertical metrics are not retained by default, so subsetting should succeed.
+    let subset = font.subset(&chars).unwrap();
+    let tables = read_tables(&subset.to_opentype());
+    assert!(!tables.contains_key(b"vhea") && !tables.contains_key(b"vmtx"));
+}
+
+#[test]
+fn subsetting_with_vertical_metrics() {
+    let font_bytes = patch_font(MONO_FONT.bytes, add_vertical_metrics);
+    let chars: BTreeSet<char> = ('A'..='Z').collect();
+    let font = Font::new(&font_bytes).unwrap();
+    let options = SubsetOptions::default().vertical_metrics(true);
+    let subset = font.subset_with_options(&chars, options).unwrap();
+
+    let ttf = subset.to_opentype();
+    let tables = read_tables(&ttf);
+    assert_eq!(tables[b"vhea"].len(), 36);
+    let long_metrics_count = u16::from_be_bytes([tables[b"vhea"][34], tables[b"vhea"][35]]);
+    let glyph_count = subset.glyphs.len();
+    assert!(usize::from(long_metrics_count) <= glyph_count);
+    let expected_vmtx_len = 2 * glyph_count + 2 * usize::from(long_metrics_count);
+    assert_eq!(tables[b"vmtx"].len(), expected_vmtx_len);
+    assert_valid_font(&ttf, true, chars.iter().copied());
+
+    // Check that vertical metrics round-trip for the retained glyphs.
+    let read_u16 = |bytes: &[u8], pos: usize| u16::from_be_bytes([bytes[pos], bytes[pos + 1]]);
+    let vmtx = &tables[b"vmtx"];
+    let long_metrics_count = usize::from(long_metrics_count);
+    for (new_idx, old_idx) in subset.new_to_old_glyphs().into_iter().enumerate() {
+        let (advance_height, top_side_bearing) = if new_idx < long_metrics_count {
+            (read_u16(vmtx, 4 * new_idx), read_u16(vmtx, 4 * new_idx + 2))
+        } else {
+            let tsb_pos = 4 * long_metrics_count + 2 * (new_idx - long_metrics_count);
+            (
+                read_u16(vmtx, 4 * long_metrics_count - 4),
+                read_u16(vmtx, tsb_pos),
+            )
+        };
+        assert_eq!(advance_height, 1_000 + old_idx % 7, "{old_idx}");
+        assert_eq!(top_side_bearing, old_idx % 100, "{old_idx}");
+    }
+
+    let woff2 = subset.to_woff2();
+    assert_valid_font(&woff2, false, chars.iter().copied());
+}
+
+#[test_casing(2, FONTS)]
+fn reading_line_metrics(font: TestFont) {
+    let font_file = ReadScope::new(font.bytes).read::<FontData>().unwrap();
+    let font_provider = font_file.table_provider(0).unwrap();
+    let hhea_data = font_provider.read_table_data(tag::HHEA).unwrap();
+    let expected = ReadScope::new(&hhea_data).read::<HheaTable>().unwrap();
+
+    let font = Font::new(font.bytes).unwrap();
+    assert_eq!(font.ascender(), expected.ascender);
+    assert_eq!(font.descender(), expected.descender);
+    assert_eq!(font.line_gap(), expected.line_gap);
+}
+
+#[test_casing(2, FONTS)]
+fn reading_unsupported_table_data(font: TestFont) {
+    let tables = read_tables(font.bytes);
+    let parsed_font = Font::new(font.bytes).unwrap();
+
+    let mut reader = Reader::new(&tables[b"hhea"]);
+    assert_eq!(reader.read_u32().unwrap(), 0x_0001_0000);
+    assert_eq!(reader.read_i16().unwrap(), parsed_font.ascender());
+    assert_eq!(reader.read_i16().unwrap(), parsed_font.descender());
+    assert_eq!(reader.read_i16().unwrap(), parsed_font.line_gap());
+    assert_eq!(reader.offset(), 10);
+
+    let reader = Reader::new(&tables[b"head"]);
+    let mut units_per_em = reader.range(18..20).unwrap();
+    assert_eq!(units_per_em.read_u16().unwrap(), parsed_font.units_per_em());
+    assert!(units_per_em.remaining().is_empty());
+
+    let err = units_per_em.skip(1).unwrap_err();
+    assert!(
+        matches!(err.kind(), ParseErrorKind::UnexpectedEof),
+        "{err:?}"
+    );
+    assert_eq!(err.offset(), 20);
+    assert_eq!(err.table(), None);
+
+    let err = reader.range(0..usize::MAX).unwrap_err();
+    assert!(
+        matches!(err.kind(), ParseErrorKind::RangeOutOfBounds { .. }),
+        "{err:?}"
+    );
+}
+
+#[test_casing(2, FONTS)]
+fn collecting_parse_stats(font: TestFont) {
+    let (parsed_font, stats) = Font::parse_with_stats(font.bytes).unwrap();
+    let tables = read_tables(font.bytes);
+    assert_eq!(usize::from(stats.table_count()), tables.len());
+    assert_eq!(stats.glyph_count(), parsed_font.glyph_count);
+    assert_eq!(stats.cmap_format(), parsed_font.cmap.format());
+    assert!(matches!(stats.cmap_format(), 4 | 12));
+    assert_eq!(stats.has_hinting(), tables.contains_key(b"fpgm"));
+
+    let subset = parsed_font.subset_for_text("Hello").unwrap().to_opentype();
+    let (_, subset_stats) = Font::parse_with_stats(&subset).unwrap();
+    assert_eq!(subset_stats.glyph_count(), 5); // .notdef + 'H', 'e', 'l', 'o'
+    assert_eq!(
+        usize::from(subset_stats.table_count()),
+        read_tables(&subset).len()
+    );
+}
+
+#[test_casing(2, FONTS)]
+fn parsing_with_limits(font: TestFont) {
+    let (_, stats) = Font::parse_with_stats(font.bytes).unwrap();
+    let limits = ParseLimits::default()
+        .max_font_len(font.bytes.len())
+        .max_table_count(stats.table_count())
+        .max_glyph_count(stats.glyph_count());
+    Font::parse_with_limits(font.bytes, limits).unwrap();
+
+    let exceeded_limits = [
+        (
+            limits.max_font_len(font.bytes.len() - 1),
+            LimitKind::FontLen,
+        ),
+        (
+            limits.max_table_count(stats.table_count() - 1),
+            LimitKind::TableCount,
+        ),
+        (
+            limits.max_glyph_count(stats.glyph_count() - 1),
+            LimitKind::GlyphCount,
+        ),
+        (
+            limits.max_cmap_segment_count(1),
+            LimitKind::CmapSegmentCount,
+        ),
+    ];
+    for (limits, expected_kind) in exceeded_limits {
+        let err = Font::parse_with_limits(font.bytes, limits).unwrap_err();
+        assert!(
+            matches!(err.kind(), ParseErrorKind::LimitExceeded { limit, .. } if *limit == expected_kind),
+            "{err:?}"
+        );
+    }
+}
+
+#[test]
+fn cmap_segment_limit_is_checked_before_parsing_segments() {
+    let font = patch_font(MONO_FONT.bytes, |tables| {
+        let mut cmap = format4_cmap_table(&[(0x41, 1)]);
+        // Claim the maximum number of segments; the subtable is too short to hold them.
+        let seg_count_x2_offset = 12 + 6;
+        cmap[seg_count_x2_offset..seg_count_x2_offset + 2].copy_from_slice(&[0xff, 0xfe]);
+        tables.insert(*b"cmap", cmap);
+    });
+
+    let err = Font::parse_lenient(&font).unwrap_err();
+    assert!(
+        !matches!(err.kind(), ParseErrorKind::LimitExceeded { .. }),
+        "{err:?}"
+    );
+    let limits = ParseLimits::default().max_cmap_segment_count(1_000);
+    let err = Font::parse_with_limits(&font, limits).unwrap_err();
+    assert_eq!(err.table(), Some(TableTag::CMAP));
+    assert!(
+        matches!(
+            err.kind(),
+            ParseErrorKind::LimitExceeded {
+                limit: LimitKind::CmapSegmentCount,
+                value: 32_767,
+                max: 1_000,
+            }
+        ),
+        "{err:?}"
+    );
+}
+
+#[test_casing(2, FONTS)]
+#[allow(clippy::float_cmp)] // compared values are exactly representable
+fn reading_font_revision(font: TestFont) {
+    let font_file = ReadScope::new(font.bytes).read::<FontData>().unwrap();
+    let font_provider = font_file.table_provider(0).unwrap();
+    let head_data = font_provider.read_table_data(tag::HEAD).unwrap();
+    let expected = ReadScope::new(&head_data).read::<HeadTable>().unwrap();
+
+    let font = Font::new(font.bytes).unwrap();
+    assert_eq!(font.font_revision(), f32::from(expected.font_revision));
+
+    let subset = font.subset_for_text("Hello").unwrap().to_opentype();
+    let subset = Font::new(&subset).unwrap();
+    assert_eq!(subset.font_revision(), font.font_revision());
+}
+
+#[test]
+#[allow(clippy::float_cmp)] // compared values are exactly representable
+fn reading_fractional_font_revision() {
+    let font_bytes = patch_font(MONO_FONT.bytes, |tables| {
+        let head = tables.get_mut(b"head").unwrap();
+        head[4..8].copy_from_slice(&0x_0002_4000_u32.to_be_bytes());
+    });
+    let font = Font::new(&font_bytes).unwrap();
+    assert_eq!(font.font_revision(), 2.25);
+    let subset = font.subset_for_text("Hello").unwrap().to_opentype();
+    assert_eq!(Font::new(&subset).unwrap().font_revision(), 2.25);
+}
+
+#[test_casing(2, FONTS)]
+fn reading_typo_line_metrics(font: TestFont) {
+    let font_file = ReadScope::new(font.bytes).read::<FontData>().unwrap();
+    let font_provider = font_file.table_provider(0).unwrap();
+    let os2_data = font_provider.read_table_data(tag::OS_2).unwrap();
+    let expected = ReadScope::new(&os2_data)
+        .read_dep::<Os2>(os2_data.len())
+        .unwrap();
+
+    let expected_metrics = expected.version0.unwrap();
+
+    let font = Font::new(font.bytes).unwrap();
+    assert_eq!(font.typo_ascender(), expected_metrics.s_typo_ascender);
+    assert_eq!(font.typo_descender(), expected_metrics.s_typo_descender);
+    assert_eq!(font.typo_line_gap(), expected_metrics.s_typo_line_gap);
+    assert_eq!(
+        font.uses_typo_metrics(),
+        expected
+            .fs_selection
+            .contains(FsSelection::USE_TYPO_METRICS)
+    );
+}
+
+#[test]
+fn reading_typo_metrics_from_legacy_os2_table() {
+    let font_bytes = patch_font(MONO_FONT.bytes, |tables| {
+        tables.get_mut(b"OS/2").unwrap().truncate(68);
+    });
+    let font = Font::new(&font_bytes).unwrap();
+    assert_eq!(font.typo_ascender(), font.ascender());
+    assert_eq!(font.typo_descender(), font.descender());
+    assert_eq!(font.typo_line_gap(), font.line_gap());
+    assert!(!font.uses_typo_metrics());
+}
+
+#[test_casing(3, [0, 40, 60])]
+fn subsetting_font_with_truncated_os2_table(len: usize) {
+    let font_bytes = patch_font(MONO_FONT.bytes, |tables| {
+        tables.get_mut(b"OS/2").unwrap().truncate(len);
+    });
+    let font = Font::new(&font_bytes).unwrap();
+    assert_eq!(font.typo_ascender(), font.ascender());
+    assert!(!font.uses_typo_metrics());
+
+    let subset = font.subset_for_text("Hello").unwrap();
+    let ttf = subset.to_opentype();
+    assert_eq!(subset.estimated_opentype_size(), ttf.len());
+    let original_os2 = &read_tables(&font_bytes)[b"OS/2"];
+    assert_eq!(read_tables(&ttf)[b"OS/2"], *original_os2);
+
+    let options = SubsetOptions::default().recompute_unicode_ranges(true);
+    let subset = font
+        .subset_with_options(&"Hello".chars().collect(), options)
+        .unwrap();
+    subset.to_opentype();
+}
+
+#[test]
+fn subsetting_variable_font_to_default_instance() {
+    assert!(!Font::new(MONO_FONT.bytes).unwrap().is_variable());
+    let font = Font::new(SANS_FONT.bytes).unwrap();
+    assert!(font.is_variable());
+    let original_tables = read_tables(SANS_FONT.bytes);
+    let variation_tags = [b"fvar", b"gvar", b"avar", b"HVAR", b"STAT"];
+
+    let chars: BTreeSet<char> = (' '..='~').collect();
+    let subset = font.subset(&chars).unwrap();
+    let new_to_old = subset.new_to_old_glyphs();
+    let subset = subset.to_opentype();
+    assert_valid_font(&subset, true, chars.into_iter());
+    let tables = read_tables(&subset);
+    for tag in variation_tags {
+        let tag_str = String::from_utf8_lossy(tag);
+        assert!(original_tables.contains_key(tag), "{tag_str}");
+        assert!(!tables.contains_key(tag), "{tag_str}");
+    }
+
+    let subset = Font::new(&subset).unwrap();
+    assert!(!subset.is_variable());
+    subset.validate_glyph_bounds().unwrap();
+    // Simple glyph outlines are copied verbatim, i.e., they correspond to the default instance.
+    for (new_idx, &old_idx) in (0_u16..).zip(&new_to_old) {
+        let new_glyph = subset.glyph(new_idx).unwrap().inner;
+        if let Glyph::Simple(new_bytes) = new_glyph {
+            let Glyph::Simple(old_bytes) = font.glyph(old_idx).unwrap().inner else {
+                panic!("unexpected original glyph #{old_idx}");
+            };
+            assert_eq!(new_bytes, old_bytes);
+        }
+    }
+}
+
+#[test_casing(2, FONTS)]
+fn enumerating_glyphs(test_font: TestFont) {
+    let font = Font::new(test_font.bytes).unwrap();
+    let maxp = &read_tables(test_font.bytes)[b"maxp"];
+    assert_eq!(font.glyph_count(), u16::from_be_bytes([maxp[4], maxp[5]]));
+    assert_eq!(font.glyph_info(font.glyph_count()).unwrap(), None);
+
+    let subset = font.subset_all().unwrap();
+    let mut composite_count = 0;
+    for glyph_idx in 0..font.glyph_count() {
+        let glyph = font.glyph_info(glyph_idx).unwrap().unwrap();
+        if let GlyphKind::Composite { components } = glyph.kind() {
+            assert!(components.iter().all(|&idx| idx < font.glyph_count()));
+            composite_count += 1;
+        }
+        assert_eq!(Some(glyph), subset.glyph(glyph_idx), "{glyph_idx}");
+    }
+    assert!(composite_count > 0);
+}
+
+#[test_casing(2, FONTS)]
+fn subsetting_all_glyphs(font: TestFont) {
+    let original_tables = read_tables(font.bytes);
+    let font = Font::new(font.bytes).unwrap();
+    let subset = font.subset_all().unwrap();
+
+    let ttf = subset.to_opentype();
+    let tables = read_tables(&ttf);
+    assert_eq!(tables[b"cmap"], original_tables[b"cmap"]);
+    assert_eq!(tables[b"maxp"][4..6], original_tables[b"maxp"][4..6]);
+    assert_eq!(tables[b"glyf"].len(), original_tables[b"glyf"].len());
+    assert_valid_font(&ttf, true, ' '..='~');
+
+    let woff2 = subset.to_woff2();
+    assert_valid_font(&woff2, false, ' '..='~');
+
+    assert_eq!(subset.retained_glyph_count(), usize::from(font.glyph_count));
+    let coverage = font.coverage_bitset();
+    let covered_chars: Vec<_> = ('\0'..=char::MAX)
+        .filter(|&ch| coverage.contains(ch))
+        .collect();
+    assert_eq!(subset.retained_char_count(), covered_chars.len());
+    assert_eq!(subset.retained_chars().collect::<Vec<_>>(), covered_chars);
+}
+
+#[test_casing(2, FONTS)]
+fn retained_glyph_and_char_counts(font: TestFont) {
+    let font = Font::new(font.bytes).unwrap();
+    let unmapped_char = '\u{10ffff}';
+    assert_eq!(font.map_char(unmapped_char).unwrap(), 0);
+    let chars: BTreeSet<char> = (' '..='~').chain([unmapped_char]).collect();
+
+    let subset = FontSubset::new(&font, &chars).unwrap();
+    assert_eq!(subset.retained_char_count(), 95);
+    let retained_chars: Vec<_> = subset.retained_chars().collect();
+    assert_eq!(retained_chars, (' '..='~').collect::<Vec<_>>());
+    let glyph_count = subset.retained_glyph_count();
+    assert_eq!(glyph_count, subset.new_to_old_glyphs().len());
+    assert!(glyph_count > 95, "{glyph_count}");
+    let ttf = subset.to_opentype();
+    assert_eq!(
+        read_tables(&ttf)[b"maxp"][4..6],
+        u16::try_from(glyph_count).unwrap().to_be_bytes()
+    );
+
+    let fallback_glyph = font.map_char('?').unwrap();
+    let options = SubsetOptions::default().fallback_glyph(fallback_glyph);
+    let subset = font.subset_with_options(&chars, options).unwrap();
+    assert_eq!(subset.retained_char_count(), 96);
+    assert_eq!(subset.retained_chars().last(), Some(unmapped_char));
+    assert_eq!(subset.retained_glyph_count(), glyph_count);
+}
+
+#[test_casing(2, FONTS)]
+fn chars_for_glyphs(font: TestFont) {
+    let font = Font::new(font.bytes).unwrap();
+    let (composite_char, composite_glyph) = composite_glyphs(&font)[0];
+    let unmapped_char = '\u{10ffff}';
+    let chars: BTreeSet<char> = ['A', 'b', composite_char, unmapped_char].into();
+    let subset = FontSubset::new(&font, &chars).unwrap();
+
+    let new_to_old_glyphs = subset.new_to_old_glyphs();
+    let mut mapped_chars = vec![];
+    for (new_idx, &old_idx) in (0_u16..).zip(&new_to_old_glyphs) {
+        let glyph_chars = subset.chars_for_glyph(new_idx);
+        for &ch in &glyph_chars {
+            assert_eq!(font.map_char(ch).unwrap(), old_idx);
+        }
+        if old_idx == composite_glyph {
+            assert_eq!(glyph_chars, [composite_char]);
+        }
+        mapped_chars.extend(glyph_chars);
+    }
+    mapped_chars.sort_unstable();
+    assert_eq!(mapped_chars, subset.retained_chars().collect::<Vec<_>>());
+    assert!(subset.chars_for_glyph(0).is_empty());
+    // Component glyphs aren't mapped to any chars.
+    let mapped_glyph_count = (1..new_to_old_glyphs.len())
+        .filter(|&idx| !subset.chars_for_glyph(idx.try_into().unwrap()).is_empty())
+        .count();
+    assert_eq!(mapped_glyph_count, 3);
+    assert!(new_to_old_glyphs.len() > 4);
+    let out_of_range = u16::try_from(new_to_old_glyphs.len()).unwrap();
+    assert!(subset.chars_for_glyph(out_of_range).is_empty());
+
+    // Chars sharing a glyph are all reported.
+    let fallback_glyph = font.map_char('?').unwrap();
+    let options = SubsetOptions::default().fallback_glyph(fallback_glyph);
+    let chars: BTreeSet<char> = ['?', unmapped_char].into();
+    let subset = font.subset_with_options(&chars, options).unwrap();
+    assert_eq!(subset.chars_for_glyph(1), ['?', unmapped_char]);
+
+    let subset = font.subset_all().unwrap();
+    let glyph_idx = font.map_char('A').unwrap();
+    assert!(subset.chars_for_glyph(glyph_idx).contains(&'A'));
+}
+
+#[test_casing(2, FONTS)]
+fn keeping_glyph_names(font: TestFont) {
+    let chars: BTreeSet<char> = ('A'..='Z').chain(['!', '@', '~']).collect();
+    let font_bytes = font.bytes;
+    let font = Font::new(font_bytes).unwrap();
+    let mut subset = font.subset(&chars).unwrap();
+    subset.keep_glyph_names().unwrap();
+    let ttf = subset.to_opentype();
+    assert_valid_font(&ttf, true, chars.iter().copied());
+
+    let original_post = &read_tables(font_bytes)[b"post"];
+    let post = &read_tables(&ttf)[b"post"];
+    assert_eq!(post[4..16], original_post[4..16]);
+    assert_eq!(post[16..32], [0; 16]);
+    if original_post[..4] == [0, 3, 0, 0] {
+        // The font doesn't contain glyph names
+        assert_eq!(post.len(), 32);
+        return;
+    }
+
+    assert_eq!(post[..4], [0, 2, 0, 0]);
+    let original_post = ReadScope::new(original_post).read::<PostTable>().unwrap();
+    let post = ReadScope::new(post).read::<PostTable>().unwrap();
+    for (&old_idx, &new_idx) in &subset.old_to_new_glyph_idx {
+        let name = post.glyph_name(new_idx).unwrap();
+        assert!(name.is_some(), "{new_idx}");
+        assert_eq!(name, original_post.glyph_name(old_idx).unwrap());
+    }
+}
+
+#[test]
+fn zeroing_post_memory_usage_fields() {
+    let font_bytes = patch_font(MONO_FONT.bytes, |tables| {
+        let post = tables.get_mut(b"post").unwrap();
+        for (i, byte) in post[16..32].iter_mut().enumerate() {
+            *byte = u8::try_from(i).unwrap() + 1;
+        }
+    });
+    let font = Font::new(&font_bytes).unwrap();
+    let original_post = &read_tables(&font_bytes)[b"post"];
+    let mut subset = font.subset_for_text("Hello").unwrap();
+
+    for keep_names in [false, true] {
+        if keep_names {
+            subset.keep_glyph_names().unwrap();
+        }
+        let ttf = subset.to_opentype();
+        assert_valid_font(&ttf, true, "Hello".chars());
+        let post = &read_tables(&ttf)[b"post"];
+        // `italicAngle`, `underlinePosition`, `underlineThickness` and `isFixedPitch` are retained.
+        assert_eq!(post[4..16], original_post[4..16]);
+        assert_eq!(post[16..32], [0; 16]);
+        let expected_version = if keep_names {
+            [0, 2, 0, 0]
+        } else {
+            [0, 3, 0, 0]
+        };
+        assert_eq!(post[..4], expected_version);
+    }
+}
+
+/// Creates a `post` table of version 2.5 mapping glyph #`i` to the standard name #`i` for `i < 258`
+/// and to `i - 127` for larger `i`.
+fn post_v2_5_table(original_post: &[u8], glyph_count: u16) -> Vec<u8> {
+    let mut post = vec![0, 2, 0x50, 0];
+    post.extend_from_slice(&original_post[4..32]);
+    post.extend_from_slice(&glyph_count.to_be_bytes());
+    post.extend((0..glyph_count).map(|i| {
+        if i < 258 {
+            0
+        } else {
+            (-127_i8).to_be_bytes()[0]
+        }
+    }));
+    post
+}
+
+#[test]
+fn keeping_glyph_names_from_post_v2_5() {
+    let font_bytes = patch_font(MONO_FONT.bytes, |tables| {
+        let maxp = &tables[b"maxp"];
+        let glyph_count = u16::from_be_bytes([maxp[4], maxp[5]]);
+        let post = post_v2_5_table(&tables[b"post"], glyph_count);
+        tables.insert(*b"post", post);
+    });
+    let font = Font::new(&font_bytes).unwrap();
+    let chars: BTreeSet<char> = ('a'..='z').collect();
+    let mut subset = font.subset(&chars).unwrap();
+    subset.keep_glyph_names().unwrap();
+    let ttf = subset.to_opentype();
+    assert_valid_font(&ttf, true, chars.iter().copied());
+
+    let post = &read_tables(&ttf)[b"post"];
+    assert_eq!(post[..4], [0, 2, 0, 0]);
+    let post = ReadScope::new(post).read::<PostTable>().unwrap();
+    let name_indices = &post.opt_sub_table.as_ref().unwrap().glyph_name_index;
+    assert_eq!(name_indices.len(), subset.glyphs.len());
+    for (&old_idx, &new_idx) in &subset.old_to_new_glyph_idx {
+        let expected_name_idx = if old_idx < 258 {
+            old_idx
+        } else {
+            old_idx - 127
+        };
+        assert_eq!(name_indices.get_item(new_idx.into()), expected_name_idx);
+    }
+
+    // Glyph #752 ('~') has an out-of-range name index.
+    let mut subset = font.subset(&BTreeSet::from(['~'])).unwrap();
+    let err = subset.keep_glyph_names().unwrap_err();
+    assert_eq!(err.table, Some(TableTag::POST));
+    assert!(
+        matches!(err.kind, ParseErrorKind::OffsetOutOfBounds(752)),
+        "{err:?}"
+    );
+}
+
+#[test]
+fn parsing_font_with_incorrect_checksum() {
+    let mut font_bytes = MONO_FONT.bytes.to_vec();
+    let table_count = u16::from_be_bytes([font_bytes[4], font_bytes[5]]);
+    let cmap_record_start = (0..usize::from(table_count))
+        .map(|i| 12 + 16 * i)
+        .find(|&start| font_bytes[start..start + 4] == *b"cmap")
+        .unwrap();
+    font_bytes[cmap_record_start + 4] ^= 1;
+
+    let err = Font::new(&font_bytes).unwrap_err();
+    assert_eq!(err.table, Some(TableTag::CMAP));
+    assert!(
+        matches!(err.kind, ParseErrorKind::Checksum { .. }),
+        "{err:?}"
+    );
+
+    let font = Font::parse_lenient(&font_bytes).unwrap();
+    let chars: BTreeSet<char> = (' '..='~').collect();
+    let ttf = font.subset(&chars).unwrap().to_opentype();
+    assert_valid_font(&ttf, true, chars.into_iter());
+
+    // Other checks are still performed.
+    let mut unaligned_bytes = font_bytes.clone();
+    let offset_and_len = &mut unaligned_bytes[cmap_record_start + 8..cmap_record_start + 16];
+    let offset = u32::from_be_bytes(offset_and_len[..4].try_into().unwrap());
+    let len = u32::from_be_bytes(offset_and_len[4..].try_into().unwrap());
+    offset_and_len[..4].copy_from_slice(&(offset + 2).to_be_bytes());
+    offset_and_len[4..].copy_from_slice(&(len - 2).to_be_bytes());
+    let err = Font::parse_lenient(&unaligned_bytes).unwrap_err();
+    assert_eq!(err.table, Some(TableTag::CMAP));
+    assert!(
+        matches!(err.kind, ParseErrorKind::UnalignedTable),
+        "{err:?}"
+    );
+
+    let font_len = u32::try_from(font_bytes.len()).unwrap();
+    font_bytes[cmap_record_start + 8..cmap_record_start + 12]
+        .copy_from_slice(&font_len.to_be_bytes());
+    let err = Font::parse_lenient(&font_bytes).unwrap_err();
+    assert!(
+        matches!(err.kind, ParseErrorKind::RangeOutOfBounds { .. }),
+        "{err:?}"
+    );
+}
+
+#[test]
+fn parsing_font_embedded_into_container() {
+    let mut container = b"%PDF-1.7 stream\n".to_vec();
+    let offset = container.len();
+    container.extend_from_slice(MONO_FONT.bytes);
+    container.extend_from_slice(b"\nendstream");
+
+    let font = Font::parse_at(&container, offset).unwrap();
+    let expected_font = Font::new(MONO_FONT.bytes).unwrap();
+    assert_eq!(font.glyph_count, expected_font.glyph_count);
+    let ttf = font.subset_for_text("Hello").unwrap().to_opentype();
+    let expected_ttf = expected_font
+        .subset_for_text("Hello")
+        .unwrap()
+        .to_opentype();
+    assert_eq!(ttf, expected_ttf);
+
+    let err = Font::parse_at(&container, offset + 4).unwrap_err();
+    assert!(
+        matches!(err.kind, ParseErrorKind::UnexpectedFontVersion),
+        "{err:?}"
+    );
+    let err = Font::parse_at(&container, container.len() + 1).unwrap_err();
+    assert!(
+        matches!(err.kind, ParseErrorKind::OffsetOutOfBounds(_)),
+        "{err:?}"
+    );
+}
+
+#[test_casing(2, FONTS)]
+fn coverage_bitset_matches_cmap(font: TestFont) {
+    let font = Font::new(font.bytes).unwrap();
+    let coverage = font.coverage_bitset();
+    let mut covered_chars = BTreeSet::new();
+    for ch in '\0'..=char::MAX {
+        let is_covered = font.map_char(ch).unwrap() != 0;
+        assert_eq!(coverage.contains(ch), is_covered, "{ch:?}");
+        if is_covered {
+            covered_chars.insert(ch);
+        }
+    }
+    assert!(covered_chars.len() > 100);
+
+    let chars: BTreeSet<char> = ('\0'..='\u{3ff}')
+        .chain(['\u{fffd}', '\u{1f600}'])
+        .collect();
+    let expected: BTreeSet<char> = chars.intersection(&covered_chars).copied().collect();
+    assert_eq!(coverage.intersect(&chars), expected);
+    assert_eq!(coverage.intersect(&covered_chars), covered_chars);
+}
+
+#[test]
+fn retaining_complex_script_glyphs() {
+    let font = Font::new(MONO_FONT.bytes).unwrap();
+    let options = SubsetOptions::default().retain_script_glyphs(Script::Arabic);
+    let chars = BTreeSet::from(['a', 'b', 'c']);
+    let subset = font.subset_with_options(&chars, options.clone()).unwrap();
+    assert!(!subset.retains_all_glyphs);
+    assert_eq!(subset.glyphs.len(), 4);
+
+    let chars = BTreeSet::from(['a', 'b', 'c', '\u{628}']);
+    let subset = font.subset_with_options(&chars, options).unwrap();
+    assert!(subset.retains_all_glyphs);
+    assert_eq!(subset.glyphs.len(), usize::from(font.glyph_count));
+    let ttf = subset.to_opentype();
+    assert_eq!(
+        read_tables(&ttf)[b"cmap"],
+        read_tables(MONO_FONT.bytes)[b"cmap"]
+    );
+    assert_valid_font(&ttf, true, chars.into_iter().take(3));
+}
+
+#[test]
+fn extending_subset_with_complex_script_chars() {
+    let font = Font::new(MONO_FONT.bytes).unwrap();
+    let options = SubsetOptions::default().retain_script_glyphs(Script::Arabic);
+    let base_chars = BTreeSet::from(['a', 'b', 'c']);
+    let mut subset = font
+        .subset_with_options(&base_chars, options.clone())
+        .unwrap();
+    let base_glyphs = subset.new_to_old_glyphs();
+    subset.add_chars(&BTreeSet::from(['d'])).unwrap();
+    assert_eq!(subset.glyphs.len(), 5);
+
+    subset.add_chars(&BTreeSet::from(['\u{628}'])).unwrap();
+    assert!(!subset.retains_all_glyphs);
+    let glyphs = subset.new_to_old_glyphs();
+    assert_eq!(glyphs.len(), usize::from(font.glyph_count));
+    assert_eq!(glyphs[..base_glyphs.len()], base_glyphs);
+    let all_glyphs: BTreeSet<_> = glyphs.into_iter().collect();
+    assert_eq!(all_glyphs, (0..font.glyph_count).collect());
+
+    let ttf = subset.to_opentype();
+    assert_eq!(subset.estimated_opentype_size(), ttf.len());
+    assert_valid_font(&ttf, true, "abcd".chars());
+}
+
+/// Creates a `cmap` table with a single format 4 subtable mapping each of the specified chars
+/// via a separate segment.
+fn format4_cmap_table(mappings: &[(u16, u16)]) -> Vec<u8> {
+    let segments: Vec<_> = mappings
+        .iter()
+        .map(|&(ch, glyph_idx)| (ch, glyph_idx.wrapping_sub(ch)))
+        .chain([(u16::MAX, 1)])
+        .collect();
+    let segment_count = u16::try_from(segments.len()).unwrap();
+    let entry_selector = u16::try_from(segment_count.ilog2()).unwrap();
+    let search_range = 2_u16 << entry_selector;
+    let subtable_header = [
+        4,
+        16 + 8 * segment_count,
+        0,
+        2 * segment_count,
+        search_range,
+        entry_selector,
+        2 * segment_count - search_range,
+    ];
+
+    let mut words = vec![0, 1, 0, 3, 0, 12];
+    words.extend_from_slice(&subtable_header);
+    words.extend(segments.iter().map(|&(ch, _)| ch)); // endCode
+    words.push(0);
+    words.extend(segments.iter().map(|&(ch, _)| ch)); // startCode
+    words.extend(segments.iter().map(|&(_, delta)| delta)); // idDelta
+    words.extend(segments.iter().map(|_| 0)); // idRangeOffset
+    words.iter().flat_map(|word| word.to_be_bytes()).collect()
+}
+
+#[test]
+fn subsetting_private_use_area_chars() {
+    let font = Font::new(MONO_FONT.bytes).unwrap();
+    let glyph_chars = ['A', 'B', 'C', 'a', 'b', 'z'];
+    let pua_chars = [
+        '\u{e000}', '\u{e001}', '\u{e002}', '\u{f8f0}', '\u{f8fe}', '\u{f8ff}',
+    ];
+    let mappings: Vec<_> = pua_chars
+        .iter()
+        .zip(glyph_chars)
+        .map(|(&pua_char, ch)| {
+            let pua_char = u16::try_from(u32::from(pua_char)).unwrap();
+            (pua_char, font.map_char(ch).unwrap())
+        })
+        .collect();
+    let icon_font_bytes = patch_font(MONO_FONT.bytes, |tables| {
+        tables.insert(*b"cmap", format4_cmap_table(&mappings));
+    });
+    let icon_font = Font::new(&icon_font_bytes).unwrap();
+    for (&pua_char, &(_, glyph_idx)) in pua_chars.iter().zip(&mappings) {
+        assert_eq!(icon_font.map_char(pua_char).unwrap(), glyph_idx);
+    }
+
+    let chars: BTreeSet<char> = pua_chars.into_iter().collect();
+    let ttf = icon_font.subset(&chars).unwrap().to_opentype();
+    assert_valid_font(&ttf, true, pua_chars.into_iter());
+
+    let subset_font = Font::new(&ttf).unwrap();
+    assert!(matches!(subset_font.cmap, CmapTable::Deltas(_)));
+    let (loca, glyf) = font.truetype_outlines().unwrap();
+    let (subset_loca, subset_glyf) = subset_font.truetype_outlines().unwrap();
+    for (pua_char, ch) in pua_chars.into_iter().zip(glyph_chars) {
+        let original_glyph = font.map_char(ch).unwrap();
+        let original_glyph = &glyf.as_ref()[loca.glyph_range(original_glyph).unwrap()];
+        let glyph = subset_font.map_char(pua_char).unwrap();
+        let glyph = &subset_glyf.as_ref()[subset_loca.glyph_range(glyph).unwrap()];
+        assert_eq!(glyph, original_glyph, "{pua_char:?}");
+    }
+}
+
+#[test_casing(2, FONTS)]
+fn reporting_unmapped_chars(test_font: TestFont) {
+    let font = Font::new(test_font.bytes).unwrap();
+    let chars = BTreeSet::from(['A', 'z', '\u{378}', '\u{10fffd}']);
+    let (subset, unmapped_chars) = font.subset_reporting(&chars).unwrap();
+    assert_eq!(unmapped_chars, BTreeSet::from(['\u{378}', '\u{10fffd}']));
+    assert_eq!(
+        subset.to_opentype(),
+        font.subset(&chars).unwrap().to_opentype()
+    );
+}
+
+#[test]
+fn chars_explicitly_mapped_to_notdef_are_not_reported_as_unmapped() {
+    let font = Font::new(MONO_FONT.bytes).unwrap();
+    let mappings = [
+        (u16::from(b'A'), font.map_char('A').unwrap()),
+        (u16::from(b'B'), 0),
+    ];
+    let font_bytes = patch_font(MONO_FONT.bytes, |tables| {
+        tables.insert(*b"cmap", format4_cmap_table(&mappings));
+    });
+    let font = Font::new(&font_bytes).unwrap();
+    assert_eq!(font.map_char('B').unwrap(), 0);
+
+    let chars = BTreeSet::from(['A', 'B', 'C']);
+    let (subset, unmapped_chars) = font.subset_reporting(&chars).unwrap();
+    assert_eq!(unmapped_chars, BTreeSet::from(['C']));
+    assert_valid_font(&subset.to_opentype(), true, ['A'].into_iter());
+}
+
+const INDEX_TO_LOC_FORMAT: ops::Range<usize> = 50..52;
+
+/// Converts the short `loca` table in the font to the long format, optionally patching offsets.
+fn with_long_loca(font_bytes: &[u8], patch_offsets: impl FnOnce(&mut [u32])) -> Vec<u8> {
+    patch_font(font_bytes, |tables| {
+        let loca = &tables[b"loca"];
+        let mut offsets: Vec<_> = loca
+            .chunks_exact(2)
+            .map(|chunk| u32::from(u16::from_be_bytes([chunk[0], chunk[1]])) * 2)
+            .collect();
+        patch_offsets(&mut offsets);
+        let long_loca = offsets.iter().flat_map(|offset| offset.to_be_bytes());
+        tables.insert(*b"loca", long_loca.collect());
+        tables.get_mut(b"head").unwrap()[INDEX_TO_LOC_FORMAT].copy_from_slice(&[0, 1]);
+    })
+}
+
+#[test_casing(2, FONTS)]
+fn out_of_bounds_long_loca_offsets_are_detected(test_font: TestFont) {
+    let font = Font::new(test_font.bytes).unwrap();
+    let glyph_idx = font.map_char('A').unwrap();
+    let glyf_len = read_tables(test_font.bytes)[b"glyf"].len();
+    let glyf_len_u32 = u32::try_from(glyf_len).unwrap();
+
+    for end_offset in [glyf_len_u32 + 4, u32::MAX] {
+        let font_bytes = with_long_loca(test_font.bytes, |offsets| {
+            offsets[usize::from(glyph_idx) + 1] = end_offset;
+        });
+        let font = Font::new(&font_bytes).unwrap();
+        let err = font.glyph(glyph_idx).unwrap_err();
+        assert_eq!(err.table(), Some(TableTag::LOCA));
+        assert!(
+            matches!(
+                err.kind(),
+                ParseErrorKind::RangeOutOfBounds { range, len }
+                    if range.end == end_offset as usize && *len == glyf_len
+            ),
+            "{err:?}"
+        );
+        let err = font.subset(&BTreeSet::from(['A'])).unwrap_err();
+        assert_eq!(err.table(), Some(TableTag::LOCA));
+        // The following glyph has an inverted range.
+        let err = font.glyph(glyph_idx + 1).unwrap_err();
+        assert!(
+            matches!(err.kind(), ParseErrorKind::RangeOutOfBounds { .. }),
+            "{err:?}"
+        );
+    }
+}
+
+#[test_casing(2, FONTS)]
+fn long_loca_is_downgraded_to_short(test_font: TestFont) {
+    let tables = read_tables(test_font.bytes);
+    assert_eq!(tables[b"head"][INDEX_TO_LOC_FORMAT], [0, 0]);
+    let font_bytes = with_long_loca(test_font.bytes, |_| {});
+    let font = Font::new(&font_bytes).unwrap();
+    let expected_len = 4 * (usize::from(font.glyph_count) + 1);
+    assert_eq!(read_tables(&font_bytes)[b"loca"].len(), expected_len);
+
+    let chars: BTreeSet<char> = (' '..='~').collect();
+    let subset = font.subset(&chars).unwrap();
+    let ttf = subset.to_opentype();
+    assert_valid_font(&ttf, true, chars.iter().copied());
+    let subset_tables = read_tables(&ttf);
+    assert_eq!(subset_tables[b"head"][INDEX_TO_LOC_FORMAT], [0, 0]);
+    let expected_len = 2 * (subset.retained_glyph_count() + 1);
+    assert_eq!(subset_tables[b"loca"].len(), expected_len);
+
+    let subset_font = Font::new(&ttf).unwrap();
+    let (loca, glyf) = font.truetype_outlines().unwrap();
+    let (subset_loca, subset_glyf) = subset_font.truetype_outlines().unwrap();
+    for (new_idx, old_idx) in (0..).zip(subset.new_to_old_glyphs()) {
+        let original_glyph = &glyf.as_ref()[loca.glyph_range(old_idx).unwrap()];
+        let glyph = &subset_glyf.as_ref()[subset_loca.glyph_range(new_idx).unwrap()];
+        // Component IDs in composite glyphs are remapped, so only simple glyphs are compared.
+        if let GlyphKind::Simple { .. } = subset.glyph(new_idx).unwrap().kind() {
+            assert_eq!(glyph, original_glyph, "glyph #{new_idx}");
+        }
+    }
+}
+
+#[test]
+fn invalid_component_glyph_is_detected() {
+    let font = Font::new(MONO_FONT.bytes).unwrap();
+    let (ch, glyph_idx) = ('\u{80}'..='\u{2ff}')
+        .map(|ch| (ch, font.map_char(ch).unwrap()))
+        .find(|&(_, glyph_idx)| {
+            matches!(
+                font.glyph(glyph_idx).unwrap().inner,
+                Glyph::Composite { .. }
+            )
+        })
+        .unwrap();
+    let (loca, _) = font.truetype_outlines().unwrap();
+    let glyph_range = loca.glyph_range(glyph_idx).unwrap();
+    let patched = patch_font(MONO_FONT.bytes, |tables| {
+        let glyph = &mut tables.get_mut(b"glyf").unwrap()[glyph_range];
+        // Patch the glyph index of the first component (after `numberOfContours`, bbox, and component flags)
+        glyph[12..14].copy_from_slice(&u16::MAX.to_be_bytes());
+    });
+
+    let font = Font::new(&patched).unwrap();
+    let err = font.subset(&BTreeSet::from([ch])).unwrap_err();
+    assert_eq!(err.table(), Some(TableTag::GLYF));
+    assert!(
+        matches!(
+            err.kind(),
+            ParseErrorKind::InvalidComponentGlyph { referrer, referenced: u16::MAX }
+                if *referrer == glyph_idx
+        ),
+        "{err:?}"
+    );
+    let err = font.subset_all().unwrap_err();
+    assert!(
+        matches!(err.kind(), ParseErrorKind::InvalidComponentGlyph { .. }),
+        "{err:?}"
+    );
+}
+
+/// Finds composite glyphs for chars in the `'\u{80}'..='\u{2ff}'` range.
+fn composite_glyphs(font: &Font<'_>) -> Vec<(char, u16)> {
+    let mut glyphs: Vec<_> = ('\u{80}'..='\u{2ff}')
+        .map(|ch| (ch, font.map_char(ch).unwrap()))
+        .filter(|&(_, glyph_idx)| {
+            matches!(
+                font.glyph(glyph_idx).unwrap().inner,
+                Glyph::Composite { .. }
+            )
+        })
+        .collect();
+    glyphs.dedup_by_key(|(_, glyph_idx)| *glyph_idx);
+    glyphs
+}
+
+/// Patches the first component of each glyph in `patches` to reference the specified glyph.
+fn patch_first_components(raw: &[u8], patches: &[(u16, u16)]) -> Vec<u8> {
+    let font = Font::new(raw).unwrap();
+    let (loca, _) = font.truetype_outlines().unwrap();
+    let patches: Vec<_> = patches
+        .iter()
+        .map(|&(glyph_idx, referenced)| (loca.glyph_range(glyph_idx).unwrap(), referenced))
+        .collect();
+    patch_font(raw, |tables| {
+        let glyf = tables.get_mut(b"glyf").unwrap();
+        for (glyph_range, referenced) in &patches {
+            let glyph = &mut glyf[glyph_range.clone()];
+            glyph[12..14].copy_from_slice(&referenced.to_be_bytes());
+        }
+    })
+}
+
+#[test]
+fn converting_table_tags() {
+    for tag in ["glyf", "OS/2", "cvt ", "CFF "] {
+        let parsed = TableTag::try_from(tag).unwrap();
+        assert_eq!(parsed.as_str(), Some(tag));
+        assert_eq!(parsed.to_string(), tag);
+        assert_eq!(parsed, TableTag::new(tag.as_bytes().try_into().unwrap()));
+    }
+    for invalid_tag in ["", "cvt", "glyph", "gl\u{ff}", "gl\0f", "gl\nf"] {
+        assert!(TableTag::try_from(invalid_tag).is_err(), "{invalid_tag:?}");
+    }
+    assert_eq!(TableTag::new(b"glyf"), TableTag::GLYF);
+}
+
+#[test]
+fn shared_components_are_retained_once() {
+    let font = Font::new(SANS_FONT.bytes).unwrap();
+    let chars: BTreeSet<char> = (' '..='~').chain('\u{c0}'..='\u{24f}').collect();
+    let subset = font.subset(&chars).unwrap();
+
+    let component_refs: BTreeMap<u16, usize> = subset
+        .glyphs
+        .iter()
+        .filter_map(|glyph| match &glyph.inner {
+            Glyph::Composite { components, .. } => Some(components),
+            _ => None,
+        })
+        .flatten()
+        .fold(BTreeMap::new(), |mut refs, component| {
+            *refs.entry(component.glyph_idx).or_default() += 1;
+            refs
+        });
+    // Sanity check: some components are shared among many glyphs.
+    let max_refs = component_refs.values().copied().max().unwrap();
+    assert!(max_refs > 10, "{component_refs:?}");
+
+    // Each original glyph must be retained (and thus parsed) once.
+    let new_to_old = subset.new_to_old_glyphs();
+    let distinct_glyphs: BTreeSet<_> = new_to_old.iter().copied().collect();
+    assert_eq!(distinct_glyphs.len(), new_to_old.len());
+    subset.validate_against(&font).unwrap();
+}
+
+#[test]
+fn composite_glyph_cycle_is_detected() {
+    let font = Font::new(MONO_FONT.bytes).unwrap();
+    let composites = composite_glyphs(&font);
+    let [(ch, glyph_idx), (other_ch, other_idx), ..] = composites[..] else {
+        panic!("not enough composite glyphs: {composites:?}");
+    };
+
+    // Self-reference
+    let patched = patch_first_components(MONO_FONT.bytes, &[(glyph_idx, glyph_idx)]);
+    let font = Font::new(&patched).unwrap();
+    let err = font.subset(&BTreeSet::from([ch])).unwrap_err();
+    assert_eq!(err.table(), Some(TableTag::GLYF));
+    assert!(
+        matches!(err.kind(), ParseErrorKind::CompositeGlyphCycle(idx) if *idx == glyph_idx),
+        "{err:?}"
+    );
+    let err = font.glyph_svg_path(glyph_idx).unwrap_err();
+    assert!(
+        matches!(err.kind(), ParseErrorKind::CompositeDepthExceeded(idx) if *idx == glyph_idx),
+        "{err:?}"
+    );
+
+    // Reference via another glyph
+    let patched = patch_first_components(
+        MONO_FONT.bytes,
+        &[(glyph_idx, other_idx), (other_idx, glyph_idx)],
+    );
+    let font = Font::new(&patched).unwrap();
+    let err = font.subset(&BTreeSet::from([ch])).unwrap_err();
+    assert!(
+        matches!(err.kind(), ParseErrorKind::CompositeGlyphCycle(idx) if *idx == glyph_idx),
+        "{err:?}"
+    );
+    let err = font.subset(&BTreeSet::from([other_ch])).unwrap_err();
+    assert!(
+        matches!(err.kind(), ParseErrorKind::CompositeGlyphCycle(idx) if *idx == other_idx),
+        "{err:?}"
+    );
+}
+
+#[test]
+fn excessive_composite_nesting_is_detected() {
+    let font = Font::new(MONO_FONT.bytes).unwrap();
+    let composites = composite_glyphs(&font);
+    assert!(composites.len() > 20, "{composites:?}");
+
+    // Chain composite glyphs so that each glyph references the next one.
+    let chain = |len: usize| -> Vec<_> {
+        composites[..len]
+            .windows(2)
+            .map(|window| (window[0].1, window[1].1))
+            .collect()
+    };
+    let patched = patch_first_components(MONO_FONT.bytes, &chain(16));
+    let font = Font::new(&patched).unwrap();
+    font.subset(&BTreeSet::from([composites[0].0])).unwrap();
+
+    let patched = patch_first_components(MONO_FONT.bytes, &chain(20));
+    let font = Font::new(&patched).unwrap();
+    let err = font.subset(&BTreeSet::from([composites[0].0])).unwrap_err();
+    assert_eq!(err.table(), Some(TableTag::GLYF));
+    assert!(
+        matches!(err.kind(), ParseErrorKind::CompositeDepthExceeded(_)),
+        "{err:?}"
+    );
+}
+
+#[test]
+fn subsetting_for_text() {
+    let font = Font::new(MONO_FONT.bytes).unwrap();
+    let text = "Cafe\u{301} cafe\u{301}, \u{e9}t\u{e9}!";
+    let subset = font.subset_for_text(text).unwrap();
+    let mapped_chars: Vec<_> = subset.char_map.iter().map(|&(ch, _)| ch).collect();
+    assert_eq!(
+        mapped_chars,
+        [' ', '!', ',', 'C', 'a', 'c', 'e', 'f', 't', '\u{e9}', '\u{301}']
+    );
+    let expected_subset = font.subset(&text.chars().collect()).unwrap();
+    assert_eq!(subset.to_opentype(), expected_subset.to_opentype());
+    assert_valid_font(&subset.to_opentype(), true, text.chars());
+}
+
+#[test_casing(2, FONTS)]
+fn reading_glyph_contour_count(font: TestFont) {
+    let font = Font::new(font.bytes).unwrap();
+    for glyph_idx in 0..font.glyph_count {
+        let contour_count = font.glyph_contour_count(glyph_idx).unwrap();
+        match font.glyph(glyph_idx).unwrap().inner {
+            Glyph::Empty => assert_eq!(contour_count, 0),
+            Glyph::Simple(raw) => {
+                assert!(contour_count > 0);
+                assert_eq!(contour_count.to_be_bytes(), raw[..2]);
+            }
+            Glyph::Composite { .. } => assert!(contour_count < 0),
+            Glyph::Cff(_) => unreachable!("font has TrueType outlines"),
+        }
+    }
+
+    let space_glyph = font.map_char(' ').unwrap();
+    assert_eq!(font.glyph_contour_count(space_glyph).unwrap(), 0);
+    font.glyph_contour_count(font.glyph_count).unwrap_err();
+}
+
+#[test]
+fn single_font_backs_multiple_subsets() {
+    let font = Font::new(MONO_FONT.bytes).unwrap();
+    let subsets: Vec<_> = thread::scope(|scope| {
+        let handles: Vec<_> = SUBSET_CHARS
+            .into_iter()
+            .map(|chars| {
+                let font = &font;
+                scope.spawn(move || {
+                    let chars = chars.into_set();
+                    (font.subset(&chars).unwrap(), chars)
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect()
+    });
+
+    for (subset, chars) in &subsets {
+        assert_eq!(
+            subset.to_opentype(),
+            font.subset(chars).unwrap().to_opentype()
+        );
+    }
+}
+
+#[test]
+fn reporting_table_checksums() {
+    for font in FONTS {
+        let report = Font::checksum_report(font.bytes).unwrap();
+        assert_eq!(report.len(), read_tables(font.bytes).len());
+        assert!(report.iter().all(TableChecksum::matches), "{report:?}");
+    }
+
+    let mut font_bytes = MONO_FONT.bytes.to_vec();
+    let table_count = u16::from_be_bytes([font_bytes[4], font_bytes[5]]);
+    let mut corrupted_tables = vec![];
+    for i in 0..usize::from(table_count) {
+        let record_start = 12 + 16 * i;
+        let tag = &font_bytes[record_start..record_start + 4];
+        if tag == b"cmap" || tag == b"post" {
+            corrupted_tables.push(TableTag(tag.try_into().unwrap()));
+            font_bytes[record_start + 7] ^= 1;
+        }
+    }
+    assert_eq!(corrupted_tables.len(), 2);
+    Font::new(&font_bytes).unwrap_err();
+
+    let report = Font::checksum_report(&font_bytes).unwrap();
+    assert_eq!(usize::from(table_count), report.len());
+    let mismatched_tables: Vec<_> = report
+        .iter()
+        .filter(|checksum| !checksum.matches())
+        .map(TableChecksum::tag)
+        .collect();
+    assert_eq!(mismatched_tables, corrupted_tables);
+    for checksum in &report {
+        assert_eq!(
+            checksum.expected() ^ checksum.actual(),
+            u32::from(!checksum.matches())
+        );
+    }
+}
+
+#[test]
+fn subsetting_with_fallback_glyph() {
+    let font = Font::new(MONO_FONT.bytes).unwrap();
+    let fallback_glyph = font.map_char('?').unwrap();
+    let options = SubsetOptions::default().fallback_glyph(fallback_glyph);
+    let chars = BTreeSet::from(['a', 'b', '\u{4e00}', '\u{4e01}']);
+    let subset = font.subset_with_options(&chars, options).unwrap();
+    assert_eq!(subset.glyphs.len(), 4);
+    let new_fallback_glyph = subset.old_to_new_glyph_idx[&fallback_glyph];
+    assert_eq!(
+        subset.char_map[2..],
+        [
+            ('\u{4e00}', new_fallback_glyph),
+            ('\u{4e01}', new_fallback_glyph)
+        ]
+    );
+
+    let ttf = subset.to_opentype();
+    assert_valid_font(&ttf, true, chars.iter().copied());
+    let subset_font = Font::new(&ttf).unwrap();
+    assert_eq!(
+        subset_font.map_char('\u{4e00}').unwrap(),
+        new_fallback_glyph
+    );
+    assert_eq!(subset_font.map_char('?').unwrap(), 0);
+
+    // The fallback glyph is not applied if all glyphs are retained.
+    let options = SubsetOptions::default()
+        .fallback_glyph(fallback_glyph)
+        .retain_script_glyphs(Script::Arabic);
+    let chars = BTreeSet::from(['a', '\u{628}', '\u{4e00}']);
+    let subset = font.subset_with_options(&chars, options).unwrap();
+    assert!(subset.retains_all_glyphs);
+    let ttf = subset.to_opentype();
+    let subset_font = Font::new(&ttf).unwrap();
+    assert_eq!(subset_font.map_char('\u{4e00}').unwrap(), 0);
+
+    let options = SubsetOptions::default().fallback_glyph(font.glyph_count);
+    let err = font.subset_with_options(&chars, options).unwrap_err();
+    assert!(
+        matches!(err, SubsetError::InvalidFallbackGlyph(idx) if idx == font.glyph_count),
+        "{err:?}"
+    );
+}
+
+#[test]
+fn chars_explicitly_mapped_to_missing_glyph_round_trip() {
+    let font = Font::new(MONO_FONT.bytes).unwrap();
+    let mapped_chars = ['a', 'c', 'f'];
+    let mappings: Vec<_> = ('a'..='f')
+        .map(|ch| {
+            let glyph_idx = if mapped_chars.contains(&ch) {
+                font.map_char(ch).unwrap()
+            } else {
+                0 // explicitly map to the missing glyph
+            };
+            (u16::try_from(u32::from(ch)).unwrap(), glyph_idx)
+        })
+        .collect();
+    let font_bytes = patch_font(MONO_FONT.bytes, |tables| {
+        tables.insert(*b"cmap", format4_cmap_table(&mappings));
+    });
+    let patched_font = Font::new(&font_bytes).unwrap();
+    let chars: BTreeSet<char> = ('a'..='f').collect();
+    let subset = patched_font.subset(&chars).unwrap();
+    assert_eq!(subset.char_map.len(), 6);
+    assert_eq!(subset.glyphs.len(), 4);
+
+    let ttf = subset.to_opentype();
+    assert_valid_font(&ttf, true, mapped_chars.into_iter());
+    let subset_font = Font::new(&ttf).unwrap();
+    for ch in 'a'..='f' {
+        let glyph_idx = subset_font.map_char(ch).unwrap();
+        if mapped_chars.contains(&ch) {
+            let original_glyph = font.map_char(ch).unwrap();
+            assert_eq!(glyph_idx, subset.old_to_new_glyph_idx[&original_glyph]);
+        } else {
+            assert_eq!(glyph_idx, 0, "{ch:?}");
+        }
+    }
+    let coverage = subset_font.coverage_bitset();
+    assert_eq!(coverage.intersect(&chars), BTreeSet::from(mapped_chars));
+}
+
+#[test_casing(2, FONTS)]
+fn truncated_hmtx_is_detected(test_font: TestFont) {
+    let font = Font::new(test_font.bytes).unwrap();
+    let hmtx_len = read_tables(test_font.bytes)[b"hmtx"].len();
+    let patched = patch_font(test_font.bytes, |tables| {
+        tables.get_mut(b"hmtx").unwrap().truncate(hmtx_len - 2);
+    });
+    let err = Font::new(&patched).unwrap_err();
+    assert_eq!(err.table(), Some(TableTag::HMTX));
+    assert!(
+        matches!(
+            err.kind(),
+            ParseErrorKind::UnexpectedTableLen { expected, actual }
+                if *expected == hmtx_len && *actual == hmtx_len - 2
+        ),
+        "{err:?}"
+    );
+
+    // Trailing data after metrics is tolerated.
+    let patched = patch_font(test_font.bytes, |tables| {
+        tables.get_mut(b"hmtx").unwrap().extend_from_slice(&[0; 4]);
+    });
+    let patched_font = Font::new(&patched).unwrap();
+    assert_eq!(patched_font.glyph_count, font.glyph_count);
+}
+
+#[test]
+fn reading_metrics_at_number_of_h_metrics_boundary() {
+    const NUMBER_OF_H_METRICS: u16 = 10;
+
+    let glyph_count = Font::new(MONO_FONT.bytes).unwrap().glyph_count;
+    // Long metrics have distinct advances and LSBs; trailing LSBs are distinct as well.
+    let mut hmtx = vec![];
+    for i in 0..NUMBER_OF_H_METRICS {
+        hmtx.extend_from_slice(&(500 + 10 * i).to_be_bytes());
+        hmtx.extend_from_slice(&(20 + i).to_be_bytes());
+    }
+    for i in NUMBER_OF_H_METRICS..glyph_count {
+        hmtx.extend_from_slice(&(1_000 + i).to_be_bytes());
+    }
+    let font_bytes = patch_font(MONO_FONT.bytes, |tables| {
+        tables.insert(*b"hmtx", hmtx);
+        let hhea = tables.get_mut(b"hhea").unwrap();
+        hhea[34..36].copy_from_slice(&NUMBER_OF_H_METRICS.to_be_bytes());
+    });
+    let font = Font::new(&font_bytes).unwrap();
+    let last_long_idx = NUMBER_OF_H_METRICS - 1;
+    let last_long_advance = 500 + 10 * last_long_idx;
+
+    let expected_metrics = [
+        (0, (500, 20)),
+        (last_long_idx - 1, (580, 28)),
+        (last_long_idx, (last_long_advance, 29)),
+        (NUMBER_OF_H_METRICS, (last_long_advance, 1_010)),
+        (NUMBER_OF_H_METRICS + 1, (last_long_advance, 1_011)),
+        (
+            glyph_count - 1,
+            (last_long_advance, 1_000 + glyph_count - 1),
+        ),
+    ];
+    for (glyph_idx, expected) in expected_metrics {
+        assert_eq!(
+            font.hmtx.advance_and_lsb(glyph_idx).unwrap(),
+            expected,
+            "{glyph_idx}"
+        );
+        let glyph = font.glyph(glyph_idx).unwrap();
+        assert_eq!((glyph.advance, glyph.lsb), expected, "{glyph_idx}");
+    }
+    let err = font.hmtx.advance_and_lsb(glyph_count).unwrap_err();
+    assert!(
+        matches!(err.kind(), ParseErrorKind::UnexpectedEof),
+        "{err:?}"
+    );
+
+    // Metrics must be retained in subsets.
+    let glyph_ids = expected_metrics.iter().map(|&(idx, _)| idx).collect();
+    let subset = font.subset_glyphs(&glyph_ids).unwrap();
+    let subset_font_bytes = subset.to_opentype();
+    let subset_font = Font::new(&subset_font_bytes).unwrap();
+    for (new_idx, old_idx) in (0..).zip(subset.new_to_old_glyphs()) {
+        let expected = font.hmtx.advance_and_lsb(old_idx).unwrap();
+        assert_eq!(subset_font.hmtx.advance_and_lsb(new_idx).unwrap(), expected);
+    }
+}
+
+#[test]
+fn truncated_table_directory_is_detected() {
+    let table_count = u16::from_be_bytes([MONO_FONT.bytes[4], MONO_FONT.bytes[5]]);
+    let directory_len = 12 + 16 * usize::from(table_count);
+    let err = Font::new(&MONO_FONT.bytes[..directory_len - 1]).unwrap_err();
+    assert!(
+        matches!(
+            err.kind(),
+            ParseErrorKind::TruncatedTableDirectory { table_count: count } if *count == table_count
+        ),
+        "{err:?}"
+    );
+
+    let mut font_bytes = MONO_FONT.bytes.to_vec();
+    font_bytes[4..6].copy_from_slice(&u16::MAX.to_be_bytes());
+    let err = Font::checksum_report(&font_bytes).unwrap_err();
+    assert!(
+        matches!(
+            err.kind(),
+            ParseErrorKind::TruncatedTableDirectory {
+                table_count: u16::MAX
+            }
+        ),
+        "{err:?}"
+    );
+    assert_eq!(err.offset(), 12);
+}
+
+#[test_casing(2, FONTS)]
+fn retaining_glyph_classes(test_font: TestFont) {
+    let read_gdef = |font_bytes: &[u8]| {
+        let tables = read_tables(font_bytes);
+        ReadScope::new(&tables[b"GDEF"])
+            .read::<GDEFTable>()
+            .unwrap()
+    };
+
+    let font = Font::new(test_font.bytes).unwrap();
+    let chars: BTreeSet<char> = (' '..='~').chain('\u{300}'..='\u{30f}').collect();
+    let subset = font.subset(&chars).unwrap();
+    assert!(!read_tables(&subset.to_opentype()).contains_key(b"GDEF"));
+
+    let options = SubsetOptions::default().retain_glyph_classes(true);
+    let subset = font.subset_with_options(&chars, options).unwrap();
+    let ttf = subset.to_opentype();
+    assert_valid_font(&ttf, true, ' '..='~');
+    let original_class_def = read_gdef(test_font.bytes).opt_glyph_classdef.unwrap();
+    let gdef = read_gdef(&ttf);
+    assert!(gdef.opt_mark_attach_classdef.is_none());
+    let class_def = gdef.opt_glyph_classdef.unwrap();
+
+    let mut class_counts = BTreeMap::<_, usize>::new();
+    for (new_idx, old_idx) in (0..).zip(subset.new_to_old_glyphs()) {
+        let class = class_def.glyph_class_value(new_idx);
+        assert_eq!(
+            class,
+            original_class_def.glyph_class_value(old_idx),
+            "{new_idx}"
+        );
+        *class_counts.entry(class).or_default() += 1;
+    }
+    // Base glyphs and marks must be present
+    assert!(class_counts.contains_key(&1), "{class_counts:?}");
+    assert!(class_counts.contains_key(&3), "{class_counts:?}");
+}
+
+/// Creates a `MATH` table referencing glyphs for the specified chars in the `font`.
+fn test_math_table<'a>(font: &Font<'_>, constants: &'a [u8]) -> MathTable<'a> {
+    let glyph = |ch| font.map_char(ch).unwrap();
+    let sorted = |mut values: Vec<(u16, i16)>| {
+        values.sort_unstable_by_key(|&(glyph, _)| glyph);
+        values
+    };
+    let mut extended_shapes = vec![glyph('('), glyph('['), glyph('|')];
+    extended_shapes.sort_unstable();
+    let mut vertical_constructions = vec![
+        (
+            glyph('('),
+            GlyphConstruction {
+                variants: vec![(glyph('('), 1_000), (glyph('['), 1_500)],
+                assembly: Some(GlyphAssembly {
+                    italics_correction: -5,
+                    parts: vec![(glyph('('), [1, 2, 3, 4, 5, 6, 7, 8]), (glyph('|'), [0; 8])],
+                }),
+            },
+        ),
+        (
+            glyph('{'),
+            GlyphConstruction {
+                variants: vec![(glyph('{'), 1_000)],
+                assembly: None,
+            },
+        ),
+    ];
+    vertical_constructions.sort_unstable_by_key(|(glyph, _)| *glyph);
+
+    MathTable {
+        constants,
+        italics_corrections: sorted(vec![(glyph('f'), 50), (glyph('A'), 10), (glyph('x'), 7)]),
+        top_accent_attachments: sorted(vec![(glyph('A'), 300), (glyph('B'), 310)]),
+        extended_shapes,
+        min_connector_overlap: 20,
+        vertical_constructions,
+        horizontal_constructions: vec![(
+            glyph('-'),
+            GlyphConstruction {
+                variants: vec![(glyph('-'), 500), (glyph('_'), 800)],
+                assembly: None,
+            },
+        )],
+    }
+}
+
+#[test]
+fn retaining_math_table() {
+    let font = Font::new(MONO_FONT.bytes).unwrap();
+    let constants: Vec<u8> = (0..=u8::MAX).take(MathTable::CONSTANTS_LEN).collect();
+    let math = test_math_table(&font, &constants);
+    let mut raw_math = vec![];
+    math.write(&mut raw_math);
+    let font_bytes = patch_font(MONO_FONT.bytes, |tables| {
+        tables.insert(*b"MATH", raw_math);
+    });
+    // Device table offsets must be zeroed when writing the table
+    let expected_constants: Vec<u8> = constants
+        .iter()
+        .enumerate()
+        .map(|(i, &byte)| {
+            if (8..212).contains(&i) && i % 4 >= 2 {
+                0
+            } else {
+                byte
+            }
+        })
+        .collect();
+    let font = Font::new(&font_bytes).unwrap();
+    let parsed_math = font.math_table().unwrap().unwrap();
+    assert_eq!(parsed_math.constants, expected_constants);
+    assert_eq!(
+        MathTable {
+            constants: &constants,
+            ..parsed_math
+        },
+        math
+    );
+
+    let chars: BTreeSet<char> = "(-A|f".chars().collect();
+    let subset = font.subset(&chars).unwrap();
+    assert!(!read_tables(&subset.to_opentype()).contains_key(b"MATH"));
+
+    let options = SubsetOptions::default().retain_math(true);
+    let subset = font.subset_with_options(&chars, options).unwrap();
+    let ttf = subset.to_opentype();
+    assert_eq!(subset.estimated_opentype_size(), ttf.len());
+    assert_valid_font(&ttf, true, chars.iter().copied());
+
+    let subset_font = Font::new(&ttf).unwrap();
+    let glyph = |ch| subset_font.map_char(ch).unwrap();
+    let subset_math = subset_font.math_table().unwrap().unwrap();
+    assert_eq!(subset_math.constants, expected_constants);
+    assert_eq!(
+        subset_math.italics_corrections,
+        [(glyph('A'), 10), (glyph('f'), 50)]
+    );
+    assert_eq!(subset_math.top_accent_attachments, [(glyph('A'), 300)]);
+    assert_eq!(subset_math.extended_shapes, [glyph('('), glyph('|')]);
+    assert_eq!(subset_math.min_connector_overlap, 20);
+
+    let [(paren_glyph, paren_construction)] = subset_math.vertical_constructions.as_slice() else {
+        panic!(
+            "unexpected constructions: {:?}",
+            subset_math.vertical_constructions
+        );
+    };
+    assert_eq!(*paren_glyph, glyph('('));
+    assert_eq!(paren_construction.variants, [(glyph('('), 1_000)]);
+    let assembly = paren_construction.assembly.as_ref().unwrap();
+    assert_eq!(assembly.italics_correction, -5);
+    let part_glyphs: Vec<_> = assembly.parts.iter().map(|(glyph, _)| *glyph).collect();
+    assert_eq!(part_glyphs, [glyph('('), glyph('|')]);
+    assert_eq!(assembly.parts[0].1, [1, 2, 3, 4, 5, 6, 7, 8]);
+
+    let [(dash_glyph, dash_construction)] = subset_math.horizontal_constructions.as_slice() else {
+        panic!(
+            "unexpected constructions: {:?}",
+            subset_math.horizontal_constructions
+        );
+    };
+    assert_eq!(*dash_glyph, glyph('-'));
+    assert_eq!(dash_construction.variants, [(glyph('-'), 500)]);
+    assert!(dash_construction.assembly.is_none());
+}
+
+#[test]
+fn math_coverage_is_bounded_by_glyph_count() {
+    const RANGE_COUNT: u16 = 1_000;
+
+    let glyph_info_offset =
+        u16::try_from(MathTable::HEADER_LEN + MathTable::CONSTANTS_LEN).unwrap();
+    let mut raw_math: Vec<u8> = [1, 0, 10, glyph_info_offset, 0]
+        .iter()
+        .flat_map(|val: &u16| val.to_be_bytes())
+        .collect();
+    raw_math.resize(usize::from(glyph_info_offset), 0);
+    // `MathGlyphInfo` with only the extended shape coverage
+    let glyph_info = [0_u16, 0, 8, 0];
+    raw_math.extend(glyph_info.iter().flat_map(|val| val.to_be_bytes()));
+    // Coverage format 2 with many ranges covering all glyph IDs
+    raw_math.extend([2, RANGE_COUNT].iter().flat_map(|val| val.to_be_bytes()));
+    for _ in 0..RANGE_COUNT {
+        raw_math.extend([0, u16::MAX, 0].iter().flat_map(|val| val.to_be_bytes()));
+    }
+    let font_bytes = patch_font(MONO_FONT.bytes, |tables| {
+        tables.insert(*b"MATH", raw_math);
+    });
+
+    let font = Font::new(&font_bytes).unwrap();
+    let err = font.math_table().unwrap_err();
+    assert_eq!(err.table(), Some(TableTag::MATH));
+    assert!(
+        matches!(err.kind(), ParseErrorKind::RangeOutOfBounds { range, len } if range.start == 0 && *len == usize::from(font.glyph_count)),
+        "{err:?}"
+    );
+}
+
+#[test_casing(2, FONTS)]
+fn recomputing_unicode_ranges(test_font: TestFont) {
+    let read_unicode_ranges = |font_bytes: &[u8]| {
+        let tables = read_tables(font_bytes);
+        let os2 = &tables[b"OS/2"];
+        let ranges: [u32; 4] =
+            array::from_fn(|i| u32::from_be_bytes(os2[42 + 4 * i..46 + 4 * i].try_into().unwrap()));
+        (ranges, os2.clone())
+    };
+
+    let font = Font::new(test_font.bytes).unwrap();
+    let (original_ranges, original_os2) = read_unicode_ranges(test_font.bytes);
+    // Sanity check: the font covers more than Basic Latin.
+    assert_ne!(original_ranges, [1, 0, 0, 0]);
+
+    let chars: BTreeSet<char> = (' '..='~').collect();
+    let subset = font.subset(&chars).unwrap();
+    let (ranges, _) = read_unicode_ranges(&subset.to_opentype());
+    assert_eq!(ranges, original_ranges);
+
+    let options = SubsetOptions::default().recompute_unicode_ranges(true);
+    let subset = font.subset_with_options(&chars, options.clone()).unwrap();
+    let ttf = subset.to_opentype();
+    assert_valid_font(&ttf, true, chars.iter().copied());
+    let (ranges, os2) = read_unicode_ranges(&ttf);
+    assert_eq!(ranges, [1, 0, 0, 0]);
+    // Other fields (except for char indices) must be retained.
+    assert_eq!(os2[..42], original_os2[..42]);
+    assert_eq!(os2[58..64], original_os2[58..64]);
+    assert_eq!(os2[68..], original_os2[68..]);
+
+    // Chars not covered by the font must not influence ranges.
+    let chars: BTreeSet<char> = "Hi, мир \u{4e16}\u{754c}".chars().collect();
+    let subset = font.subset_with_options(&chars, options.clone()).unwrap();
+    let (ranges, _) = read_unicode_ranges(&subset.to_opentype());
+    assert_eq!(ranges, [1 | (1 << 9), 0, 0, 0]);
+
+    let subset = FontSubset::all(&font, options.clone()).unwrap();
+    let (ranges, _) = read_unicode_ranges(&subset.to_opentype());
+    assert_eq!(ranges[0] & (1 | (1 << 9)), 1 | (1 << 9), "{ranges:x?}");
+    // Ranges must be the same as for a subset with all covered chars.
+    let coverage = font.coverage_bitset();
+    let chars: BTreeSet<char> = ('\0'..=char::MAX)
+        .filter(|&ch| coverage.contains(ch))
+        .collect();
+    let subset = font.subset_with_options(&chars, options).unwrap();
+    assert_eq!(read_unicode_ranges(&subset.to_opentype()).0, ranges);
+}
+
+#[test_casing(2, FONTS)]
+fn updating_os2_char_indices(test_font: TestFont) {
+    let read_os2 = |font_bytes: &[u8]| {
+        let os2 = read_tables(font_bytes)[b"OS/2"].clone();
+        let first_char = u16::from_be_bytes([os2[64], os2[65]]);
+        let last_char = u16::from_be_bytes([os2[66], os2[67]]);
+        (first_char, last_char, os2)
+    };
+
+    let font = Font::new(test_font.bytes).unwrap();
+    let (original_first, original_last, original_os2) = read_os2(test_font.bytes);
+    // Sanity check: the font covers more than Basic Latin.
+    assert!(original_last > 0x7e, "{original_last:x}");
+
+    let chars: BTreeSet<char> = (' '..='~').collect();
+    let ttf = font.subset(&chars).unwrap().to_opentype();
+    assert_valid_font(&ttf, true, chars.iter().copied());
+    let (first_char, last_char, os2) = read_os2(&ttf);
+    assert_eq!((first_char, last_char), (0x20, 0x7e));
+    assert_eq!(os2.len(), original_os2.len());
+    assert_eq!(os2[..64], original_os2[..64]);
+    assert_eq!(os2[68..], original_os2[68..]);
+
+    // Chars not covered by the font must not influence indices.
+    let chars: BTreeSet<char> = "Hi, мир \u{4e16}\u{754c}".chars().collect();
+    let ttf = font.subset(&chars).unwrap().to_opentype();
+    let (first_char, last_char, _) = read_os2(&ttf);
+    assert_eq!((first_char, last_char), (0x20, 0x440));
+
+    // If no chars are covered, indices are retained.
+    let ttf = font.subset(&BTreeSet::new()).unwrap().to_opentype();
+    let (first_char, last_char, _) = read_os2(&ttf);
+    assert_eq!((first_char, last_char), (original_first, original_last));
+
+    let ttf = font.subset_all().unwrap().to_opentype();
+    assert_eq!(read_os2(&ttf).2, original_os2);
+}
+
+#[test_casing(2, FONTS)]
+fn synthesizing_missing_os2(test_font: TestFont) {
+    let font_bytes = patch_font(test_font.bytes, |tables| {
+        tables.remove(b"OS/2");
+    });
+    let font = Font::new(&font_bytes).unwrap();
+    assert_eq!(font.typo_ascender(), font.ascender());
+    assert_eq!(font.typo_descender(), font.descender());
+    assert_eq!(font.typo_line_gap(), font.line_gap());
+    assert!(!font.uses_typo_metrics());
+
+    let chars: BTreeSet<char> = "Hello, world!".chars().collect();
+    let subset = font.subset(&chars).unwrap();
+    let ttf = subset.to_opentype();
+    assert_eq!(subset.estimated_opentype_size(), ttf.len());
+    assert_valid_font(&ttf, true, chars.iter().copied());
+
+    let os2 = read_tables(&ttf)[b"OS/2"].clone();
+    let field = |offset: usize| u16::from_be_bytes([os2[offset], os2[offset + 1]]);
+    let signed_field = |offset: usize| i16::from_be_bytes([os2[offset], os2[offset + 1]]);
+    assert_eq!(os2.len(), 96);
+    assert_eq!(field(0), 4); // version
+    assert!(signed_field(2) > 0); // xAvgCharWidth
+    assert_eq!(field(4), 400); // usWeightClass
+    assert_eq!(field(64), u16::from(b' '));
+    assert_eq!(field(66), u16::from(b'w'));
+    // Only Basic Latin is covered.
+    assert_eq!(
+        os2[42..58],
+        [0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]
+    );
+    assert_eq!(signed_field(68), font.ascender());
+    assert_eq!(signed_field(70), font.descender());
+    assert_eq!(signed_field(72), font.line_gap());
+    assert!(field(74) >= font.ascender().unsigned_abs()); // usWinAscent
+    assert!(field(76) >= font.descender().unsigned_abs()); // usWinDescent
+    assert_eq!(signed_field(86), 0); // sxHeight; 'x' is not retained
+    assert!(signed_field(88) > 0); // sCapHeight
+}
+
+#[test]
+fn synthesized_os2_win_metrics_are_clamped_to_zero() {
+    let font_bytes = patch_font(MONO_FONT.bytes, |tables| {
+        tables.remove(b"OS/2");
+        // Make all vertical metrics lie above the baseline (for `usWinDescent`)...
+        let head = tables.get_mut(b"head").unwrap();
+        head[38..40].copy_from_slice(&10_i16.to_be_bytes()); // yMin
+        let hhea = tables.get_mut(b"hhea").unwrap();
+        hhea[6..8].copy_from_slice(&20_i16.to_be_bytes()); // descender
+    });
+    let font = Font::new(&font_bytes).unwrap();
+    assert_eq!(font.descender(), 20);
+    let os2 = read_tables(&font.subset_for_text("Hello").unwrap().to_opentype())[b"OS/2"].clone();
+    assert!(u16::from_be_bytes([os2[74], os2[75]]) > 0); // usWinAscent
+    assert_eq!(u16::from_be_bytes([os2[76], os2[77]]), 0); // usWinDescent
+
+    // ...or below the baseline (for `usWinAscent`).
+    let font_bytes = patch_font(MONO_FONT.bytes, |tables| {
+        tables.remove(b"OS/2");
+        let head = tables.get_mut(b"head").unwrap();
+        head[42..44].copy_from_slice(&(-10_i16).to_be_bytes()); // yMax
+        let hhea = tables.get_mut(b"hhea").unwrap();
+        hhea[4..6].copy_from_slice(&(-20_i16).to_be_bytes()); // ascender
+    });
+    let font = Font::new(&font_bytes).unwrap();
+    assert_eq!(font.ascender(), -20);
+    let os2 = read_tables(&font.subset_for_text("Hello").unwrap().to_opentype())[b"OS/2"].clone();
+    assert_eq!(u16::from_be_bytes([os2[74], os2[75]]), 0); // usWinAscent
+    assert!(u16::from_be_bytes([os2[76], os2[77]]) > 0); // usWinDescent
+}
+
+fn kern_subtable(coverage: u16, pairs: &[(u16, u16, i16)]) -> Vec<u8> {
+    let pair_count = u16::try_from(pairs.len()).unwrap();
+    let mut subtable = vec![];
+    subtable.extend_from_slice(&0_u16.to_be_bytes()); // version
+    subtable.extend_from_slice(&(14 + 6 * pair_count).to_be_bytes());
+    subtable.extend_from_slice(&coverage.to_be_bytes());
+    subtable.extend_from_slice(&pair_count.to_be_bytes());
+    subtable.extend_from_slice(&[0; 6]); // binary search params are not checked by the parser
+    for &(left, right, value) in pairs {
+        subtable.extend_from_slice(&left.to_be_bytes());
+        subtable.extend_from_slice(&right.to_be_bytes());
+        subtable.extend_from_slice(&value.to_be_bytes());
+    }
+    subtable
+}
+
+#[test]
+fn retaining_kern_table() {
+    let font = Font::new(MONO_FONT.bytes).unwrap();
+    let glyph = |ch| font.map_char(ch).unwrap();
+    let mut pairs = vec![
+        (glyph('A'), glyph('V'), -80),
+        (glyph('V'), glyph('A'), -80),
+        (glyph('T'), glyph('o'), -60),
+        (glyph('o'), glyph('T'), -50),
+        (glyph('A'), glyph('Ж'), -10),
+        (glyph('Ж'), glyph('A'), -10),
+        (glyph('W'), glyph('o'), -40),
+    ];
+    pairs.sort_unstable_by_key(|&(left, right, _)| (left, right));
+    let format2_subtable = [0, 0, 0, 8, 2, 1, 0, 0];
+    let mut kern = vec![0, 0, 0, 3]; // version, nTables
+    kern.extend_from_slice(&kern_subtable(1, &pairs));
+    kern.extend_from_slice(&format2_subtable);
+    kern.extend_from_slice(&kern_subtable(1, &[(glyph('Ж'), glyph('Ж'), 5)]));
+    let font_bytes = patch_font(MONO_FONT.bytes, |tables| {
+        tables.insert(*b"kern", kern.clone());
+    });
+    let font = Font::new(&font_bytes).unwrap();
+
+    // Use char order different from the glyph order, so that pairs need to be re-sorted.
+    let subset = font.subset_for_text("oVTA").unwrap();
+    let ttf = subset.to_opentype();
+    assert_valid_font(&ttf, true, "oVTA".chars());
+    assert_eq!(subset.estimated_opentype_size(), ttf.len());
+    let new_to_old = subset.new_to_old_glyphs();
+    let old_to_new = |old_idx| {
+        let new_idx = new_to_old.iter().position(|&idx| idx == old_idx)?;
+        Some(u16::try_from(new_idx).unwrap())
+    };
+    let mut expected_pairs: Vec<_> = pairs
+        .iter()
+        .filter_map(|&(left, right, value)| Some((old_to_new(left)?, old_to_new(right)?, value)))
+        .collect();
+    expected_pairs.sort_unstable_by_key(|&(left, right, _)| (left, right));
+    assert_eq!(expected_pairs.len(), 4);
+
+    let mut expected_subtable = kern_subtable(1, &expected_pairs);
+    // searchRange, entrySelector, rangeShift for 4 pairs
+    expected_subtable[8..14].copy_from_slice(&[0, 24, 0, 2, 0, 0]);
+    let expected_kern = [&[0, 0, 0, 1][..], &expected_subtable].concat();
+    assert_eq!(read_tables(&ttf)[b"kern"], expected_kern);
+
+    let subset_font = Font::new(&ttf).unwrap();
+    let subset_kern = subset_font.kern_table().unwrap().unwrap();
+    assert_eq!(subset_kern.subtables.len(), 1);
+    let subtable = &subset_kern.subtables[0];
+    assert_eq!(subtable.coverage, 1);
+    let actual_pairs: Vec<_> = subtable
+        .pairs()
+        .map(|(left, right, value)| (left, right, i16::from_be_bytes(value)))
+        .collect();
+    assert_eq!(actual_pairs, expected_pairs);
+
+    // No retained pairs => no `kern` table
+    let subset = font.subset_for_text("xyz").unwrap();
+    assert!(!read_tables(&subset.to_opentype()).contains_key(b"kern"));
+    // If all glyphs are retained, the table is copied verbatim.
+    let subset = font.subset_all().unwrap();
+    assert_eq!(read_tables(&subset.to_opentype())[b"kern"], kern);
+
+    // Apple's table format and unknown versions are not supported; the table is dropped.
+    for version in [1, 2] {
+        let font_bytes = patch_font(MONO_FONT.bytes, |tables| {
+            tables.insert(*b"kern", vec![0, version, 0, 0, 0, 0, 0, 0]);
+        });
+        let font = Font::new(&font_bytes).unwrap();
+        let subset = font.subset_for_text("oVTA").unwrap();
+        assert!(!read_tables(&subset.to_opentype()).contains_key(b"kern"));
+    }
+}
+
+/// `(glyph ID, glyph data record)` pairs for an `sbix` strike, ordered by the glyph ID.
+type SbixRecords<'a> = &'a [(u16, &'a [u8])];
+
+fn sbix_table(glyph_count: u16, strikes: &[(u16, SbixRecords<'_>)]) -> Vec<u8> {
+    let strike_count = u32::try_from(strikes.len()).unwrap();
+    let mut sbix = vec![0, 1, 0, 1]; // version, flags
+    sbix.extend_from_slice(&strike_count.to_be_bytes());
+    let mut strike_data = vec![];
+    for &(ppem, glyphs) in strikes {
+        let strike_offset = 8 + 4 * strike_count + u32::try_from(strike_data.len()).unwrap();
+        sbix.extend_from_slice(&strike_offset.to_be_bytes());
+
+        strike_data.extend_from_slice(&ppem.to_be_bytes());
+        strike_data.extend_from_slice(&72_u16.to_be_bytes()); // ppi
+        let mut offset = 4 + 4 * (u32::from(glyph_count) + 1);
+        let mut records = vec![];
+        for glyph_idx in 0..=glyph_count {
+            strike_data.extend_from_slice(&offset.to_be_bytes());
+            if let Some((_, record)) = glyphs.iter().find(|(idx, _)| *idx == glyph_idx) {
+                offset += u32::try_from(record.len()).unwrap();
+                records.extend_from_slice(record);
+            }
+        }
+        strike_data.extend_from_slice(&records);
+    }
+    sbix.extend_from_slice(&strike_data);
+    sbix
+}
+
+#[test]
+fn retaining_sbix_table() {
+    let font = Font::new(MONO_FONT.bytes).unwrap();
+    let glyph = |ch| font.map_char(ch).unwrap();
+    let png_record = [&[0, 1, 0, 2][..], b"png ", b"\x89PNG"].concat();
+    let jpg_record = [&[0, 0, 0, 0][..], b"jpg ", b"JFIF"].concat();
+    let dupe_record = [&[0, 0, 0, 0][..], b"dupe", &glyph('A').to_be_bytes()].concat();
+    let mut small_glyphs = [
+        (glyph('A'), &png_record[..]),
+        (glyph('B'), &dupe_record),
+        (glyph('Z'), &jpg_record),
+    ];
+    small_glyphs.sort_unstable_by_key(|(idx, _)| *idx);
+    let large_glyphs = [(glyph('Z'), &png_record[..])];
+    let sbix = sbix_table(
+        font.glyph_count,
+        &[(20, &small_glyphs), (40, &large_glyphs)],
+    );
+    let font_bytes = patch_font(MONO_FONT.bytes, |tables| {
+        tables.insert(*b"sbix", sbix.clone());
+    });
+    let font = Font::new(&font_bytes).unwrap();
+
+    // By default, the table is dropped.
+    let subset = font.subset_for_text("ZAB").unwrap();
+    assert!(!read_tables(&subset.to_opentype()).contains_key(b"sbix"));
+
+    let options = SubsetOptions::default().retain_sbix(true);
+    let subset = font
+        .subset_with_options(&"ZAB".chars().collect(), options)
+        .unwrap();
+    let ttf = subset.to_opentype();
+    assert_valid_font(&ttf, true, "ZAB".chars());
+    assert_eq!(subset.estimated_opentype_size(), ttf.len());
+    let subset_font = Font::new(&ttf).unwrap();
+    let new_glyph = |ch| subset_font.map_char(ch).unwrap();
+    let subset_sbix = subset_font.sbix_table().unwrap().unwrap();
+    assert_eq!(subset_sbix.flags, 1);
+    assert_eq!(subset_sbix.strikes.len(), 2);
+
+    let sizes: Vec<_> = subset_sbix
+        .strikes
+        .iter()
+        .map(|strike| (strike.ppem, strike.ppi))
+        .collect();
+    assert_eq!(sizes, [(20, 72), (40, 72)]);
+    let records: Vec<Vec<_>> = subset_sbix
+        .strikes
+        .iter()
+        .map(|strike| {
+            (0..subset_font.glyph_count)
+                .filter_map(|idx| Some((idx, strike.glyph(idx).unwrap()?.record)))
+                .collect()
+        })
+        .collect();
+    let mut expected_records = vec![
+        (new_glyph('A'), &png_record[..]),
+        (new_glyph('Z'), &jpg_record),
+    ];
+    expected_records.sort_unstable_by_key(|(idx, _)| *idx);
+    // The `dupe` record is dropped.
+    assert_eq!(records[0], expected_records);
+    assert_eq!(records[1], [(new_glyph('Z'), &png_record[..])]);
+}
+
+#[test_casing(2, FONTS)]
+fn trimming_name_table(test_font: TestFont) {
+    let font = Font::new(test_font.bytes).unwrap();
+    let original_records = font.name_table().unwrap().unwrap().records;
+    assert!(original_records.iter().any(|record| record.name_id == 0));
+
+    let mut subset = font.subset_for_text("Hello").unwrap();
+    let ttf = subset.to_opentype();
+    assert_valid_font(&ttf, true, "Hello".chars());
+    assert!(read_tables(&ttf)[b"name"].len() < font.name.unwrap().as_ref().len());
+    let subset_font = Font::new(&ttf).unwrap();
+    let records = subset_font.name_table().unwrap().unwrap().records;
+    let expected_records: Vec<_> = original_records
+        .iter()
+        .filter(|record| [0, 1, 2, 4, 5, 6, 7, 13, 14].contains(&record.name_id))
+        .copied()
+        .collect();
+    assert!(!expected_records.is_empty());
+    assert_eq!(records, expected_records);
+
+    subset.with_retained_name_ids(&BTreeSet::from([0, 1]));
+    let ttf = subset.to_opentype();
+    assert_eq!(subset.estimated_opentype_size(), ttf.len());
+    let subset_font = Font::new(&ttf).unwrap();
+    let records = subset_font.name_table().unwrap().unwrap().records;
+    assert!(records.is_sorted_by_key(NameRecord::sort_key));
+    let name_ids: BTreeSet<_> = records.iter().map(|record| record.name_id).collect();
+    assert_eq!(name_ids, BTreeSet::from([0, 1]));
+}
+
+/// Creates a format 1 `name` table with records in the `(platform, encoding, language, name ID)` format
+/// and language tags. All strings are encoded in UTF-16BE.
+fn format1_name_table(records: &[([u16; 4], &str)], lang_tags: &[&str]) -> Vec<u8> {
+    let strings: Vec<Vec<u8>> = records
+        .iter()
+        .map(|(_, string)| *string)
+        .chain(lang_tags.iter().copied())
+        .map(|string| string.encode_utf16().flat_map(u16::to_be_bytes).collect())
+        .collect();
+    let storage_offset = 6 + 12 * records.len() + 2 + 4 * lang_tags.len();
+
+    let mut table = vec![0, 1];
+    table.extend_from_slice(&u16::try_from(records.len()).unwrap().to_be_bytes());
+    table.extend_from_slice(&u16::try_from(storage_offset).unwrap().to_be_bytes());
+    let mut string_offset = 0;
+    let mut string_records = strings.iter().map(|string| {
+        let record = [string.len(), string_offset].map(|val| u16::try_from(val).unwrap());
+        string_offset += string.len();
+        record
+    });
+    for (ids, _) in records {
+        let string_record = string_records.next().unwrap();
+        table.extend(
+            ids.iter()
+                .chain(&string_record)
+                .flat_map(|val| val.to_be_bytes()),
+        );
+    }
+    table.extend_from_slice(&u16::try_from(lang_tags.len()).unwrap().to_be_bytes());
+    table.extend(string_records.flatten().flat_map(u16::to_be_bytes));
+    table.extend(strings.into_iter().flatten());
+    table
+}
+
+#[test]
+fn trimming_name_table_with_overlapping_strings() {
+    const STORAGE_LEN: usize = 60_000;
+    const RECORD_COUNT: u16 = 64;
+
+    // All records reference long, overlapping ranges of the string storage.
+    let storage_offset = 6 + 12 * usize::from(RECORD_COUNT);
+    let mut table = [0, RECORD_COUNT, u16::try_from(storage_offset).unwrap()]
+        .iter()
+        .flat_map(|val| val.to_be_bytes())
+        .collect::<Vec<_>>();
+    for name_id in 0..RECORD_COUNT {
+        let string_len = u16::try_from(STORAGE_LEN).unwrap() - RECORD_COUNT;
+        let record = [3, 1, 0x409, name_id, string_len, name_id];
+        table.extend(record.iter().flat_map(|val| val.to_be_bytes()));
+    }
+    table.extend((0..STORAGE_LEN).map(|i| u8::try_from(i % 251).unwrap()));
+    let font_bytes = patch_font(MONO_FONT.bytes, |tables| {
+        tables.insert(*b"name", table);
+    });
+
+    let font = Font::new(&font_bytes).unwrap();
+    let mut subset = font.subset_for_text("Hello").unwrap();
+    subset.with_retained_name_ids(&(0..RECORD_COUNT).step_by(2).collect());
+    let ttf = subset.to_opentype();
+    assert_eq!(subset.estimated_opentype_size(), ttf.len());
+    assert!(read_tables(&ttf)[b"name"].len() < storage_offset + STORAGE_LEN);
+
+    let original_records = font.name_table().unwrap().unwrap().records;
+    let subset_font = Font::new(&ttf).unwrap();
+    let records = subset_font.name_table().unwrap().unwrap().records;
+    let expected_records: Vec<_> = original_records.into_iter().step_by(2).collect();
+    assert_eq!(records, expected_records);
+}
+
+#[test]
+fn trimming_format1_name_table() {
+    let records = [
+        ([3, 1, 0x409, 3], "Unique ID"),
+        ([3, 1, 0x409, 1], "Test"),
+        ([3, 1, 0x409, 2], "Regular"),
+        ([3, 1, 0x8000, 1], "Test"),
+        ([3, 1, 0x8000, 2], "Normal"),
+        ([3, 1, 0x8001, 1], "Тест"),
+        ([3, 1, 0x8001, 9], "Designer"),
+    ];
+    let font_bytes = patch_font(MONO_FONT.bytes, |tables| {
+        tables.insert(*b"name", format1_name_table(&records, &["en-GB", "ru"]));
+    });
+    let font = Font::new(&font_bytes).unwrap();
+    let subset = font.subset_for_text("Hello").unwrap();
+    let ttf = subset.to_opentype();
+    assert_eq!(subset.estimated_opentype_size(), ttf.len());
+    assert_valid_font(&ttf, true, "Hello".chars());
+
+    let subset_font = Font::new(&ttf).unwrap();
+    let name_table = subset_font.name_table().unwrap().unwrap();
+    assert_eq!(name_table.version, 1);
+    let name_ids: Vec<_> = name_table
+        .records
+        .iter()
+        .map(NameRecord::sort_key)
+        .collect();
+    assert_eq!(
+        name_ids,
+        [
+            [3, 1, 0x409, 1],
+            [3, 1, 0x409, 2],
+            [3, 1, 0x8000, 1],
+            [3, 1, 0x8000, 2],
+            [3, 1, 0x8001, 1]
+        ]
+    );
+    assert_eq!(name_table.records[0].string, name_table.records[2].string);
+    let name = &read_tables(&ttf)[b"name"];
+    // Only storage ranges of dropped records ("Unique ID" and "Designer") are removed.
+    let storage_len = 2 * "TestRegularTestNormalТестen-GBru".chars().count();
+    assert_eq!(name.len(), 6 + 12 * 5 + 2 + 4 * 2 + storage_len);
+    let lang_tags: Vec<String> = name_table
+        .lang_tags
+        .iter()
+        .map(|tag| {
+            let units: Vec<_> = tag
+                .chunks_exact(2)
+                .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+                .collect();
+            String::from_utf16(&units).unwrap()
+        })
+        .collect();
+    assert_eq!(lang_tags, ["en-GB", "ru"]);
+}
+
+#[test_casing(2, [false, true])]
+fn unparseable_name_table_is_copied_verbatim(unsupported_version: bool) {
+    let mut name = read_tables(MONO_FONT.bytes)[b"name"].clone();
+    if unsupported_version {
+        name[..2].copy_from_slice(&2_u16.to_be_bytes());
+    } else {
+        // Make the string of the first record (which isn't retained by default) out of bounds.
+        name[14..16].copy_from_slice(&u16::MAX.to_be_bytes());
+    }
+    let font_bytes = patch_font(MONO_FONT.bytes, |tables| {
+        tables.insert(*b"name", name.clone());
+    });
+    let font = Font::new(&font_bytes).unwrap();
+    font.name_table().unwrap_err();
+
+    let subset = font.subset_for_text("Hello").unwrap();
+    let ttf = subset.to_opentype();
+    assert_eq!(subset.estimated_opentype_size(), ttf.len());
+    assert_valid_font(&ttf, true, "Hello".chars());
+    assert_eq!(read_tables(&ttf)[b"name"], name);
+}
+
+#[test_casing(2, FONTS)]
+fn extracting_outline_tables(test_font: TestFont) {
+    let font = Font::new(test_font.bytes).unwrap();
+    let chars: BTreeSet<char> = (' '..='~').chain("ÅÁ".chars()).collect();
+    let mut subset = FontSubset::new(&font, &chars).unwrap();
+    for strip_hinting in [false, true] {
+        if strip_hinting {
+            subset.strip_hinting();
+        }
+        let tables = read_tables(&subset.to_opentype());
+        let glyf = subset.glyf_table_bytes().unwrap();
+        assert_eq!(*glyf, tables[b"glyf"]);
+        let loca = subset.loca_table_bytes().unwrap();
+        assert_eq!(*loca, tables[b"loca"]);
+        let is_short_loca = loca.len() == 2 * (subset.retained_glyph_count() + 1);
+        let index_to_loc_format = u16::from_be_bytes([tables[b"head"][50], tables[b"head"][51]]);
+        assert_eq!(index_to_loc_format, u16::from(!is_short_loca));
+        assert_eq!(subset.outline_tables_bytes(), Some((glyf, loca)));
+    }
+}
+
+#[test_casing(2, FONTS)]
+fn outline_tables_are_borrowed_when_retaining_all_glyphs(test_font: TestFont) {
+    let font = Font::new(test_font.bytes).unwrap();
+    let original_tables = read_tables(test_font.bytes);
+    let mut subset = font.subset_all().unwrap();
+    let (glyf, loca) = subset.outline_tables_bytes().unwrap();
+    assert!(matches!(glyf, Cow::Borrowed(_)));
+    assert!(matches!(loca, Cow::Borrowed(_)));
+    assert_eq!(*glyf, original_tables[b"glyf"]);
+    assert_eq!(*loca, original_tables[b"loca"]);
+    let ttf = subset.to_opentype();
+    assert_eq!(subset.estimated_opentype_size(), ttf.len());
+    let tables = read_tables(&ttf);
+    assert_eq!(tables[b"glyf"], original_tables[b"glyf"]);
+    assert_eq!(tables[b"loca"], original_tables[b"loca"]);
+    assert_eq!(tables[b"head"][50..52], original_tables[b"head"][50..52]);
+
+    subset.strip_hinting();
+    let (glyf, loca) = subset.outline_tables_bytes().unwrap();
+    assert!(matches!(glyf, Cow::Owned(_)));
+    assert!(matches!(loca, Cow::Owned(_)));
+    let tables = read_tables(&subset.to_opentype());
+    assert_eq!(*glyf, tables[b"glyf"]);
+    assert_eq!(*loca, tables[b"loca"]);
+}
+
+#[test_casing(2, FONTS)]
+fn stripping_hinting(test_font: TestFont) {
+    let font = Font::new(test_font.bytes).unwrap();
+    let chars: BTreeSet<char> = (' '..='~').chain("ÅÁ".chars()).collect();
+    let mut subset = FontSubset::new(&font, &chars).unwrap();
+    let hinted_ttf = subset.to_opentype();
+    subset.strip_hinting();
+    let ttf = subset.to_opentype();
+    assert!(ttf.len() < hinted_ttf.len());
+    assert_eq!(subset.estimated_opentype_size(), ttf.len());
+    assert_valid_font(&ttf, true, chars.iter().copied());
+
+    let tables = read_tables(&ttf);
+    for hinting_table in [b"cvt ", b"fpgm", b"prep"] {
+        assert!(!tables.contains_key(hinting_table));
+    }
+    let stripped_font = Font::new(&ttf).unwrap();
+    let hinted_font = Font::new(&hinted_ttf).unwrap();
+    for glyph_idx in 0..stripped_font.glyph_count {
+        let glyph = stripped_font.glyph(glyph_idx).unwrap();
+        assert!(!glyph.inner.has_instructions(), "{glyph_idx}");
+        assert_eq!(
+            stripped_font.glyph_svg_path(glyph_idx).unwrap(),
+            hinted_font.glyph_svg_path(glyph_idx).unwrap(),
+            "{glyph_idx}"
+        );
+    }
+}
+
+#[test_casing(2, FONTS)]
+fn preserving_gasp_table(test_font: TestFont) {
+    let original_tables = read_tables(test_font.bytes);
+    let font = Font::new(test_font.bytes).unwrap();
+    let subset = font.subset_for_text("Hello").unwrap();
+    let tables = read_tables(&subset.to_opentype());
+    assert_eq!(tables[b"gasp"], original_tables[b"gasp"]);
+
+    let woff2 = subset.to_woff2();
+    let font_file = ReadScope::new(&woff2).read::<FontData>().unwrap();
+    let font_provider = font_file.table_provider(0).unwrap();
+    let gasp = font_provider.read_table_data(tag::GASP).unwrap();
+    assert_eq!(*gasp, original_tables[b"gasp"]);
+
+    let font_bytes = patch_font(test_font.bytes, |tables| {
+        tables.remove(b"gasp");
+    });
+    let font = Font::new(&font_bytes).unwrap();
+    let ttf = font.subset_for_text("Hello").unwrap().to_opentype();
+    assert_valid_font(&ttf, true, "Hello".chars());
+    assert!(!read_tables(&ttf).contains_key(b"gasp"));
+}
+
+#[test_casing(2, FONTS)]
+fn listing_dropped_tables(test_font: TestFont) {
+    let font = Font::new(test_font.bytes).unwrap();
+    let original_tables = read_tables(test_font.bytes);
+    let table_tags: Vec<_> = original_tables.keys().map(|&tag| TableTag(tag)).collect();
+    assert_eq!(font.table_tags(), table_tags);
+
+    let subset = font.subset_for_text("Hello").unwrap();
+    let output_tables = subset.output_tables();
+    let ttf = subset.to_opentype();
+    let written_tags: Vec<_> = read_tables(&ttf).keys().map(|&tag| TableTag(tag)).collect();
+    assert_eq!(output_tables, written_tags);
+
+    let dropped_tables = subset.dropped_tables();
+    for tag in [b"GDEF", b"GPOS", b"GSUB"] {
+        assert!(
+            dropped_tables.contains(&TableTag(*tag)),
+            "{dropped_tables:?}"
+        );
+    }
+    let is_variable = test_font.name == SANS_FONT.name;
+    assert_eq!(dropped_tables.contains(&TableTag(*b"fvar")), is_variable);
+    for tag in &dropped_tables {
+        assert!(original_tables.contains_key(&tag.0));
+        assert!(!output_tables.contains(tag));
+    }
+    assert!(dropped_tables.is_sorted());
+    assert_eq!(
+        dropped_tables.len() + output_tables.len(),
+        original_tables.len()
+    );
+
+    let options = SubsetOptions::default().retain_glyph_classes(true);
+    let chars = "Hello".chars().collect();
+    let subset = font.subset_with_options(&chars, options).unwrap();
+    assert!(!subset.dropped_tables().contains(&TableTag(*b"GDEF")));
+}
+
+#[test]
+fn dropping_name_table() {
+    let font = Font::new(MONO_FONT.bytes).unwrap();
+    let chars: BTreeSet<char> = (' '..='~').collect();
+    let options = SubsetOptions::default().drop_name(true);
+    let subset = font.subset_with_options(&chars, options).unwrap();
+
+    let ttf = subset.to_opentype();
+    let tables = read_tables(&ttf);
+    assert!(!tables.contains_key(b"name"));
+    assert!(tables.contains_key(b"OS/2"));
+    // The subset can be parsed and subsetted further.
+    let subset_font = Font::new(&ttf).unwrap();
+    assert!(subset_font.name_table().unwrap().is_none());
+    let nested_subset = subset_font.subset_for_text("abc").unwrap().to_opentype();
+    assert!(!read_tables(&nested_subset).contains_key(b"name"));
+    assert_valid_font(&nested_subset, true, "abc".chars());
+
+    let woff2 = subset.to_woff2();
+    let font_file = ReadScope::new(&woff2).read::<FontData>().unwrap();
+    let font_provider = font_file.table_provider(0).unwrap();
+    assert!(!font_provider.has_table(tag::NAME));
+    for (tag, table) in &tables {
+        let mut woff2_table = font_provider
+            .read_table_data(u32::from_be_bytes(*tag))
+            .unwrap()
+            .into_owned();
+        if tag == b"head" {
+            // The checksum adjustment differs because of different file layouts.
+            woff2_table[8..12].copy_from_slice(&table[8..12]);
+        }
+        assert_eq!(woff2_table, *table);
+    }
+}
+
+#[test]
+fn new_to_old_glyph_mapping() {
+    let font = Font::new(MONO_FONT.bytes).unwrap();
+    let subset = font.subset_for_text("cab").unwrap();
+    assert_eq!(subset.new_to_old_glyphs(), [0, 136, 148, 149]);
+
+    let subset = font.subset_all().unwrap();
+    let glyphs = subset.new_to_old_glyphs();
+    assert_eq!(glyphs.len(), usize::from(font.glyph_count));
+    assert!(glyphs
+        .iter()
+        .enumerate()
+        .all(|(i, &idx)| usize::from(idx) == i));
+}
+
+#[test]
+fn dropping_unused_cvt_table() {
+    let font = Font::new(MONO_FONT.bytes).unwrap();
+    let a_glyph = font.glyph(font.map_char('A').unwrap()).unwrap().inner;
+    assert!(a_glyph.has_instructions());
+
+    // `prep` is present, so `cvt ` is retained regardless of the retained glyphs.
+    let subset = font.subset_for_text(" ").unwrap().to_opentype();
+    let tables = read_tables(&subset);
+    assert!(tables.contains_key(b"cvt "));
+    assert_valid_font(&subset, true, " ".chars());
+
+    let glyph_count = usize::from(font.glyph_count);
+    let font_bytes = patch_font(MONO_FONT.bytes, |tables| {
+        tables.remove(b"prep");
+        // Make the notdef glyph empty (and thus unhinted) by making its `loca` range empty.
+        let loca = tables.get_mut(b"loca").unwrap();
+        let entry_len = loca.len() / (glyph_count + 1);
+        loca.copy_within(entry_len..2 * entry_len, 0);
+    });
+    let font = Font::new(&font_bytes).unwrap();
+    assert!(!font.glyph(0).unwrap().inner.has_instructions());
+    let subset = font.subset_for_text(" ").unwrap().to_opentype();
+    let tables = read_tables(&subset);
+    assert!(!tables.contains_key(b"cvt "));
+    assert!(tables.contains_key(b"fpgm"));
+    assert_valid_font(&subset, true, " ".chars());
+
+    let subset = font.subset_for_text(" A").unwrap().to_opentype();
+    let tables = read_tables(&subset);
+    assert!(tables.contains_key(b"cvt "));
+    assert_valid_font(&subset, true, " A".chars());
+}
+
+#[test_casing(2, FONTS)]
+fn sharded_subsetting(test_font: TestFont) {
+    let font = Font::new(test_font.bytes).unwrap();
+    assert!(font.subset_sharded(&[]).unwrap().is_empty());
+
+    let buckets: Vec<BTreeSet<char>> = vec![
+        (' '..='~').collect(),
+        ('\u{a0}'..='\u{17f}').collect(),
+        ('\u{400}'..='\u{4ff}').collect(),
+        BTreeSet::new(),
+    ];
+    let shards = font.subset_sharded(&buckets).unwrap();
+    assert_eq!(shards.len(), buckets.len());
+    for (shard, chars) in shards.iter().zip(&buckets) {
+        let ttf = shard.to_opentype();
+        assert_valid_font(
+            &ttf,
+            true,
+            font.coverage_bitset().intersect(chars).into_iter(),
+        );
+        // Shards must be identical to subsets created separately.
+        let expected_ttf = font.subset(chars).unwrap().to_opentype();
+        assert_eq!(ttf, expected_ttf);
+    }
+}
+
+#[test]
+fn sharded_subsetting_with_options() {
+    let font = Font::new(MONO_FONT.bytes).unwrap();
+    let options = SubsetOptions::default()
+        .retain_script_glyphs(Script::Arabic)
+        .compact_cmap(true);
+    let buckets = [
+        BTreeSet::from(['a', 'b', 'c']),
+        BTreeSet::from(['x', '\u{628}']),
+    ];
+    let shards = font
+        .subset_sharded_with_options(&buckets, &options)
+        .unwrap();
+    assert!(!shards[0].retains_all_glyphs);
+    assert!(shards[1].retains_all_glyphs);
+    for (shard, chars) in shards.iter().zip(&buckets) {
+        let expected_subset = font.subset_with_options(chars, options.clone()).unwrap();
+        assert_eq!(shard.to_opentype(), expected_subset.to_opentype());
+    }
+
+    let options = SubsetOptions::default().fallback_glyph(font.glyph_count);
+    let err = font
+        .subset_sharded_with_options(&buckets, &options)
+        .unwrap_err();
+    assert!(
+        matches!(err, SubsetError::InvalidFallbackGlyph(idx) if idx == font.glyph_count),
+        "{err:?}"
+    );
+}
+
+#[test_casing(2, FONTS)]
+fn extending_subset_with_chars(test_font: TestFont) {
+    let font = Font::new(test_font.bytes).unwrap();
+    let base_chars: BTreeSet<char> = "world".chars().collect();
+    let base = font.subset(&base_chars).unwrap();
+    let mut extended = font.subset(&base_chars).unwrap();
+
+    let new_chars: BTreeSet<char> = "Hello, world!".chars().collect();
+    extended.add_chars(&new_chars).unwrap();
+    let base_glyphs = base.new_to_old_glyphs();
+    let extended_glyphs = extended.new_to_old_glyphs();
+    assert!(extended_glyphs.len() > base_glyphs.len());
+    assert_eq!(extended_glyphs[..base_glyphs.len()], base_glyphs);
+
+    // Chars must be ordered even though some of the new chars precede the existing ones.
+    let all_chars: BTreeSet<char> = base_chars.union(&new_chars).copied().collect();
+    let fresh = font.subset(&all_chars).unwrap();
+    assert_eq!(
+        extended.retained_chars().collect::<Vec<_>>(),
+        fresh.retained_chars().collect::<Vec<_>>()
+    );
+    assert_eq!(
+        extended.retained_glyph_count(),
+        fresh.retained_glyph_count()
+    );
+    assert_eq!(extended.bounding_box(), fresh.bounding_box());
+
+    let ttf = extended.to_opentype();
+    assert_eq!(extended.estimated_opentype_size(), ttf.len());
+    assert_valid_font(&ttf, true, all_chars.iter().copied());
+    let subset_font = Font::new(&ttf).unwrap();
+    for ch in all_chars {
+        let glyph_idx = subset_font.map_char(ch).unwrap();
+        let old_idx = extended_glyphs[usize::from(glyph_idx)];
+        assert_eq!(old_idx, font.map_char(ch).unwrap(), "{ch:?}");
+    }
+
+    // Adding already retained chars is a no-op.
+    extended.add_chars(&base_chars).unwrap();
+    assert_eq!(extended.new_to_old_glyphs(), extended_glyphs);
+    assert_eq!(extended.to_opentype(), ttf);
+}
+
+#[test_casing(2, FONTS)]
+fn empty_subset(test_font: TestFont) {
+    let font = Font::new(test_font.bytes).unwrap();
+    let subset = font.empty_subset().unwrap();
+    assert_eq!(subset.retained_glyph_count(), 1);
+    assert_eq!(subset.retained_char_count(), 0);
+    assert_eq!(subset.new_to_old_glyphs(), [0]);
+    let head = font.head.as_ref();
+    let original_bbox = head[36..44]
+        .chunks_exact(2)
+        .map(|bytes| i16::from_be_bytes(bytes.try_into().unwrap()));
+    assert!(subset.bounding_box().into_iter().eq(original_bbox));
+
+    let ttf = subset.to_opentype();
+    assert_eq!(ttf, font.subset(&BTreeSet::new()).unwrap().to_opentype());
+    assert_valid_font(&ttf, true, iter::empty());
+    let subset_font = Font::new(&ttf).unwrap();
+    assert_eq!(subset_font.glyph_count(), 1);
+    for ch in [' ', 'A', '\u{fffd}', '\u{10ffff}'] {
+        assert_eq!(subset_font.map_char(ch).unwrap(), 0);
+    }
+    let (loca, _) = subset_font.truetype_outlines().unwrap();
+    let notdef_range = loca.glyph_range(0).unwrap();
+    assert!(!notdef_range.is_empty());
+    assert_eq!(subset.glyf_table_bytes().unwrap().len(), notdef_range.end);
+    subset.validate_against(&font).unwrap();
+}
+
+#[test_casing(2, FONTS)]
+fn subsetting_by_glyph_ids(test_font: TestFont) {
+    let font = Font::new(test_font.bytes).unwrap();
+    let chars: BTreeSet<char> = "Hello, Åland!".chars().collect();
+    let glyph_ids: BTreeSet<u16> = chars.iter().map(|&ch| font.map_char(ch).unwrap()).collect();
+    let subset = font.subset_glyphs(&glyph_ids).unwrap();
+    assert_eq!(subset.retained_char_count(), 0);
+    let new_to_old = subset.new_to_old_glyphs();
+    assert_eq!(new_to_old[0], 0);
+    assert!(glyph_ids.iter().all(|idx| new_to_old.contains(idx)));
+
+    // The retained glyphs must be the same as for the char-based subset, including components.
+    let char_subset = font.subset(&chars).unwrap();
+    let expected_glyphs: BTreeSet<_> = char_subset.new_to_old_glyphs().into_iter().collect();
+    let glyphs: BTreeSet<_> = new_to_old.iter().copied().collect();
+    assert_eq!(glyphs, expected_glyphs);
+    assert_eq!(new_to_old.len(), glyphs.len());
+
+    let ttf = subset.to_opentype();
+    assert_valid_font(&ttf, true, iter::empty());
+    let subset_font = Font::new(&ttf).unwrap();
+    assert_eq!(usize::from(subset_font.glyph_count), new_to_old.len());
+    assert_eq!(subset_font.map_char('H').unwrap(), 0);
+    for (new_idx, &old_idx) in (0..).zip(&new_to_old) {
+        let glyph = subset.glyph(new_idx).unwrap();
+        assert_eq!(glyph.advance(), font.glyph(old_idx).unwrap().advance);
+    }
+
+    let err = font
+        .subset_glyphs(&BTreeSet::from([1, font.glyph_count]))
+        .unwrap_err();
+    assert!(
+        matches!(err, SubsetError::InvalidGlyph(idx) if idx == font.glyph_count),
+        "{err:?}"
+    );
+}
+
+#[test_casing(2, FONTS)]
+fn validating_subset_against_original_font(test_font: TestFont) {
+    let font = Font::new(test_font.bytes).unwrap();
+    // `Å` is a composite glyph, so its component IDs are rewritten.
+    let subset = font.subset_for_text("Hello, Åland!").unwrap();
+    subset.validate_against(&font).unwrap();
+    font.subset_all().unwrap().validate_against(&font).unwrap();
+
+    let other_font = if test_font.name == MONO_FONT.name {
+        SANS_FONT
+    } else {
+        MONO_FONT
+    };
+    let other_font = Font::new(other_font.bytes).unwrap();
+    let err = subset.validate_against(&other_font).unwrap_err();
+    assert!(matches!(err, SubsetError::GlyphMismatch(_)), "{err:?}");
+}
+
+#[test_casing(2, FONTS)]
+fn recomputing_head_bounding_box(test_font: TestFont) {
+    let read_bbox = |head: &[u8]| -> [i16; 4] {
+        let mut bbox = [0; 4];
+        for (coord, bytes) in bbox.iter_mut().zip(head[36..44].chunks_exact(2)) {
+            *coord = i16::from_be_bytes(bytes.try_into().unwrap());
+        }
+        bbox
+    };
+
+    let font = Font::new(test_font.bytes).unwrap();
+    let original_bbox = read_bbox(font.head.as_ref());
+    let chars: BTreeSet<char> = "-.:".chars().collect();
+    // By default, the bounding box is copied from the original font.
+    let subset = font.subset(&chars).unwrap();
+    assert_eq!(subset.bounding_box(), original_bbox);
+    assert_eq!(
+        read_bbox(&read_tables(&subset.to_opentype())[b"head"]),
+        original_bbox
+    );
+
+    let options = SubsetOptions::default().recompute_bounding_box(true);
+    let subset = font.subset_with_options(&chars, options).unwrap();
+    let bbox = subset.bounding_box();
+    assert!(
+        bbox[0] >= original_bbox[0] && bbox[1] >= original_bbox[1],
+        "{bbox:?}"
+    );
+    assert!(
+        bbox[2] <= original_bbox[2] && bbox[3] <= original_bbox[3],
+        "{bbox:?}"
+    );
+    // Punctuation doesn't reach the font ascent or descent.
+    assert_ne!(bbox, original_bbox);
+
+    let glyph_bboxes = subset
+        .new_to_old_glyphs()
+        .into_iter()
+        .filter_map(|old_idx| font.glyph_bbox(old_idx).unwrap());
+    for glyph_bbox in glyph_bboxes {
+        assert!(bbox[0] <= glyph_bbox[0] && bbox[1] <= glyph_bbox[1]);
+        assert!(bbox[2] >= glyph_bbox[2] && bbox[3] >= glyph_bbox[3]);
+    }
+
+    let ttf = subset.to_opentype();
+    assert_eq!(read_bbox(&read_tables(&ttf)[b"head"]), bbox);
+    let subset_font = Font::new(&ttf).unwrap();
+    assert_eq!(subset_font.subset_all().unwrap().bounding_box(), bbox);
+}
+
+#[test]
+fn inspecting_subset_glyphs() {
+    let font = Font::new(MONO_FONT.bytes).unwrap();
+    let subset = font.subset_for_text(" Á").unwrap();
+    assert_eq!(subset.glyph(1).unwrap().kind(), &GlyphKind::Empty);
+    assert_eq!(subset.glyph(1).unwrap().advance(), 600);
+
+    let new_to_old = subset.new_to_old_glyphs();
+    let accented_idx = font.map_char('Á').unwrap();
+    let accented_idx = new_to_old.iter().position(|&idx| idx == accented_idx);
+    // Components are pushed before the composite glyph itself.
+    assert_eq!(accented_idx, Some(4));
+    let accented = subset.glyph(4).unwrap();
+    let GlyphKind::Composite { components } = accented.kind() else {
+        panic!("unexpected glyph: {accented:?}");
+    };
+    assert_eq!(components.len(), 2);
+    for &component in components {
+        let component_info = subset.glyph(component).unwrap();
+        let GlyphKind::Simple { contour_count } = *component_info.kind() else {
+            panic!("unexpected component: {component_info:?}");
+        };
+        let old_idx = new_to_old[usize::from(component)];
+        let expected_count = font.glyph_contour_count(old_idx).unwrap();
+        assert_eq!(i16::try_from(contour_count).unwrap(), expected_count);
+    }
+    assert!(subset
+        .glyph(u16::try_from(new_to_old.len()).unwrap())
+        .is_none());
+}
+
+#[test]
+fn blanking_glyphs() {
+    let font = Font::new(MONO_FONT.bytes).unwrap();
+    let a_idx = font.map_char('A').unwrap();
+    let options = SubsetOptions::default().blank_glyphs(&BTreeSet::from([a_idx]));
+    let chars = BTreeSet::from(['A', 'B', 'Á']);
+    let subset = font.subset_with_options(&chars, options).unwrap();
+
+    let new_to_old = subset.new_to_old_glyphs();
+    let new_a_idx = new_to_old.iter().position(|&idx| idx == a_idx).unwrap();
+    let new_a_idx = u16::try_from(new_a_idx).unwrap();
+    let a_glyph = subset.glyph(new_a_idx).unwrap();
+    assert_eq!(a_glyph.kind(), &GlyphKind::Empty);
+    let original = font.subset_for_text("A").unwrap();
+    assert_eq!(a_glyph.advance(), original.glyph(1).unwrap().advance());
+
+    // The composite glyph still references the blanked glyph.
+    let accented_idx = font.map_char('Á').unwrap();
+    let accented_idx = new_to_old.iter().position(|&idx| idx == accented_idx);
+    let accented = subset.glyph(u16::try_from(accented_idx.unwrap()).unwrap());
+    let GlyphKind::Composite { components } = accented.unwrap().kind().clone() else {
+        panic!("unexpected accented glyph");
+    };
+    assert!(components.contains(&new_a_idx));
+
+    let subset = subset.to_opentype();
+    assert_valid_font(&subset, true, chars.iter().copied());
+}
+
+#[test_casing(3, [0, 15, 16_385])]
+fn out_of_range_units_per_em_is_rejected(units_per_em: u16) {
+    let font_bytes = patch_font(MONO_FONT.bytes, |tables| {
+        tables.get_mut(b"head").unwrap()[18..20].copy_from_slice(&units_per_em.to_be_bytes());
+    });
+    let err = Font::new(&font_bytes).unwrap_err();
+    assert_eq!(err.table(), Some(TableTag::HEAD));
+    assert!(
+        matches!(err.kind(), ParseErrorKind::InvalidUnitsPerEm(val) if *val == units_per_em),
+        "{err:?}"
+    );
+}
+
+#[test_casing(3, [16, 1_234, 16_384])]
+fn non_power_of_two_units_per_em_is_supported(units_per_em: u16) {
+    let font_bytes = patch_font(MONO_FONT.bytes, |tables| {
+        tables.get_mut(b"head").unwrap()[18..20].copy_from_slice(&units_per_em.to_be_bytes());
+    });
+    let font = Font::new(&font_bytes).unwrap();
+    assert_eq!(font.units_per_em(), units_per_em);
+}
+
+/// Reads `segCountX2 / 2` from the first `cmap` subtable, which must have format 4.
+fn format4_segment_count(cmap: &[u8]) -> u16 {
+    let offset = u32::from_be_bytes(cmap[8..12].try_into().unwrap()) as usize;
+    let subtable = &cmap[offset..];
+    assert_eq!(subtable[..2], [0, 4]);
+    u16::from_be_bytes([subtable[6], subtable[7]]) / 2
+}
+
+#[test_casing(2, FONTS)]
+fn compacting_cmap_segments(test_font: TestFont) {
+    let font = Font::new(test_font.bytes).unwrap();
+    // Accented Latin chars are often composite glyphs, so their components are interleaved
+    // with them in the subset, breaking runs of sequential glyph IDs.
+    let chars: BTreeSet<char> = (' '..='~').chain('\u{c0}'..='\u{17f}').collect();
+    let subset = font.subset(&chars).unwrap();
+    let ttf = subset.to_opentype();
+    let options = SubsetOptions::default().compact_cmap(true);
+    let compact_subset = font.subset_with_options(&chars, options).unwrap();
+    let compact_ttf = compact_subset.to_opentype();
+    assert_eq!(compact_subset.estimated_opentype_size(), compact_ttf.len());
+    assert_valid_font(
+        &compact_ttf,
+        true,
+        font.coverage_bitset().intersect(&chars).into_iter(),
+    );
+    compact_subset.validate_against(&font).unwrap();
+
+    let cmap = &read_tables(&ttf)[b"cmap"];
+    let compact_cmap = &read_tables(&compact_ttf)[b"cmap"];
+    let segment_count = format4_segment_count(cmap);
+    let compact_segment_count = format4_segment_count(compact_cmap);
+    assert!(compact_segment_count < segment_count);
+    assert!(compact_cmap.len() < cmap.len());
+
+    let subset_font = Font::new(&ttf).unwrap();
+    let compact_font = Font::new(&compact_ttf).unwrap();
+    assert_eq!(
+        compact_font.coverage_bitset(),
+        subset_font.coverage_bitset()
+    );
+    for ch in chars.iter().copied().chain(['\u{180}', '\u{ffff}']) {
+        assert_eq!(
+            compact_font.map_char(ch).unwrap(),
+            subset_font.map_char(ch).unwrap()
+        );
+    }
+
+    // Sequential glyph IDs should not be compacted.
+    let chars: BTreeSet<char> = ('0'..='9').collect();
+    let options = SubsetOptions::default().compact_cmap(true);
+    let compact_ttf = font
+        .subset_with_options(&chars, options)
+        .unwrap()
+        .to_opentype();
+    assert_eq!(compact_ttf, font.subset(&chars).unwrap().to_opentype());
+}
+
+/// Leaves a single encoding record in the `cmap` table, pointing to the subtable with the specified offset.
+fn retain_single_cmap_subtable(cmap: &mut [u8], platform_id: u16, encoding_id: u16, offset: u32) {
+    cmap[2..4].copy_from_slice(&1_u16.to_be_bytes());
+    cmap[4..6].copy_from_slice(&platform_id.to_be_bytes());
+    cmap[6..8].copy_from_slice(&encoding_id.to_be_bytes());
+    cmap[8..12].copy_from_slice(&offset.to_be_bytes());
+}
+
+#[test]
+fn cmap_subtable_offset_pointing_into_directory_is_rejected() {
+    let mut cmap = format4_cmap_table(&[(u16::from(b'A'), 5)]);
+    // Point the only subtable into the encoding records.
+    retain_single_cmap_subtable(&mut cmap, 3, 1, 4);
+
+    let font_bytes = patch_font(MONO_FONT.bytes, |tables| {
+        tables.insert(*b"cmap", cmap);
+    });
+    let err = Font::new(&font_bytes).unwrap_err();
+    assert!(
+        matches!(err.kind, ParseErrorKind::OffsetOutOfBounds(4)),
+        "{err:?}"
+    );
+    assert_eq!(err.table, Some(TableTag::CMAP));
+    // The error should point to the subtable offset in the encoding record.
+    let cmap_record = font_bytes[12..]
+        .chunks_exact(16)
+        .find(|record| record[..4] == *b"cmap")
+        .unwrap();
+    let cmap_offset = u32::from_be_bytes(cmap_record[8..12].try_into().unwrap());
+    assert_eq!(err.offset, cmap_offset as usize + 8);
+}
+
+#[test]
+fn offsets_of_unused_cmap_subtables_are_not_validated() {
+    let format4_cmap = format4_cmap_table(&[(u16::from(b'A'), 5)]);
+    let mut words: Vec<u16> = vec![0, 3]; // version, numTables
+    words.extend_from_slice(&[0, 3, 0, 28]); // used format 4 subtable
+    words.extend_from_slice(&[3, 0, 0, 0]); // unsupported encoding
+    words.extend_from_slice(&[3, 1, 0, 0]); // superseded by the first subtable
+    let mut cmap: Vec<u8> = words.iter().flat_map(|word| word.to_be_bytes()).collect();
+    cmap.extend_from_slice(&format4_cmap[12..]);
+
+    let font_bytes = patch_font(MONO_FONT.bytes, |tables| {
+        tables.insert(*b"cmap", cmap);
+    });
+    let font = Font::new(&font_bytes).unwrap();
+    assert_eq!(font.map_char('A').unwrap(), 5);
+    assert_eq!(font.map_char('B').unwrap(), 0);
+}
+
+#[test]
+fn format4_and_format12_cmap_subtables_are_consistent() {
+    let font = Font::new(MONO_FONT.bytes).unwrap();
+    let CmapTable::Coverage(_) = &font.cmap else {
+        panic!("format 12 subtable is not preferred: {:?}", font.cmap);
+    };
+
+    let original_cmap = &read_tables(MONO_FONT.bytes)[b"cmap"];
+    let subtable_offset = |format: u16| {
+        let record_count = usize::from(u16::from_be_bytes([original_cmap[2], original_cmap[3]]));
+        original_cmap[4..4 + 8 * record_count]
+            .chunks_exact(8)
+            .map(|record| u32::from_be_bytes(record[4..].try_into().unwrap()))
+            .find(|&offset| {
+                let offset = offset as usize;
+                original_cmap[offset..offset + 2] == format.to_be_bytes()
+            })
+            .unwrap()
+    };
+
+    let format4_font = patch_font(MONO_FONT.bytes, |tables| {
+        let cmap = tables.get_mut(b"cmap").unwrap();
+        retain_single_cmap_subtable(cmap, 3, 1, subtable_offset(4));
+    });
+    let format4_font = Font::new(&format4_font).unwrap();
+    assert!(matches!(format4_font.cmap, CmapTable::Deltas(_)));
+    let format12_font = patch_font(MONO_FONT.bytes, |tables| {
+        let cmap = tables.get_mut(b"cmap").unwrap();
+        retain_single_cmap_subtable(cmap, 3, 10, subtable_offset(12));
+    });
+    let format12_font = Font::new(&format12_font).unwrap();
+    assert!(matches!(format12_font.cmap, CmapTable::Coverage(_)));
+
+    for ch in '\0'..='\u{ffff}' {
+        let expected_glyph = format4_font.map_char(ch).unwrap();
+        assert_eq!(
+            format12_font.map_char(ch).unwrap(),
+            expected_glyph,
+            "{ch:?}"
+        );
+        assert_eq!(font.map_char(ch).unwrap(), expected_glyph, "{ch:?}");
+    }
+}
+
+#[test]
+fn format12_cmap_subtable_wins_on_disagreement() {
+    let format4_subtable = &format4_cmap_table(&[(u16::from(b'A'), 5)])[12..];
+    // Format 12 subtable with a single group mapping 'A' to glyph #6
+    let format12_subtable: Vec<u8> = [12_u16, 0]
+        .iter()
+        .flat_map(|word| word.to_be_bytes())
+        .chain(
+            [28_u32, 0, 1, 0x41, 0x41, 6]
+                .iter()
+                .flat_map(|dword| dword.to_be_bytes()),
+        )
+        .collect();
+
+    let format4_offset = 4 + 2 * 8;
+    let format12_offset = format4_offset + format4_subtable.len();
+    let mut cmap = vec![0, 0, 0, 2];
+    for (encoding_id, offset) in [(1_u16, format4_offset), (10, format12_offset)] {
+        cmap.extend_from_slice(&3_u16.to_be_bytes());
+        cmap.extend_from_slice(&encoding_id.to_be_bytes());
+        cmap.extend_from_slice(&u32::try_from(offset).unwrap().to_be_bytes());
+    }
+    cmap.extend_from_slice(format4_subtable);
+    cmap.extend_from_slice(&format12_subtable);
+
+    let font_bytes = patch_font(MONO_FONT.bytes, |tables| {
+        tables.insert(*b"cmap", cmap);
+    });
+    let font = Font::new(&font_bytes).unwrap();
+    assert_eq!(font.map_char('A').unwrap(), 6);
+    assert_eq!(font.map_char('B').unwrap(), 0);
+}
+
+#[test]
+fn format12_glyph_id_overflow_is_detected() {
+    // Format 12 subtable with a single group mapping 'A'..='Z' to glyphs starting from #65,520
+    let format12_subtable: Vec<u8> = [12_u16, 0]
+        .iter()
+        .flat_map(|word| word.to_be_bytes())
+        .chain(
+            [28_u32, 0, 1, 0x41, 0x5a, 0xfff0]
+                .iter()
+                .flat_map(|dword| dword.to_be_bytes()),
+        )
+        .collect();
+    let mut cmap = vec![0, 0, 0, 1, 0, 3, 0, 10, 0, 0, 0, 12];
+    cmap.extend_from_slice(&format12_subtable);
+
+    let font_bytes = patch_font(MONO_FONT.bytes, |tables| {
+        tables.insert(*b"cmap", cmap);
+    });
+    let font = Font::new(&font_bytes).unwrap();
+    assert_eq!(font.map_char('P').unwrap(), u16::MAX);
+    let err = font.map_char('Q').unwrap_err();
+    assert!(
+        matches!(
+            &err.kind,
+            ParseErrorKind::RangeOutOfBounds { range, len: 65_536 } if *range == (0xfff0..0x1_000a)
+        ),
+        "{err:?}"
+    );
+}
+
+/// Variation selector together with `(start, additionalCount)` default UVS ranges
+/// and `(base char, glyph ID)` non-default UVS mappings.
+type VariationSelectorRecord<'a> = (u32, &'a [(u32, u8)], &'a [(u32, u16)]);
+
+fn format14_cmap_subtable(records: &[VariationSelectorRecord<'_>]) -> Vec<u8> {
+    let u24 = |value: u32| value.to_be_bytes()[1..].to_vec();
+    let record_count = u32::try_from(records.len()).unwrap();
+    let mut header = vec![];
+    let mut tables = vec![];
+    let tables_offset = 10 + 11 * record_count;
+    for &(selector, default_ranges, glyph_mappings) in records {
+        header.extend_from_slice(&u24(selector));
+        let mut default_offset = 0;
+        if !default_ranges.is_empty() {
+            default_offset = tables_offset + u32::try_from(tables.len()).unwrap();
+            tables.extend_from_slice(&u32::try_from(default_ranges.len()).unwrap().to_be_bytes());
+            for &(start, additional_count) in default_ranges {
+                tables.extend_from_slice(&u24(start));
+                tables.push(additional_count);
+            }
+        }
+        header.extend_from_slice(&default_offset.to_be_bytes());
+        let mut glyph_offset = 0;
+        if !glyph_mappings.is_empty() {
+            glyph_offset = tables_offset + u32::try_from(tables.len()).unwrap();
+            tables.extend_from_slice(&u32::try_from(glyph_mappings.len()).unwrap().to_be_bytes());
+            for &(base, glyph_idx) in glyph_mappings {
+                tables.extend_from_slice(&u24(base));
+                tables.extend_from_slice(&glyph_idx.to_be_bytes());
+            }
+        }
+        header.extend_from_slice(&glyph_offset.to_be_bytes());
+    }
+
+    let len = tables_offset + u32::try_from(tables.len()).unwrap();
+    let mut subtable = vec![0, 14];
+    subtable.extend_from_slice(&len.to_be_bytes());
+    subtable.extend_from_slice(&record_count.to_be_bytes());
+    subtable.extend_from_slice(&header);
+    subtable.extend_from_slice(&tables);
+    subtable
+}
+
+const TEXT_SELECTOR: char = '\u{fe0e}';
+const EMOJI_SELECTOR: char = '\u{fe0f}';
+
+/// Patches `MONO_FONT` to contain a format 14 `cmap` subtable with text and emoji variation sequences.
+fn font_with_variation_sequences() -> Vec<u8> {
+    let font = Font::new(MONO_FONT.bytes).unwrap();
+    let glyph = |ch| font.map_char(ch).unwrap();
+    let format12_subtable = {
+        let original_cmap = &read_tables(MONO_FONT.bytes)[b"cmap"];
+        let record_count = usize::from(u16::from_be_bytes([original_cmap[2], original_cmap[3]]));
+        let offset = original_cmap[4..4 + 8 * record_count]
+            .chunks_exact(8)
+            .find(|record| record[..4] == [0, 3, 0, 10])
+            .map(|record| u32::from_be_bytes(record[4..].try_into().unwrap()) as usize)
+            .unwrap();
+        let len = u32::from_be_bytes(original_cmap[offset + 4..offset + 8].try_into().unwrap());
+        original_cmap[offset..offset + len as usize].to_vec()
+    };
+    let format14_subtable = format14_cmap_subtable(&[
+        (TEXT_SELECTOR.into(), &[(u32::from('A'), 2)], &[]),
+        (
+            EMOJI_SELECTOR.into(),
+            &[],
+            &[
+                (u32::from('A'), glyph('Ж')),
+                (u32::from('B'), glyph('B')),
+                (u32::from('x'), glyph('y')),
+                (0x0378, glyph('z')), // unassigned code point not covered by the font
+            ],
+        ),
+    ]);
+    let format14_offset = 20 + u32::try_from(format12_subtable.len()).unwrap();
+    let mut cmap = vec![0, 0, 0, 2, 0, 0, 0, 4, 0, 0, 0, 20, 0, 0, 0, 5];
+    cmap.extend_from_slice(&format14_offset.to_be_bytes());
+    cmap.extend_from_slice(&format12_subtable);
+    cmap.extend_from_slice(&format14_subtable);
+    patch_font(MONO_FONT.bytes, |tables| {
+        tables.insert(*b"cmap", cmap);
+    })
+}
+
+#[test]
+fn format14_cmap_subtable_is_retained() {
+    let font = Font::new(MONO_FONT.bytes).unwrap();
+    let glyph = |ch| font.map_char(ch).unwrap();
+    let font_bytes = font_with_variation_sequences();
+    let font = Font::new(&font_bytes).unwrap();
+
+    assert_eq!(
+        font.map_variation('A', TEXT_SELECTOR).unwrap(),
+        Some(glyph('A'))
+    );
+    assert_eq!(
+        font.map_variation('C', TEXT_SELECTOR).unwrap(),
+        Some(glyph('C'))
+    );
+    assert_eq!(font.map_variation('D', TEXT_SELECTOR).unwrap(), None);
+    assert_eq!(
+        font.map_variation('A', EMOJI_SELECTOR).unwrap(),
+        Some(glyph('Ж'))
+    );
+    assert_eq!(
+        font.map_variation('x', EMOJI_SELECTOR).unwrap(),
+        Some(glyph('y'))
+    );
+    assert_eq!(font.map_variation('y', EMOJI_SELECTOR).unwrap(), None);
+    assert_eq!(font.map_variation('A', '\u{fe00}').unwrap(), None);
+
+    let subset = font.subset_for_text("AB\u{378}").unwrap();
+    let ttf = subset.to_opentype();
+    assert_valid_font(&ttf, true, "AB".chars());
+    let new_to_old = subset.new_to_old_glyphs();
+    // The variant glyph must be retained; glyphs for non-retained base chars must not.
+    assert!(new_to_old.contains(&glyph('Ж')));
+    assert!(!new_to_old.contains(&glyph('y')));
+    assert!(!new_to_old.contains(&glyph('z')));
+
+    let subset_font = Font::new(&ttf).unwrap();
+    let new_glyph = |ch| subset_font.map_char(ch).unwrap();
+    let variant_glyph = subset_font
+        .map_variation('A', EMOJI_SELECTOR)
+        .unwrap()
+        .unwrap();
+    assert_eq!(new_to_old[usize::from(variant_glyph)], glyph('Ж'));
+    assert_eq!(
+        subset_font.map_variation('A', TEXT_SELECTOR).unwrap(),
+        Some(new_glyph('A'))
+    );
+    assert_eq!(
+        subset_font.map_variation('B', TEXT_SELECTOR).unwrap(),
+        Some(new_glyph('B'))
+    );
+    assert_eq!(subset_font.map_variation('C', TEXT_SELECTOR).unwrap(), None);
+    assert_eq!(
+        subset_font.map_variation('B', EMOJI_SELECTOR).unwrap(),
+        Some(new_glyph('B'))
+    );
+    assert_eq!(
+        subset_font.map_variation('x', EMOJI_SELECTOR).unwrap(),
+        None
+    );
+
+    // The redundant mapping for `B` must be converted to a default one.
+    let expected_subtable = format14_cmap_subtable(&[
+        (TEXT_SELECTOR.into(), &[(u32::from('A'), 1)], &[]),
+        (
+            EMOJI_SELECTOR.into(),
+            &[(u32::from('B'), 0)],
+            &[(u32::from('A'), variant_glyph)],
+        ),
+    ]);
+    let subset_cmap = &read_tables(&ttf)[b"cmap"];
+    assert!(subset_cmap.ends_with(&expected_subtable));
+
+    // No retained sequences => no format 14 subtable.
+    let subset = font.subset_for_text("012").unwrap();
+    let subset_cmap = &read_tables(&subset.to_opentype())[b"cmap"];
+    assert_eq!(subset_cmap[2..4], [0, 1]);
+}
+
+/// Creates a `cmap` table with a format 0 subtable for the specified `(char code, glyph)` mappings.
+fn format0_cmap_table(platform_id: u16, encoding_id: u16, mappings: &[(u8, u16)]) -> Vec<u8> {
+    let words = [0, 1, platform_id, encoding_id, 0, 12, 0, 262, 0];
+    let mut cmap: Vec<u8> = words.iter().flat_map(|word| word.to_be_bytes()).collect();
+    let mut glyph_ids = [0_u8; 256];
+    for &(code, glyph_idx) in mappings {
+        glyph_ids[usize::from(code)] = u8::try_from(glyph_idx).unwrap();
+    }
+    cmap.extend_from_slice(&glyph_ids);
+    cmap
+}
+
+#[test_casing(2, [(1, 0), (3, 1)])]
+fn format0_cmap_subtable(platform_id: u16, encoding_id: u16) {
+    let is_mac_roman = platform_id == 1;
+    // High char codes differ between Mac Roman and Unicode (i.e., Latin-1).
+    let high_chars = if is_mac_roman {
+        [(0x80, 'Ä'), (0xa5, '•'), (0xdb, '€')]
+    } else {
+        [(0xc4, 'Ä'), (0xb5, 'µ'), (0xe9, 'é')]
+    };
+    let mapped_chars: Vec<(u8, char)> = ('A'..='Z')
+        .map(|ch| (u8::try_from(ch).unwrap(), ch))
+        .chain(high_chars)
+        .collect();
+    // Glyph IDs must fit into a byte, so the mapped glyphs are arbitrary.
+    let mappings: Vec<_> = (1..)
+        .zip(&mapped_chars)
+        .map(|(glyph_idx, &(code, _))| (code, glyph_idx))
+        .collect();
+    let cmap = format0_cmap_table(platform_id, encoding_id, &mappings);
+
+    let font_bytes = patch_font(MONO_FONT.bytes, |tables| {
+        tables.insert(*b"cmap", cmap);
+    });
+    let byte_font = Font::new(&font_bytes).unwrap();
+    assert!(matches!(byte_font.cmap, CmapTable::Byte(_)));
+    assert_eq!(byte_font.cmap.format(), 0);
+    for (glyph_idx, &(_, ch)) in (1..).zip(&mapped_chars) {
+        assert_eq!(byte_font.map_char(ch).unwrap(), glyph_idx, "{ch:?}");
+    }
+    for ch in ['\0', '@', 'a', 'À', '\u{ff}', '\u{ffff}', '\u{1f600}'] {
+        assert_eq!(byte_font.map_char(ch).unwrap(), 0, "{ch:?}");
+    }
+    let expected_coverage: BTreeSet<char> = mapped_chars.iter().map(|&(_, ch)| ch).collect();
+    let coverage = byte_font.coverage_bitset();
+    assert_eq!(coverage.char_count(), expected_coverage.len());
+    assert_eq!(
+        coverage.intersect(&('\0'..='\u{ffff}').collect()),
+        expected_coverage
+    );
+
+    // Subsets are written with a Unicode subtable.
+    let text = format!(
+        "HELLO{}",
+        high_chars.map(|(_, ch)| ch).iter().collect::<String>()
+    );
+    let ttf = byte_font.subset_for_text(&text).unwrap().to_opentype();
+    assert_valid_font(&ttf, true, text.chars());
+    let subset_font = Font::new(&ttf).unwrap();
+    assert!(matches!(subset_font.cmap, CmapTable::Deltas(_)));
+    for ch in text.chars() {
+        assert_ne!(subset_font.map_char(ch).unwrap(), 0, "{ch:?}");
+    }
+}
+
+#[test]
+fn unicode_cmap_subtable_is_preferred_to_mac_roman() {
+    let font = Font::new(MONO_FONT.bytes).unwrap();
+    let glyph_idx = font.map_char('A').unwrap();
+    let mac_roman_cmap = format0_cmap_table(1, 0, &[(b'A', glyph_idx), (b'B', glyph_idx)]);
+    let unicode_cmap = format4_cmap_table(&[(u16::from(b'A'), glyph_idx)]);
+
+    // Combine both subtables into a single table.
+    let words: [u16; 10] = [0, 2, 1, 0, 0, 20, 3, 1, 0, 20 + 262];
+    let mut cmap: Vec<u8> = words.iter().flat_map(|word| word.to_be_bytes()).collect();
+    cmap.extend_from_slice(&mac_roman_cmap[12..]);
+    cmap.extend_from_slice(&unicode_cmap[12..]);
+    let font_bytes = patch_font(MONO_FONT.bytes, |tables| {
+        tables.insert(*b"cmap", cmap);
+    });
+    let font = Font::new(&font_bytes).unwrap();
+    assert!(matches!(font.cmap, CmapTable::Deltas(_)));
+    assert_eq!(font.map_char('A').unwrap(), glyph_idx);
+    assert_eq!(font.map_char('B').unwrap(), 0);
+}
+
+#[test_casing(2, [false, true])]
+fn malformed_mac_roman_cmap_subtable_is_ignored(out_of_bounds: bool) {
+    let font = Font::new(MONO_FONT.bytes).unwrap();
+    let glyph_idx = font.map_char('A').unwrap();
+    let unicode_cmap = format4_cmap_table(&[(u16::from(b'A'), glyph_idx)]);
+    let unicode_subtable = &unicode_cmap[12..];
+
+    let mac_roman_offset = if out_of_bounds {
+        0x_00ff_0000
+    } else {
+        20 + u32::try_from(unicode_subtable.len()).unwrap()
+    };
+    let [offset_hi, offset_lo] = [mac_roman_offset >> 16, mac_roman_offset & 0xffff]
+        .map(|word| u16::try_from(word).unwrap());
+    let words: [u16; 10] = [0, 2, 1, 0, offset_hi, offset_lo, 3, 1, 0, 20];
+    let mut cmap: Vec<u8> = words.iter().flat_map(|word| word.to_be_bytes()).collect();
+    cmap.extend_from_slice(unicode_subtable);
+    if !out_of_bounds {
+        // Truncated format 0 subtable
+        let mac_roman_cmap = format0_cmap_table(1, 0, &[(b'A', 1)]);
+        cmap.extend_from_slice(&mac_roman_cmap[12..40]);
+    }
+    let font_bytes = patch_font(MONO_FONT.bytes, |tables| {
+        tables.insert(*b"cmap", cmap);
+    });
+
+    let font = Font::new(&font_bytes).unwrap();
+    assert!(matches!(font.cmap, CmapTable::Deltas(_)));
+    assert_eq!(font.map_char('A').unwrap(), glyph_idx);
+}
+
+#[test]
+fn getting_variation_glyphs() {
+    let font = Font::new(MONO_FONT.bytes).unwrap();
+    assert_eq!(font.variation_glyphs().unwrap().count(), 0);
+    let glyph = |ch| font.map_char(ch).unwrap();
+    let font_bytes = font_with_variation_sequences();
+    let font = Font::new(&font_bytes).unwrap();
+
+    let mut expected_glyphs = vec![glyph('Ж'), glyph('B'), glyph('y'), glyph('z')];
+    expected_glyphs.sort_unstable();
+    let variation_glyphs: Vec<_> = font.variation_glyphs().unwrap().collect();
+    assert_eq!(variation_glyphs, expected_glyphs);
+
+    let subset = font.subset_for_text("AB").unwrap();
+    let new_to_old = subset.new_to_old_glyphs();
+    let subset_font_bytes = subset.to_opentype();
+    let subset_font = Font::new(&subset_font_bytes).unwrap();
+    // The redundant mapping for `B` is dropped from the subset.
+    let variation_glyphs: Vec<_> = subset_font
+        .variation_glyphs()
+        .unwrap()
+        .map(|glyph_idx| new_to_old[usize::from(glyph_idx)])
+        .collect();
+    assert_eq!(variation_glyphs, [glyph('Ж')]);
+}
+
+#[test]
+fn malformed_format14_cmap_subtable_is_dropped() {
+    let font = Font::new(MONO_FONT.bytes).unwrap();
+    let y_glyph = font.map_char('y').unwrap();
+    let font_bytes = font_with_variation_sequences();
+    let cmap = &read_tables(&font_bytes)[b"cmap"];
+    let format14_offset = u32::from_be_bytes(cmap[16..20].try_into().unwrap()) as usize;
+
+    // Record count pointing past the table end
+    let mut malformed_cmap = cmap.clone();
+    malformed_cmap[format14_offset + 6..format14_offset + 10].copy_from_slice(&[0xff; 4]);
+    let font_bytes = patch_font(&font_bytes, |tables| {
+        tables.insert(*b"cmap", malformed_cmap);
+    });
+    let font = Font::new(&font_bytes).unwrap();
+    font.map_variation('A', EMOJI_SELECTOR).unwrap_err();
+    let subset = font.subset_for_text("ABx").unwrap();
+    assert_eq!(subset.retained_char_count(), 3);
+    let ttf = subset.to_opentype();
+    assert_valid_font(&ttf, true, "ABx".chars());
+    let subset_font = Font::new(&ttf).unwrap();
+    assert_eq!(subset_font.variation_glyphs().unwrap().count(), 0);
+
+    // Mapping to an out-of-range glyph
+    let font_bytes = font_with_variation_sequences();
+    let mut malformed_cmap = cmap.clone();
+    let mapping = [&[0, 0, b'x'][..], &y_glyph.to_be_bytes()].concat();
+    let mapping_pos = malformed_cmap[format14_offset..]
+        .windows(mapping.len())
+        .position(|window| window == mapping)
+        .unwrap();
+    let glyph_pos = format14_offset + mapping_pos + 3;
+    malformed_cmap[glyph_pos..glyph_pos + 2].copy_from_slice(&u16::MAX.to_be_bytes());
+    let font_bytes = patch_font(&font_bytes, |tables| {
+        tables.insert(*b"cmap", malformed_cmap);
+    });
+    let font = Font::new(&font_bytes).unwrap();
+    let subset = font.subset_for_text("ABx").unwrap();
+    let ttf = subset.to_opentype();
+    assert_valid_font(&ttf, true, "ABx".chars());
+    let subset_font = Font::new(&ttf).unwrap();
+    // Only the mapping for `A` is retained; the mapping for `B` is redundant.
+    assert_eq!(subset_font.variation_glyphs().unwrap().count(), 1);
+    assert_eq!(
+        subset_font.map_variation('x', EMOJI_SELECTOR).unwrap(),
+        None
+    );
+}
+
+#[test]
+fn format6_cmap_subtable() {
+    let font = Font::new(MONO_FONT.bytes).unwrap();
+    // Map 'A'..='Z' except for 'Q'.
+    let glyph_ids: Vec<u16> = ('A'..='Z')
+        .map(|ch| {
+            if ch == 'Q' {
+                0
+            } else {
+                font.map_char(ch).unwrap()
+            }
+        })
+        .collect();
+    let entry_count = u16::try_from(glyph_ids.len()).unwrap();
+    let mut words = vec![0, 1, 3, 1, 0, 12]; // header + encoding record
+    words.extend_from_slice(&[6, 10 + 2 * entry_count, 0, u16::from(b'A'), entry_count]);
+    words.extend_from_slice(&glyph_ids);
+    let cmap: Vec<u8> = words.iter().flat_map(|word| word.to_be_bytes()).collect();
+
+    let font_bytes = patch_font(MONO_FONT.bytes, |tables| {
+        tables.insert(*b"cmap", cmap);
+    });
+    let trimmed_font = Font::new(&font_bytes).unwrap();
+    assert!(matches!(trimmed_font.cmap, CmapTable::Trimmed(_)));
+    assert_eq!(trimmed_font.cmap.format(), 6);
+    for ch in ['\0', '@', 'Q', '[', 'a', '\u{ffff}', '\u{1f600}'] {
+        assert_eq!(trimmed_font.map_char(ch).unwrap(), 0, "{ch:?}");
+    }
+    for ch in ('A'..='Z').filter(|&ch| ch != 'Q') {
+        assert_eq!(
+            trimmed_font.map_char(ch).unwrap(),
+            font.map_char(ch).unwrap(),
+            "{ch:?}"
+        );
+    }
+    let coverage = trimmed_font.coverage_bitset();
+    let expected_coverage: BTreeSet<char> = ('A'..='Z').filter(|&ch| ch != 'Q').collect();
+    assert_eq!(coverage.char_count(), 25);
+    assert_eq!(
+        coverage.intersect(&('\0'..='\u{ffff}').collect()),
+        expected_coverage
+    );
+
+    // Subsets are written with a format 4 subtable.
+    let ttf = trimmed_font.subset_for_text("HELLO").unwrap().to_opentype();
+    assert_valid_font(&ttf, true, "HELO".chars());
+    let subset_font = Font::new(&ttf).unwrap();
+    assert!(matches!(subset_font.cmap, CmapTable::Deltas(_)));
+}
+
+#[test]
+fn subsetting_color_font() {
+    let font = Font::new(MONO_FONT.bytes).unwrap();
+    assert!(!font.is_color());
+
+    let font_bytes = patch_font(MONO_FONT.bytes, |tables| {
+        // Version 0 `CPAL` table with a single palette containing a single color
+        let cpal = [0_u16, 1, 1, 1, 0, 0, 14, 0, 0]
+            .iter()
+            .flat_map(|word| word.to_be_bytes())
+            .chain([0, 0, 0, 255])
+            .collect();
+        tables.insert(*b"CPAL", cpal);
+    });
+    let font = Font::new(&font_bytes).unwrap();
+    assert!(font.is_color());
+
+    let chars = ('a'..='z').collect();
+    let subset = font.subset(&chars).unwrap().to_opentype();
+    let tables = read_tables(&subset);
+    assert!(!tables.contains_key(b"CPAL"));
+    assert_valid_font(&subset, true, chars.iter().copied());
+
+    let options = SubsetOptions::default().error_on_color(true);
+    let err = font.subset_with_options(&chars, options).unwrap_err();
+    assert!(matches!(err, SubsetError::ColorTablesDropped), "{err:?}");
+}
+
+/// Exercises the parsing logic on the provided bytes; the only expectation is that it doesn't panic.
+fn parse_arbitrary_bytes(bytes: &[u8]) {
+    Font::checksum_report(bytes).ok();
+    Font::new(bytes).ok();
+    Font::parse_with_stats(bytes).ok();
+    Font::parse_with_limits(bytes, ParseLimits::default()).ok();
+    let Ok(font) = Font::parse_lenient(bytes) else {
+        return;
+    };
+    for ch in [' ', 'A', 'z', '\u{ffff}', '\u{1f600}', char::MAX] {
+        font.map_char(ch).ok();
+    }
+    font.coverage_bitset();
+    for glyph_idx in 0..font.glyph_count.min(16) {
+        font.glyph_bbox(glyph_idx).ok();
+    }
+    font.glyph_names().ok();
+    font.typo_ascender();
+    font.uses_typo_metrics();
+}
+
+/// Generates font data: arbitrary bytes, optionally prefixed with the TrueType font version.
+fn arbitrary_font_bytes() -> impl Strategy<Value = Vec<u8>> {
+    let bytes = proptest::collection::vec(any::<u8>(), 0..1_024);
+    (any::<bool>(), bytes).prop_map(|(with_version, mut bytes)| {
+        if with_version && bytes.len() >= 4 {
+            bytes[..4].copy_from_slice(&0x_0001_0000_u32.to_be_bytes());
+        }
+        bytes
+    })
+}
+
+/// Generates a mutation of a test font table: the table index, corrupted bytes near the table start
+/// (where headers are located), and an optional truncated length.
+fn table_mutation() -> impl Strategy<Value = (Index, Vec<(usize, u8)>, Option<Index>)> {
+    let corruptions = proptest::collection::vec((0_usize..64, any::<u8>()), 0..4);
+    (
+        any::<Index>(),
+        corruptions,
+        proptest::option::of(any::<Index>()),
+    )
+}
+
+proptest! {
+    #[test]
+    fn parsing_arbitrary_bytes_does_not_panic(bytes in arbitrary_font_bytes()) {
+        parse_arbitrary_bytes(&bytes);
+    }
+
+    #[test]
+    fn parsing_mutated_font_does_not_panic((table_idx, corruptions, truncated_len) in table_mutation()) {
+        let mut tables = read_tables(MONO_FONT.bytes);
+        let tag = *table_idx.get(&tables.keys().copied().collect::<Vec<_>>());
+        let table = tables.get_mut(&tag).unwrap();
+        for (pos, byte) in corruptions {
+            if let Some(table_byte) = table.get_mut(pos) {
+                *table_byte = byte;
+            }
+        }
+        if let Some(len) = truncated_len {
+            table.truncate(len.index(table.len() + 1));
+        }
+        parse_arbitrary_bytes(&write_tables(0x_0001_0000, &tables));
+    }
+
+    #[test]
+    fn parsing_truncated_font_does_not_panic(len in 0..MONO_FONT.bytes.len()) {
+        parse_arbitrary_bytes(&MONO_FONT.bytes[..len]);
+    }
+}
+
+/// Index subtable in test embedded bitmap tables.
+struct TestBitmapSubtable {
+    index_format: u16,
+    image_format: u16,
+    /// Glyphs with bitmaps. For index formats 1 and 3, other glyphs in the covered range have missing bitmaps.
+    glyphs: &'static [u16],
+}
+
+const TEST_BIG_METRICS: [u8; 8] = [2, 8, 0, 2, 8, 0, 0, 8];
+
+fn test_bitmap_image(image_format: u16, glyph_idx: u16, ppem: u8) -> Vec<u8> {
+    let [_, glyph_byte] = glyph_idx.to_be_bytes();
+    match image_format {
+        // Small metrics + byte-aligned data
+        1 => vec![2, 8, 0, 2, 8, glyph_byte, ppem],
+        // Bit-aligned data; metrics are stored in `EBLC`
+        5 => vec![glyph_byte, ppem],
+        // Big metrics + byte-aligned data
+        6 => [TEST_BIG_METRICS.as_slice(), &[glyph_byte, ppem, 0xff]].concat(),
+        // Small metrics + a single component referencing glyph 4
+        8 => vec![2, 8, 0, 2, 8, 0, 0, 1, 0, 4, 0, 0],
+        _ => unreachable!(),
+    }
+}
+
+/// Builds `EBLC` and `EBDT` tables with the specified strikes.
+fn embedded_bitmap_tables(strikes: &[(u8, &[TestBitmapSubtable])]) -> (Vec<u8>, Vec<u8>) {
+    let strike_count = u32::try_from(strikes.len()).unwrap();
+    let mut eblc = [0x_0002_0000_u32, strike_count]
+        .iter()
+        .flat_map(|word| word.to_be_bytes())
+        .collect::<Vec<_>>();
+    let mut ebdt = 0x_0002_0000_u32.to_be_bytes().to_vec();
+    eblc.resize(eblc.len() + 48 * strikes.len(), 0);
+
+    for (i, &(ppem, subtables)) in strikes.iter().enumerate() {
+        let array_start = eblc.len();
+        eblc.resize(array_start + 8 * subtables.len(), 0);
+        for (j, subtable) in subtables.iter().enumerate() {
+            let first_glyph = subtable.glyphs[0];
+            let last_glyph = *subtable.glyphs.last().unwrap();
+            let subtable_offset = u32::try_from(eblc.len() - array_start).unwrap();
+            let entry = &mut eblc[array_start + 8 * j..array_start + 8 * (j + 1)];
+            entry[..2].copy_from_slice(&first_glyph.to_be_bytes());
+            entry[2..4].copy_from_slice(&last_glyph.to_be_bytes());
+            entry[4..].copy_from_slice(&subtable_offset.to_be_bytes());
+
+            eblc.extend_from_slice(&subtable.index_format.to_be_bytes());
+            eblc.extend_from_slice(&subtable.image_format.to_be_bytes());
+            eblc.extend_from_slice(&u32::try_from(ebdt.len()).unwrap().to_be_bytes());
+            let image_data_start = ebdt.len();
+            let images = subtable
+                .glyphs
+                .iter()
+                .map(|&glyph_idx| test_bitmap_image(subtable.image_format, glyph_idx, ppem));
+            match subtable.index_format {
+                1 | 3 => {
+                    for glyph_idx in first_glyph..=last_glyph {
+                        let offset = ebdt.len() - image_data_start;
+                        if subtable.index_format == 1 {
+                            eblc.extend_from_slice(&u32::try_from(offset).unwrap().to_be_bytes());
+                        } else {
+                            eblc.extend_from_slice(&u16::try_from(offset).unwrap().to_be_bytes());
+                        }
+                        if subtable.glyphs.contains(&glyph_idx) {
+                            ebdt.extend(test_bitmap_image(subtable.image_format, glyph_idx, ppem));
+                        }
+                    }
+                    let offset = ebdt.len() - image_data_start;
+                    if subtable.index_format == 1 {
+                        eblc.extend_from_slice(&u32::try_from(offset).unwrap().to_be_bytes());
+                    } else {
+                        eblc.extend_from_slice(&u16::try_from(offset).unwrap().to_be_bytes());
+                    }
+                }
+                2 | 5 => {
+                    eblc.extend_from_slice(&2_u32.to_be_bytes()); // imageSize
+                    eblc.extend_from_slice(&TEST_BIG_METRICS);
+                    if subtable.index_format == 5 {
+                        let glyph_count = u32::try_from(subtable.glyphs.len()).unwrap();
+                        eblc.extend_from_slice(&glyph_count.to_be_bytes());
+                        for glyph_idx in subtable.glyphs {
+                            eblc.extend_from_slice(&glyph_idx.to_be_bytes());
+                        }
+                    }
+                    ebdt.extend(images.flatten());
+                }
+                4 => {
+                    let glyph_count = u32::try_from(subtable.glyphs.len()).unwrap();
+                    eblc.extend_from_slice(&glyph_count.to_be_bytes());
+                    for (&glyph_idx, image) in subtable.glyphs.iter().zip(images) {
+                        let offset = u16::try_from(ebdt.len() - image_data_start).unwrap();
+                        eblc.extend_from_slice(&glyph_idx.to_be_bytes());
+                        eblc.extend_from_slice(&offset.to_be_bytes());
+                        ebdt.extend(image);
+                    }
+                    let offset = u16::try_from(ebdt.len() - image_data_start).unwrap();
+                    eblc.extend_from_slice(&[0, 0]);
+                    eblc.extend_from_slice(&offset.to_be_bytes());
+                }
+                _ => unreachable!(),
+            }
+            eblc.resize(eblc.len().next_multiple_of(4), 0);
+        }
+
+        let tables_size = u32::try_from(eblc.len() - array_start).unwrap();
+        let record_start = 8 + 48 * i;
+        let record = &mut eblc[record_start..record_start + 48];
+        let array_offset = u32::try_from(array_start).unwrap();
+        record[..4].copy_from_slice(&array_offset.to_be_bytes());
+        record[4..8].copy_from_slice(&tables_size.to_be_bytes());
+        let subtable_count = u32::try_from(subtables.len()).unwrap();
+        record[8..12].copy_from_slice(&subtable_count.to_be_bytes());
+        let start_glyph = subtables.iter().map(|subtable| subtable.glyphs[0]).min();
+        let end_glyph = subtables
+            .iter()
+            .filter_map(|subtable| subtable.glyphs.last())
+            .max();
+        record[40..42].copy_from_slice(&start_glyph.unwrap().to_be_bytes());
+        record[42..44].copy_from_slice(&end_glyph.unwrap().to_be_bytes());
+        // ppemX, ppemY, bitDepth, flags (horizontal metrics)
+        record[44..].copy_from_slice(&[ppem, ppem, 1, 1]);
+    }
+    (eblc, ebdt)
+}
+
+/// Looks up an embedded bitmap using `allsorts`, returning its debug representation.
+fn lookup_bitmap(tables: &RawTables, glyph_idx: u16, ppem: u8) -> Option<String> {
+    let eblc = ReadScope::new(&tables[b"EBLC"])
+        .read::<CBLCTable<'_>>()
+        .unwrap();
+    let ebdt = ReadScope::new(&tables[b"EBDT"])
+        .read::<CBDTTable<'_>>()
+        .unwrap();
+    let strike = eblc.find_strike(glyph_idx, ppem, BitDepth::ThirtyTwo)?;
+    let bitmap = cbdt::lookup(glyph_idx, &strike, &ebdt).unwrap()?;
+    Some(match bitmap {
+        GlyphBitmapData::Format1 {
+            small_metrics,
+            data,
+        } => format!("1: {small_metrics:?} {data:?}"),
+        GlyphBitmapData::Format5 { big_metrics, data } => format!("5: {big_metrics:?} {data:?}"),
+        GlyphBitmapData::Format6 { big_metrics, data } => format!("6: {big_metrics:?} {data:?}"),
+        GlyphBitmapData::Format8 { .. } => "8".to_owned(),
+        _ => unreachable!(),
+    })
+}
+
+#[test]
+fn retaining_embedded_bitmaps() {
+    const SUBTABLES: &[TestBitmapSubtable] = &[
+        TestBitmapSubtable {
+            index_format: 1,
+            image_format: 1,
+            glyphs: &[3, 5], // 'A' (glyph 4) has a missing bitmap
+        },
+        TestBitmapSubtable {
+            index_format: 3,
+            image_format: 6,
+            glyphs: &[16, 17],
+        },
+        TestBitmapSubtable {
+            index_format: 2,
+            image_format: 5,
+            glyphs: &[136, 137],
+        },
+        TestBitmapSubtable {
+            index_format: 4,
+            image_format: 1,
+            glyphs: &[148, 149],
+        },
+        TestBitmapSubtable {
+            index_format: 5,
+            image_format: 5,
+            glyphs: &[264, 674],
+        },
+        TestBitmapSubtable {
+            index_format: 1,
+            image_format: 8,
+            glyphs: &[9], // 'À'
+        },
+    ];
+    const LARGE_SUBTABLES: &[TestBitmapSubtable] = &[TestBitmapSubtable {
+        index_format: 1,
+        image_format: 1,
+        glyphs: &[16],
+    }];
+
+    let (eblc, ebdt) = embedded_bitmap_tables(&[(12, SUBTABLES), (24, LARGE_SUBTABLES)]);
+    let font_bytes = patch_font(MONO_FONT.bytes, |tables| {
+        tables.insert(*b"EBLC", eblc);
+        tables.insert(*b"EBDT", ebdt);
+    });
+    let original_tables = read_tables(&font_bytes);
+    let font = Font::new(&font_bytes).unwrap();
+    let chars: BTreeSet<char> = " AÁÀBCabcz?".chars().collect();
+
+    let subset = font.subset(&chars).unwrap().to_opentype();
+    let tables = read_tables(&subset);
+    assert!(!tables.contains_key(b"EBLC"));
+    assert!(!tables.contains_key(b"EBDT"));
+
+    let options = SubsetOptions::default().retain_bitmaps(true);
+    let subset = font.subset_with_options(&chars, options).unwrap();
+    let new_to_old = subset.new_to_old_glyphs();
+    let estimated_size = subset.estimated_opentype_size();
+    let subset = subset.to_opentype();
+    assert_eq!(estimated_size, subset.len());
+    assert_valid_font(&subset, true, chars.iter().copied());
+    let tables = read_tables(&subset);
+
+    for &ch in &chars {
+        let old_idx = font.map_char(ch).unwrap();
+        let new_idx = new_to_old.iter().position(|&idx| idx == old_idx).unwrap();
+        let new_idx = u16::try_from(new_idx).unwrap();
+        for ppem in [12, 24] {
+            let expected = lookup_bitmap(&original_tables, old_idx, ppem);
+            let actual = lookup_bitmap(&tables, new_idx, ppem);
+            if ch == 'À' {
+                // Composite bitmaps are dropped.
+                assert_eq!(expected.as_deref(), Some("8"));
+                assert_eq!(actual, None);
+            } else {
+                let is_missing = ch == 'A';
+                assert_eq!(expected.is_none(), is_missing, "{ch:?}");
+                assert_eq!(actual, expected, "{ch:?}");
+            }
+        }
+    }
+}
+
+/// Collects outline drawing commands in the same format as [`Font::glyph_svg_path()`].
+#[derive(Debug, Default)]
+struct SvgPathSink(String);
+
+impl OutlineSink for SvgPathSink {
+    fn move_to(&mut self, to: Vector2F) {
+        if !self.0.is_empty() {
+            self.0.push(' ');
+        }
+        write!(self.0, "M{} {}", to.x(), to.y()).unwrap();
+    }
+
+    fn line_to(&mut self, to: Vector2F) {
+        write!(self.0, " L{} {}", to.x(), to.y()).unwrap();
+    }
+
+    fn quadratic_curve_to(&mut self, control: Vector2F, to: Vector2F) {
+        let (ctrl_x, ctrl_y) = (control.x(), control.y());
+        write!(self.0, " Q{ctrl_x} {ctrl_y} {} {}", to.x(), to.y()).unwrap();
+    }
+
+    fn cubic_curve_to(&mut self, control: LineSegment2F, to: Vector2F) {
+        let (from, ctrl) = (control.from(), control.to());
+        write!(
+            self.0,
+            " C{} {} {} {}",
+            from.x(),
+            from.y(),
+            ctrl.x(),
+            ctrl.y()
+        )
+        .unwrap();
+        write!(self.0, " {} {}", to.x(), to.y()).unwrap();
+    }
+
+    fn close(&mut self) {
+        self.0 += " Z";
+    }
+}
+
+#[test_casing(2, FONTS)]
+fn converting_glyph_outlines_to_svg_paths(test_font: TestFont) {
+    let font = Font::new(test_font.bytes).unwrap();
+    let space_idx = font.map_char(' ').unwrap();
+    assert_eq!(font.glyph_svg_path(space_idx).unwrap(), None);
+    let o_idx = font.map_char('o').unwrap();
+    let path = font.glyph_svg_path(o_idx).unwrap().unwrap();
+    assert_eq!(path.matches('M').count(), 2, "{path}");
+    assert_eq!(path.matches('Z').count(), 2, "{path}");
+    assert!(path.contains('Q'), "{path}");
+
+    let tables = read_tables(test_font.bytes);
+    let head = ReadScope::new(&tables[b"head"])
+        .read::<HeadTable>()
+        .unwrap();
+    let loca_args = (usize::from(font.glyph_count), head.index_to_loc_format);
+    let loca = ReadScope::new(&tables[b"loca"])
+        .read_dep::<LocaTable<'_>>(loca_args)
+        .unwrap();
+    let mut glyf = ReadScope::new(&tables[b"glyf"])
+        .read_dep::<GlyfTable<'_>>(&loca)
+        .unwrap();
+    for glyph_idx in 0..font.glyph_count {
+        let mut sink = SvgPathSink::default();
+        glyf.visit(glyph_idx, &mut sink).unwrap();
+        let expected = (!sink.0.is_empty()).then_some(sink.0);
+        let actual = font.glyph_svg_path(glyph_idx).unwrap();
+        assert_eq!(actual, expected, "glyph #{glyph_idx}");
+    }
+}
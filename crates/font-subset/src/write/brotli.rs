@@ -2,8 +2,17 @@
 
 use core::ops;
 
-use super::FontWriter;
-use crate::alloc::{vec, Box, Vec};
+use brotli::enc::{
+    backward_references::{BrotliEncoderMode, BrotliEncoderParams, BrotliHasherParams},
+    command::BrotliDistanceParams,
+    encode::{BROTLI_DISTANCE_ALPHABET_SIZE, BROTLI_MAX_DISTANCE, BROTLI_MAX_DISTANCE_BITS},
+};
+
+use super::{FontWriter, Woff2Options};
+use crate::{
+    alloc::{vec, BTreeMap, Box, Vec},
+    TableTag,
+};
 
 struct TableDataReader<'a> {
     writer: &'a FontWriter,
@@ -80,6 +89,19 @@ impl brotli::CustomRead<()> for TableDataReader<'_> {
     }
 }
 
+/// Reader for a single byte slice.
+struct SliceReader<'a>(&'a [u8]);
+
+impl brotli::CustomRead<()> for SliceReader<'_> {
+    fn read(&mut self, data: &mut [u8]) -> Result<usize, ()> {
+        let read = data.len().min(self.0.len());
+        let (head, tail) = self.0.split_at(read);
+        data[..read].copy_from_slice(head);
+        self.0 = tail;
+        Ok(read)
+    }
+}
+
 #[derive(Default)]
 struct Buffer(Vec<u8>);
 
@@ -136,22 +158,93 @@ impl<T: Clone + Default> brotli::enc::Allocator<T> for GlobalAlloc {
 
 impl brotli::enc::BrotliAlloc for GlobalAlloc {}
 
+/// Returns Brotli encoder parameters with all fields set explicitly, so that the compressed output
+/// doesn't change if the defaults in the `brotli` crate change. Besides `quality` and `lgwin`, the values
+/// correspond to the defaults in `brotli` v8.0.
+///
+/// Note that this doesn't guarantee reproducibility across `brotli` versions, since the encoder logic
+/// itself may change.
+fn encoder_params(quality: u8, lgwin: u8) -> BrotliEncoderParams {
+    BrotliEncoderParams {
+        dist: BrotliDistanceParams {
+            distance_postfix_bits: 0,
+            num_direct_distance_codes: 0,
+            alphabet_size: BROTLI_DISTANCE_ALPHABET_SIZE(0, 0, BROTLI_MAX_DISTANCE_BITS),
+            max_distance: BROTLI_MAX_DISTANCE,
+        },
+        mode: BrotliEncoderMode::BROTLI_MODE_GENERIC,
+        log_meta_block: false,
+        large_window: false,
+        avoid_distance_prefix_search: false,
+        quality: quality.into(),
+        q9_5: false,
+        lgwin: lgwin.into(),
+        lgblock: 0,
+        size_hint: 0,
+        disable_literal_context_modeling: 0,
+        stride_detection_quality: 0,
+        high_entropy_detection_quality: 0,
+        cdf_adaptation_detection: 0,
+        prior_bitmask_detection: 0,
+        literal_adaptation: [(0, 0); 4],
+        catable: false,
+        use_dictionary: true,
+        appendable: false,
+        magic_number: false,
+        favor_cpu_efficiency: false,
+        hasher: BrotliHasherParams {
+            type_: 6,
+            block_bits: 8,
+            bucket_bits: 15,
+            hash_len: 5,
+            num_last_distances_to_check: 16,
+            literal_byte_score: 0,
+        },
+    }
+}
+
+fn compress(reader: &mut impl brotli::CustomRead<()>, options: &Woff2Options) -> Vec<u8> {
+    let mut buffer = Buffer::default();
+    ::brotli::BrotliCompressCustomIo(
+        reader,
+        &mut buffer,
+        &mut [0_u8; 4_096],
+        &mut [0_u8; 4_096],
+        &encoder_params(options.quality, options.lgwin),
+        GlobalAlloc,
+        &mut |_, _, _, _| { /* do nothing */ },
+        (),
+    )
+    .expect("Writing to Vec never fails");
+
+    buffer.0
+}
+
+/// Compresses an arbitrary byte slice (e.g., the WOFF2 metadata block).
+pub(super) fn compress_bytes(bytes: &[u8], options: &Woff2Options) -> Vec<u8> {
+    compress(&mut SliceReader(bytes), options)
+}
+
 impl FontWriter {
-    pub(super) fn compress_data(&self) -> Vec<u8> {
-        let mut buffer = Buffer::default();
-        ::brotli::BrotliCompressCustomIo(
-            &mut TableDataReader::new(self),
-            &mut buffer,
-            &mut [0_u8; 4_096],
-            &mut [0_u8; 4_096],
-            &::brotli::enc::BrotliEncoderParams::default(),
-            GlobalAlloc,
-            &mut |_, _, _, _| { /* do nothing */ },
-            (),
-        )
-        .expect("Writing to Vec never fails");
-
-        buffer.0
+    pub(super) fn compress_data(&self, options: &Woff2Options) -> Vec<u8> {
+        compress(&mut TableDataReader::new(self), options)
+    }
+
+    /// Compresses each table separately and returns the compressed sizes.
+    pub(super) fn compress_tables_separately(&self) -> BTreeMap<TableTag, usize> {
+        let options = Woff2Options::default();
+        let data_offset = self.tables.first().map_or(0, |record| record.offset);
+        self.tables
+            .iter()
+            .map(|record| {
+                let start = (record.offset - data_offset) as usize;
+                let table_data = &self.table_data[start..start + record.length as usize];
+                (
+                    record.tag,
+                    compress(&mut SliceReader(table_data), &options).len(),
+                )
+            })
+            .collect()
     }
 }
 
@@ -170,7 +263,7 @@ mod tests {
         let font_bytes = fs::read("examples/FiraMono-Regular.ttf").unwrap();
         let font = Font::new(&font_bytes).unwrap();
         let chars = (' '..='~').collect();
-        let subset = FontSubset::new(font, &chars).unwrap();
+        let subset = FontSubset::new(&font, &chars).unwrap();
         let writer = subset.to_writer();
 
         let mut data_reader = TableDataReader::new(&writer);
@@ -198,4 +291,47 @@ mod tests {
             pos += len;
         }
     }
+
+    #[test]
+    fn estimating_table_contributions() {
+        let font_bytes = fs::read("examples/FiraMono-Regular.ttf").unwrap();
+        let font = Font::new(&font_bytes).unwrap();
+        let chars = (' '..='~').collect();
+        let subset = FontSubset::new(&font, &chars).unwrap();
+        let contributions = subset.woff2_table_contributions();
+
+        let writer = subset.to_writer();
+        let tags: Vec<_> = contributions.keys().copied().collect();
+        let mut expected_tags: Vec<_> = writer.tables.iter().map(|record| record.tag).collect();
+        expected_tags.sort_unstable();
+        assert_eq!(tags, expected_tags);
+        assert!(contributions.values().all(|&size| size > 0));
+
+        let (&largest_table, _) = contributions.iter().max_by_key(|(_, size)| **size).unwrap();
+        assert_eq!(largest_table, TableTag::GLYF);
+        let total_size: usize = contributions.values().sum();
+        assert!(total_size > writer.compress_data(&Woff2Options::default()).len());
+    }
+
+    #[test]
+    fn compression_is_deterministic() {
+        let font_bytes = fs::read("examples/FiraMono-Regular.ttf").unwrap();
+        let chars = (' '..='~').collect();
+        let compressed: Vec<_> = (0..3)
+            .map(|_| {
+                // Parse the font anew each time to rule out any shared state.
+                let font = Font::new(&font_bytes).unwrap();
+                let subset = FontSubset::new(&font, &chars).unwrap();
+                subset.to_writer().compress_data(&Woff2Options::default())
+            })
+            .collect();
+        assert!(compressed.windows(2).all(|window| window[0] == window[1]));
+
+        let metadata = b"<metadata version=\"1.0\" />";
+        let options = Woff2Options::default();
+        assert_eq!(
+            compress_bytes(metadata, &options),
+            compress_bytes(metadata, &options)
+        );
+    }
 }
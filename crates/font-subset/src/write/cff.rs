@@ -0,0 +1,280 @@
+//! Serialization of the `CFF ` table for font subsets.
+
+use super::write_u16;
+use crate::{
+    alloc::{vec, BTreeSet, Vec},
+    font::{CffGlyph, CffTable, DictEntry, Index},
+};
+
+/// Writes an `INDEX` structure with the specified objects.
+fn write_index<'a>(objects: impl Iterator<Item = &'a [u8]> + Clone, writer: &mut Vec<u8>) {
+    let count = u16::try_from(objects.clone().count()).expect("too many CFF objects");
+    write_u16(writer, count);
+    if count == 0 {
+        return;
+    }
+
+    let data_len: usize = objects.clone().map(<[u8]>::len).sum();
+    let max_offset = u32::try_from(data_len + 1).expect("CFF INDEX overflow");
+    let off_size = (max_offset.ilog2() / 8 + 1) as usize;
+    // `unwrap()` is safe: `off_size` is in 1..=4
+    writer.push(off_size.try_into().unwrap());
+    let mut offset = 1_u32;
+    writer.extend_from_slice(&offset.to_be_bytes()[4 - off_size..]);
+    for object in objects.clone() {
+        // `unwrap()` is safe: the total length is checked above
+        offset += u32::try_from(object.len()).unwrap();
+        writer.extend_from_slice(&offset.to_be_bytes()[4 - off_size..]);
+    }
+    for object in objects {
+        writer.extend_from_slice(object);
+    }
+}
+
+/// Writes an `INDEX` with subroutines. Subroutines not in `used` are replaced with empty ones,
+/// so that subroutine indices (and thus, charstrings calling subroutines) are unchanged.
+fn write_subrs_index(subrs: &Index<'_>, used: &BTreeSet<u16>, writer: &mut Vec<u8>) {
+    let subrs = (0..subrs.len()).map(|idx| {
+        if used.contains(&idx) {
+            // `expect()` is safe: used subroutines were read when parsing glyphs.
+            subrs.object(idx).expect("failed reading subroutine")
+        } else {
+            &[]
+        }
+    });
+    write_index(subrs, writer);
+}
+
+fn write_operator(operator: u16, writer: &mut Vec<u8>) {
+    if let Ok(operator) = u8::try_from(operator) {
+        writer.push(operator);
+    } else {
+        writer.extend_from_slice(&operator.to_be_bytes());
+    }
+}
+
+/// Writes an integer operand in the fixed-length 5-byte encoding, so that the operand length
+/// doesn't depend on the value.
+fn write_offset_operand(value: usize, writer: &mut Vec<u8>) {
+    writer.push(29);
+    let value = i32::try_from(value).expect("CFF offset overflow");
+    writer.extend_from_slice(&value.to_be_bytes());
+}
+
+/// Writes a `DICT` retaining `entries` except for ones with `replaced_operators`, and appending
+/// `offset_entries` encoded with [`write_offset_operand()`].
+fn write_dict(
+    entries: &[DictEntry<'_>],
+    replaced_operators: &[u16],
+    offset_entries: &[(u16, &[usize])],
+) -> Vec<u8> {
+    let mut writer = vec![];
+    let retained_entries = entries
+        .iter()
+        .filter(|entry| !replaced_operators.contains(&entry.operator));
+    for entry in retained_entries {
+        writer.extend_from_slice(entry.operands);
+        write_operator(entry.operator, &mut writer);
+    }
+    for &(operator, operands) in offset_entries {
+        for &operand in operands {
+            write_offset_operand(operand, &mut writer);
+        }
+        write_operator(operator, &mut writer);
+    }
+    writer
+}
+
+/// Offsets of `CFF ` table parts referenced from DICTs.
+#[derive(Debug, Default)]
+struct DictOffsets {
+    charset: usize,
+    fd_select: usize,
+    charstrings: usize,
+    fd_array: usize,
+    /// `(length, offset)` of private dicts for each font dict.
+    private_dicts: Vec<[usize; 2]>,
+}
+
+impl CffTable<'_> {
+    const TOP_DICT_OPERATORS: [u16; 6] = [
+        DictEntry::CHARSET,
+        DictEntry::ENCODING,
+        DictEntry::CHARSTRINGS,
+        DictEntry::PRIVATE,
+        DictEntry::FD_ARRAY,
+        DictEntry::FD_SELECT,
+    ];
+
+    fn write_top_dict(&self, offsets: &DictOffsets) -> Vec<u8> {
+        let (charset, charstrings) = ([offsets.charset], [offsets.charstrings]);
+        let (fd_array, fd_select) = ([offsets.fd_array], [offsets.fd_select]);
+        let mut offset_entries = vec![
+            (DictEntry::CHARSET, &charset[..]),
+            (DictEntry::CHARSTRINGS, &charstrings[..]),
+        ];
+        if self.is_cid_keyed() {
+            offset_entries.push((DictEntry::FD_ARRAY, &fd_array));
+            offset_entries.push((DictEntry::FD_SELECT, &fd_select));
+        } else {
+            offset_entries.push((DictEntry::PRIVATE, &offsets.private_dicts[0]));
+        }
+        // The encoding is dropped since OpenType fonts use the `cmap` table instead.
+        write_dict(&self.top_dict, &Self::TOP_DICT_OPERATORS, &offset_entries)
+    }
+
+    fn write_fd_array(&self, offsets: &DictOffsets, writer: &mut Vec<u8>) {
+        let font_dicts: Vec<_> = self
+            .font_dicts
+            .iter()
+            .zip(&offsets.private_dicts)
+            .map(|(font_dict, private_dict)| {
+                let offset_entries = [(DictEntry::PRIVATE, &private_dict[..])];
+                write_dict(&font_dict.entries, &[DictEntry::PRIVATE], &offset_entries)
+            })
+            .collect();
+        write_index(font_dicts.iter().map(Vec::as_slice), writer);
+    }
+
+    /// Writes the subset of this table containing the specified glyphs, ordered by the new glyph ID.
+    ///
+    /// The charset is always written in format 0. Unused subroutines are emptied, but not removed.
+    pub(super) fn write_subset(&self, glyphs: &[&CffGlyph<'_>], writer: &mut Vec<u8>) {
+        const HEADER: [u8; 4] = [1, 0, 4, 4]; // version 1.0, header length, absolute offset size
+
+        let mut used_global_subrs = BTreeSet::new();
+        let mut used_local_subrs = vec![BTreeSet::new(); self.font_dicts.len()];
+        for glyph in glyphs {
+            used_global_subrs.extend(&glyph.global_subrs);
+            used_local_subrs[usize::from(glyph.font_dict)].extend(&glyph.local_subrs);
+        }
+
+        let mut global_subrs = vec![];
+        write_subrs_index(&self.global_subrs, &used_global_subrs, &mut global_subrs);
+        let mut charset = vec![0]; // format
+        for glyph in &glyphs[1..] {
+            write_u16(&mut charset, glyph.id);
+        }
+        let mut fd_select = vec![];
+        if self.is_cid_keyed() {
+            Self::write_fd_select(glyphs, &mut fd_select);
+        }
+        let mut charstrings = vec![];
+        write_index(
+            glyphs.iter().map(|glyph| glyph.charstring),
+            &mut charstrings,
+        );
+
+        let private_dicts = self.write_private_dicts(&used_local_subrs);
+
+        // Lengths of DICTs don't depend on offsets, so they can be computed with placeholder offsets.
+        let mut offsets = DictOffsets {
+            private_dicts: vec![[0; 2]; self.font_dicts.len()],
+            ..DictOffsets::default()
+        };
+        let mut top_dict_index = vec![];
+        write_index(
+            [self.write_top_dict(&offsets).as_slice()].into_iter(),
+            &mut top_dict_index,
+        );
+        let mut fd_array = vec![];
+        if self.is_cid_keyed() {
+            self.write_fd_array(&offsets, &mut fd_array);
+        }
+
+        let mut offset = HEADER.len()
+            + self.name_index.raw.len()
+            + top_dict_index.len()
+            + self.string_index.raw.len()
+            + global_subrs.len();
+        offsets.charset = offset;
+        offset += charset.len();
+        offsets.fd_select = offset;
+        offset += fd_select.len();
+        offsets.charstrings = offset;
+        offset += charstrings.len();
+        offsets.fd_array = offset;
+        offset += fd_array.len();
+        for ((dict, subrs), private_dict) in private_dicts.iter().zip(&mut offsets.private_dicts) {
+            *private_dict = [dict.len(), offset];
+            offset += dict.len() + subrs.len();
+        }
+
+        top_dict_index.clear();
+        write_index(
+            [self.write_top_dict(&offsets).as_slice()].into_iter(),
+            &mut top_dict_index,
+        );
+        if self.is_cid_keyed() {
+            fd_array.clear();
+            self.write_fd_array(&offsets, &mut fd_array);
+        }
+
+        let start = writer.len();
+        writer.extend_from_slice(&HEADER);
+        writer.extend_from_slice(self.name_index.raw);
+        writer.extend_from_slice(&top_dict_index);
+        writer.extend_from_slice(self.string_index.raw);
+        writer.extend_from_slice(&global_subrs);
+        writer.extend_from_slice(&charset);
+        writer.extend_from_slice(&fd_select);
+        writer.extend_from_slice(&charstrings);
+        writer.extend_from_slice(&fd_array);
+        for (dict, subrs) in &private_dicts {
+            writer.extend_from_slice(dict);
+            writer.extend_from_slice(subrs);
+        }
+        debug_assert_eq!(writer.len() - start, offset);
+    }
+
+    /// Writes private dicts together with local subroutines for each font dict.
+    fn write_private_dicts(&self, used_local_subrs: &[BTreeSet<u16>]) -> Vec<(Vec<u8>, Vec<u8>)> {
+        self.font_dicts
+            .iter()
+            .zip(used_local_subrs)
+            .map(|(font_dict, used_subrs)| {
+                let Some(local_subrs) = &font_dict.local_subrs else {
+                    return (write_dict(&font_dict.private_entries, &[], &[]), vec![]);
+                };
+                // Local subroutines are placed immediately after the private dict; the `Subrs` offset
+                // is relative to the dict start. The dict length doesn't depend on the offset value.
+                let placeholder = [(DictEntry::SUBRS, &[0][..])];
+                let dict_len = write_dict(
+                    &font_dict.private_entries,
+                    &[DictEntry::SUBRS],
+                    &placeholder,
+                )
+                .len();
+                let offset_entries = [(DictEntry::SUBRS, &[dict_len][..])];
+                let dict = write_dict(
+                    &font_dict.private_entries,
+                    &[DictEntry::SUBRS],
+                    &offset_entries,
+                );
+                let mut subrs = vec![];
+                write_subrs_index(local_subrs, used_subrs, &mut subrs);
+                (dict, subrs)
+            })
+            .collect()
+    }
+
+    /// Writes `FDSelect` in format 3.
+    fn write_fd_select(glyphs: &[&CffGlyph<'_>], writer: &mut Vec<u8>) {
+        let runs: Vec<_> = glyphs
+            .chunk_by(|prev, glyph| prev.font_dict == glyph.font_dict)
+            .collect();
+        writer.push(3); // format
+        write_u16(
+            writer,
+            runs.len().try_into().expect("too many FDSelect ranges"),
+        );
+        let mut first_glyph = 0_u16;
+        for run in runs {
+            write_u16(writer, first_glyph);
+            writer.push(run[0].font_dict);
+            // `unwrap()` is safe: the number of glyphs fits into `u16`
+            first_glyph += u16::try_from(run.len()).unwrap();
+        }
+        write_u16(writer, first_glyph); // sentinel
+    }
+}
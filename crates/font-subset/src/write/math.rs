@@ -0,0 +1,202 @@
+//! Serialization of `MATH` tables for font subsets.
+
+use super::write_u16;
+use crate::{
+    alloc::Vec,
+    font::{GlyphAssembly, GlyphConstruction, MathTable},
+};
+
+/// Returns the byte length of a format 1 coverage table for `glyph_count` glyphs.
+fn coverage_len(glyph_count: usize) -> usize {
+    4 + 2 * glyph_count
+}
+
+/// Writes a format 1 coverage table. `glyphs` must be ordered.
+fn write_coverage(glyphs: impl ExactSizeIterator<Item = u16>, writer: &mut Vec<u8>) {
+    // `unwrap()` is safe: the number of glyphs fits into `u16`.
+    let glyph_count = glyphs.len().try_into().unwrap();
+    write_u16(writer, 1); // format
+    write_u16(writer, glyph_count);
+    for glyph in glyphs {
+        write_u16(writer, glyph);
+    }
+}
+
+/// Converts an offset into `u16`. The caller must ensure that the table is small enough.
+fn offset16(offset: usize) -> u16 {
+    offset.try_into().expect("offset overflow")
+}
+
+impl GlyphConstruction {
+    fn len(&self) -> usize {
+        let assembly_len = self.assembly.as_ref().map_or(0, |assembly| {
+            MathTable::VALUE_RECORD_LEN + 2 + GlyphAssembly::PART_RECORD_LEN * assembly.parts.len()
+        });
+        4 + Self::VARIANT_RECORD_LEN * self.variants.len() + assembly_len
+    }
+
+    fn write(&self, writer: &mut Vec<u8>) {
+        let assembly_offset = if self.assembly.is_some() {
+            offset16(4 + Self::VARIANT_RECORD_LEN * self.variants.len())
+        } else {
+            0
+        };
+        write_u16(writer, assembly_offset);
+        write_u16(writer, offset16(self.variants.len()));
+        for &(glyph, advance) in &self.variants {
+            write_u16(writer, glyph);
+            write_u16(writer, advance);
+        }
+
+        if let Some(assembly) = &self.assembly {
+            writer.extend_from_slice(&assembly.italics_correction.to_be_bytes());
+            write_u16(writer, 0); // deviceOffset
+            write_u16(writer, offset16(assembly.parts.len()));
+            for (glyph, fields) in &assembly.parts {
+                write_u16(writer, *glyph);
+                writer.extend_from_slice(fields);
+            }
+        }
+    }
+}
+
+impl MathTable<'_> {
+    /// Returns the byte length of a `MathItalicsCorrectionInfo` / `MathTopAccentAttachment` subtable.
+    fn values_len(values: &[(u16, i16)]) -> usize {
+        if values.is_empty() {
+            0
+        } else {
+            4 + Self::VALUE_RECORD_LEN * values.len() + coverage_len(values.len())
+        }
+    }
+
+    fn glyph_info_len(&self) -> usize {
+        let extended_shapes_len = if self.extended_shapes.is_empty() {
+            0
+        } else {
+            coverage_len(self.extended_shapes.len())
+        };
+        8 + Self::values_len(&self.italics_corrections)
+            + Self::values_len(&self.top_accent_attachments)
+            + extended_shapes_len
+    }
+
+    fn constructions_len(constructions: &[(u16, GlyphConstruction)]) -> usize {
+        if constructions.is_empty() {
+            return 0;
+        }
+        let constructions_len: usize = constructions
+            .iter()
+            .map(|(_, construction)| construction.len())
+            .sum();
+        2 * constructions.len() + coverage_len(constructions.len()) + constructions_len
+    }
+
+    fn variants_len(&self) -> usize {
+        10 + Self::constructions_len(&self.vertical_constructions)
+            + Self::constructions_len(&self.horizontal_constructions)
+    }
+
+    /// Returns the byte length of the table written by [`Self::write()`].
+    pub(crate) fn len(&self) -> usize {
+        Self::HEADER_LEN + Self::CONSTANTS_LEN + self.glyph_info_len() + self.variants_len()
+    }
+
+    /// Writes this table. Device tables are dropped (i.e., their offsets are set to 0),
+    /// and the `MathKernInfo` subtable is not written.
+    pub(crate) fn write(&self, writer: &mut Vec<u8>) {
+        let glyph_info_offset = Self::HEADER_LEN + Self::CONSTANTS_LEN;
+        write_u16(writer, 1); // majorVersion
+        write_u16(writer, 0); // minorVersion
+        write_u16(writer, offset16(Self::HEADER_LEN));
+        write_u16(writer, offset16(glyph_info_offset));
+        write_u16(writer, offset16(glyph_info_offset + self.glyph_info_len()));
+
+        let constants_start = writer.len();
+        writer.extend_from_slice(self.constants);
+        for i in 0..(Self::CONSTANTS_LEN - Self::CONSTANTS_PREFIX_LEN) / Self::VALUE_RECORD_LEN {
+            let offset = constants_start + Self::CONSTANTS_PREFIX_LEN + Self::VALUE_RECORD_LEN * i;
+            writer[offset + 2..offset + 4].fill(0); // deviceOffset
+        }
+
+        self.write_glyph_info(writer);
+        self.write_variants(writer);
+    }
+
+    fn write_glyph_info(&self, writer: &mut Vec<u8>) {
+        let mut offset = 8;
+        let mut write_offset = |len: usize| {
+            write_u16(writer, if len == 0 { 0 } else { offset16(offset) });
+            offset += len;
+        };
+        write_offset(Self::values_len(&self.italics_corrections));
+        write_offset(Self::values_len(&self.top_accent_attachments));
+        if self.extended_shapes.is_empty() {
+            write_offset(0);
+        } else {
+            write_offset(coverage_len(self.extended_shapes.len()));
+        }
+        write_u16(writer, 0); // mathKernInfoOffset
+
+        Self::write_values(&self.italics_corrections, writer);
+        Self::write_values(&self.top_accent_attachments, writer);
+        if !self.extended_shapes.is_empty() {
+            write_coverage(self.extended_shapes.iter().copied(), writer);
+        }
+    }
+
+    /// Writes a `MathItalicsCorrectionInfo` / `MathTopAccentAttachment` subtable.
+    fn write_values(values: &[(u16, i16)], writer: &mut Vec<u8>) {
+        if values.is_empty() {
+            return;
+        }
+        write_u16(writer, offset16(4 + Self::VALUE_RECORD_LEN * values.len())); // coverageOffset
+        write_u16(writer, offset16(values.len()));
+        for (_, value) in values {
+            writer.extend_from_slice(&value.to_be_bytes());
+            write_u16(writer, 0); // deviceOffset
+        }
+        write_coverage(values.iter().map(|&(glyph, _)| glyph), writer);
+    }
+
+    fn write_variants(&self, writer: &mut Vec<u8>) {
+        let (vertical, horizontal) = (&self.vertical_constructions, &self.horizontal_constructions);
+        write_u16(writer, self.min_connector_overlap);
+
+        let mut offset = 10 + 2 * (vertical.len() + horizontal.len());
+        let vertical_coverage_offset = offset;
+        if !vertical.is_empty() {
+            offset += coverage_len(vertical.len());
+        }
+        let horizontal_coverage_offset = offset;
+        if !horizontal.is_empty() {
+            offset += coverage_len(horizontal.len());
+        }
+        for (constructions, coverage_offset) in [
+            (vertical, vertical_coverage_offset),
+            (horizontal, horizontal_coverage_offset),
+        ] {
+            let coverage_offset = if constructions.is_empty() {
+                0
+            } else {
+                offset16(coverage_offset)
+            };
+            write_u16(writer, coverage_offset);
+        }
+        write_u16(writer, offset16(vertical.len()));
+        write_u16(writer, offset16(horizontal.len()));
+        for (_, construction) in vertical.iter().chain(horizontal) {
+            write_u16(writer, offset16(offset));
+            offset += construction.len();
+        }
+
+        for constructions in [vertical, horizontal] {
+            if !constructions.is_empty() {
+                write_coverage(constructions.iter().map(|(glyph, _)| *glyph), writer);
+            }
+        }
+        for (_, construction) in vertical.iter().chain(horizontal) {
+            construction.write(writer);
+        }
+    }
+}
@@ -1,18 +1,29 @@
 //! Logic for serializing `FontSubset`s in OpenType format.
 
-use core::{iter, mem};
+use core::{iter, mem, ops};
 
 use crate::{
-    alloc::{vec, Vec},
+    alloc::{vec, BTreeMap, BTreeSet, Cow, Vec},
     font::{
-        CmapTable, Glyph, GlyphComponent, GlyphComponentArgs, GlyphWithMetrics, HheaTable,
-        HmtxTable, LocaFormat, LocaTable, SegmentDeltas, SegmentWithDelta, SegmentedCoverage,
-        SequentialMapGroup, TransformData,
+        BitmapGlyph, ClassDef, CmapTable, EmbeddedBitmaps, Glyph, GlyphComponent,
+        GlyphComponentArgs, GlyphData, GlyphName, GlyphWithMetrics, HheaTable, HmtxTable,
+        KernTable, LocaFormat, LocaTable, NameRecord, NameTable, Os2Table, PostGlyphNames,
+        SbixTable, SegmentDeltas, SegmentWithDelta, SegmentedCoverage, SequentialMapGroup,
+        TransformData, VariationSequences, BITMAP_SIZE_LEN,
     },
+    subset::{BitmapStrikeSubset, KernSubtableSubset, SbixStrikeSubset, VariationSelectorSubset},
     Font, FontSubset, TableTag,
 };
 
 mod brotli;
+mod cff;
+mod math;
+mod os2;
+
+use self::os2::SynthesizedOs2;
+
+/// `glyf` and `loca` tables of a subset, in this order.
+type OutlineTables<'a> = (Cow<'a, [u8]>, Cow<'a, [u8]>);
 
 fn write_u16(writer: &mut Vec<u8>, value: u16) {
     writer.extend_from_slice(&value.to_be_bytes());
@@ -48,37 +59,23 @@ fn write_uint_base128(buffer: &mut Vec<u8>, val: u32) {
 }
 
 impl CmapTable<'static> {
-    fn from_map(map: &[(char, u16)]) -> Self {
+    /// Creates a table for the specified char map. If `compact` is set, contiguous char ranges
+    /// with non-sequential glyph IDs may be encoded as format 4 segments with a `glyphIdArray`.
+    fn from_map(map: &[(char, u16)], compact: bool) -> Self {
         let coverage = Self::create_coverage(map);
         let can_be_encoded_as_deltas = map
             .last()
             .is_none_or(|&(ch, _)| u32::from(ch) < u32::from(u16::MAX));
-        if can_be_encoded_as_deltas {
-            #[allow(clippy::cast_possible_truncation)]
-            // `_ as u16` is safe due to the `can_be_encoded_as_deltas` check
-            let delta_segments = coverage.groups.iter().map(|group| {
-                let start_code = group.start_char_code as u16;
-                SegmentWithDelta {
-                    start_code,
-                    end_code: group.end_char_code as u16,
-                    id_delta: (group.start_glyph_id as u16).wrapping_sub(start_code),
-                    id_range_offset: 0,
-                }
-            });
-            // Add en empty segment with `start_code == end_code == 0xffff` as per spec.
-            let delta_segments = delta_segments.chain([SegmentWithDelta {
-                start_code: u16::MAX,
-                end_code: u16::MAX,
-                id_delta: 1, // will map `start_code` to glyph #0 (the missing glyph) as recommended
-                id_range_offset: 0,
-            }]);
-            Self::Deltas(SegmentDeltas {
-                segments: delta_segments.collect(),
-                glyph_id_array: &[],
-            })
-        } else {
-            Self::Coverage(coverage)
+        if !can_be_encoded_as_deltas {
+            return Self::Coverage(coverage);
         }
+
+        let deltas = if compact {
+            SegmentDeltas::with_glyph_arrays(&coverage.groups)
+        } else {
+            None
+        };
+        Self::Deltas(deltas.unwrap_or_else(|| SegmentDeltas::from_groups(&coverage.groups)))
     }
 
     fn create_coverage(map: &[(char, u16)]) -> SegmentedCoverage {
@@ -116,28 +113,271 @@ impl CmapTable<'static> {
 }
 
 impl CmapTable<'_> {
-    fn write(&self, writer: &mut Vec<u8>) {
+    /// Writes a `cmap` table with a single subtable, plus a format 14 subtable if `variation_sequences`
+    /// are not empty. Must only be called on tables created via [`Self::from_map()`].
+    fn write(&self, variation_sequences: &[VariationSelectorSubset], writer: &mut Vec<u8>) {
+        let table_start = writer.len();
+        let has_variations = !variation_sequences.is_empty();
         write_u16(writer, 0); // table version
-        write_u16(writer, 1); // num_tables
+        write_u16(writer, if has_variations { 2 } else { 1 }); // num_tables
 
         write_u16(writer, CmapTable::UNICODE_PLATFORM);
         let encoding_id = match self {
             Self::Deltas(_) => 3,
             Self::Coverage(_) => 4,
+            Self::Trimmed(_) | Self::Byte(_) => {
+                unreachable!("trimmed and byte encoding subtables are never written")
+            }
         };
         write_u16(writer, encoding_id);
-        write_u32(writer, 12); // subtable_offset
+        let subtable_offset = if has_variations { 20 } else { 12 };
+        write_u32(writer, subtable_offset);
+        let variations_offset_pos = writer.len() + 4;
+        if has_variations {
+            write_u16(writer, CmapTable::UNICODE_PLATFORM);
+            write_u16(writer, VariationSequences::ENCODING_ID);
+            write_u32(writer, 0); // will be filled in after the main subtable is written
+        }
 
         match self {
             Self::Deltas(deltas) => deltas.write(writer),
             Self::Coverage(coverage) => coverage.write(writer),
+            Self::Trimmed(_) | Self::Byte(_) => {
+                unreachable!("trimmed and byte encoding subtables are never written")
+            }
+        }
+
+        if has_variations {
+            let offset = u32::try_from(writer.len() - table_start).expect("cmap overflow");
+            writer[variations_offset_pos..variations_offset_pos + 4]
+                .copy_from_slice(&offset.to_be_bytes());
+            VariationSelectorSubset::write_subtable(variation_sequences, writer);
+        }
+    }
+}
+
+fn write_u24(writer: &mut Vec<u8>, value: u32) {
+    writer.extend_from_slice(&value.to_be_bytes()[1..]);
+}
+
+impl VariationSelectorSubset {
+    /// Byte length of a `VariationSelector` record.
+    const RECORD_LEN: usize = 11;
+
+    /// Writes a Unicode variation sequences (format 14) subtable.
+    fn write_subtable(records: &[Self], writer: &mut Vec<u8>) {
+        let subtable_start = writer.len();
+        write_u16(writer, 14); // subtable format
+        write_u32(writer, 0); // length; will be filled in after the subtable is written
+        write_u32(
+            writer,
+            records.len().try_into().expect("too many selectors"),
+        );
+        let records_start = writer.len();
+        writer.resize(records_start + Self::RECORD_LEN * records.len(), 0);
+
+        for (i, record) in records.iter().enumerate() {
+            let default_offset = if record.default_bases.is_empty() {
+                0
+            } else {
+                let offset = writer.len() - subtable_start;
+                record.write_default_table(writer);
+                offset
+            };
+            let glyph_offset = if record.glyph_mappings.is_empty() {
+                0
+            } else {
+                let offset = writer.len() - subtable_start;
+                write_u32(writer, record.glyph_mappings.len().try_into().unwrap());
+                for &(base, glyph_idx) in &record.glyph_mappings {
+                    write_u24(writer, base.into());
+                    write_u16(writer, glyph_idx);
+                }
+                offset
+            };
+
+            let mut record_bytes = Vec::with_capacity(Self::RECORD_LEN);
+            write_u24(&mut record_bytes, record.selector);
+            write_u32(
+                &mut record_bytes,
+                default_offset.try_into().expect("cmap overflow"),
+            );
+            write_u32(
+                &mut record_bytes,
+                glyph_offset.try_into().expect("cmap overflow"),
+            );
+            let entry_start = records_start + Self::RECORD_LEN * i;
+            writer[entry_start..entry_start + Self::RECORD_LEN].copy_from_slice(&record_bytes);
+        }
+
+        let len = u32::try_from(writer.len() - subtable_start).expect("cmap overflow");
+        writer[subtable_start + 2..subtable_start + 6].copy_from_slice(&len.to_be_bytes());
+    }
+
+    /// Writes the default UVS table.
+    fn write_default_table(&self, writer: &mut Vec<u8>) {
+        let ranges = self.default_ranges();
+        write_u32(writer, ranges.len().try_into().unwrap());
+        for (start, additional_count) in ranges {
+            write_u24(writer, start);
+            writer.push(additional_count);
         }
     }
+
+    /// Merges consecutive default base chars into `(start, additionalCount)` ranges.
+    fn default_ranges(&self) -> Vec<(u32, u8)> {
+        let mut ranges = Vec::<(u32, u8)>::new();
+        for &base in &self.default_bases {
+            let base = u32::from(base);
+            match ranges.last_mut() {
+                Some((start, additional_count))
+                    if *additional_count < u8::MAX
+                        && base == *start + u32::from(*additional_count) + 1 =>
+                {
+                    *additional_count += 1;
+                }
+                _ => ranges.push((base, 0)),
+            }
+        }
+        ranges
+    }
+}
+
+impl SegmentDeltas<'static> {
+    /// Empty segment with `start_code == end_code == 0xffff`, which must terminate segments as per spec.
+    const LAST_SEGMENT: SegmentWithDelta = SegmentWithDelta {
+        start_code: u16::MAX,
+        end_code: u16::MAX,
+        id_delta: 1, // will map `start_code` to glyph #0 (the missing glyph) as recommended
+        id_range_offset: 0,
+    };
+
+    /// Encodes each group as a separate segment with a delta. Char codes in groups must fit into `u16`.
+    #[allow(clippy::cast_possible_truncation)] // `_ as u16` is safe due to the precondition
+    fn from_groups(groups: &[SequentialMapGroup]) -> Self {
+        let segments = groups.iter().map(|group| {
+            let start_code = group.start_char_code as u16;
+            SegmentWithDelta {
+                start_code,
+                end_code: group.end_char_code as u16,
+                id_delta: (group.start_glyph_id as u16).wrapping_sub(start_code),
+                id_range_offset: 0,
+            }
+        });
+        Self {
+            segments: segments.chain([Self::LAST_SEGMENT]).collect(),
+            glyph_id_array: Cow::Borrowed(&[]),
+        }
+    }
+
+    /// Merges runs of groups with contiguous chars into segments with glyph IDs stored in `glyphIdArray`
+    /// where this reduces the subtable size. A delta segment takes 8 bytes, while an array segment takes
+    /// 8 bytes plus 2 bytes per char, so merging pays off for runs of short groups. Char codes in groups
+    /// must fit into `u16`.
+    ///
+    /// Returns `None` if no groups should be merged, or if the merged subtable would overflow offsets.
+    #[allow(clippy::cast_possible_truncation)] // `_ as u16` is safe due to the precondition
+    fn with_glyph_arrays(groups: &[SequentialMapGroup]) -> Option<Self> {
+        let in_array = Self::choose_array_groups(groups);
+        if !in_array.iter().any(|&(in_array, _)| in_array) {
+            return None;
+        }
+
+        let mut segments = Vec::<SegmentWithDelta>::new();
+        let mut glyph_ids = Vec::<u16>::new();
+        // `(segment index, start position in glyph_ids)` for array segments
+        let mut array_segments = vec![];
+        for (group, &(in_array, starts_array)) in groups.iter().zip(&in_array) {
+            if !in_array {
+                let start_code = group.start_char_code as u16;
+                segments.push(SegmentWithDelta {
+                    start_code,
+                    end_code: group.end_char_code as u16,
+                    id_delta: (group.start_glyph_id as u16).wrapping_sub(start_code),
+                    id_range_offset: 0,
+                });
+                continue;
+            }
+
+            if starts_array {
+                array_segments.push((segments.len(), glyph_ids.len()));
+                segments.push(SegmentWithDelta {
+                    start_code: group.start_char_code as u16,
+                    end_code: group.end_char_code as u16,
+                    id_delta: 0,
+                    id_range_offset: 0, // will be set below
+                });
+            } else {
+                // `unwrap()` is safe: an array segment is always started before being continued
+                segments.last_mut().unwrap().end_code = group.end_char_code as u16;
+            }
+            let char_count = group.end_char_code - group.start_char_code + 1;
+            glyph_ids.extend((0..char_count).map(|i| (group.start_glyph_id + i) as u16));
+        }
+        segments.push(Self::LAST_SEGMENT);
+
+        // Offsets are counted from the `idRangeOffset` entry of the segment.
+        for (segment_idx, array_pos) in array_segments {
+            let offset = 2 * (segments.len() - segment_idx + array_pos);
+            segments[segment_idx].id_range_offset = u16::try_from(offset).ok()?;
+        }
+        let this = Self {
+            segments,
+            glyph_id_array: glyph_ids.iter().flat_map(|id| id.to_be_bytes()).collect(),
+        };
+        u16::try_from(this.subtable_len()).ok()?;
+        Some(this)
+    }
+
+    /// Chooses groups to be encoded as parts of array segments so that the total segment size
+    /// is minimized. Returns `(in_array, starts_array)` flags for each group.
+    fn choose_array_groups(groups: &[SequentialMapGroup]) -> Vec<(bool, bool)> {
+        const SEGMENT_LEN: usize = 8;
+
+        // Dynamic programming over groups. `delta_cost` / `array_cost` are the minimum sizes of encoding
+        // groups up to and including the current one, provided that the current group is encoded
+        // as a delta segment / as a part of an array segment, respectively.
+        let mut delta_cost = 0_usize;
+        let mut array_cost = usize::MAX;
+        // For each group: whether the previous group is in an array segment given that the current group
+        // is encoded as a delta segment / as a part of an array segment, and whether the current group
+        // continues the array segment of the previous group.
+        let mut choices = Vec::with_capacity(groups.len());
+        for (i, group) in groups.iter().enumerate() {
+            let char_count = (group.end_char_code - group.start_char_code) as usize + 1;
+            let is_contiguous = i > 0 && groups[i - 1].end_char_code + 1 == group.start_char_code;
+            let (prev_cost, prev_is_array) = if array_cost < delta_cost {
+                (array_cost, true)
+            } else {
+                (delta_cost, false)
+            };
+
+            // Continuing an array segment is always cheaper than starting a new one after it.
+            let continues_array =
+                is_contiguous && array_cost <= delta_cost.saturating_add(SEGMENT_LEN);
+            let (new_array_cost, array_prev_is_array) = if continues_array {
+                (array_cost, true)
+            } else {
+                (prev_cost + SEGMENT_LEN, prev_is_array)
+            };
+            array_cost = new_array_cost.saturating_add(2 * char_count);
+            delta_cost = prev_cost + SEGMENT_LEN;
+            choices.push((prev_is_array, array_prev_is_array, continues_array));
+        }
+
+        let mut flags = vec![(false, false); groups.len()];
+        let mut is_array = array_cost < delta_cost;
+        for (i, &(delta_prev, array_prev, continues_array)) in choices.iter().enumerate().rev() {
+            flags[i] = (is_array, is_array && !continues_array);
+            is_array = if is_array { array_prev } else { delta_prev };
+        }
+        flags
+    }
 }
 
 impl SegmentDeltas<'_> {
     fn subtable_len(&self) -> usize {
-        16 + 8 * self.segments.len()
+        16 + 8 * self.segments.len() + self.glyph_id_array.len()
     }
 
     fn write(&self, writer: &mut Vec<u8>) {
@@ -172,7 +412,7 @@ impl SegmentDeltas<'_> {
         for segment in &self.segments {
             write_u16(writer, segment.id_range_offset);
         }
-        writer.extend_from_slice(self.glyph_id_array);
+        writer.extend_from_slice(&self.glyph_id_array);
     }
 }
 
@@ -204,31 +444,277 @@ impl SegmentedCoverage {
     }
 }
 
-impl FontSubset<'_> {
+/// Options for serializing a [`FontSubset`] in the WOFF2 format.
+///
+/// # Reproducibility
+///
+/// WOFF2 output is deterministic: Brotli compression uses fixed parameters, so serializing the same subset
+/// with the same options always produces the same bytes, provided that the version of the `brotli` crate
+/// is the same. Output is **not** guaranteed to be reproducible across `brotli` versions, since
+/// the encoder implementation may change; pin the `brotli` version in the lockfile if this is important.
+#[derive(Debug, Clone)]
+pub struct Woff2Options {
+    alignment: u32,
+    metadata: Option<Vec<u8>>,
+    quality: u8,
+    lgwin: u8,
+}
+
+impl Default for Woff2Options {
+    fn default() -> Self {
+        Self {
+            alignment: 4,
+            metadata: None,
+            quality: 11,
+            lgwin: 22,
+        }
+    }
+}
+
+impl Woff2Options {
+    /// Pads the WOFF2 file with zero bytes so that its length is a multiple of `alignment` bytes
+    /// (e.g., 64 or 512). The padding is appended after the compressed font data and is accounted for
+    /// in the file length in the WOFF2 header. By default, the file is aligned to 4 bytes, as required
+    /// by the spec.
+    ///
+    /// Since the spec requires 4-byte alignment, `alignment` is rounded up to the nearest positive multiple of 4.
+    #[must_use]
+    pub fn align_to(mut self, alignment: u32) -> Self {
+        self.alignment = alignment.clamp(4, u32::MAX - 3).next_multiple_of(4);
+        self
+    }
+
+    /// Sets the extended metadata block embedded into the WOFF2 file (e.g., licensing information).
+    /// The metadata should be an XML document as described in the WOFF spec; it is not validated.
+    /// The metadata is compressed with Brotli and placed after the compressed font data.
+    /// By default, no metadata is embedded.
+    #[must_use]
+    pub fn metadata(mut self, metadata: Option<Vec<u8>>) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Sets the Brotli compression `quality` (0 to 11) and the base-2 logarithm of the sliding window
+    /// size `lgwin` (10 to 24). By default, the maximum quality (11) and the 4 MiB window (22) are used,
+    /// which gives the best compression ratio, but is slow for large fonts. Lower qualities (e.g., 5)
+    /// compress much faster at the cost of a somewhat larger output.
+    ///
+    /// Out-of-range values are clamped to the nearest valid value.
+    #[must_use]
+    pub fn compression(mut self, quality: u8, lgwin: u8) -> Self {
+        self.quality = quality.min(11);
+        self.lgwin = lgwin.clamp(10, 24);
+        self
+    }
+}
+
+impl<'a> FontSubset<'a> {
     /// Serializes this subset to the OpenType format.
     pub fn to_opentype(&self) -> Vec<u8> {
         self.to_writer().into_opentype()
     }
 
+    /// Serializes this subset to the OpenType format, streaming the output to the provided writer.
+    /// The output is the same as for [`Self::to_opentype()`], but it isn't copied into an intermediate
+    /// buffer, which may be useful when writing many subsets to files.
+    ///
+    /// # Errors
+    ///
+    /// Propagates I/O errors returned by the writer.
+    #[cfg(feature = "std")]
+    pub fn write_opentype<W: std::io::Write>(&self, out: &mut W) -> std::io::Result<()> {
+        self.to_writer().write_opentype(out)
+    }
+
     /// Serializes this subset to the WOFF2 format.
     pub fn to_woff2(&self) -> Vec<u8> {
-        self.to_writer().into_woff2()
+        self.to_woff2_with_options(&Woff2Options::default())
+    }
+
+    /// Serializes this subset to the WOFF2 format with the specified options.
+    pub fn to_woff2_with_options(&self, options: &Woff2Options) -> Vec<u8> {
+        self.to_writer().into_woff2(options)
+    }
+
+    /// Serializes this subset to the WOFF2 format with the specified Brotli compression `quality`
+    /// and window size. This is a shortcut for [`Self::to_woff2_with_options()`] with
+    /// [`Woff2Options::compression()`]; see the latter for details (e.g., on handling out-of-range values).
+    pub fn to_woff2_with_quality(&self, quality: u8, lgwin: u8) -> Vec<u8> {
+        self.to_woff2_with_options(&Woff2Options::default().compression(quality, lgwin))
+    }
+
+    /// Returns the `glyf` and `loca` tables of this subset, as they would be written by [`Self::to_opentype()`].
+    /// This is useful to embed glyph outlines directly (e.g., into PDF documents) without assembling
+    /// the entire font. The `loca` table uses the short format if its byte length is
+    /// `2 * (self.retained_glyph_count() + 1)`, and the long format otherwise. Returns `None` if the font
+    /// has CFF outlines.
+    ///
+    /// If the subset [retains all glyphs](Font::subset_all()) and hinting is not [stripped](Self::strip_hinting()),
+    /// the tables are borrowed from the original font. Otherwise, both tables are serialized on each call;
+    /// the subset doesn't cache serialized data.
+    pub fn outline_tables_bytes(&self) -> Option<OutlineTables<'a>> {
+        if let Some((loca, glyf)) = self.verbatim_outlines() {
+            return Some((Cow::Borrowed(glyf), Cow::Borrowed(loca.as_bytes())));
+        }
+        if matches!(self.font.glyph_data, GlyphData::Cff(_)) {
+            return None;
+        }
+        let mut glyf = vec![];
+        let locations = self.write_glyf(&mut glyf);
+        let mut loca = vec![];
+        LocaTable::write(&locations, &mut loca);
+        Some((Cow::Owned(glyf), Cow::Owned(loca)))
+    }
+
+    /// Returns the `glyf` table of this subset, as it would be written by [`Self::to_opentype()`].
+    /// Returns `None` if the font has CFF outlines. See [`Self::outline_tables_bytes()`] for details.
+    pub fn glyf_table_bytes(&self) -> Option<Cow<'a, [u8]>> {
+        self.outline_tables_bytes().map(|(glyf, _)| glyf)
+    }
+
+    /// Returns the `loca` table of this subset corresponding to the [`glyf` table](Self::glyf_table_bytes()),
+    /// as it would be written by [`Self::to_opentype()`]. Returns `None` if the font has CFF outlines.
+    /// See [`Self::outline_tables_bytes()`] for details.
+    pub fn loca_table_bytes(&self) -> Option<Cow<'a, [u8]>> {
+        self.outline_tables_bytes().map(|(_, loca)| loca)
+    }
+
+    /// Serializes this subset to the WOFF (version 1.0) format. Unlike WOFF2, each table is compressed
+    /// separately with zlib; tables which don't shrink after compression are stored uncompressed.
+    /// WOFF is less compact than WOFF2, but it is supported by some older consumers lacking WOFF2 support.
+    #[cfg(feature = "woff")]
+    pub fn to_woff1(&self) -> Vec<u8> {
+        self.to_writer().into_woff1()
+    }
+
+    /// Returns the byte size of the OpenType font that the [WOFF2 serialization](Self::to_woff2()) of this subset
+    /// decompresses to (i.e., the `totalSfntSize` field of the WOFF2 header). This is cheaper than
+    /// serializing to WOFF2 since it doesn't involve compression.
+    pub fn woff2_decompressed_len(&self) -> usize {
+        self.to_counting_writer().decompressed_len()
+    }
+
+    /// Computes the exact byte size of the [OpenType serialization](Self::to_opentype()) of this subset without
+    /// assembling the serialized font. Tables are written one by one into a scratch buffer and discarded,
+    /// and tables copied verbatim from the original font are not copied at all, so this is cheaper
+    /// than the serialization. This is useful to select a set of chars fitting into a size budget.
+    ///
+    /// The size of the [WOFF2 serialization](Self::to_woff2()) cannot be predicted without compressing the font,
+    /// but it's usually several times smaller than the returned size.
+    ///
+    /// This is an alias for [`Self::woff2_decompressed_len()`]: the OpenType serialization is exactly
+    /// what the WOFF2 serialization decompresses to.
+    pub fn estimated_opentype_size(&self) -> usize {
+        self.woff2_decompressed_len()
+    }
+
+    /// Estimates contributions of each table to the [WOFF2 serialization](Self::to_woff2()) of this subset
+    /// by compressing each table separately (with the same Brotli parameters) and returning compressed sizes.
+    ///
+    /// This is a diagnostic tool, e.g. to tune compression parameters. WOFF2 compresses all tables
+    /// as a single block, so the sum of returned sizes is generally larger than the actual compressed size.
+    pub fn woff2_table_contributions(&self) -> BTreeMap<TableTag, usize> {
+        self.to_writer().compress_tables_separately()
+    }
+
+    /// Returns a human-readable listing of the table directory of the [OpenType serialization](Self::to_opentype())
+    /// of this subset, similar to `ttx -l`. Each line after the header contains the table tag, offset, length
+    /// and checksum; tables are sorted by tag.
+    ///
+    /// This is a diagnostic tool; the output format is not stable.
+    #[cfg(feature = "std")]
+    pub fn directory_dump(&self) -> String {
+        use core::fmt::Write as _;
+
+        let mut writer = self.to_writer();
+        writer.adjust_data(Font::checksum(&writer.write_sfnt_header()));
+        writer.tables.sort_unstable_by_key(|record| record.tag.0);
+
+        let mut dump = String::from("tag    offset    length  checksum\n");
+        for record in &writer.tables {
+            let TableRecord {
+                tag,
+                checksum,
+                offset,
+                length,
+            } = record;
+            // `unwrap()` is safe: writing to a `String` is infallible
+            writeln!(
+                &mut dump,
+                "{tag:<4} {offset:>9} {length:>9}  0x{checksum:08x}"
+            )
+            .unwrap();
+        }
+        dump
+    }
+
+    /// Returns tags of the tables in the serialized subset, ordered by the tag as in the table directory.
+    /// The set of tables is the same for all output formats.
+    ///
+    /// This method serializes the subset (without compression), so it's relatively expensive.
+    pub fn output_tables(&self) -> Vec<TableTag> {
+        let writer = self.to_writer();
+        let mut tags: Vec<_> = writer.tables.iter().map(|record| record.tag).collect();
+        tags.sort_unstable();
+        tags
+    }
+
+    /// Returns tags of the tables present in the original font, but not in the [serialized subset](Self::output_tables()),
+    /// ordered by the tag. This can be used to warn about features lost during subsetting, e.g., kerning
+    /// if the `GPOS` table is dropped.
+    pub fn dropped_tables(&self) -> Vec<TableTag> {
+        let output_tables: BTreeSet<_> = self.output_tables().into_iter().collect();
+        let dropped_tables: BTreeSet<_> = self
+            .font
+            .table_tags
+            .iter()
+            .copied()
+            .filter(|tag| !output_tables.contains(tag))
+            .collect();
+        dropped_tables.into_iter().collect()
     }
 
     fn to_writer(&self) -> FontWriter {
-        let cmap = CmapTable::from_map(&self.char_map);
+        let sfnt_version = match self.font.glyph_data {
+            GlyphData::TrueType { .. } => Font::SFNT_VERSION,
+            GlyphData::Cff(_) => Font::CFF_SFNT_VERSION,
+        };
+        let mut writer = FontWriter::new(sfnt_version);
+        self.write_tables(&mut writer);
+        writer
+    }
 
-        let mut writer = FontWriter::default();
-        writer.write_table(TableTag::CMAP, |buffer| cmap.write(buffer));
-        if let Some(cvt) = self.font.cvt {
+    /// Writes all tables of this subset using a [counting](FontWriter::counting()) writer, which only records
+    /// table lengths.
+    fn to_counting_writer(&self) -> FontWriter {
+        let mut writer = FontWriter::counting();
+        self.write_tables(&mut writer);
+        writer
+    }
+
+    fn write_tables(&self, writer: &mut FontWriter) {
+        if self.retains_all_glyphs {
+            writer.write_raw_table(TableTag::CMAP, self.font.raw_cmap.as_ref());
+        } else {
+            let cmap = CmapTable::from_map(&self.char_map, self.options.compact_cmap);
+            writer.write_table(TableTag::CMAP, |buffer| {
+                cmap.write(&self.variation_sequences, buffer);
+            });
+        }
+        if let (true, Some(cvt)) = (self.retains_cvt(), self.font.cvt) {
             writer.write_raw_table(TableTag::CVT, cvt.as_ref());
         }
-        if let Some(fpgm) = self.font.fpgm {
+        if let (false, Some(fpgm)) = (self.strips_hinting, self.font.fpgm) {
             writer.write_raw_table(TableTag::FPGM, fpgm.as_ref());
         }
 
+        let h_metrics: Vec<_> = self
+            .glyphs
+            .iter()
+            .map(|glyph| (glyph.advance, glyph.lsb))
+            .collect();
         let number_of_h_metrics = writer.write_table(TableTag::HMTX, |buffer| {
-            HmtxTable::write_for_glyphs(&self.glyphs, buffer)
+            HmtxTable::write_metrics(&h_metrics, self.options.compact_hmtx, buffer)
         });
         let mut hhea = self.font.hhea;
         hhea.number_of_h_metrics = number_of_h_metrics;
@@ -236,96 +722,738 @@ impl FontSubset<'_> {
             hhea.write(buffer);
         });
 
-        let maxp = self.font.maxp.as_ref();
-        writer.write_table(TableTag::MAXP, |buffer| {
-            // Patch the number of glyphs (u16 at bytes 4..6), and leave other bytes intact.
-            buffer.extend_from_slice(&maxp[..4]);
-            // `unwrap()` should be safe: the subset shouldn't contain >65536 glyphs because the original font doesn't.
-            write_u16(buffer, self.glyphs.len().try_into().unwrap());
-            buffer.extend_from_slice(&maxp[6..]);
-        });
+        if let (true, Some(mut vhea)) = (self.options.vertical_metrics, self.font.vhea) {
+            let v_metrics: Vec<_> = self
+                .glyphs
+                .iter()
+                .map(|glyph| glyph.vertical_metrics.expect("no vertical metrics"))
+                .collect();
+            // `vhea` has the same layout as `hhea`, so `number_of_h_metrics` is actually `numOfLongVerMetrics`
+            vhea.number_of_h_metrics = writer.write_table(TableTag::VMTX, |buffer| {
+                HmtxTable::write_metrics(&v_metrics, self.options.compact_hmtx, buffer)
+            });
+            writer.write_table(TableTag::VHEA, |buffer| {
+                vhea.write(buffer);
+            });
+        }
+
+        writer.write_table(TableTag::MAXP, |buffer| self.write_maxp(buffer));
 
-        // TODO: reduce `name` table?
-        writer.write_raw_table(TableTag::NAME, self.font.name.as_ref());
-        writer.write_raw_table(TableTag::OS2, self.font.os2.as_ref());
+        if let Some(name_table) = &self.name_table {
+            writer.write_table(TableTag::NAME, |buffer| {
+                name_table.write_subset(&self.retained_name_ids, buffer);
+            });
+        } else if let (false, Some(name)) = (self.options.drop_name, self.font.name) {
+            writer.write_raw_table(TableTag::NAME, name.as_ref());
+        }
+        if let Some(gasp) = self.font.gasp {
+            writer.write_raw_table(TableTag::GASP, gasp.as_ref());
+        }
+        writer.write_table(TableTag::OS2, |buffer| self.write_os2(buffer));
 
         let post = self.font.post.as_ref();
         writer.write_table(TableTag::POST, |buffer| {
-            // Truncate the `post` table to not contain glyph names
-            write_u32(buffer, 0x_00030000); // version
-            buffer.extend_from_slice(&post[4..32]);
+            let version = if self.glyph_names.is_some() {
+                PostGlyphNames::VERSION_2
+            } else {
+                // Truncate the `post` table to not contain glyph names
+                PostGlyphNames::VERSION_3
+            };
+            write_u32(buffer, version);
+            // Copy `italicAngle`, `underlinePosition`, `underlineThickness` and `isFixedPitch`.
+            buffer.extend_from_slice(&post[4..PostGlyphNames::MEMORY_USAGE_OFFSET]);
+            // Zero the memory usage hints (`minMemType42` etc.); they describe the original font.
+            let memory_usage_len = PostGlyphNames::HEADER_LEN - PostGlyphNames::MEMORY_USAGE_OFFSET;
+            buffer.resize(buffer.len() + memory_usage_len, 0);
+            if let Some(glyph_names) = &self.glyph_names {
+                PostGlyphNames::write(glyph_names, buffer);
+            }
         });
 
-        if let Some(prep) = self.font.prep {
+        if let (false, Some(prep)) = (self.strips_hinting, self.font.prep) {
             writer.write_raw_table(TableTag::PREP, prep.as_ref());
         }
 
-        let locations = writer.write_table(TableTag::GLYF, |buffer| {
-            let mut locations = vec![0];
-            let initial_offset = buffer.len();
-            for glyph in &self.glyphs {
-                let glyph = &glyph.inner;
-                glyph.write(buffer);
-                locations.push(buffer.len() - initial_offset);
-            }
-            locations
-        });
+        self.write_optional_tables(writer);
 
-        let loca_format = writer.write_table(TableTag::LOCA, |buffer| {
-            LocaTable::write(&locations, buffer)
-        });
+        let loca_format = self.write_outlines(writer);
         writer.write_table(TableTag::HEAD, |buffer| {
-            Self::write_head_table(self.font.head.as_ref(), loca_format, buffer);
+            Self::write_head_table(
+                self.font.head.as_ref(),
+                self.bounding_box,
+                loca_format,
+                buffer,
+            );
         });
+    }
 
-        writer
+    /// Checks whether the `cvt ` table should be written. The control values are only accessed by instructions,
+    /// so they can be dropped if neither the retained glyphs nor the control value program contain any.
+    fn retains_cvt(&self) -> bool {
+        !self.strips_hinting
+            && (self.font.prep.is_some()
+                || self
+                    .glyphs
+                    .iter()
+                    .any(|glyph| glyph.inner.has_instructions()))
+    }
+
+    /// Writes the `maxp` table with the patched number of glyphs. If hinting is stripped, hinting-related
+    /// limits are reset as well.
+    fn write_maxp(&self, writer: &mut Vec<u8>) {
+        /// Offset of `maxZones` in `maxp` version 1.0.
+        const MAX_ZONES_OFFSET: usize = 14;
+        /// Offset of `maxComponentElements` (i.e., the first field after hinting-related ones).
+        const MAX_COMPONENT_ELEMENTS_OFFSET: usize = 28;
+
+        let maxp = self.font.maxp.as_ref();
+        // Patch the number of glyphs (u16 at bytes 4..6), and leave other bytes intact.
+        writer.extend_from_slice(&maxp[..4]);
+        // `unwrap()` should be safe: the subset shouldn't contain >65536 glyphs because the original font doesn't.
+        write_u16(writer, self.glyphs.len().try_into().unwrap());
+        if self.strips_hinting && maxp.len() >= MAX_COMPONENT_ELEMENTS_OFFSET {
+            writer.extend_from_slice(&maxp[6..MAX_ZONES_OFFSET]);
+            // `maxZones` = 1 (no twilight zone); `maxTwilightPoints`, `maxStorage`, `maxFunctionDefs`,
+            // `maxInstructionDefs`, `maxStackElements` and `maxSizeOfInstructions` = 0
+            write_u16(writer, 1);
+            writer.resize(
+                writer.len() + MAX_COMPONENT_ELEMENTS_OFFSET - MAX_ZONES_OFFSET - 2,
+                0,
+            );
+            writer.extend_from_slice(&maxp[MAX_COMPONENT_ELEMENTS_OFFSET..]);
+        } else {
+            writer.extend_from_slice(&maxp[6..]);
+        }
+    }
+
+    /// Writes optional tables (`GDEF`, `kern`, `MATH`, embedded bitmaps and `sbix`) if they are retained
+    /// in this subset.
+    fn write_optional_tables(&self, writer: &mut FontWriter) {
+        if let Some(glyph_classes) = &self.glyph_classes {
+            writer.write_table(TableTag::GDEF, |buffer| {
+                ClassDef::write_gdef(glyph_classes, buffer);
+            });
+        }
+
+        if let (true, Some(kern)) = (self.retains_all_glyphs, self.font.kern) {
+            writer.write_raw_table(TableTag::KERN, kern.as_ref());
+        } else if let Some(subtables) = &self.kern_subtables {
+            writer.write_table(TableTag::KERN, |buffer| {
+                KernSubtableSubset::write_table(subtables, buffer);
+            });
+        }
+
+        if let (true, true, Some(math)) = (
+            self.options.retain_math,
+            self.retains_all_glyphs,
+            self.font.math,
+        ) {
+            writer.write_raw_table(TableTag::MATH, math.as_ref());
+        } else if let Some(math) = &self.math_table {
+            writer.write_table(TableTag::MATH, |buffer| math.write(buffer));
+        }
+
+        if let Some(strikes) = &self.bitmap_strikes {
+            let mut ebdt = vec![];
+            writer.write_table(TableTag::EBLC, |buffer| {
+                BitmapStrikeSubset::write_tables(strikes, buffer, &mut ebdt);
+            });
+            writer.write_raw_table(TableTag::EBDT, &ebdt);
+            if let Some(ebsc) = self.font.ebsc {
+                writer.write_raw_table(TableTag::EBSC, ebsc.as_ref());
+            }
+        }
+
+        if let Some((flags, strikes)) = &self.sbix_strikes {
+            writer.write_table(TableTag::SBIX, |buffer| {
+                SbixStrikeSubset::write_table(*flags, strikes, self.glyphs.len(), buffer);
+            });
+        }
+    }
+
+    /// Writes glyphs to the `glyf` table. Returns glyph locations relative to the table start.
+    fn write_glyf(&self, buffer: &mut Vec<u8>) -> Vec<usize> {
+        let mut locations = vec![0];
+        let initial_offset = buffer.len();
+        for glyph in &self.glyphs {
+            let glyph = &glyph.inner;
+            glyph.write(self.strips_hinting, buffer);
+            locations.push(buffer.len() - initial_offset);
+        }
+        locations
+    }
+
+    /// Returns the original `loca` and `glyf` tables if they can be copied to the subset verbatim, i.e.,
+    /// if the font has TrueType outlines, all glyphs are retained and hinting is not stripped.
+    fn verbatim_outlines(&self) -> Option<(&LocaTable<'a>, &'a [u8])> {
+        if !self.retains_all_glyphs || self.strips_hinting {
+            return None;
+        }
+        let (loca, glyf) = self.font.truetype_outlines().ok()?;
+        Some((loca, glyf.bytes()))
+    }
+
+    /// Writes glyph outlines (`CFF `, or `glyf` + `loca`). Returns the `loca` format for TrueType outlines.
+    fn write_outlines(&self, writer: &mut FontWriter) -> Option<LocaFormat> {
+        if let GlyphData::Cff(cff) = &self.font.glyph_data {
+            let glyphs: Vec<_> = self
+                .glyphs
+                .iter()
+                .map(|glyph| match &glyph.inner {
+                    Glyph::Cff(glyph) => glyph,
+                    _ => unreachable!("fonts with CFF outlines only contain CFF glyphs"),
+                })
+                .collect();
+            writer.write_table(TableTag::CFF, |buffer| cff.write_subset(&glyphs, buffer));
+            None
+        } else if let Some((loca, glyf)) = self.verbatim_outlines() {
+            writer.write_raw_table(TableTag::GLYF, glyf);
+            writer.write_raw_table(TableTag::LOCA, loca.as_bytes());
+            Some(loca.format())
+        } else {
+            let locations = writer.write_table(TableTag::GLYF, |buffer| self.write_glyf(buffer));
+
+            let loca_format = writer.write_table(TableTag::LOCA, |buffer| {
+                LocaTable::write(&locations, buffer)
+            });
+            Some(loca_format)
+        }
+    }
+
+    /// Writes the `OS/2` table with `usFirstCharIndex` / `usLastCharIndex` fields (and, if requested,
+    /// Unicode ranges) recomputed from the chars covered by this subset. Other fields are copied verbatim.
+    /// If the original font has no `OS/2` table, a minimal table is synthesized instead.
+    fn write_os2(&self, writer: &mut Vec<u8>) {
+        let Some(original) = &self.font.os2 else {
+            self.synthesize_os2().write(writer);
+            return;
+        };
+        let start = writer.len();
+        writer.extend_from_slice(original.raw);
+        let covered_ranges = self.covered_char_ranges();
+        if self.options.recompute_unicode_ranges {
+            let unicode_ranges = os2::unicode_range_bits(covered_ranges.iter().copied());
+            Self::patch_unicode_ranges(&mut writer[start..], unicode_ranges);
+        }
+        if self.retains_all_glyphs {
+            // The original `cmap` table is retained, so char indices don't change.
+            return;
+        }
+        // If no chars are covered, char indices are retained (there's no meaningful value for them).
+        if let Some([first_char, last_char]) = Self::char_index_bounds(&covered_ranges) {
+            let offset = start + Os2Table::FIRST_CHAR_INDEX_OFFSET;
+            // Fields missing in a truncated legacy table are not added.
+            if let Some(fields) = writer.get_mut(offset..offset + 4) {
+                fields[..2].copy_from_slice(&first_char.to_be_bytes());
+                fields[2..].copy_from_slice(&last_char.to_be_bytes());
+            }
+        }
+    }
+
+    /// Returns ordered inclusive ranges of code points covered by this subset.
+    fn covered_char_ranges(&self) -> Vec<(u32, u32)> {
+        if self.retains_all_glyphs {
+            return self.font.coverage_bitset().ranges().to_vec();
+        }
+        self.char_map
+            .iter()
+            .filter(|&&(_, glyph_idx)| glyph_idx != 0)
+            .map(|&(ch, _)| (u32::from(ch), u32::from(ch)))
+            .collect()
+    }
+
+    /// Returns `usFirstCharIndex` / `usLastCharIndex` values for the ordered `covered_ranges`.
+    /// Char indices are capped at 0xffff as per spec.
+    fn char_index_bounds(covered_ranges: &[(u32, u32)]) -> Option<[u16; 2]> {
+        let first_char = covered_ranges.first()?.0;
+        let last_char = covered_ranges.last()?.1;
+        Some([first_char, last_char].map(|ch| u16::try_from(ch).unwrap_or(u16::MAX)))
+    }
+
+    /// Collects inputs for a minimal `OS/2` table from the `head` and `hhea` tables of the original font
+    /// and the glyphs retained in this subset.
+    fn synthesize_os2(&self) -> SynthesizedOs2 {
+        const MAC_STYLE_BOLD: u16 = 1;
+        const MAC_STYLE_ITALIC: u16 = 1 << 1;
+
+        let head = self.font.head.as_ref();
+        let mac_style = u16::from_be_bytes([
+            head[Font::HEAD_MAC_STYLE_OFFSET],
+            head[Font::HEAD_MAC_STYLE_OFFSET + 1],
+        ]);
+
+        let (advance_sum, advance_count) = self
+            .glyphs
+            .iter()
+            .filter(|glyph| glyph.advance != 0)
+            .fold((0_u64, 0_u64), |(sum, count), glyph| {
+                (sum + u64::from(glyph.advance), count + 1)
+            });
+        let avg_char_width = advance_sum.checked_div(advance_count).unwrap_or(0);
+
+        let covered_ranges = self.covered_char_ranges();
+        let [first_char_index, last_char_index] =
+            Self::char_index_bounds(&covered_ranges).unwrap_or([0; 2]);
+        let [_, y_min, _, y_max] = self.bounding_box;
+        let (ascender, descender) = (self.font.ascender(), self.font.descender());
+        let glyph_height = |ch| {
+            self.retained_glyph_for_char(ch)
+                .and_then(|glyph| glyph.inner.bbox())
+                .map_or(0, |[_, _, _, y_max]| y_max)
+        };
+
+        SynthesizedOs2 {
+            units_per_em: self.font.units_per_em(),
+            avg_char_width: i16::try_from(avg_char_width).unwrap_or(i16::MAX),
+            is_bold: mac_style & MAC_STYLE_BOLD != 0,
+            is_italic: mac_style & MAC_STYLE_ITALIC != 0,
+            unicode_ranges: os2::unicode_range_bits(covered_ranges.iter().copied()),
+            first_char_index,
+            last_char_index,
+            line_metrics: [ascender, descender, self.font.line_gap()],
+            win_ascent: u16::try_from(ascender.max(y_max)).unwrap_or(0),
+            win_descent: u16::try_from(-i32::from(descender.min(y_min))).unwrap_or(0),
+            x_height: glyph_height('x'),
+            cap_height: glyph_height('H'),
+        }
+    }
+
+    fn retained_glyph_for_char(&self, ch: char) -> Option<&GlyphWithMetrics<'_>> {
+        let glyph_idx = if self.retains_all_glyphs {
+            self.font.map_char(ch).ok()?
+        } else {
+            let pos = self
+                .char_map
+                .binary_search_by_key(&ch, |&(ch, _)| ch)
+                .ok()?;
+            self.char_map[pos].1
+        };
+        if glyph_idx == 0 {
+            return None;
+        }
+        self.glyphs.get(usize::from(glyph_idx))
     }
 
-    fn write_head_table(original: &[u8], loca_format: LocaFormat, writer: &mut Vec<u8>) {
+    fn patch_unicode_ranges(os2: &mut [u8], unicode_ranges: [u32; 4]) {
+        let offset = Os2Table::UNICODE_RANGE_OFFSET;
+        for (i, field) in unicode_ranges.into_iter().enumerate() {
+            if let Some(dest) = os2.get_mut(offset + 4 * i..offset + 4 * (i + 1)) {
+                dest.copy_from_slice(&field.to_be_bytes());
+            }
+        }
+    }
+
+    /// Writes the `head` table with the specified font bounding box. If `loca_format` is `None`
+    /// (i.e., the font has CFF outlines), the original `indexToLocFormat` value is retained.
+    fn write_head_table(
+        original: &[u8],
+        bbox: [i16; 4],
+        loca_format: Option<LocaFormat>,
+        writer: &mut Vec<u8>,
+    ) {
         const LOCA_FORMAT_OFFSET: usize = 50;
 
         writer.extend_from_slice(&original[..Font::HEAD_CHECKSUM_OFFSET]);
         write_u32(writer, 0); // Zero the checksum as per spec. It will be adjusted later
-        writer.extend_from_slice(&original[Font::HEAD_CHECKSUM_OFFSET + 4..LOCA_FORMAT_OFFSET]);
-        write_u16(
-            writer,
-            match loca_format {
-                LocaFormat::Short => 0,
-                LocaFormat::Long => 1,
-            },
-        );
+        writer.extend_from_slice(&original[Font::HEAD_CHECKSUM_OFFSET + 4..Font::HEAD_BBOX_OFFSET]);
+        for coord in bbox {
+            writer.extend_from_slice(&coord.to_be_bytes());
+        }
+        writer.extend_from_slice(&original[Font::HEAD_BBOX_OFFSET + 8..LOCA_FORMAT_OFFSET]);
+        match loca_format {
+            Some(LocaFormat::Short) => write_u16(writer, 0),
+            Some(LocaFormat::Long) => write_u16(writer, 1),
+            None => writer.extend_from_slice(&original[LOCA_FORMAT_OFFSET..LOCA_FORMAT_OFFSET + 2]),
+        }
         writer.extend_from_slice(&original[LOCA_FORMAT_OFFSET + 2..]);
     }
 }
 
 impl HmtxTable<'_> {
-    fn write_for_glyphs(glyphs: &[GlyphWithMetrics<'_>], writer: &mut Vec<u8>) -> u16 {
-        let mut number_of_h_metrics = glyphs.len();
-        while let Some([prev, current]) = glyphs[..number_of_h_metrics].last_chunk::<2>() {
-            if prev.advance != current.advance {
+    /// Writes metrics provided as `(advance, side_bearing)` tuples indexed by the glyph index.
+    /// If `compact` is set, trailing metrics with equal advances are written as side bearings only.
+    /// Returns the number of long metrics.
+    fn write_metrics(metrics: &[(u16, u16)], compact: bool, writer: &mut Vec<u8>) -> u16 {
+        let number_of_h_metrics = Self::long_metric_count(metrics, compact);
+        for (i, &(advance, side_bearing)) in metrics.iter().enumerate() {
+            if i < number_of_h_metrics {
+                write_u16(writer, advance);
+            }
+            write_u16(writer, side_bearing);
+        }
+
+        // `unwrap()` should be safe: `number_of_h_metrics` <= number of glyphs, which doesn't exceed u16::MAX
+        number_of_h_metrics.try_into().unwrap()
+    }
+
+    /// Returns the number of long metrics to write for `metrics`.
+    fn long_metric_count(metrics: &[(u16, u16)], compact: bool) -> usize {
+        let mut number_of_h_metrics = metrics.len();
+        while let (true, Some([(prev_advance, _), (advance, _)])) =
+            (compact, metrics[..number_of_h_metrics].last_chunk::<2>())
+        {
+            if prev_advance != advance {
                 break;
             }
             number_of_h_metrics -= 1;
         }
+        number_of_h_metrics
+    }
+}
 
-        for (i, glyph) in glyphs.iter().enumerate() {
-            if i < number_of_h_metrics {
-                write_u16(writer, glyph.advance);
-                write_u16(writer, glyph.lsb);
-            } else {
-                write_u16(writer, glyph.lsb);
+impl HheaTable<'_> {
+    fn write(&self, writer: &mut Vec<u8>) {
+        writer.extend_from_slice(&self.raw[..Self::EXPECTED_LEN - 2]);
+        write_u16(writer, self.number_of_h_metrics);
+    }
+}
+
+impl PostGlyphNames<'_> {
+    /// Writes glyph names in the version 2.0 format (excluding the header).
+    fn write(glyph_names: &[GlyphName<'_>], writer: &mut Vec<u8>) {
+        write_u16(
+            writer,
+            glyph_names.len().try_into().expect("too many glyphs"),
+        );
+
+        let mut custom_names = vec![];
+        let mut custom_name_indices = BTreeMap::new();
+        for &name in glyph_names {
+            let name_idx = match name {
+                GlyphName::Standard(idx) => idx,
+                GlyphName::Custom(name) => *custom_name_indices.entry(name).or_insert_with(|| {
+                    custom_names.push(name);
+                    let custom_idx = u16::try_from(custom_names.len() - 1).unwrap();
+                    Self::STANDARD_NAME_COUNT
+                        .checked_add(custom_idx)
+                        .expect("too many glyph names")
+                }),
+            };
+            write_u16(writer, name_idx);
+        }
+
+        for name in custom_names {
+            // `unwrap()` is safe: the name was read from a Pascal string
+            writer.push(name.len().try_into().unwrap());
+            writer.extend_from_slice(name);
+        }
+    }
+}
+
+impl<'a> NameTable<'a> {
+    /// Returns records with the specified name IDs, sorted by platform, encoding, language and name IDs
+    /// as required by the spec.
+    fn retained_records(&self, name_ids: &BTreeSet<u16>) -> Vec<NameRecord<'a>> {
+        let mut records: Vec<_> = self
+            .records
+            .iter()
+            .filter(|record| name_ids.contains(&record.name_id))
+            .copied()
+            .collect();
+        records.sort_unstable_by_key(NameRecord::sort_key);
+        records
+    }
+
+    /// Returns the byte length of the table excluding the string storage.
+    fn header_len(&self, record_count: usize) -> usize {
+        let lang_tags_len = if self.version == 1 {
+            2 + Self::LANG_TAG_RECORD_LEN * self.lang_tags.len()
+        } else {
+            0
+        };
+        6 + Self::RECORD_LEN * record_count + lang_tags_len
+    }
+
+    /// Returns sorted, non-overlapping ranges of the string storage covering the retained strings.
+    fn storage_ranges(&self, records: &[NameRecord<'a>]) -> Vec<ops::Range<usize>> {
+        let mut ranges: Vec<_> = records
+            .iter()
+            .map(|record| record.string)
+            .chain(self.lang_tags.iter().copied())
+            .map(|string| {
+                let start = self.storage_offset(string);
+                start..start + string.len()
+            })
+            .collect();
+        ranges.sort_unstable_by_key(|range| range.start);
+
+        let mut merged: Vec<ops::Range<usize>> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
             }
         }
+        merged
+    }
+
+    /// Writes the table retaining only records with the specified name IDs. Language tags (for format 1)
+    /// are retained as is.
+    ///
+    /// The string storage is repacked by copying the source ranges covering the retained strings. Thus,
+    /// strings sharing (parts of) the storage in the original table share them in the subset as well,
+    /// and string offsets never exceed the original ones.
+    fn write_subset(&self, name_ids: &BTreeSet<u16>, writer: &mut Vec<u8>) {
+        let records = self.retained_records(name_ids);
+        let header_len = self.header_len(records.len());
+        write_u16(writer, self.version);
+        // `unwrap()`s are safe: the number of records and the header length don't exceed the original ones
+        write_u16(writer, records.len().try_into().unwrap());
+        write_u16(writer, header_len.try_into().unwrap());
+
+        let ranges = self.storage_ranges(&records);
+        let mut new_starts = Vec::with_capacity(ranges.len());
+        let mut storage = vec![];
+        for range in &ranges {
+            new_starts.push(storage.len());
+            storage.extend_from_slice(&self.storage[range.clone()]);
+        }
+        let write_string = |writer: &mut Vec<u8>, string: &[u8]| {
+            let offset = self.storage_offset(string);
+            let range_idx = ranges.partition_point(|range| range.start <= offset) - 1;
+            let new_offset = new_starts[range_idx] + offset - ranges[range_idx].start;
+            // `unwrap()`s are safe: the string was read using a `u16` length, and the new offset
+            // doesn't exceed the original `u16` one
+            write_u16(writer, string.len().try_into().unwrap());
+            write_u16(writer, new_offset.try_into().unwrap());
+        };
+
+        for record in &records {
+            for field in record.sort_key() {
+                write_u16(writer, field);
+            }
+            write_string(writer, record.string);
+        }
+        if self.version == 1 {
+            // `unwrap()` is safe: the number of tags was read as `u16`
+            write_u16(writer, self.lang_tags.len().try_into().unwrap());
+            for &lang_tag in &self.lang_tags {
+                write_string(writer, lang_tag);
+            }
+        }
+        writer.extend_from_slice(&storage);
+    }
+}
+
+impl BitmapStrikeSubset<'_> {
+    /// Writes the `EBLC` table to `eblc` and the `EBDT` table to `ebdt`.
+    fn write_tables(strikes: &[Self], eblc: &mut Vec<u8>, ebdt: &mut Vec<u8>) {
+        let eblc_start = eblc.len();
+        write_u32(eblc, EmbeddedBitmaps::VERSION);
+        write_u32(eblc, strikes.len().try_into().expect("too many strikes"));
+        let size_records_start = eblc.len();
+        eblc.resize(size_records_start + BITMAP_SIZE_LEN * strikes.len(), 0);
+        write_u32(ebdt, EmbeddedBitmaps::VERSION);
+
+        for (i, strike) in strikes.iter().enumerate() {
+            let runs = strike.runs();
+
+            let array_start = eblc.len();
+            eblc.resize(array_start + 8 * runs.len(), 0);
+            for (run_idx, run) in runs.iter().enumerate() {
+                let subtable_offset = u32::try_from(eblc.len() - array_start).unwrap();
+                let entry_start = array_start + 8 * run_idx;
+                let entry = &mut eblc[entry_start..entry_start + 8];
+                entry[0..2].copy_from_slice(&run[0].0.to_be_bytes());
+                entry[2..4].copy_from_slice(&run[run.len() - 1].0.to_be_bytes());
+                entry[4..8].copy_from_slice(&subtable_offset.to_be_bytes());
+                Self::write_subtable(run, eblc, ebdt);
+            }
+
+            let mut record = strike.record;
+            let array_offset = u32::try_from(array_start - eblc_start).expect("EBLC overflow");
+            record[0..4].copy_from_slice(&array_offset.to_be_bytes());
+            let tables_size = u32::try_from(eblc.len() - array_start).expect("EBLC overflow");
+            record[4..8].copy_from_slice(&tables_size.to_be_bytes());
+            // `unwrap()` is safe: the number of runs is bounded by the number of glyphs
+            let subtable_count = u32::try_from(runs.len()).unwrap();
+            record[8..12].copy_from_slice(&subtable_count.to_be_bytes());
+            let first_glyph = strike.glyphs.first().map_or(0, |(idx, _)| *idx);
+            let last_glyph = strike.glyphs.last().map_or(0, |(idx, _)| *idx);
+            record[40..42].copy_from_slice(&first_glyph.to_be_bytes());
+            record[42..44].copy_from_slice(&last_glyph.to_be_bytes());
+            let record_start = size_records_start + BITMAP_SIZE_LEN * i;
+            eblc[record_start..record_start + BITMAP_SIZE_LEN].copy_from_slice(&record);
+        }
+    }
+
+    /// Splits glyphs into runs with consecutive IDs, the same image format and shared metrics.
+    /// Each run is written as a separate index subtable.
+    fn runs(&self) -> Vec<&[(u16, BitmapGlyph<'_>)]> {
+        self.glyphs
+            .chunk_by(|(prev_idx, prev), (idx, glyph)| {
+                *idx == prev_idx + 1
+                    && glyph.image_format == prev.image_format
+                    && glyph.shared_metrics == prev.shared_metrics
+            })
+            .collect()
+    }
+
+    /// Writes an index subtable for a run of glyphs with consecutive IDs and the same image format.
+    /// Uses index format 2 if metrics are shared among glyphs, and format 1 otherwise.
+    fn write_subtable(run: &[(u16, BitmapGlyph<'_>)], eblc: &mut Vec<u8>, ebdt: &mut Vec<u8>) {
+        let first_glyph = &run[0].1;
+        let index_format = if first_glyph.shared_metrics.is_some() {
+            2
+        } else {
+            1
+        };
+        write_u16(eblc, index_format);
+        write_u16(eblc, first_glyph.image_format);
+        write_u32(eblc, ebdt.len().try_into().expect("EBDT overflow"));
+
+        let image_data_offset = ebdt.len();
+        if let Some((image_size, big_metrics)) = first_glyph.shared_metrics {
+            write_u32(eblc, image_size);
+            eblc.extend_from_slice(&big_metrics);
+            for (_, glyph) in run {
+                ebdt.extend_from_slice(glyph.data);
+            }
+        } else {
+            for (_, glyph) in run {
+                let offset = ebdt.len() - image_data_offset;
+                write_u32(eblc, offset.try_into().expect("EBDT overflow"));
+                ebdt.extend_from_slice(glyph.data);
+            }
+            let offset = ebdt.len() - image_data_offset;
+            write_u32(eblc, offset.try_into().expect("EBDT overflow"));
+        }
+    }
+}
+
+impl SbixStrikeSubset<'_> {
+    /// Writes the `sbix` table with the specified `flags` for a font with `glyph_count` glyphs.
+    fn write_table(flags: u16, strikes: &[Self], glyph_count: usize, writer: &mut Vec<u8>) {
+        let table_start = writer.len();
+        write_u16(writer, SbixTable::VERSION);
+        write_u16(writer, flags);
+        write_u32(writer, strikes.len().try_into().expect("too many strikes"));
+        let offsets_start = writer.len();
+        writer.resize(offsets_start + 4 * strikes.len(), 0);
+
+        for (i, strike) in strikes.iter().enumerate() {
+            let strike_start = writer.len();
+            let strike_offset = u32::try_from(strike_start - table_start).expect("sbix overflow");
+            let offset_pos = offsets_start + 4 * i;
+            writer[offset_pos..offset_pos + 4].copy_from_slice(&strike_offset.to_be_bytes());
+
+            write_u16(writer, strike.ppem);
+            write_u16(writer, strike.ppi);
+            // Glyph data starts after the header and the `glyphDataOffsets` array.
+            let mut data_offset = 4 + 4 * (glyph_count + 1);
+            let mut glyphs = strike.glyphs.iter().peekable();
+            for glyph_idx in 0..=glyph_count {
+                write_u32(writer, data_offset.try_into().expect("sbix overflow"));
+                if let Some((_, glyph)) = glyphs.next_if(|(idx, _)| usize::from(*idx) == glyph_idx)
+                {
+                    data_offset += glyph.record.len();
+                }
+            }
+            for (_, glyph) in &strike.glyphs {
+                writer.extend_from_slice(glyph.record);
+            }
+        }
+    }
+}
+
+impl KernSubtableSubset {
+    /// Writes a `kern` table (version 0) with format 0 subtables.
+    fn write_table(subtables: &[Self], writer: &mut Vec<u8>) {
+        write_u16(writer, 0); // version
+        write_u16(
+            writer,
+            subtables.len().try_into().expect("too many subtables"),
+        );
+        for subtable in subtables {
+            subtable.write(writer);
+        }
+    }
+
+    fn write(&self, writer: &mut Vec<u8>) {
+        // `unwrap()` is safe: the subset cannot contain more pairs than the original subtable
+        let pair_count = u16::try_from(self.pairs.len()).unwrap();
+        // The length may overflow for large subtables; readers compute it from the number of pairs instead.
+        let len = KernTable::SUBTABLE_HEADER_LEN + KernTable::PAIR_LEN * self.pairs.len();
+        write_u16(writer, 0); // version
+        #[allow(clippy::cast_possible_truncation)]
+        write_u16(writer, len as u16);
+        write_u16(writer, self.coverage);
+
+        write_u16(writer, pair_count);
+        let entry_selector = pair_count.ilog2();
+        let search_range = KernTable::PAIR_LEN << entry_selector;
+        let range_shift = KernTable::PAIR_LEN * self.pairs.len() - search_range;
+        // Like the length, binary search params may overflow for large subtables; this is handled
+        // the same way as in other tools (truncating `searchRange`, and saturating `rangeShift`).
+        #[allow(clippy::cast_possible_truncation)]
+        write_u16(writer, search_range as u16);
+        // `unwrap()` is safe: `entry_selector < 16`
+        write_u16(writer, entry_selector.try_into().unwrap());
+        write_u16(writer, range_shift.try_into().unwrap_or(u16::MAX));
+        for &(left, right, value) in &self.pairs {
+            write_u16(writer, left);
+            write_u16(writer, right);
+            writer.extend_from_slice(&value);
+        }
+    }
+}
+
+impl ClassDef<'_> {
+    /// Writes a `GDEF` table (version 1.0) containing only the glyph class definition.
+    /// `glyph_classes` must be non-empty, ordered by the glyph ID and contain only non-zero classes.
+    fn write_gdef(glyph_classes: &[(u16, u16)], writer: &mut Vec<u8>) {
+        const HEADER_LEN: u16 = 12;
+
+        write_u32(writer, 0x_0001_0000); // version
+        write_u16(writer, HEADER_LEN); // glyphClassDefOffset
+        write_u16(writer, 0); // attachListOffset
+        write_u16(writer, 0); // ligCaretListOffset
+        write_u16(writer, 0); // markAttachClassDefOffset
+        Self::write(glyph_classes, writer);
+    }
+
+    /// Returns the length of the class array (format 1) and class ranges (format 2) for `glyph_classes`.
+    fn array_len_and_ranges(glyph_classes: &[(u16, u16)]) -> (usize, Vec<&[(u16, u16)]>) {
+        let (first_glyph, _) = glyph_classes[0];
+        let (last_glyph, _) = glyph_classes[glyph_classes.len() - 1];
+        let ranges: Vec<_> = glyph_classes
+            .chunk_by(|(prev_idx, prev_class), (idx, class)| {
+                *idx == prev_idx + 1 && class == prev_class
+            })
+            .collect();
+        let array_len = usize::from(last_glyph - first_glyph) + 1;
+        (array_len, ranges)
+    }
 
-        // `unwrap()` should be safe: `number_of_h_metrics` <= number of glyphs, which doesn't exceed u16::MAX
-        number_of_h_metrics.try_into().unwrap()
+    /// Checks whether format 1 produces smaller (or equal) output than format 2.
+    fn prefers_array(array_len: usize, ranges: &[&[(u16, u16)]]) -> bool {
+        2 * array_len <= Self::RANGE_RECORD_LEN * ranges.len()
     }
-}
 
-impl HheaTable<'_> {
-    fn write(&self, writer: &mut Vec<u8>) {
-        writer.extend_from_slice(&self.raw[..Self::EXPECTED_LEN - 2]);
-        write_u16(writer, self.number_of_h_metrics);
+    /// Writes a class definition in the format producing the smaller output.
+    fn write(glyph_classes: &[(u16, u16)], writer: &mut Vec<u8>) {
+        let (first_glyph, _) = glyph_classes[0];
+        let (last_glyph, _) = glyph_classes[glyph_classes.len() - 1];
+        let (array_len, ranges) = Self::array_len_and_ranges(glyph_classes);
+        if Self::prefers_array(array_len, &ranges) {
+            write_u16(writer, 1); // format
+            write_u16(writer, first_glyph);
+            // `unwrap()` is safe: the length is bounded by the number of glyphs
+            write_u16(writer, array_len.try_into().unwrap());
+            let mut classes = glyph_classes.iter().peekable();
+            for glyph_idx in first_glyph..=last_glyph {
+                let class = classes.next_if(|(idx, _)| *idx == glyph_idx);
+                write_u16(writer, class.map_or(0, |&(_, class)| class));
+            }
+        } else {
+            write_u16(writer, 2); // format
+            write_u16(writer, ranges.len().try_into().expect("too many ranges"));
+            for range in ranges {
+                write_u16(writer, range[0].0);
+                write_u16(writer, range[range.len() - 1].0);
+                write_u16(writer, range[0].1);
+            }
+        }
     }
 }
 
@@ -378,74 +1506,146 @@ impl TableRecord {
             .wrapping_add(self.length)
     }
 
+    /// Known table tags in the order specified by the WOFF2 spec. The index of a tag in this array
+    /// is used in the table directory instead of the tag itself.
+    const WOFF2_KNOWN_TAGS: [&'static [u8; 4]; 63] = [
+        b"cmap", b"head", b"hhea", b"hmtx", b"maxp", b"name", b"OS/2", b"post", b"cvt ", b"fpgm",
+        b"glyf", b"loca", b"prep", b"CFF ", b"VORG", b"EBDT", b"EBLC", b"gasp", b"hdmx", b"kern",
+        b"LTSH", b"PCLT", b"VDMX", b"vhea", b"vmtx", b"BASE", b"GDEF", b"GPOS", b"GSUB", b"EBSC",
+        b"JSTF", b"MATH", b"CBDT", b"CBLC", b"COLR", b"CPAL", b"SVG ", b"sbix", b"acnt", b"avar",
+        b"bdat", b"bloc", b"bsln", b"cvar", b"fdsc", b"feat", b"fmtx", b"fvar", b"gvar", b"hsty",
+        b"just", b"lcar", b"mort", b"morx", b"opbd", b"prop", b"trak", b"Zapf", b"Silf", b"Glat",
+        b"Gloc", b"Feat", b"Sill",
+    ];
+    /// Flags value signaling that the table tag is written explicitly.
+    const WOFF2_ARBITRARY_TAG: u8 = 0x3f;
+
+    fn woff2_tag_idx(&self) -> Option<u8> {
+        let idx = Self::WOFF2_KNOWN_TAGS
+            .iter()
+            .position(|&tag| *tag == self.tag.0)?;
+        // `unwrap()` is safe: there are 63 known tags
+        Some(u8::try_from(idx).unwrap())
+    }
+
     fn woff2_len(&self) -> usize {
-        1 /* flags */ + uint_base128_len(self.length)
+        let tag_len = if self.woff2_tag_idx().is_some() { 0 } else { 4 };
+        1 /* flags */ + tag_len + uint_base128_len(self.length)
+    }
+
+    /// Writes a WOFF table directory entry. `compressed_len` is equal to the table length
+    /// if the table is stored uncompressed.
+    #[cfg(feature = "woff")]
+    fn write_woff1(&self, offset: usize, compressed_len: usize, buffer: &mut Vec<u8>) {
+        buffer.extend_from_slice(&self.tag.0);
+        // `unwrap`s are safe: the WOFF file length is checked to fit into `u32`
+        write_u32(buffer, offset.try_into().unwrap());
+        write_u32(buffer, compressed_len.try_into().unwrap());
+        write_u32(buffer, self.length);
+        write_u32(buffer, self.checksum);
     }
 
     fn write_woff2(&self, buffer: &mut Vec<u8>) {
         const NULL_TRANSFORM: u8 = 0b_1100_0000;
 
-        let flags = match self.tag {
-            TableTag::CMAP => 0,
-            TableTag::HEAD => 1,
-            TableTag::HHEA => 2,
-            TableTag::HMTX => 3,
-            TableTag::MAXP => 4,
-            TableTag::NAME => 5,
-            TableTag::OS2 => 6,
-            TableTag::POST => 7,
-            TableTag::CVT => 8,
-            TableTag::FPGM => 9,
-            TableTag::GLYF => 10 | NULL_TRANSFORM,
-            TableTag::LOCA => 11 | NULL_TRANSFORM,
-            TableTag::PREP => 12,
-            _ => unreachable!("subsetting only produces well-known tables"),
-        };
-        buffer.push(flags);
+        if let Some(tag_idx) = self.woff2_tag_idx() {
+            let flags = match self.tag {
+                TableTag::GLYF | TableTag::LOCA => tag_idx | NULL_TRANSFORM,
+                _ => tag_idx,
+            };
+            buffer.push(flags);
+        } else {
+            buffer.push(Self::WOFF2_ARBITRARY_TAG);
+            buffer.extend_from_slice(&self.tag.0);
+        }
         write_uint_base128(buffer, self.length);
     }
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 struct FontWriter {
+    /// sfnt version written to the font header, which depends on the outline format.
+    sfnt_version: u32,
     tables: Vec<TableRecord>,
-    /// Contains *aligned* table data
+    /// Contains *aligned* table data. For a [counting](Self::counting()) writer, this is a scratch buffer
+    /// holding at most one table.
     table_data: Vec<u8>,
+    /// Total length of aligned table data.
+    data_len: usize,
+    /// If set, table data is discarded once the table is written; only table lengths are recorded.
+    counting: bool,
 }
 
 impl FontWriter {
     const SFNT_HEADER_LEN: usize = 12;
+    #[cfg(feature = "woff")]
+    const WOFF1_HEADER_LEN: usize = 44;
+    #[cfg(feature = "woff")]
+    const WOFF1_TABLE_RECORD_LEN: usize = 20;
     const WOFF2_HEADER_LEN: usize = 48;
 
+    fn new(sfnt_version: u32) -> Self {
+        Self {
+            sfnt_version,
+            tables: vec![],
+            table_data: vec![],
+            data_len: 0,
+            counting: false,
+        }
+    }
+
+    /// Creates a writer that only records table lengths. Such a writer can only be used to compute
+    /// the [serialized length](Self::decompressed_len()) of the font; table checksums are not computed.
+    fn counting() -> Self {
+        Self {
+            counting: true,
+            ..Self::new(0)
+        }
+    }
+
     fn write_table<T>(&mut self, tag: TableTag, with: impl FnOnce(&mut Vec<u8>) -> T) -> T {
-        let offset = self.table_data.len();
+        let start = self.table_data.len();
+        let offset = self.data_len;
         debug_assert_eq!(offset % 4, 0, "unaligned offset: {offset}");
 
         let output = with(&mut self.table_data);
-        let length = self.table_data.len() - offset;
-        // Pad the table heap to a 4-byte boundary.
-        if length % 4 > 0 {
-            let zero_padding = 4 - length % 4;
-            self.table_data.extend(iter::repeat_n(0_u8, zero_padding));
+        let length = self.table_data.len() - start;
+        let checksum = if self.counting {
+            self.table_data.clear();
+            0
+        } else {
+            // Pad the table heap to a 4-byte boundary.
+            if length % 4 > 0 {
+                let zero_padding = 4 - length % 4;
+                self.table_data.extend(iter::repeat_n(0_u8, zero_padding));
+            }
+            Font::checksum(&self.table_data[start..])
+        };
+        self.push_record(tag, checksum, length);
+        output
+    }
+
+    fn write_raw_table(&mut self, tag: TableTag, content: &[u8]) {
+        if self.counting {
+            self.push_record(tag, 0, content.len());
+        } else {
+            self.write_table(tag, |buffer| buffer.extend_from_slice(content));
         }
+    }
 
-        let checksum = Font::checksum(&self.table_data[offset..]);
+    fn push_record(&mut self, tag: TableTag, checksum: u32, length: usize) {
         self.tables.push(TableRecord {
             tag,
             checksum,
-            offset: u32::try_from(offset).expect("table offset overflow"),
+            offset: u32::try_from(self.data_len).expect("table offset overflow"),
             length: u32::try_from(length).expect("table length overflow"),
         });
-        output
-    }
-
-    fn write_raw_table(&mut self, tag: TableTag, content: &[u8]) {
-        self.write_table(tag, |buffer| buffer.extend_from_slice(content));
+        self.data_len += length.next_multiple_of(4);
     }
 
     fn write_sfnt_header(&self) -> Vec<u8> {
         let mut buffer = vec![];
-        write_u32(&mut buffer, Font::SFNT_VERSION);
+        write_u32(&mut buffer, self.sfnt_version);
 
         // `unwrap()`s are safe: we don't have many tables written.
         let table_count = u16::try_from(self.tables.len()).unwrap();
@@ -466,7 +1666,29 @@ impl FontWriter {
         Self::SFNT_HEADER_LEN + self.tables.len() * TableRecord::BYTE_LEN
     }
 
+    /// Returns the length of the font in the OpenType format.
+    fn decompressed_len(&self) -> usize {
+        self.data_offset() + self.data_len
+    }
+
     fn into_opentype(mut self) -> Vec<u8> {
+        let mut buffer = self.finalize_opentype();
+        buffer.extend(self.table_data);
+        buffer
+    }
+
+    /// Streams the OpenType serialization to the provided writer. Unlike [`Self::into_opentype()`],
+    /// this doesn't copy table data into a separate buffer.
+    #[cfg(feature = "std")]
+    fn write_opentype(mut self, out: &mut impl std::io::Write) -> std::io::Result<()> {
+        let header = self.finalize_opentype();
+        out.write_all(&header)?;
+        out.write_all(&self.table_data)
+    }
+
+    /// Adjusts table data for the OpenType serialization and returns the sfnt header together
+    /// with the table directory, which precede table data in the serialized font.
+    fn finalize_opentype(&mut self) -> Vec<u8> {
         let mut buffer = self.write_sfnt_header();
         self.adjust_data(Font::checksum(&buffer));
 
@@ -474,7 +1696,6 @@ impl FontWriter {
         for record in &self.tables {
             record.write_opentype(&mut buffer);
         }
-        buffer.extend(self.table_data);
         buffer
     }
 
@@ -509,25 +1730,101 @@ impl FontWriter {
         self.table_data[offset..offset + 4].copy_from_slice(&checksum_adjustment.to_be_bytes());
     }
 
-    fn into_woff2(mut self) -> Vec<u8> {
+    #[cfg(feature = "woff")]
+    fn into_woff1(mut self) -> Vec<u8> {
+        const WOFF1_SIGNATURE: u32 = 0x_774f_4646;
+        const ZLIB_LEVEL: u8 = miniz_oxide::deflate::CompressionLevel::BestCompression as u8;
+
+        self.adjust_data(Font::checksum(&self.write_sfnt_header()));
+
+        // Tables are placed in the same order as in the OpenType serialization, so that a decoder
+        // reconstructs the font with the same checksum adjustment.
+        let data_offset = self.data_offset();
+        let tables = self.tables.iter().map(|record| {
+            let start = record.offset as usize - data_offset;
+            let table = &self.table_data[start..start + record.length as usize];
+            let compressed = miniz_oxide::deflate::compress_to_vec_zlib(table, ZLIB_LEVEL);
+            if compressed.len() < table.len() {
+                compressed
+            } else {
+                table.to_vec()
+            }
+        });
+        let tables: Vec<_> = tables.collect();
+
+        let mut offset = Self::WOFF1_HEADER_LEN + Self::WOFF1_TABLE_RECORD_LEN * self.tables.len();
+        let mut table_offsets = Vec::with_capacity(tables.len());
+        for table in &tables {
+            table_offsets.push(offset);
+            offset += table.len().next_multiple_of(4);
+        }
+        let file_len = u32::try_from(offset).expect("file length overflow");
+
+        let mut buffer = vec![];
+        write_u32(&mut buffer, WOFF1_SIGNATURE);
+        write_u32(&mut buffer, self.sfnt_version);
+        write_u32(&mut buffer, file_len);
+        // `unwrap()` is safe: we don't write many tables
+        write_u16(&mut buffer, self.tables.len().try_into().unwrap());
+        write_u16(&mut buffer, 0); // reserved
+        let decompressed_len = self.decompressed_len();
+        write_u32(
+            &mut buffer,
+            decompressed_len.try_into().expect("font length overflow"),
+        );
+        write_u16(&mut buffer, 1); // major version of the font
+        write_u16(&mut buffer, 0); // minor version of the font
+        for _ in 0..5 {
+            write_u32(&mut buffer, 0); // no metadata or private blocks
+        }
+        debug_assert_eq!(buffer.len(), Self::WOFF1_HEADER_LEN);
+
+        // Table directory entries must be sorted by tag.
+        let mut entries: Vec<_> = self.tables.iter().zip(&tables).zip(table_offsets).collect();
+        entries.sort_unstable_by_key(|((record, _), _)| record.tag.0);
+        for ((record, table), offset) in entries {
+            record.write_woff1(offset, table.len(), &mut buffer);
+        }
+        for table in tables {
+            buffer.extend_from_slice(&table);
+            buffer.resize(buffer.len().next_multiple_of(4), 0);
+        }
+        debug_assert_eq!(buffer.len(), offset);
+        buffer
+    }
+
+    fn into_woff2(mut self, options: &Woff2Options) -> Vec<u8> {
         const WOFF2_SIGNATURE: u32 = 0x_774f_4632;
 
         self.adjust_data(Font::checksum(&self.write_sfnt_header()));
 
-        let compressed_data = self.compress_data();
+        let compressed_data = self.compress_data(options);
         let tables_len = self
             .tables
             .iter()
             .map(TableRecord::woff2_len)
             .sum::<usize>();
-        let mut file_len = Self::WOFF2_HEADER_LEN + tables_len + compressed_data.len();
-        if file_len % 4 != 0 {
-            file_len += 4 - file_len % 4;
-        }
+        let data_end = Self::WOFF2_HEADER_LEN + tables_len + compressed_data.len();
+        let metadata = options.metadata.as_deref().map(|metadata| {
+            // The metadata block must start at a 4-byte boundary.
+            let offset = data_end.next_multiple_of(4);
+            (
+                offset,
+                metadata.len(),
+                brotli::compress_bytes(metadata, options),
+            )
+        });
+        let data_end = metadata
+            .as_ref()
+            .map_or(data_end, |(offset, _, compressed)| {
+                offset + compressed.len()
+            });
+        // `unwrap()` is safe: `u32` always fits into `usize` on supported platforms
+        let file_len = data_end.next_multiple_of(usize::try_from(options.alignment).unwrap());
 
         let mut buffer = vec![];
         write_u32(&mut buffer, WOFF2_SIGNATURE);
-        write_u32(&mut buffer, Font::SFNT_VERSION);
+        write_u32(&mut buffer, self.sfnt_version);
         write_u32(
             &mut buffer,
             file_len.try_into().expect("file length overflow"),
@@ -536,14 +1833,23 @@ impl FontWriter {
         write_u16(&mut buffer, self.tables.len().try_into().unwrap());
         write_u16(&mut buffer, 0); // reserved
 
-        let decompressed_len = self.data_offset() + self.table_data.len();
+        let decompressed_len = self.decompressed_len();
         // `unwrap`s are safe, since `file_len` fits into u32.
         write_u32(&mut buffer, decompressed_len.try_into().unwrap());
         write_u32(&mut buffer, compressed_data.len().try_into().unwrap());
         write_u32(&mut buffer, 0); // WOFF version
-        write_u32(&mut buffer, 0); // metadata offset
-        write_u32(&mut buffer, 0); // metadata length
-        write_u32(&mut buffer, 0); // original metadata length
+        let (meta_offset, meta_orig_len, meta_len) = metadata
+            .as_ref()
+            .map_or((0, 0, 0), |(offset, orig_len, compressed)| {
+                (*offset, *orig_len, compressed.len())
+            });
+        // `unwrap`s are safe: the metadata block is within the file
+        write_u32(&mut buffer, meta_offset.try_into().unwrap());
+        write_u32(&mut buffer, meta_len.try_into().unwrap());
+        write_u32(
+            &mut buffer,
+            meta_orig_len.try_into().expect("metadata length overflow"),
+        );
         write_u32(&mut buffer, 0); // private block offset
         write_u32(&mut buffer, 0); // private block length
         debug_assert_eq!(buffer.len(), Self::WOFF2_HEADER_LEN);
@@ -553,24 +1859,32 @@ impl FontWriter {
         }
         debug_assert_eq!(buffer.len(), Self::WOFF2_HEADER_LEN + tables_len);
         buffer.extend(compressed_data);
-
-        // Pad `buffer` to be 4-byte aligned. This is required even though we don't have metadata or private blocks.
-        if buffer.len() % 4 != 0 {
-            let padding = 4 - buffer.len() % 4;
-            buffer.extend(iter::repeat_n(0, padding));
+        if let Some((offset, _, compressed_metadata)) = metadata {
+            buffer.resize(offset, 0);
+            buffer.extend(compressed_metadata);
         }
-        debug_assert_eq!(file_len, buffer.len());
+
+        // Pad `buffer` to be aligned. 4-byte alignment is required even though we don't have
+        // private blocks.
+        buffer.resize(file_len, 0);
         buffer
     }
 }
 
 impl Glyph<'_> {
-    fn write(&self, writer: &mut Vec<u8>) {
+    /// Writes this glyph. If `strip_instructions` is set, glyph instructions are removed.
+    fn write(&self, strip_instructions: bool, writer: &mut Vec<u8>) {
         match self {
             Self::Empty => { /* do nothing */ }
-            Self::Simple(bytes) => {
-                writer.extend_from_slice(bytes);
-            }
+            Self::Simple(bytes) => match Self::split_instructions(bytes) {
+                Some((head, _, tail)) if strip_instructions => {
+                    writer.extend_from_slice(head);
+                    write_u16(writer, 0); // instructionLength
+                    writer.extend_from_slice(tail);
+                }
+                _ => writer.extend_from_slice(bytes),
+            },
+            Self::Cff(_) => unreachable!("CFF glyphs are written to the `CFF ` table"),
             Self::Composite {
                 header,
                 components,
@@ -578,10 +1892,21 @@ impl Glyph<'_> {
             } => {
                 write_u16(writer, u16::MAX); // numberOfContours = -1
                 writer.extend_from_slice(header);
-                for component in components {
-                    component.write(writer);
+                for (i, component) in components.iter().enumerate() {
+                    if strip_instructions && i + 1 == components.len() {
+                        let flags = component.flags & !GlyphComponent::WE_HAVE_INSTRUCTIONS;
+                        GlyphComponent {
+                            flags,
+                            ..component.clone()
+                        }
+                        .write(writer);
+                    } else {
+                        component.write(writer);
+                    }
+                }
+                if !strip_instructions {
+                    writer.extend_from_slice(instructions);
                 }
-                writer.extend_from_slice(instructions);
             }
         }
     }
@@ -614,13 +1939,85 @@ impl GlyphComponent {
 
 #[cfg(test)]
 mod tests {
-    use std::borrow::Cow;
-
-    use allsorts::{binary::read::ReadScope, font_data::FontData, tables::FontTableProvider};
+    use std::{
+        borrow::Cow,
+        io::{self, Read, Seek},
+    };
+
+    use allsorts::{
+        binary::read::ReadScope, font_data::FontData, tables::FontTableProvider, woff2::Woff2Font,
+    };
     use test_casing::{test_casing, Product};
 
     use super::*;
-    use crate::tests::{TestCharSubset, TestFont, FONTS, SUBSET_CHARS};
+    use crate::{
+        tests::{assert_valid_font, read_tables, TestCharSubset, TestFont, FONTS, SUBSET_CHARS},
+        ParseErrorKind, SubsetOptions,
+    };
+
+    #[test]
+    fn compacting_cmap_segments() {
+        // 'a'..='c' are mapped to non-sequential glyphs, 'd'..='z' to sequential ones.
+        let map: Vec<_> = [('a', 1), ('b', 5), ('c', 3)]
+            .into_iter()
+            .chain(('d'..='z').zip(10..))
+            .collect();
+        let CmapTable::Deltas(deltas) = CmapTable::from_map(&map, false) else {
+            unreachable!();
+        };
+        assert_eq!(deltas.segments.len(), 5);
+        assert!(deltas.glyph_id_array.is_empty());
+
+        let CmapTable::Deltas(deltas) = CmapTable::from_map(&map, true) else {
+            unreachable!();
+        };
+        let ranges: Vec<_> = deltas
+            .segments
+            .iter()
+            .map(|segment| {
+                (
+                    segment.start_code,
+                    segment.end_code,
+                    segment.id_range_offset,
+                )
+            })
+            .collect();
+        // The array segment offset points to the start of `glyphIdArray` (3 segments -> 6 bytes).
+        assert_eq!(
+            ranges,
+            [(0x61, 0x63, 6), (0x64, 0x7a, 0), (0xffff, 0xffff, 0)]
+        );
+        assert_eq!(*deltas.glyph_id_array, [0, 1, 0, 5, 0, 3]);
+        assert_eq!(deltas.subtable_len(), 16 + 3 * 8 + 6);
+
+        // Non-contiguous chars cannot be merged.
+        let map = [('a', 1), ('c', 5), ('e', 3)];
+        let CmapTable::Deltas(deltas) = CmapTable::from_map(&map, true) else {
+            unreachable!();
+        };
+        assert_eq!(deltas.segments.len(), 4);
+        assert!(deltas.glyph_id_array.is_empty());
+    }
+
+    #[test]
+    fn unaligned_tables_are_padded_with_zeros() {
+        let mut writer = FontWriter::new(Font::SFNT_VERSION);
+        writer.write_raw_table(TableTag::CVT, &[1, 2, 3, 4, 5, 6, 7]);
+        writer.write_raw_table(TableTag::FPGM, &[0xff; 5]);
+
+        assert_eq!(
+            writer.table_data,
+            [1, 2, 3, 4, 5, 6, 7, 0, 0xff, 0xff, 0xff, 0xff, 0xff, 0, 0, 0]
+        );
+        let [cvt, fpgm] = writer.tables.as_slice() else {
+            panic!("unexpected tables: {:?}", writer.tables);
+        };
+        assert_eq!((cvt.offset, cvt.length), (0, 7));
+        assert_eq!((fpgm.offset, fpgm.length), (8, 5));
+        // Checksums must be computed over padded tables.
+        assert_eq!(cvt.checksum, 0x_0102_0304 + 0x_0506_0700);
+        assert_eq!(fpgm.checksum, 0x_ffff_ffff_u32.wrapping_add(0x_ff00_0000));
+    }
 
     #[test]
     fn leb128_encoding() {
@@ -641,17 +2038,124 @@ mod tests {
         }
     }
 
+    #[test]
+    fn writing_class_defs() {
+        let samples: [(&[(u16, u16)], u16); 3] = [
+            // Dense classes are written in format 1, with zero classes filling the gaps
+            (&[(3, 1), (4, 3), (6, 1), (7, 3)], 1),
+            // Runs of equal classes are written in format 2
+            (
+                &[(1, 1), (2, 1), (3, 1), (4, 1), (5, 1), (10, 3), (11, 3)],
+                2,
+            ),
+            (&[(5, 2), (1_000, 3)], 2),
+        ];
+        for (glyph_classes, expected_format) in samples {
+            let mut buffer = vec![];
+            ClassDef::write(glyph_classes, &mut buffer);
+            assert_eq!(buffer[..2], expected_format.to_be_bytes());
+
+            let class_def = ReadScope::new(&buffer)
+                .read::<allsorts::layout::ClassDef>()
+                .unwrap();
+            for glyph_idx in 0..1_100 {
+                let expected_class = glyph_classes
+                    .iter()
+                    .find_map(|&(idx, class)| (idx == glyph_idx).then_some(class))
+                    .unwrap_or(0);
+                assert_eq!(class_def.glyph_class_value(glyph_idx), expected_class);
+            }
+        }
+    }
+
+    #[test]
+    fn computing_unicode_range_bits() {
+        assert_eq!(os2::unicode_range_bits(iter::empty()), [0; 4]);
+        let ascii = (u32::from(' '), u32::from('~'));
+        assert_eq!(os2::unicode_range_bits([ascii].into_iter()), [1, 0, 0, 0]);
+
+        let ranges = [
+            ascii,
+            (0x0410, 0x044f),   // Cyrillic => bit 9
+            (0x2000, 0x2000),   // General Punctuation => bit 31
+            (0x4e00, 0x4e00),   // CJK Unified Ideographs => bit 59
+            (0x1d400, 0x1d401), // Mathematical Alphanumeric Symbols => bits 89 and 57 (non-BMP)
+            (0x1f030, 0x1f030), // Domino Tiles => bit 122
+        ];
+        let bits = os2::unicode_range_bits(ranges.into_iter());
+        assert_eq!(
+            bits,
+            [
+                1 | (1 << 9) | (1 << 31),
+                (1 << (57 - 32)) | (1 << (59 - 32)),
+                1 << (89 - 64),
+                1 << (122 - 96),
+            ]
+        );
+
+        // A range spanning multiple blocks sets all corresponding bits.
+        let bits = os2::unicode_range_bits([(0x0000, 0x024f)].into_iter());
+        assert_eq!(bits, [0b1111, 0, 0, 0]);
+    }
+
+    #[cfg(feature = "woff")]
+    #[test_casing(10, Product((FONTS, SUBSET_CHARS)))]
+    fn woff1_tables_are_written_correctly(font: TestFont, chars: TestCharSubset) {
+        let font = Font::new(font.bytes).unwrap();
+        let subset = FontSubset::new(&font, &chars.into_set()).unwrap();
+        let woff = subset.to_woff1();
+        let ttf_tables = read_tables(&subset.to_opentype());
+
+        let read_u32 = |pos: usize| u32::from_be_bytes(woff[pos..pos + 4].try_into().unwrap());
+        assert_eq!(read_u32(0), 0x_774f_4646);
+        assert_eq!(read_u32(8) as usize, woff.len());
+        assert_eq!(woff.len() % 4, 0);
+        let table_count = u16::from_be_bytes([woff[12], woff[13]]);
+        assert_eq!(usize::from(table_count), ttf_tables.len());
+
+        let mut uncompressed_count = 0;
+        let mut prev_tag = [0; 4];
+        for i in 0..usize::from(table_count) {
+            let record_start =
+                FontWriter::WOFF1_HEADER_LEN + FontWriter::WOFF1_TABLE_RECORD_LEN * i;
+            let tag: [u8; 4] = woff[record_start..record_start + 4].try_into().unwrap();
+            assert!(tag > prev_tag, "table records are not sorted");
+            prev_tag = tag;
+            let offset = read_u32(record_start + 4) as usize;
+            let compressed_len = read_u32(record_start + 8) as usize;
+            let len = read_u32(record_start + 12) as usize;
+            assert_eq!(offset % 4, 0);
+
+            let data = &woff[offset..offset + compressed_len];
+            let table = if compressed_len == len {
+                uncompressed_count += 1;
+                data.to_vec()
+            } else {
+                assert!(compressed_len < len);
+                miniz_oxide::inflate::decompress_to_vec_zlib(data).unwrap()
+            };
+            // Since the font layout is the same, the `head` checksum adjustment must match as well.
+            assert_eq!(table, ttf_tables[&tag], "{:?}", TableTag(tag));
+        }
+        // Some small tables (e.g., `maxp`) don't shrink after compression.
+        assert!(uncompressed_count > 0);
+
+        let font_file = ReadScope::new(&woff).read::<FontData>().unwrap();
+        let font_provider = font_file.table_provider(0).unwrap();
+        allsorts::Font::new(font_provider).unwrap();
+    }
+
     #[test_casing(10, Product((FONTS, SUBSET_CHARS)))]
     #[test]
     fn woff2_tables_are_written_correctly(font: TestFont, chars: TestCharSubset) {
         let font = Font::new(font.bytes).unwrap();
-        let writer = FontSubset::new(font, &chars.into_set())
+        let writer = FontSubset::new(&font, &chars.into_set())
             .unwrap()
             .to_writer();
         let FontWriter {
             tables, table_data, ..
         } = writer.clone();
-        let woff2 = writer.into_woff2();
+        let woff2 = writer.into_woff2(&Woff2Options::default());
 
         let font_file = ReadScope::new(&woff2).read::<FontData>().unwrap();
         let font_provider = font_file.table_provider(0).unwrap();
@@ -674,4 +2178,567 @@ mod tests {
 
         allsorts::Font::new(font_provider).unwrap();
     }
+
+    #[test_casing(10, Product((FONTS, SUBSET_CHARS)))]
+    fn woff2_outline_tables_use_null_transform(font: TestFont, chars: TestCharSubset) {
+        let font = Font::new(font.bytes).unwrap();
+        let chars = chars.into_set();
+        let subset = FontSubset::new(&font, &chars).unwrap();
+        let woff2 = subset.to_woff2();
+
+        let woff2_font = ReadScope::new(&woff2).read::<Woff2Font>().unwrap();
+        let directory = &woff2_font.table_directory;
+        let glyf_pos = directory
+            .iter()
+            .position(|entry| entry.tag == u32::from_be_bytes(TableTag::GLYF.0))
+            .unwrap();
+        // `loca` must immediately follow `glyf` in the table directory.
+        let loca_entry = &directory[glyf_pos + 1];
+        assert_eq!(loca_entry.tag, u32::from_be_bytes(TableTag::LOCA.0));
+        // No table is transformed; for `glyf` / `loca`, this is signalled by transform version 3,
+        // and for other tables, by transform version 0. In both cases, `transformLength` is absent.
+        for entry in directory {
+            assert_eq!(entry.transform_length, None, "{entry:?}");
+        }
+
+        // Check transform flags in the raw table directory.
+        let mut offset = FontWriter::WOFF2_HEADER_LEN;
+        let mut flags = vec![];
+        for _ in 0..directory.len() {
+            let flag = woff2[offset];
+            flags.push(flag);
+            offset += if flag & 0x3f == TableRecord::WOFF2_ARBITRARY_TAG {
+                5
+            } else {
+                1
+            };
+            // Skip `origLength` encoded as `UIntBase128`.
+            while woff2[offset] & 0x80 != 0 {
+                offset += 1;
+            }
+            offset += 1;
+        }
+        // Known tag indices are 10 for `glyf` and 11 for `loca`, plus the null transform bits (0xc0).
+        assert_eq!(flags[glyf_pos], 0xca);
+        assert_eq!(flags[glyf_pos + 1], 0xcb);
+        for (i, &flag) in flags.iter().enumerate() {
+            if i != glyf_pos && i != glyf_pos + 1 {
+                assert_eq!(flag & 0xc0, 0, "{flag:#x}");
+            }
+        }
+
+        // Performs strict WOFF2 decoding if the OpenType sanitizer is available.
+        assert_valid_font(&woff2, false, chars.iter().copied());
+    }
+
+    #[test_casing(10, Product((FONTS, SUBSET_CHARS)))]
+    fn streaming_opentype_output(font: TestFont, chars: TestCharSubset) {
+        let font = Font::new(font.bytes).unwrap();
+        let subset = FontSubset::new(&font, &chars.into_set()).unwrap();
+        let mut file = tempfile::tempfile().unwrap();
+        subset.write_opentype(&mut file).unwrap();
+
+        file.seek(io::SeekFrom::Start(0)).unwrap();
+        let mut streamed = vec![];
+        file.read_to_end(&mut streamed).unwrap();
+        assert_eq!(streamed, subset.to_opentype());
+    }
+
+    #[test_casing(2, FONTS)]
+    fn woff2_decompressed_len_is_correct(font: TestFont) {
+        let font = Font::new(font.bytes).unwrap();
+        let subset = FontSubset::new(&font, &(' '..='~').collect()).unwrap();
+        let decompressed_len = subset.woff2_decompressed_len();
+        assert_eq!(decompressed_len, subset.to_opentype().len());
+
+        let woff2 = subset.to_woff2();
+        let total_sfnt_size = u32::from_be_bytes(woff2[16..20].try_into().unwrap());
+        assert_eq!(total_sfnt_size as usize, decompressed_len);
+    }
+
+    #[test_casing(10, Product((FONTS, SUBSET_CHARS)))]
+    fn estimating_opentype_size(font: TestFont, chars: TestCharSubset) {
+        let font = Font::new(font.bytes).unwrap();
+        let chars = chars.into_set();
+        let mut subset = FontSubset::new(&font, &chars).unwrap();
+        assert_eq!(subset.estimated_opentype_size(), subset.to_opentype().len());
+        subset.keep_glyph_names().unwrap();
+        assert_eq!(subset.estimated_opentype_size(), subset.to_opentype().len());
+
+        let options = SubsetOptions::default()
+            .retain_glyph_classes(true)
+            .compact_hmtx(false)
+            .drop_name(true);
+        let subset = font.subset_with_options(&chars, options).unwrap();
+        assert_eq!(subset.estimated_opentype_size(), subset.to_opentype().len());
+    }
+
+    #[test_casing(2, FONTS)]
+    fn estimating_opentype_size_for_all_glyphs(font: TestFont) {
+        let font = Font::new(font.bytes).unwrap();
+        let subset = font.subset_all().unwrap();
+        assert_eq!(subset.estimated_opentype_size(), subset.to_opentype().len());
+    }
+
+    #[test_casing(3, [4, 64, 512])]
+    fn aligning_woff2_output(alignment: u32) {
+        let font = Font::new(FONTS[0].bytes).unwrap();
+        let subset = FontSubset::new(&font, &(' '..='~').collect()).unwrap();
+        let options = Woff2Options::default().align_to(alignment);
+        let woff2 = subset.to_woff2_with_options(&options);
+
+        assert_eq!(woff2.len() % alignment as usize, 0);
+        let declared_len = u32::from_be_bytes(woff2[8..12].try_into().unwrap());
+        assert_eq!(declared_len as usize, woff2.len());
+        let unpadded_woff2 = subset.to_woff2();
+        assert!(woff2.len() - unpadded_woff2.len() < alignment as usize);
+        assert!(woff2[unpadded_woff2.len()..].iter().all(|&byte| byte == 0));
+
+        let font_file = ReadScope::new(&woff2).read::<FontData>().unwrap();
+        let font_provider = font_file.table_provider(0).unwrap();
+        allsorts::Font::new(font_provider).unwrap();
+    }
+
+    #[test]
+    fn dumping_table_directory() {
+        let font = Font::new(FONTS[0].bytes).unwrap();
+        let subset = FontSubset::new(&font, &('a'..='z').collect()).unwrap();
+        let dump = subset.directory_dump();
+        let ttf = subset.to_opentype();
+
+        let table_count = usize::from(u16::from_be_bytes([ttf[4], ttf[5]]));
+        let mut lines = dump.lines();
+        assert!(lines.next().unwrap().starts_with("tag"));
+        let records = ttf[FontWriter::SFNT_HEADER_LEN..].chunks_exact(TableRecord::BYTE_LEN);
+        for record in records.take(table_count) {
+            let line = lines.next().unwrap();
+            let tag = core::str::from_utf8(&record[..4]).unwrap();
+            let read_u32 =
+                |pos: usize| u32::from_be_bytes(record[pos..pos + 4].try_into().unwrap());
+            let (checksum, offset, length) = (read_u32(4), read_u32(8), read_u32(12));
+            let fields: Vec<_> = line.split_whitespace().collect();
+            assert_eq!(fields[0], tag.trim_end(), "{line}");
+            assert_eq!(fields[1], offset.to_string(), "{line}");
+            assert_eq!(fields[2], length.to_string(), "{line}");
+            assert_eq!(fields[3], format!("0x{checksum:08x}"), "{line}");
+        }
+        assert_eq!(lines.next(), None);
+    }
+
+    #[test]
+    fn embedding_woff2_metadata() {
+        const METADATA: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<metadata version="1.0">
+  <license url="https://openfontlicense.org/" />
+</metadata>
+"#;
+
+        let font = Font::new(FONTS[0].bytes).unwrap();
+        let subset = FontSubset::new(&font, &(' '..='~').collect()).unwrap();
+        let options = Woff2Options::default()
+            .align_to(64)
+            .metadata(Some(METADATA.as_bytes().to_vec()));
+        let woff2 = subset.to_woff2_with_options(&options);
+
+        assert_eq!(woff2.len() % 64, 0);
+        let declared_len = u32::from_be_bytes(woff2[8..12].try_into().unwrap());
+        assert_eq!(declared_len as usize, woff2.len());
+        let meta_offset = u32::from_be_bytes(woff2[28..32].try_into().unwrap());
+        assert_eq!(meta_offset % 4, 0);
+        let unpadded_woff2 = subset.to_woff2();
+        assert!(meta_offset as usize >= unpadded_woff2.len());
+
+        let woff2_font = ReadScope::new(&woff2).read::<Woff2Font>().unwrap();
+        let metadata = woff2_font.extended_metadata().unwrap();
+        assert_eq!(metadata.as_deref(), Some(METADATA));
+        let header = &woff2_font.woff_header;
+        assert_eq!(header.meta_orig_length as usize, METADATA.len());
+        assert!(header.meta_offset + header.meta_length <= declared_len);
+
+        let font_file = ReadScope::new(&woff2).read::<FontData>().unwrap();
+        let font_provider = font_file.table_provider(0).unwrap();
+        allsorts::Font::new(font_provider).unwrap();
+    }
+
+    #[test_casing(3, [(5, 22), (0, 10), (9, 24)])]
+    fn woff2_compression_quality(quality: u8, lgwin: u8) {
+        let font = Font::new(FONTS[0].bytes).unwrap();
+        let subset = FontSubset::new(&font, &(' '..='~').collect()).unwrap();
+        let woff2 = subset.to_woff2_with_quality(quality, lgwin);
+        let best_woff2 = subset.to_woff2();
+        assert!(woff2.len() >= best_woff2.len(), "{}", woff2.len());
+        // Signature, flavor and `totalSfntSize` must not depend on compression
+        assert_eq!(woff2[..8], best_woff2[..8]);
+        assert_eq!(woff2[16..20], best_woff2[16..20]);
+
+        let font_file = ReadScope::new(&woff2).read::<FontData>().unwrap();
+        let font_provider = font_file.table_provider(0).unwrap();
+        allsorts::Font::new(font_provider).unwrap();
+    }
+
+    #[test]
+    fn out_of_range_woff2_compression_is_clamped() {
+        let options = Woff2Options::default().compression(12, 30);
+        assert_eq!((options.quality, options.lgwin), (11, 24));
+        let options = Woff2Options::default().compression(5, 0);
+        assert_eq!((options.quality, options.lgwin), (5, 10));
+
+        let font = Font::new(FONTS[0].bytes).unwrap();
+        let subset = FontSubset::new(&font, &('a'..='z').collect()).unwrap();
+        assert!(subset.to_woff2_with_quality(12, 30) == subset.to_woff2_with_quality(11, 24));
+    }
+
+    #[test]
+    fn woff2_alignment_is_rounded_up() {
+        for (alignment, expected) in [(0, 4), (1, 4), (4, 4), (63, 64), (u32::MAX, u32::MAX - 3)] {
+            let options = Woff2Options::default().align_to(alignment);
+            assert_eq!(options.alignment, expected, "{alignment}");
+        }
+
+        let font = Font::new(FONTS[0].bytes).unwrap();
+        let subset = FontSubset::new(&font, &('a'..='z').collect()).unwrap();
+        let options = Woff2Options::default().align_to(6);
+        assert_eq!(subset.to_woff2_with_options(&options).len() % 8, 0);
+    }
+
+    #[test]
+    fn writing_metrics() {
+        let metrics = [(500, 10), (600, 20), (600, 30), (600, 40)];
+        let mut buffer = vec![];
+        let number_of_h_metrics = HmtxTable::write_metrics(&metrics, true, &mut buffer);
+        assert_eq!(number_of_h_metrics, 2);
+        assert_eq!(buffer, [1, 244, 0, 10, 2, 88, 0, 20, 0, 30, 0, 40]);
+
+        buffer.clear();
+        let number_of_h_metrics = HmtxTable::write_metrics(&metrics, false, &mut buffer);
+        assert_eq!(number_of_h_metrics, 4);
+        assert_eq!(
+            buffer,
+            [1, 244, 0, 10, 2, 88, 0, 20, 2, 88, 0, 30, 2, 88, 0, 40]
+        );
+    }
+
+    #[test]
+    fn disabling_hmtx_compaction() {
+        let font = Font::new(FONTS[0].bytes).unwrap();
+        let chars = ('A'..='Z').collect();
+        let options = SubsetOptions::default().compact_hmtx(false);
+        let subset = FontSubset::with_options(&font, &chars, options).unwrap();
+        let glyph_count = subset.glyphs.len();
+        let ttf = subset.to_opentype();
+
+        let font = Font::new(&ttf).unwrap();
+        assert_eq!(usize::from(font.hhea.number_of_h_metrics), glyph_count);
+        let tables = read_tables(&ttf);
+        assert_eq!(tables[b"hmtx"].len(), 4 * glyph_count);
+        for (new_idx, glyph) in subset.glyphs.iter().enumerate() {
+            let new_idx = u16::try_from(new_idx).unwrap();
+            let (advance, lsb) = font.hmtx.advance_and_lsb(new_idx).unwrap();
+            assert_eq!((advance, lsb), (glyph.advance, glyph.lsb));
+        }
+    }
+
+    #[test]
+    fn empty_last_glyph_is_written_correctly() {
+        let font = Font::new(FONTS[0].bytes).unwrap();
+        // No-break space is mapped to an empty glyph, and it has the highest code point, so it will be last.
+        let chars = ['A', '\u{a0}'].into_iter().collect();
+        let subset = FontSubset::new(&font, &chars).unwrap();
+        assert_eq!(subset.glyphs.len(), 3);
+        assert!(matches!(subset.glyphs[1].inner, Glyph::Simple(_)));
+        assert!(matches!(subset.glyphs[2].inner, Glyph::Empty));
+
+        let writer = subset.to_writer();
+        let loca = writer
+            .tables
+            .iter()
+            .find(|record| record.tag == TableTag::LOCA)
+            .unwrap();
+        let start = loca.offset as usize;
+        let loca_bytes = &writer.table_data[start..start + loca.length as usize];
+        let locations: Vec<_> = loca_bytes
+            .chunks_exact(2)
+            .map(|chunk| u16::from_be_bytes(chunk.try_into().unwrap()))
+            .collect();
+        assert_eq!(locations.len(), 4);
+        assert!(locations[1] < locations[2]);
+        assert_eq!(locations[2], locations[3]);
+
+        let ttf = subset.to_opentype();
+        let font = Font::new(&ttf).unwrap();
+        let glyph = font.glyph(2).unwrap();
+        assert!(matches!(glyph.inner, Glyph::Empty));
+        assert_eq!(font.map_char('\u{a0}').unwrap(), 2);
+    }
+
+    /// Assembles a minimal TrueType font with the specified `glyphs` to unit-test glyph serialization.
+    /// Glyph #`i` (`i >= 1`) is mapped to char `'@' + i`, and all glyphs have the same advance.
+    /// Besides the tables required for glyph parsing, the font contains an empty `name` table
+    /// and a version 3.0 `post` table, so that it can be parsed by [`Font::new()`].
+    pub(crate) fn build_minimal_font(glyphs: &[Glyph<'_>]) -> Vec<u8> {
+        const UNITS_PER_EM: u16 = 1_000;
+        const ADVANCE: u16 = 600;
+
+        let glyph_count = u16::try_from(glyphs.len()).unwrap();
+        let mut writer = FontWriter::new(Font::SFNT_VERSION);
+        let locations = writer.write_table(TableTag::GLYF, |buffer| {
+            let start = buffer.len();
+            let mut locations = vec![0];
+            for glyph in glyphs {
+                glyph.write(false, buffer);
+                locations.push(buffer.len() - start);
+            }
+            locations
+        });
+        let loca_format = writer.write_table(TableTag::LOCA, |buffer| {
+            LocaTable::write(&locations, buffer)
+        });
+
+        let bbox = glyphs
+            .iter()
+            .filter_map(Glyph::bbox)
+            .reduce(|[x_min, y_min, x_max, y_max], bbox| {
+                [
+                    x_min.min(bbox[0]),
+                    y_min.min(bbox[1]),
+                    x_max.max(bbox[2]),
+                    y_max.max(bbox[3]),
+                ]
+            })
+            .unwrap_or_default();
+        writer.write_table(TableTag::HEAD, |buffer| {
+            write_u32(buffer, 0x_0001_0000); // version
+            write_u32(buffer, 0x_0001_0000); // fontRevision
+            write_u32(buffer, 0); // checksumAdjustment
+            write_u32(buffer, 0x_5f0f_3cf5); // magicNumber
+            write_u16(buffer, 0b1011); // flags
+            write_u16(buffer, UNITS_PER_EM);
+            buffer.extend_from_slice(&[0; 16]); // created, modified
+            for coord in bbox {
+                buffer.extend_from_slice(&coord.to_be_bytes());
+            }
+            write_u16(buffer, 0); // macStyle
+            write_u16(buffer, 8); // lowestRecPPEM
+            write_u16(buffer, 2); // fontDirectionHint
+            write_u16(buffer, u16::from(matches!(loca_format, LocaFormat::Long)));
+            write_u16(buffer, 0); // glyphDataFormat
+        });
+
+        let metrics: Vec<_> = glyphs
+            .iter()
+            .map(|glyph| {
+                let lsb = glyph.bbox().map_or(0, |[x_min, ..]| x_min);
+                (ADVANCE, u16::from_be_bytes(lsb.to_be_bytes()))
+            })
+            .collect();
+        let number_of_h_metrics = writer.write_table(TableTag::HMTX, |buffer| {
+            HmtxTable::write_metrics(&metrics, true, buffer)
+        });
+        writer.write_table(TableTag::HHEA, |buffer| {
+            write_u32(buffer, 0x_0001_0000); // version
+            buffer.extend_from_slice(&800_i16.to_be_bytes()); // ascender
+            buffer.extend_from_slice(&(-200_i16).to_be_bytes()); // descender
+            write_u16(buffer, 0); // lineGap
+            write_u16(buffer, ADVANCE); // advanceWidthMax
+            buffer.extend_from_slice(&bbox[0].to_be_bytes()); // minLeftSideBearing
+            let min_rsb = i16::try_from(ADVANCE).unwrap() - bbox[2];
+            buffer.extend_from_slice(&min_rsb.to_be_bytes());
+            buffer.extend_from_slice(&bbox[2].to_be_bytes()); // xMaxExtent
+            write_u16(buffer, 1); // caretSlopeRise
+            buffer.extend_from_slice(&[0; 4]); // caretSlopeRun, caretOffset
+            buffer.extend_from_slice(&[0; 10]); // reserved, metricDataFormat
+            write_u16(buffer, number_of_h_metrics);
+        });
+
+        writer.write_table(TableTag::MAXP, |buffer| {
+            write_u32(buffer, 0x_0001_0000); // version
+            write_u16(buffer, glyph_count);
+            buffer.extend_from_slice(&[0; 8]); // maxPoints, maxContours, maxCompositePoints, maxCompositeContours
+            write_u16(buffer, 2); // maxZones
+            buffer.extend_from_slice(&[0; 16]); // remaining limits
+        });
+
+        let char_map: Vec<_> = (1..glyph_count)
+            .map(|glyph_idx| {
+                (
+                    char::from_u32(0x40 + u32::from(glyph_idx)).unwrap(),
+                    glyph_idx,
+                )
+            })
+            .collect();
+        let cmap = CmapTable::from_map(&char_map, false);
+        writer.write_table(TableTag::CMAP, |buffer| cmap.write(&[], buffer));
+        writer.write_table(TableTag::NAME, |buffer| {
+            write_u16(buffer, 0); // version
+            write_u16(buffer, 0); // count
+            write_u16(buffer, 6); // storageOffset
+        });
+        writer.write_table(TableTag::POST, |buffer| {
+            write_u32(buffer, 0x_0003_0000); // version
+            buffer.extend_from_slice(&[0; 28]);
+        });
+        writer.into_opentype()
+    }
+
+    /// Simple triangle glyph with a single contour and no instructions.
+    const TRIANGLE_GLYPH: &[u8] = &[
+        0, 1, // numberOfContours
+        0, 0, 0, 0, 1, 244, 2, 188, // bbox: (0, 0) - (500, 700)
+        0, 2, // endPtsOfContours
+        0, 0, // instructionLength
+        1, 1, 1, // flags: on-curve points with word coordinates
+        0, 0, 0, 250, 0, 250, // x deltas
+        0, 0, 2, 188, 253, 68, // y deltas
+    ];
+
+    const COMPONENT_TRANSFORMS: [TransformData; 4] = [
+        TransformData::None,
+        TransformData::Scale(0x2000),
+        TransformData::TwoScales([0x4000, 0x2000]),
+        TransformData::Affine([0x4000, 0x1000, 0xf000, 0x2000]),
+    ];
+
+    #[test_casing(4, COMPONENT_TRANSFORMS)]
+    fn writing_composite_glyph_transforms(transform: TransformData) {
+        use allsorts::tables::{
+            glyf::{
+                CompositeGlyphArgument, CompositeGlyphScale, GlyfTable, Glyph as ReferenceGlyph,
+            },
+            loca::LocaTable as ReferenceLocaTable,
+            F2Dot14, HeadTable, IndexToLocFormat,
+        };
+
+        const ARG_1_AND_2_ARE_WORDS: u16 = 0x0001;
+        const ARGS_ARE_XY_VALUES: u16 = 0x0002;
+
+        let transform_flags = match transform {
+            TransformData::None => 0,
+            TransformData::Scale(_) => 0x0008,
+            TransformData::TwoScales(_) => 0x0040,
+            TransformData::Affine(_) => 0x0080,
+        };
+        let arg_variants = [
+            (
+                GlyphComponentArgs::U16(0x_0af6),
+                [
+                    CompositeGlyphArgument::I8(10),
+                    CompositeGlyphArgument::I8(-10),
+                ],
+            ),
+            (
+                GlyphComponentArgs::U32(0x_0100_ff00),
+                [
+                    CompositeGlyphArgument::I16(256),
+                    CompositeGlyphArgument::I16(-256),
+                ],
+            ),
+        ];
+
+        for (args, expected_args) in arg_variants {
+            let mut flags = ARGS_ARE_XY_VALUES | transform_flags;
+            if matches!(args, GlyphComponentArgs::U32(_)) {
+                flags |= ARG_1_AND_2_ARE_WORDS;
+            }
+            let component = GlyphComponent {
+                flags,
+                glyph_idx: 1,
+                args: args.clone(),
+                transform,
+            };
+            let composite = Glyph::Composite {
+                header: TRIANGLE_GLYPH[2..10].try_into().unwrap(),
+                components: vec![component.clone()],
+                instructions: &[],
+            };
+            let ttf = build_minimal_font(&[Glyph::Empty, Glyph::Simple(TRIANGLE_GLYPH), composite]);
+            assert_valid_font(&ttf, true, ['A', 'B'].into_iter());
+
+            let font = Font::new(&ttf).unwrap();
+            let Glyph::Composite { components, .. } = font.glyph(2).unwrap().inner else {
+                panic!("unexpected glyph kind");
+            };
+            assert_eq!(components.len(), 1);
+            assert_eq!(components[0].flags, flags);
+            assert_eq!(components[0].glyph_idx, 1);
+            assert_eq!(components[0].args, args);
+            assert_eq!(components[0].transform, transform);
+
+            let tables = read_tables(&ttf);
+            let head = ReadScope::new(&tables[b"head"])
+                .read::<HeadTable>()
+                .unwrap();
+            assert_eq!(head.index_to_loc_format, IndexToLocFormat::Long);
+            let loca = ReadScope::new(&tables[b"loca"])
+                .read_dep::<ReferenceLocaTable<'_>>((3, head.index_to_loc_format))
+                .unwrap();
+            let mut glyf = ReadScope::new(&tables[b"glyf"])
+                .read_dep::<GlyfTable<'_>>(&loca)
+                .unwrap();
+            let ReferenceGlyph::Composite(glyph) = glyf.get_parsed_glyph(2).unwrap() else {
+                panic!("unexpected reference glyph kind");
+            };
+            assert_eq!(glyph.glyphs.len(), 1);
+            let component = &glyph.glyphs[0];
+            assert_eq!(component.glyph_index, 1);
+            assert!(component.flags.args_are_xy_values());
+            assert_eq!([component.argument1, component.argument2], expected_args);
+
+            let to_f2dot14 = |val: u16| F2Dot14::from_raw(i16::from_be_bytes(val.to_be_bytes()));
+            let expected_scale = match transform {
+                TransformData::None => None,
+                TransformData::Scale(scale) => Some(CompositeGlyphScale::Scale(to_f2dot14(scale))),
+                TransformData::TwoScales([x, y]) => Some(CompositeGlyphScale::XY {
+                    x_scale: to_f2dot14(x),
+                    y_scale: to_f2dot14(y),
+                }),
+                TransformData::Affine([xx, xy, yx, yy]) => Some(CompositeGlyphScale::Matrix([
+                    [to_f2dot14(xx), to_f2dot14(xy)],
+                    [to_f2dot14(yx), to_f2dot14(yy)],
+                ])),
+            };
+            assert_eq!(component.scale, expected_scale);
+        }
+    }
+
+    #[test]
+    fn flattening_composite_glyphs_with_shared_components() {
+        const ARGS_ARE_XY_VALUES: u16 = 0x0002;
+        const MORE_COMPONENTS: u16 = 0x0020;
+
+        // Each composite glyph references the previous glyph several times.
+        let composite = |glyph_idx: u16, component_count: usize| {
+            let components = (0..component_count)
+                .map(|i| GlyphComponent {
+                    flags: if i + 1 < component_count {
+                        ARGS_ARE_XY_VALUES | MORE_COMPONENTS
+                    } else {
+                        ARGS_ARE_XY_VALUES
+                    },
+                    glyph_idx,
+                    args: GlyphComponentArgs::U16(0),
+                    transform: TransformData::None,
+                })
+                .collect();
+            Glyph::Composite {
+                header: TRIANGLE_GLYPH[2..10].try_into().unwrap(),
+                components,
+                instructions: &[],
+            }
+        };
+        let mut glyphs = vec![Glyph::Empty, Glyph::Simple(TRIANGLE_GLYPH)];
+        glyphs.extend((1..=15).map(|glyph_idx| composite(glyph_idx, 8)));
+        let ttf = build_minimal_font(&glyphs);
+        let font = Font::new(&ttf).unwrap();
+
+        // 8 + 8^2 + 8^3 + 8^4 = 4,680 flattened components
+        let path = font.glyph_svg_path(5).unwrap().unwrap();
+        assert_eq!(path.matches('M').count(), 8_usize.pow(4));
+        let err = font.glyph_svg_path(16).unwrap_err();
+        assert!(
+            matches!(err.kind(), ParseErrorKind::CompositeComponentsExceeded(_)),
+            "{err:?}"
+        );
+        // Subsetting doesn't flatten glyphs, so it isn't affected.
+        font.subset_glyphs(&BTreeSet::from([16])).unwrap();
+    }
 }